@@ -70,6 +70,15 @@ impl<S: ethers::signers::Signer + 'static> Bridge<SignerMiddleware<Provider<Http
         Ok(receipt)
     }
 
+    /// Locks ETH and credits the secp256k1 Volt account controlled by the locking key.
+    ///
+    /// The Volt address is derived from the signer's Ethereum address, so the
+    /// user can spend the bridged funds with the same key.
+    pub async fn lock_to_self(&self, amount: u128) -> Result<TransactionReceipt, BridgeError> {
+        let to = volt_address_for(&self.provider.address());
+        self.lock(&to, amount).await
+    }
+
     /// Unlocks ETH from the contract and sends it to the specified address.
     pub async fn unlock(
         &self,
@@ -182,6 +191,11 @@ impl<S: ethers::signers::Signer + 'static> Bridge<SignerMiddleware<Provider<Http
     }
 }
 
+/// Returns the Volt address controlled by the key behind an Ethereum address.
+pub fn volt_address_for(eth_address: &EthAddress) -> Address {
+    core::types::address_from_eth(&eth_address.to_fixed_bytes())
+}
+
 /// Creates a new bridge with a local wallet.
 pub async fn new_bridge_with_wallet(
     rpc_url: &str,
@@ -0,0 +1,152 @@
+//! Airdrop distribution files.
+//!
+//! An issuer builds a distribution from a CSV of `address,amount` lines.
+//! The resulting file holds the distribution root and every recipient's
+//! inclusion proof, so it can be shared with recipients who claim from it.
+
+use crate::commands::send::parse_address;
+use crate::errors::WalletError;
+use core::airdrop::{AirdropEntry, AirdropTree};
+use core::types::{Address, Balance, TokenId};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A recipient's claim in a distribution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AirdropClaim {
+    /// The entry's index in the distribution
+    pub index: u64,
+    /// Hex-encoded recipient address
+    pub address: String,
+    /// The amount the recipient can claim
+    pub amount: Balance,
+    /// Hex-encoded inclusion proof
+    pub proof: Vec<String>,
+}
+
+/// A distribution built from a CSV file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Distribution {
+    /// The token being distributed
+    pub token_id: TokenId,
+    /// Hex-encoded distribution root
+    pub merkle_root: String,
+    /// Sum of all claim amounts
+    pub total: Balance,
+    /// Hex-encoded airdrop ID, set once the airdrop is created
+    #[serde(default)]
+    pub airdrop_id: Option<String>,
+    /// Claims of all recipients
+    pub claims: Vec<AirdropClaim>,
+}
+
+impl Distribution {
+    /// Builds a distribution from CSV contents of `address,amount` lines.
+    ///
+    /// Blank lines are skipped, and so is a header on the first line.
+    pub fn from_csv(contents: &str, token_id: TokenId) -> Result<Self, WalletError> {
+        let mut entries = Vec::new();
+        for (line_number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (address, amount) = line.split_once(',').ok_or_else(|| {
+                WalletError::InvalidAmount(format!("Line {}: expected address,amount", line_number + 1))
+            })?;
+            let amount = match amount.trim().parse::<Balance>() {
+                Ok(amount) => amount,
+                Err(_) if line_number == 0 => continue,
+                Err(e) => {
+                    return Err(WalletError::InvalidAmount(format!("Line {}: {}", line_number + 1, e)));
+                }
+            };
+            if amount == 0 {
+                return Err(WalletError::InvalidAmount(format!("Line {}: amount is zero", line_number + 1)));
+            }
+
+            entries.push(AirdropEntry {
+                address: parse_address(address.trim(), "recipient")?,
+                amount,
+            });
+        }
+
+        let total = entries
+            .iter()
+            .try_fold(0u128, |total, entry| total.checked_add(entry.amount))
+            .ok_or_else(|| WalletError::InvalidAmount("Distribution total overflows".to_string()))?;
+
+        let tree = AirdropTree::build(&entries).map_err(|e| WalletError::InvalidAmount(e.to_string()))?;
+        let claims = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let proof = tree
+                    .proof(index as u64)
+                    .map_err(|e| WalletError::ProofError(e.to_string()))?;
+                Ok(AirdropClaim {
+                    index: index as u64,
+                    address: hex::encode(entry.address),
+                    amount: entry.amount,
+                    proof: proof.iter().map(hex::encode).collect(),
+                })
+            })
+            .collect::<Result<_, WalletError>>()?;
+
+        Ok(Self {
+            token_id,
+            merkle_root: hex::encode(tree.root()),
+            total,
+            airdrop_id: None,
+            claims,
+        })
+    }
+
+    /// Loads a distribution file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, WalletError> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Saves the distribution to a file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), WalletError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Returns the decoded distribution root.
+    pub fn root(&self) -> Result<[u8; 32], WalletError> {
+        decode_hash(&self.merkle_root)
+    }
+
+    /// Returns the decoded airdrop ID, if the airdrop has been created.
+    pub fn airdrop_id(&self) -> Result<[u8; 32], WalletError> {
+        let airdrop_id = self.airdrop_id.as_ref().ok_or_else(|| {
+            WalletError::WalletError("Distribution has no airdrop ID (run create-airdrop first)".to_string())
+        })?;
+        decode_hash(airdrop_id)
+    }
+
+    /// Finds the claim of an address.
+    pub fn claim_for(&self, address: &Address) -> Option<&AirdropClaim> {
+        let address = hex::encode(address);
+        self.claims.iter().find(|claim| claim.address == address)
+    }
+}
+
+impl AirdropClaim {
+    /// Returns the decoded inclusion proof.
+    pub fn proof(&self) -> Result<Vec<[u8; 32]>, WalletError> {
+        self.proof.iter().map(|sibling| decode_hash(sibling)).collect()
+    }
+}
+
+/// Decodes a hex-encoded 32-byte hash.
+fn decode_hash(hash: &str) -> Result<[u8; 32], WalletError> {
+    hex::decode(hash.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::ProofError(format!("Invalid hash: {}", hash)))
+}
@@ -0,0 +1,18 @@
+//! Airdrop status command for the CLI wallet.
+
+use crate::airdrop::Distribution;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::airdrop::Airdrop;
+use std::path::Path;
+
+/// Runs the airdrop-status command.
+///
+/// Gets the airdrop of a distribution file from the node, checking the
+/// node's proof.
+pub async fn run<P: AsRef<Path>>(config: &WalletConfig, distribution_path: P) -> Result<Airdrop, WalletError> {
+    let distribution = Distribution::load(distribution_path)?;
+    rpc::get_airdrop(&config.node, &distribution.airdrop_id()?).await
+}
@@ -0,0 +1,72 @@
+//! Authorize session key command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::session_keys::SpendingLimit;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the authorize-session-key command.
+///
+/// Authorizes a session key to send transfers from the wallet's account, up
+/// to the given `<token_id>:<amount>` limits per window of `window_secs`,
+/// starting now and until `expiry`. Returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    session_key: &str,
+    limits: &[String],
+    window_secs: u64,
+    expiry: u64,
+) -> Result<String, WalletError> {
+    let session_key = parse_address(session_key, "session key")?;
+    let limits = limits
+        .iter()
+        .map(|limit| limit.parse::<SpendingLimit>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| WalletError::TransactionError(e.to_string()))?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    let start = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!(
+        "Authorizing session key 0x{} to spend {} every {} seconds",
+        hex::encode(session_key),
+        limits.iter().map(|limit| limit.to_string()).collect::<Vec<_>>().join(", "),
+        window_secs
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, 0).await?;
+
+    let mut msg = SystemMsg::AuthorizeSessionKey {
+        owner,
+        session_key,
+        limits,
+        window_secs,
+        start,
+        expiry,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,70 @@
+//! Authorize standing order command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Balance, Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the authorize-standing-order command.
+///
+/// Authorizes a payee to pull `amount` of a token from the wallet once every
+/// `period_secs`, starting now and until `expiry`, and returns the
+/// hex-encoded order ID.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    payee: &str,
+    token_id: TokenId,
+    amount: Balance,
+    period_secs: u64,
+    expiry: u64,
+) -> Result<String, WalletError> {
+    let payee = parse_address(payee, "payee")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payer = wallet.address()?;
+    let start = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!(
+        "Authorizing 0x{} to pull {} of token {} every {} seconds",
+        hex::encode(payee),
+        amount,
+        token_id,
+        period_secs
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payer, 0).await?;
+
+    let mut msg = SystemMsg::AuthorizeStandingOrder {
+        payer,
+        payee,
+        token_id,
+        amount,
+        period_secs,
+        start,
+        expiry,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await?;
+    Ok(hex::encode(core::standing_orders::order_id(&payer, &payee, token_id, nonce)))
+}
@@ -0,0 +1,25 @@
+//! Build airdrop command for the CLI wallet.
+
+use crate::airdrop::Distribution;
+use crate::errors::WalletError;
+use anyhow::Result;
+use core::types::TokenId;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the build-airdrop command.
+///
+/// Builds a distribution from a CSV file of `address,amount` lines, writes
+/// it with every recipient's claim proof to `out` and returns it.
+pub fn run<P: AsRef<Path>, Q: AsRef<Path>>(csv_path: P, token_id: TokenId, out: Q) -> Result<Distribution, WalletError> {
+    let contents = std::fs::read_to_string(csv_path)?;
+    let distribution = Distribution::from_csv(&contents, token_id)?;
+    info!(
+        "Built distribution of {} entries with root {}",
+        distribution.claims.len(),
+        distribution.merkle_root
+    );
+
+    distribution.save(out)?;
+    Ok(distribution)
+}
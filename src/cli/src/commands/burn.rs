@@ -0,0 +1,48 @@
+//! Burn command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the burn command.
+///
+/// Destroys `amount` of a token held by the wallet, reducing its supply, and
+/// returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: TokenId,
+    amount: u128,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let from = wallet.address()?;
+    info!("Burning {} of token {} from {}", amount, token_id, hex::encode(from));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, token_id).await?;
+
+    let mut msg = SystemMsg::Burn {
+        from,
+        token_id,
+        amount,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,57 @@
+//! Cancel standing order command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::standing_orders::OrderId;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the cancel-standing-order command.
+///
+/// Cancels a standing order paid by the wallet and returns the new state
+/// root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    order_id: &str,
+) -> Result<String, WalletError> {
+    let order_id = parse_order_id(order_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payer = wallet.address()?;
+    info!("Cancelling standing order {}", hex::encode(order_id));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payer, 0).await?;
+
+    let mut msg = SystemMsg::CancelStandingOrder {
+        payer,
+        order_id,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
+
+/// Parses a hex-encoded standing order ID.
+pub fn parse_order_id(order_id: &str) -> Result<OrderId, WalletError> {
+    hex::decode(order_id.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::TransactionError(format!("Invalid standing order ID: {}", order_id)))
+}
@@ -0,0 +1,23 @@
+//! Channel command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::channels::{ChannelId, PaymentChannel};
+
+/// Runs the channel command.
+///
+/// Gets a payment channel from the node, checking the node's proof. Returns
+/// `None` if the channel has been closed or reclaimed.
+pub async fn run(config: &WalletConfig, channel_id: &str) -> Result<Option<PaymentChannel>, WalletError> {
+    rpc::get_channel(&config.node, &parse_channel_id(channel_id)?).await
+}
+
+/// Parses a hex-encoded payment channel ID.
+pub fn parse_channel_id(channel_id: &str) -> Result<ChannelId, WalletError> {
+    hex::decode(channel_id.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::TransactionError(format!("Invalid channel ID: {}", channel_id)))
+}
@@ -0,0 +1,67 @@
+//! Claim airdrop command for the CLI wallet.
+
+use crate::airdrop::Distribution;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Balance, Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the claim-airdrop command.
+///
+/// Finds the wallet's entry in a distribution file and claims it, returning
+/// the amount claimed.
+pub async fn run<P: AsRef<Path>, Q: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    distribution_path: Q,
+) -> Result<Balance, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let distribution = Distribution::load(&distribution_path)?;
+    let airdrop_id = distribution.airdrop_id()?;
+
+    let claimant = wallet.address()?;
+    let claim = distribution.claim_for(&claimant).ok_or_else(|| {
+        WalletError::WalletError(format!("0x{} is not a recipient of this airdrop", hex::encode(claimant)))
+    })?;
+
+    if rpc::is_airdrop_claimed(&config.node, &airdrop_id, claim.index).await? {
+        return Err(WalletError::TransactionError("Airdrop already claimed".to_string()));
+    }
+
+    info!("Claiming {} from airdrop {}", claim.amount, hex::encode(airdrop_id));
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let nonce = rpc::get_nonce_with_token(&config.node, &claimant, 0).await?;
+
+    let mut msg = SystemMsg::ClaimAirdrop {
+        claimant,
+        airdrop_id,
+        index: claim.index,
+        amount: claim.amount,
+        proof: claim.proof()?,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await?;
+    Ok(claim.amount)
+}
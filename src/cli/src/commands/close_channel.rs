@@ -0,0 +1,61 @@
+//! Close channel command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::channels::ChannelVoucher;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the close-channel command.
+///
+/// Closes a channel paid to the wallet with the payer's latest voucher and
+/// returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    voucher: &str,
+) -> Result<String, WalletError> {
+    let voucher = parse_voucher(voucher)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payee = wallet.address()?;
+    info!(
+        "Closing channel {} for {}",
+        hex::encode(voucher.channel_id),
+        voucher.amount
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payee, 0).await?;
+
+    let mut msg = SystemMsg::CloseChannel {
+        payee,
+        voucher,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
+
+/// Parses a hex-encoded voucher, as printed by the pay-channel command.
+pub fn parse_voucher(voucher: &str) -> Result<ChannelVoucher, WalletError> {
+    hex::decode(voucher.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .ok_or_else(|| WalletError::TransactionError("Invalid channel voucher".to_string()))
+}
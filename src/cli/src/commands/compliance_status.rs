@@ -0,0 +1,27 @@
+//! Compliance status command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::compliance::ComplianceList;
+use core::types::TokenId;
+
+/// Runs the compliance-status command.
+///
+/// Returns whether an address is on a token's allow or deny list, once the
+/// node's membership proof has been checked.
+pub async fn run(
+    config: &WalletConfig,
+    token_id: TokenId,
+    list: &str,
+    address_hex: &str,
+) -> Result<bool, WalletError> {
+    let list: ComplianceList = list
+        .parse()
+        .map_err(|e: core::CoreError| WalletError::TransactionError(e.to_string()))?;
+    let address = parse_address(address_hex, "listed")?;
+
+    rpc::get_compliance_membership(&config.node, token_id, list, &address).await
+}
@@ -0,0 +1,39 @@
+//! Confidential balance command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::confidential::{self, ConfidentialStore};
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the confidential-balance command.
+///
+/// Folds in any confidential transfers received since the last run and
+/// returns the wallet's confidential balance of the token.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: u64,
+) -> Result<u64, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    info!("Getting confidential balance of token {} for {}", token_id, hex::encode(wallet.address()?));
+
+    let mut store = ConfidentialStore::load_for_wallet(&wallet_path)?;
+    if confidential::sync(&config.node, &wallet, &mut store, token_id).await?.is_none() {
+        return Ok(0);
+    }
+
+    Ok(store.balance_mut(token_id).value)
+}
@@ -0,0 +1,117 @@
+//! Confidential send command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::confidential::{self, ConfidentialStore};
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::confidential::blinding_from_bytes;
+use core::types::{Signature, SystemMsg};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the confidential-send command.
+///
+/// Transfers a hidden amount from the wallet's confidential balance to the
+/// recipient's and returns the new state root. The recipient must have
+/// shielded the token at least once, so that it has a viewing key.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    to_hex: &str,
+    token_id: u64,
+    amount: u64,
+) -> Result<String, WalletError> {
+    // Parse the recipient address
+    let to_bytes = hex::decode(to_hex.trim_start_matches("0x")).map_err(|e| {
+        WalletError::InvalidAddress(format!("Invalid recipient address: {}", e))
+    })?;
+    let to: [u8; 32] = to_bytes.try_into().map_err(|bytes: Vec<u8>| {
+        WalletError::InvalidAddress(format!(
+            "Invalid recipient address length: {} (expected 32)",
+            bytes.len()
+        ))
+    })?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let from = wallet.address()?;
+    info!("Sending a confidential amount of token {} from {} to {}", token_id, hex::encode(from), to_hex);
+
+    let mut store = ConfidentialStore::load_for_wallet(&wallet_path)?;
+    if confidential::sync(&config.node, &wallet, &mut store, token_id).await?.is_none() {
+        return Err(WalletError::InsufficientBalance(format!(
+            "No confidential balance for token {}",
+            token_id
+        )));
+    }
+
+    let recipient = confidential::get_account(&config.node, &to, token_id)
+        .await?
+        .ok_or_else(|| {
+            WalletError::TransactionError(format!(
+                "Recipient has no confidential balance for token {}",
+                token_id
+            ))
+        })?;
+
+    let balance = store.balance_mut(token_id);
+    let remaining = balance.value.checked_sub(amount).ok_or_else(|| {
+        WalletError::InsufficientBalance(format!(
+            "Insufficient confidential balance: {} (required: {})",
+            balance.value, amount
+        ))
+    })?;
+
+    // Split the blinding factor between the amount and the remaining balance
+    let mut random = [0u8; 64];
+    OsRng.fill_bytes(&mut random);
+    let amount_blinding = blinding_from_bytes(&random);
+    let remaining_blinding = core::confidential::sub_blindings(&balance.blinding()?, &amount_blinding)
+        .map_err(|e| WalletError::ProofError(e.to_string()))?;
+
+    let (range_proof, commitments) =
+        core::confidential::prove_range(&[amount, remaining], &[amount_blinding, remaining_blinding])
+            .map_err(|e| WalletError::ProofError(e.to_string()))?;
+
+    OsRng.fill_bytes(&mut random);
+    let encrypted_opening =
+        core::confidential::encrypt_opening(&recipient.viewing_key, amount, &amount_blinding, &random)
+            .map_err(|e| WalletError::TransactionError(e.to_string()))?;
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, token_id).await?;
+
+    let mut msg = SystemMsg::ConfidentialTransfer {
+        from,
+        to,
+        token_id,
+        amount_commitment: commitments[0],
+        new_sender_commitment: commitments[1],
+        range_proof,
+        encrypted_opening,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    let root = rpc::submit_message(&config.node, &msg).await?;
+
+    let balance = store.balance_mut(token_id);
+    balance.value = remaining;
+    balance.set_blinding(&remaining_blinding);
+    store.save()?;
+
+    Ok(root)
+}
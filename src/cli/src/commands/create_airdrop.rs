@@ -0,0 +1,67 @@
+//! Create airdrop command for the CLI wallet.
+
+use crate::airdrop::Distribution;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the create-airdrop command.
+///
+/// Commits a distribution built by build-airdrop, escrowing its total from
+/// the wallet's balance of the token until `expires_at`. Records the airdrop
+/// ID in the distribution file and returns it.
+pub async fn run<P: AsRef<Path>, Q: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    distribution_path: Q,
+    expires_at: u64,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let mut distribution = Distribution::load(&distribution_path)?;
+    let merkle_root = distribution.root()?;
+
+    let issuer = wallet.address()?;
+    info!(
+        "Creating airdrop of {} of token {} to {} recipients",
+        distribution.total,
+        distribution.token_id,
+        distribution.claims.len()
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, distribution.token_id).await?;
+
+    let mut msg = SystemMsg::CreateAirdrop {
+        issuer,
+        token_id: distribution.token_id,
+        merkle_root,
+        entries: distribution.claims.len() as u64,
+        total: distribution.total,
+        expires_at,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await?;
+
+    let airdrop_id = hex::encode(core::airdrop::airdrop_id(&issuer, distribution.token_id, &merkle_root));
+    distribution.airdrop_id = Some(airdrop_id.clone());
+    distribution.save(&distribution_path)?;
+
+    Ok(airdrop_id)
+}
@@ -0,0 +1,140 @@
+//! History command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use colored::Colorize;
+use core::types::Address;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Runs the history command.
+///
+/// Prints the transfers sent and received by the wallet, including any memo
+/// attached by the sender, and returns the number of transfers found.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    received_only: bool,
+) -> Result<usize, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    // Get the address
+    let address = wallet.address()?;
+    let address_hex = hex::encode(address);
+    info!("Getting transaction history for address: {}", address_hex);
+
+    let transactions = get_transactions_from_node(&config.node, &address).await?;
+    debug!("Node returned {} transactions", transactions.len());
+
+    let mut shown = 0;
+    for tx in &transactions {
+        let from = tx.get("from").and_then(|v| v.as_str()).unwrap_or_default();
+        let to = tx.get("to").and_then(|v| v.as_str()).unwrap_or_default();
+        let received = to == address_hex;
+
+        if received_only && !received {
+            continue;
+        }
+
+        let amount = tx.get("amount").and_then(|v| v.as_str()).unwrap_or("0");
+        let token_id = tx.get("token_id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let tx_hash = tx.get("tx_hash").and_then(|v| v.as_str()).unwrap_or("unknown");
+
+        if received {
+            println!(
+                "{} {} of token {} from 0x{} ({})",
+                "Received".green(),
+                amount,
+                token_id,
+                from,
+                tx_hash
+            );
+        } else {
+            println!(
+                "{} {} of token {} to 0x{} ({})",
+                "Sent".yellow(),
+                amount,
+                token_id,
+                to,
+                tx_hash
+            );
+        }
+
+        if let Some(memo) = tx.get("memo_text").and_then(|v| v.as_str()) {
+            if !memo.is_empty() {
+                println!("    {} {}", "Memo:".cyan(), memo);
+            }
+        }
+
+        shown += 1;
+    }
+
+    Ok(shown)
+}
+
+/// Gets the transaction records for an address from the node.
+async fn get_transactions_from_node(
+    node_url: &str,
+    address: &Address,
+) -> Result<Vec<serde_json::Value>, WalletError> {
+    // Create the JSON-RPC request
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": "getTransactions",
+        "params": [hex::encode(address)],
+        "id": 1
+    });
+
+    // Make sure to append /rpc to the node URL
+    let rpc_url = if node_url.ends_with("/rpc") {
+        node_url.to_string()
+    } else {
+        format!("{}/rpc", node_url)
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&rpc_url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| WalletError::NetworkError(e.to_string()))?;
+
+    let response_text = response.text().await
+        .map_err(|e| WalletError::NetworkError(format!("Failed to get response text: {}", e)))?;
+
+    // If the response is empty, return an error
+    if response_text.is_empty() {
+        return Err(WalletError::NetworkError("Empty response from node".to_string()));
+    }
+
+    // Parse the response
+    let response: serde_json::Value = serde_json::from_str(&response_text)
+        .map_err(|e| WalletError::NetworkError(format!("Failed to parse response: {}", e)))?;
+
+    // Check for errors in the response
+    if let Some(error) = response.get("error") {
+        if !error.is_null() {
+            return Err(WalletError::NodeRequestFailed(error.to_string()));
+        }
+    }
+
+    let result = response.get("result")
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("No result in response: {}", response_text)))?;
+
+    result
+        .as_array()
+        .cloned()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid transactions format: {}", result)))
+}
@@ -0,0 +1,27 @@
+//! Import Ethereum key command for the CLI wallet.
+
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the import-eth-key command.
+///
+/// Adds a secp256k1 (Ethereum) private key to the wallet, creating the wallet
+/// if it does not exist yet, and returns the Volt address it controls.
+pub async fn run<P: AsRef<Path>>(wallet_path: P, key_hex: &str) -> Result<String, WalletError> {
+    // Load the wallet, or create one to hold the key
+    let mut wallet = if wallet_path.as_ref().exists() {
+        Wallet::load(&wallet_path)?
+    } else {
+        Wallet::new()?
+    };
+
+    // Import the key and save the wallet
+    let address = wallet.import_eth_key(key_hex)?;
+    wallet.save(&wallet_path)?;
+    info!("Imported secp256k1 key into {}", wallet_path.as_ref().display());
+
+    Ok(format!("0x{}", hex::encode(address)))
+}
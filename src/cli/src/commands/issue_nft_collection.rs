@@ -0,0 +1,46 @@
+//! Issue NFT collection command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the issue-nft-collection command.
+///
+/// Issues a new NFT collection with the wallet as issuer and returns its
+/// token ID.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    metadata: &str,
+) -> Result<TokenId, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let issuer = wallet.address()?;
+    info!("Issuing NFT collection for {} with metadata: {}", hex::encode(issuer), metadata);
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::IssueNftCollection {
+        issuer,
+        metadata: metadata.to_string(),
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_nft_collection(&config.node, &msg).await
+}
@@ -9,6 +9,9 @@ use std::path::Path;
 use tracing::{debug, info};
 
 /// Runs the issue-token command.
+///
+/// `collateral` native tokens of the issuer are locked in escrow until the
+/// token is retired.
 pub async fn run<P: AsRef<Path>>(
     config: &WalletConfig,
     wallet_path: P,
@@ -39,6 +42,7 @@ pub async fn run<P: AsRef<Path>>(
         issuer,
         token_id: 0, // Will be assigned by the system
         metadata: metadata.to_string(),
+        collateral: collateral.unwrap_or(0),
         nonce,
         signature: core::types::Signature([0u8; 64]), // Will be filled in later
     };
@@ -54,11 +58,12 @@ pub async fn run<P: AsRef<Path>>(
 
     // Create the final message with the signature
     let final_message = match message {
-        core::types::SystemMsg::IssueToken { issuer, token_id: _, metadata, nonce, signature: _ } => {
+        core::types::SystemMsg::IssueToken { issuer, token_id: _, metadata, collateral, nonce, signature: _ } => {
             core::types::SystemMsg::IssueToken {
                 issuer,
                 token_id: 0, // Will be assigned by the system
                 metadata,
+                collateral,
                 nonce,
                 signature: core::types::Signature(signature_bytes),
             }
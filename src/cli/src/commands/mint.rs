@@ -1,7 +1,9 @@
 //! Mint command for the CLI wallet.
 
+use super::send::parse_address;
 use crate::config::WalletConfig;
 use crate::errors::WalletError;
+use crate::rpc;
 use crate::wallet::Wallet;
 use anyhow::Result;
 use core::{proofs::Proof, types::Address};
@@ -23,13 +25,19 @@ pub async fn run<P: AsRef<Path>>(
         .map_err(|e| WalletError::WalletError(format!("Failed to load wallet: {}", e)))?;
 
     // Get the wallet's address as hex (this should be the treasury address)
-    let from_hex = hex::encode(wallet.address()?);
+    let from = wallet.address()?;
+    let from_hex = hex::encode(from);
+    let to = parse_address(to_hex, "recipient")?;
 
-    // Create the message to sign
-    let message = format!("mint:{}:{}", to_hex, amount);
+    // The treasury's nonce is signed, so the mint cannot be replayed
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, 0).await?;
+    debug!("Treasury nonce: {}", nonce);
+
+    // Create the message to sign, in the form every node checks it in
+    let message = MintMsg::signing_message(&from, &to, 0, amount, nonce);
     
     // Sign the message
-    let signature = wallet.sign(message.as_bytes())
+    let signature = wallet.sign(&message)
         .map_err(|e| WalletError::TransactionError(format!("Failed to sign message: {}", e)))?;
     
     // Convert the signature to hex
@@ -51,7 +59,7 @@ pub async fn run<P: AsRef<Path>>(
             "jsonrpc": "2.0",
             "id": 1,
             "method": "mint",
-            "params": [from_hex, signature_hex, to_hex, amount]
+            "params": [from_hex, signature_hex, to_hex, amount, nonce]
         }))
         .send()
         .await
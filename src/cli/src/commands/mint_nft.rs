@@ -0,0 +1,69 @@
+//! Mint NFT command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the mint-nft command.
+///
+/// Mints item `item_id` of a collection issued by the wallet to `to_hex` and
+/// returns the new state root. `metadata_hash_hex` optionally commits to the
+/// content behind the metadata URI.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    collection_id: TokenId,
+    item_id: u64,
+    to_hex: &str,
+    metadata_uri: &str,
+    metadata_hash_hex: Option<&str>,
+) -> Result<String, WalletError> {
+    let to = parse_address(to_hex, "recipient")?;
+
+    let metadata_hash = match metadata_hash_hex {
+        Some(hash_hex) => {
+            let hash: [u8; 32] = hex::decode(hash_hex.trim_start_matches("0x"))
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| WalletError::TransactionError("Metadata hash must be 32 hex-encoded bytes".to_string()))?;
+            Some(hash)
+        }
+        None => None,
+    };
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let issuer = wallet.address()?;
+    info!("Minting item {} of collection {} to {}", item_id, collection_id, hex::encode(to));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::MintNft {
+        issuer,
+        collection_id,
+        item_id,
+        to,
+        metadata_uri: metadata_uri.to_string(),
+        metadata_hash,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -1,9 +1,57 @@
 //! Commands for the CLI wallet.
 
+pub mod airdrop_status;
+pub mod authorize_session_key;
+pub mod authorize_standing_order;
 pub mod balance;
+pub mod build_airdrop;
+pub mod burn;
+pub mod cancel_standing_order;
+pub mod channel;
+pub mod claim_airdrop;
+pub mod close_channel;
+pub mod compliance_status;
+pub mod confidential_balance;
+pub mod confidential_send;
+pub mod create_airdrop;
 pub mod export_seed;
+pub mod history;
+pub mod import_eth_key;
 pub mod init_seed;
+pub mod issue_nft_collection;
 pub mod mint;
+pub mod mint_nft;
+pub mod nft_owner;
+pub mod nfts;
+pub mod open_channel;
+pub mod pay_channel;
+pub mod proposal;
+pub mod propose;
+pub mod pull_payment;
+pub mod reclaim_airdrop;
+pub mod reclaim_channel;
+pub mod register_name;
+pub mod resolve_name;
+pub mod retire_token;
+pub mod revoke_session_key;
+pub mod scan;
 pub mod send;
+pub mod session_key;
+pub mod session_send;
+pub mod set_compliance_policy;
+pub mod set_name_target;
+pub mod shield;
+pub mod sign_message;
+pub mod sign_typed_data;
+pub mod standing_orders;
+pub mod start_channel_timeout;
+pub mod stealth_address;
+pub mod token_collateral;
+pub mod transfer_nft;
+pub mod transfer_name;
+pub mod unshield;
+pub mod update_compliance_list;
+pub mod verify_message;
+pub mod vote;
 pub mod issue_token;
 pub mod mint_token;
@@ -0,0 +1,16 @@
+//! NFT owner command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::nft::NftItem;
+use core::types::TokenId;
+
+/// Runs the nft-owner command.
+///
+/// Looks an item up on the node and returns it once the ownership proof has
+/// been checked.
+pub async fn run(config: &WalletConfig, collection_id: TokenId, item_id: u64) -> Result<NftItem, WalletError> {
+    rpc::get_nft(&config.node, collection_id, item_id).await
+}
@@ -0,0 +1,47 @@
+//! NFTs command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+/// Runs the nfts command.
+///
+/// Prints the items owned by the wallet and returns how many there are.
+pub async fn run<P: AsRef<Path>>(config: &WalletConfig, wallet_path: P) -> Result<usize, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    let result = rpc::call(&config.node, "getNftsByOwner", serde_json::json!([hex::encode(owner)])).await?;
+    let items = result
+        .as_array()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid NFT list: {}", result)))?;
+
+    for item in items {
+        let collection_id = item.get("collection_id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let item_id = item.get("item_id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let metadata_uri = item.get("metadata_uri").and_then(|v| v.as_str()).unwrap_or("");
+
+        println!(
+            "{} {} of collection {} ({})",
+            "Item".green(),
+            item_id,
+            collection_id,
+            metadata_uri
+        );
+    }
+
+    Ok(items.len())
+}
@@ -0,0 +1,61 @@
+//! Open channel command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Balance, Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the open-channel command.
+///
+/// Opens a payment channel to a payee, escrowing `deposit` of a token from
+/// the wallet, and returns the hex-encoded channel ID.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    payee: &str,
+    token_id: TokenId,
+    deposit: Balance,
+    timeout_secs: u64,
+) -> Result<String, WalletError> {
+    let payee = parse_address(payee, "payee")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payer = wallet.address()?;
+    info!(
+        "Opening a channel to 0x{} with {} of token {}",
+        hex::encode(payee),
+        deposit,
+        token_id
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payer, token_id).await?;
+
+    let mut msg = SystemMsg::OpenChannel {
+        payer,
+        payee,
+        token_id,
+        deposit,
+        timeout_secs,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await?;
+    Ok(hex::encode(core::channels::channel_id(&payer, &payee, token_id, nonce)))
+}
@@ -0,0 +1,66 @@
+//! Pay channel command for the CLI wallet.
+
+use crate::commands::channel::parse_channel_id;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::channels::ChannelVoucher;
+use core::types::Balance;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the pay-channel command.
+///
+/// Signs a voucher promising the payee `amount` in total through a channel
+/// paid by the wallet, and returns it hex-encoded for handing to the payee.
+/// Nothing is sent to the network.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    channel_id: &str,
+    amount: Balance,
+) -> Result<String, WalletError> {
+    let channel_id = parse_channel_id(channel_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let channel = rpc::get_channel(&config.node, &channel_id)
+        .await?
+        .ok_or_else(|| WalletError::TransactionError(format!("Channel {} is not open", hex::encode(channel_id))))?;
+
+    if channel.payer != wallet.address()? {
+        return Err(WalletError::TransactionError(
+            "The wallet is not the channel's payer".to_string(),
+        ));
+    }
+    if amount > channel.deposit {
+        return Err(WalletError::InsufficientBalance(format!(
+            "Channel deposit is {}, voucher amount is {}",
+            channel.deposit, amount
+        )));
+    }
+
+    info!("Signing a voucher for {} through channel {}", amount, hex::encode(channel_id));
+
+    let signature = wallet.sign_message(&ChannelVoucher::signing_bytes(&channel_id, amount))?;
+    let voucher = ChannelVoucher {
+        channel_id,
+        amount,
+        signature,
+    };
+
+    let bytes = bincode::serialize(&voucher)
+        .map_err(|e| WalletError::TransactionError(format!("Failed to serialize voucher: {}", e)))?;
+    Ok(hex::encode(bytes))
+}
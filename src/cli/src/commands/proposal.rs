@@ -0,0 +1,15 @@
+//! Proposal command for the CLI wallet.
+
+use crate::commands::vote::parse_proposal_id;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::governance::Proposal;
+
+/// Runs the proposal command.
+///
+/// Gets a proposal and its tally from the node, checking the node's proof.
+pub async fn run(config: &WalletConfig, proposal_id: &str) -> Result<Proposal, WalletError> {
+    rpc::get_proposal(&config.node, &parse_proposal_id(proposal_id)?).await
+}
@@ -0,0 +1,74 @@
+//! Propose command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::compliance::CompliancePolicy;
+use core::governance::ProposalAction;
+use core::types::{Balance, Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the propose command.
+///
+/// Proposes either a new maximum supply or a new compliance policy for a
+/// token the wallet holds, open for votes for `voting_period_secs`, and
+/// returns the hex-encoded proposal ID.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: TokenId,
+    max_supply: Option<Balance>,
+    compliance_policy: Option<&str>,
+    voting_period_secs: u64,
+) -> Result<String, WalletError> {
+    let action = match (max_supply, compliance_policy) {
+        (Some(max_supply), None) => ProposalAction::SetMaxSupply(max_supply),
+        (None, Some(policy)) => {
+            let policy: CompliancePolicy = policy
+                .parse()
+                .map_err(|e: core::CoreError| WalletError::TransactionError(e.to_string()))?;
+            ProposalAction::SetCompliancePolicy(policy)
+        }
+        _ => {
+            return Err(WalletError::TransactionError(
+                "Propose exactly one of a max supply or a compliance policy".to_string(),
+            ));
+        }
+    };
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let proposer = wallet.address()?;
+    info!("Proposing to {} for token {}", action, token_id);
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &proposer, 0).await?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut msg = SystemMsg::CreateProposal {
+        proposer,
+        token_id,
+        action,
+        timestamp,
+        voting_period_secs,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_proposal(&config.node, &msg).await
+}
@@ -0,0 +1,54 @@
+//! Pull payment command for the CLI wallet.
+
+use crate::commands::cancel_standing_order::parse_order_id;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the pull-payment command.
+///
+/// Pulls the current period's payment of a standing order paid to the
+/// wallet and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    order_id: &str,
+) -> Result<String, WalletError> {
+    let order_id = parse_order_id(order_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payee = wallet.address()?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!("Pulling payment of standing order {}", hex::encode(order_id));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payee, 0).await?;
+
+    let mut msg = SystemMsg::PullPayment {
+        payee,
+        order_id,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,55 @@
+//! Reclaim airdrop command for the CLI wallet.
+
+use crate::airdrop::Distribution;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the reclaim-airdrop command.
+///
+/// Refunds the unclaimed amount of an expired airdrop created by the wallet,
+/// and returns the new state root.
+pub async fn run<P: AsRef<Path>, Q: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    distribution_path: Q,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let distribution = Distribution::load(&distribution_path)?;
+    let airdrop_id = distribution.airdrop_id()?;
+
+    let issuer = wallet.address()?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!("Reclaiming airdrop {}", hex::encode(airdrop_id));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::ReclaimAirdrop {
+        issuer,
+        airdrop_id,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,54 @@
+//! Reclaim channel command for the CLI wallet.
+
+use crate::commands::channel::parse_channel_id;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the reclaim-channel command.
+///
+/// Refunds the deposit of a channel paid by the wallet once its dispute
+/// timeout has ended, and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    channel_id: &str,
+) -> Result<String, WalletError> {
+    let channel_id = parse_channel_id(channel_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payer = wallet.address()?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!("Reclaiming channel {}", hex::encode(channel_id));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payer, 0).await?;
+
+    let mut msg = SystemMsg::ReclaimChannel {
+        payer,
+        channel_id,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
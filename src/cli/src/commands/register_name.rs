@@ -0,0 +1,55 @@
+//! Register name command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the register-name command.
+///
+/// Registers `name` for the wallet, resolving to `target_hex` or to the
+/// wallet's own address, and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    name: &str,
+    target_hex: Option<&str>,
+) -> Result<String, WalletError> {
+    core::names::validate_name(name).map_err(|e| WalletError::InvalidAddress(e.to_string()))?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    let target = match target_hex {
+        Some(target_hex) => parse_address(target_hex, "target")?,
+        None => owner,
+    };
+    info!("Registering {} for {} -> {}", name, hex::encode(owner), hex::encode(target));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, 0).await?;
+
+    let mut msg = SystemMsg::RegisterName {
+        owner,
+        name: name.to_string(),
+        target,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,25 @@
+//! Resolve name command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::names::NameRecord;
+
+/// Runs the resolve-name command.
+///
+/// Looks a name up on the node and returns its record once the resolution
+/// proof has been checked, against `root` if given and otherwise against the
+/// root the node returns.
+pub async fn run(config: &WalletConfig, name: &str, root: Option<&str>) -> Result<NameRecord, WalletError> {
+    let root = root.map(parse_root).transpose()?;
+    rpc::resolve_name(&config.node, name, root.as_ref()).await
+}
+
+/// Parses a hex-encoded state root.
+pub fn parse_root(root: &str) -> Result<[u8; 32], WalletError> {
+    hex::decode(root.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::ProofError(format!("Invalid state root: {}", root)))
+}
@@ -0,0 +1,46 @@
+//! Retire token command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the retire-token command.
+///
+/// Retires a token issued by the wallet whose supply has been fully burned,
+/// releasing its collateral, and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: TokenId,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let issuer = wallet.address()?;
+    info!("Retiring token {}", token_id);
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::RetireToken {
+        issuer,
+        token_id,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,49 @@
+//! Revoke session key command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the revoke-session-key command.
+///
+/// Revokes a session key of the wallet's account and returns the new state
+/// root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    session_key: &str,
+) -> Result<String, WalletError> {
+    let session_key = parse_address(session_key, "session key")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    info!("Revoking session key 0x{}", hex::encode(session_key));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, 0).await?;
+
+    let mut msg = SystemMsg::RevokeSessionKey {
+        owner,
+        session_key,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,93 @@
+//! Scan command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::stealth::{StealthOutput, StealthStore};
+use crate::wallet::Wallet;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Runs the scan command.
+///
+/// Checks the stealth payments processed since the last scan for ones sent to
+/// the wallet's stealth meta-address, prints and records them, and returns the
+/// number of new payments found.
+pub async fn run<P: AsRef<Path>>(config: &WalletConfig, wallet_path: P) -> Result<usize, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let (scan_secret, _) = wallet.stealth_secrets()?;
+    let meta = wallet.stealth_meta_address()?;
+
+    let mut store = StealthStore::load_for_wallet(&wallet_path)?;
+    info!("Scanning stealth payments since {}", store.last_scanned);
+
+    // Payments from the last scanned second are fetched again, as more may
+    // have arrived during that second; already recorded ones are skipped
+    let transfers = rpc::call(&config.node, "getStealthTransfers", serde_json::json!([store.last_scanned])).await?;
+    let transfers = transfers
+        .as_array()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid transfers format: {}", transfers)))?;
+    debug!("Node returned {} stealth payments", transfers.len());
+
+    let mut found = 0;
+    for tx in transfers {
+        let field = |name: &str| -> Option<[u8; 32]> {
+            tx.get(name)
+                .and_then(|v| v.as_str())
+                .and_then(|s| hex::decode(s).ok())
+                .and_then(|bytes| bytes.try_into().ok())
+        };
+
+        let (to, ephemeral_key) = match (field("to"), field("ephemeral_key")) {
+            (Some(to), Some(ephemeral_key)) => (to, ephemeral_key),
+            _ => continue,
+        };
+        let tx_hash = tx.get("tx_hash").and_then(|v| v.as_str()).unwrap_or_default();
+        let timestamp = tx.get("timestamp").and_then(|v| v.as_u64()).unwrap_or_default();
+        store.last_scanned = store.last_scanned.max(timestamp);
+
+        if store.contains(tx_hash) {
+            continue;
+        }
+
+        let tweak = match core::stealth::scan(&scan_secret, &meta.spend_key, &to, &ephemeral_key) {
+            Some(tweak) => tweak,
+            None => continue,
+        };
+
+        let output = StealthOutput {
+            address: hex::encode(to),
+            tweak: hex::encode(tweak),
+            token_id: tx.get("token_id").and_then(|v| v.as_u64()).unwrap_or(0),
+            amount: tx.get("amount").and_then(|v| v.as_str()).unwrap_or("0").to_string(),
+            tx_hash: tx_hash.to_string(),
+        };
+
+        println!(
+            "{} {} of token {} at 0x{} ({})",
+            "Received".green(),
+            output.amount,
+            output.token_id,
+            output.address,
+            output.tx_hash
+        );
+
+        store.outputs.push(output);
+        found += 1;
+    }
+
+    store.save()?;
+    Ok(found)
+}
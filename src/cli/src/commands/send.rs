@@ -2,23 +2,65 @@
 
 use crate::config::WalletConfig;
 use crate::errors::WalletError;
+use crate::rpc;
+use crate::stealth::StealthStore;
 use crate::wallet::Wallet;
 use anyhow::Result;
+use core::stealth::{StealthMetaAddress, STEALTH_ADDRESS_PREFIX};
 use core::{proofs::Proof, types::Address};
 use network::types::UpdateMsg;
+use rand::rngs::OsRng;
+use rand::RngCore;
 use std::path::Path;
 use tracing::{debug, info};
 
 /// Runs the send command.
+///
+/// `to_hex` is an address, a name, or a stealth meta-address, in which case
+/// the tokens go to a fresh one-time address of the recipient. A name's
+/// resolution proof is checked against `root`, a state root obtained
+/// independently of the node; without it the node's resolution is trusted.
+/// `from_stealth` spends from one of the wallet's own one-time addresses
+/// found by `scan`.
 pub async fn run<P: AsRef<Path>>(
     config: &WalletConfig,
     wallet_path: P,
     to_hex: &str,
     token_id: u64,
     amount: u128,
+    memo: Option<&str>,
+    from_stealth: Option<&str>,
+    root: Option<&str>,
 ) -> Result<String, WalletError> {
+    // Check the memo before touching the network
+    let memo = memo.map(|m| m.as_bytes().to_vec()).unwrap_or_default();
+    core::types::validate_memo(&memo)
+        .map_err(|e| WalletError::TransactionError(e.to_string()))?;
+
+    // Resolve the recipient, deriving a one-time address for stealth meta-addresses
+    // and checking the proof of names
+    let (to, ephemeral_key) = if to_hex.starts_with(STEALTH_ADDRESS_PREFIX) {
+        let meta: StealthMetaAddress = to_hex
+            .parse()
+            .map_err(|e: core::CoreError| WalletError::InvalidAddress(e.to_string()))?;
+
+        let mut ephemeral_bytes = [0u8; 64];
+        OsRng.fill_bytes(&mut ephemeral_bytes);
+        let (to, ephemeral_key) = core::stealth::derive_one_time_address(&meta, &ephemeral_bytes)
+            .map_err(|e| WalletError::InvalidAddress(e.to_string()))?;
+        (to, Some(ephemeral_key))
+    } else if core::names::is_name(to_hex) {
+        let root = root.map(super::resolve_name::parse_root).transpose()?;
+        let record = rpc::resolve_name(&config.node, to_hex, root.as_ref()).await?;
+        info!("Resolved {} to {}", to_hex, hex::encode(record.target));
+        (record.target, None)
+    } else {
+        (parse_address(to_hex, "recipient")?, None)
+    };
+    let to_hex = hex::encode(to);
+
     // Load the wallet
-    let wallet = match Wallet::load(wallet_path) {
+    let wallet = match Wallet::load(&wallet_path) {
         Ok(wallet) => wallet,
         Err(e) => {
             return Err(WalletError::WalletError(format!(
@@ -28,9 +70,16 @@ pub async fn run<P: AsRef<Path>>(
         }
     };
 
-    // Get the sender address
-    let from = wallet.address()?;
-    let from_hex = hex::encode(&from);
+    // Get the sender address and, when spending a stealth payment, its one-time secret
+    let (from, stealth_secret) = match from_stealth {
+        Some(address_hex) => {
+            let address = parse_address(address_hex, "stealth")?;
+            let store = StealthStore::load_for_wallet(&wallet_path)?;
+            (address, Some(store.one_time_secret(&wallet, &address)?))
+        }
+        None => (wallet.address()?, None),
+    };
+    let from_hex = hex::encode(from);
     info!("Sending {} tokens with ID {} from {} to {}", amount, token_id, from_hex, to_hex);
 
     // Verify that the sender has enough balance
@@ -48,21 +97,27 @@ pub async fn run<P: AsRef<Path>>(
     debug!("Sender nonce for token {}: {}", token_id, nonce);
 
     // Create a transaction message
-    let transaction = serde_json::json!({
-        "from": from_hex,
-        "to": to_hex.trim_start_matches("0x"),
-        "token_id": token_id,
-        "amount": amount,
-        "nonce": nonce
-    });
+    let transaction = core::types::transfer_payload(
+        &from,
+        &to,
+        token_id,
+        amount,
+        nonce,
+        &memo,
+        ephemeral_key.as_ref(),
+    );
 
     // Serialize the transaction for signing
     let transaction_bytes = serde_json::to_vec(&transaction)
         .map_err(|e| WalletError::TransactionError(format!("Failed to serialize transaction: {}", e)))?;
 
-    // Sign the transaction
-    let signature = wallet.sign(&transaction_bytes)?;
-    let signature_hex = hex::encode(signature.to_bytes());
+    // Sign the transaction with the account's scheme, or the one-time secret
+    let signature = match &stealth_secret {
+        Some(secret) => core::stealth::sign_one_time(secret, &transaction_bytes)
+            .map_err(|e| WalletError::TransactionError(e.to_string()))?,
+        None => wallet.sign_with_scheme(&transaction_bytes)?,
+    };
+    let signature_hex = hex::encode(signature.0);
 
     // Make sure to append /rpc to the node URL
     let rpc_url = if config.node.ends_with("/rpc") {
@@ -73,6 +128,15 @@ pub async fn run<P: AsRef<Path>>(
     
     let client = reqwest::Client::new();
     
+    // Only send the memo and ephemeral key parameters when needed, so older nodes keep working
+    let mut params = serde_json::json!([from_hex, to_hex, token_id, amount, nonce, signature_hex]);
+    if !memo.is_empty() || ephemeral_key.is_some() {
+        params.as_array_mut().unwrap().push(serde_json::json!(hex::encode(&memo)));
+    }
+    if let Some(ephemeral_key) = ephemeral_key {
+        params.as_array_mut().unwrap().push(serde_json::json!(hex::encode(ephemeral_key)));
+    }
+
     // Call the send RPC method on the node
     let response = client
         .post(&rpc_url)
@@ -80,7 +144,7 @@ pub async fn run<P: AsRef<Path>>(
             "jsonrpc": "2.0",
             "id": 1,
             "method": "send",
-            "params": [from_hex, to_hex, token_id, amount, nonce, signature_hex]
+            "params": params
         }))
         .send()
         .await
@@ -590,3 +654,22 @@ async fn get_nonce_with_token_from_node(node_url: &str, address: &Address, token
 
     Ok(nonce)
 }
+
+/// Parses a 32-byte hex address given on the command line.
+pub fn parse_address(address_hex: &str, what: &str) -> Result<Address, WalletError> {
+    let bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
+        WalletError::InvalidAddress(format!("Invalid {} address: {}", what, e))
+    })?;
+
+    if bytes.len() != 32 {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid {} address length: {} (expected 32)",
+            what,
+            bytes.len()
+        )));
+    }
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&bytes);
+    Ok(address)
+}
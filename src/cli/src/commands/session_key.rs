@@ -0,0 +1,19 @@
+//! Session key command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::session_keys::SessionKey;
+
+/// Runs the session-key command.
+///
+/// Gets an owner's delegation to a session key from the node, checking the
+/// node's proof. Returns `None` if the key is not authorized.
+pub async fn run(config: &WalletConfig, owner: &str, session_key: &str) -> Result<Option<SessionKey>, WalletError> {
+    let owner = parse_address(owner, "owner")?;
+    let session_key = parse_address(session_key, "session key")?;
+
+    rpc::get_session_key(&config.node, &owner, &session_key).await
+}
@@ -0,0 +1,69 @@
+//! Session send command for the CLI wallet.
+
+use crate::commands::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Balance, Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the session-send command.
+///
+/// Sends tokens from an owner's account, signing with the wallet as one of
+/// the owner's session keys, and returns the new state root. The node
+/// refuses the transfer if it exceeds what the key may still spend in the
+/// current window.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    owner: &str,
+    to: &str,
+    token_id: TokenId,
+    amount: Balance,
+) -> Result<String, WalletError> {
+    let owner = parse_address(owner, "owner")?;
+    let to = parse_address(to, "recipient")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let session_key = wallet.address()?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!(
+        "Sending {} of token {} from 0x{} to 0x{} with a session key",
+        amount,
+        token_id,
+        hex::encode(owner),
+        hex::encode(to)
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, token_id).await?;
+
+    let mut msg = SystemMsg::SessionTransfer {
+        owner,
+        session_key,
+        to,
+        token_id,
+        amount,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,53 @@
+//! Set compliance policy command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::compliance::CompliancePolicy;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the set-compliance-policy command.
+///
+/// Sets who may hold a token issued by the wallet and returns the new state
+/// root. `policy` is "open", "allow-list" or "deny-list".
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: TokenId,
+    policy: &str,
+) -> Result<String, WalletError> {
+    let policy: CompliancePolicy = policy
+        .parse()
+        .map_err(|e: core::CoreError| WalletError::TransactionError(e.to_string()))?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let issuer = wallet.address()?;
+    info!("Setting the compliance policy of token {} to {}", token_id, policy);
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::SetCompliancePolicy {
+        issuer,
+        token_id,
+        policy,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
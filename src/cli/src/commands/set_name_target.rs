@@ -0,0 +1,51 @@
+//! Set name target command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the set-name-target command.
+///
+/// Points a name owned by the wallet at `target_hex` and returns the new
+/// state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    name: &str,
+    target_hex: &str,
+) -> Result<String, WalletError> {
+    let target = parse_address(target_hex, "target")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    info!("Pointing {} at {}", name, hex::encode(target));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, 0).await?;
+
+    let mut msg = SystemMsg::SetNameTarget {
+        owner,
+        name: name.to_string(),
+        target,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
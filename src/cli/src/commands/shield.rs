@@ -0,0 +1,67 @@
+//! Shield command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::confidential::{self, ConfidentialStore};
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the shield command.
+///
+/// Moves `amount` of the wallet's public balance into its confidential
+/// balance and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: u64,
+    amount: u64,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let from = wallet.address()?;
+    info!("Shielding {} of token {} for {}", amount, token_id, hex::encode(from));
+
+    // Bring the local opening up to date before changing it
+    let mut store = ConfidentialStore::load_for_wallet(&wallet_path)?;
+    confidential::sync(&config.node, &wallet, &mut store, token_id).await?;
+
+    let viewing_key = core::confidential::viewing_key(&wallet.confidential_view_secret()?)
+        .map_err(|e| WalletError::WalletError(e.to_string()))?;
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, token_id).await?;
+
+    let mut msg = SystemMsg::Shield {
+        from,
+        token_id,
+        amount,
+        viewing_key,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    let root = rpc::submit_message(&config.node, &msg).await?;
+
+    // The shielded amount is committed with a zero blinding factor
+    let balance = store.balance_mut(token_id);
+    balance.value = balance
+        .value
+        .checked_add(amount)
+        .ok_or_else(|| WalletError::InvalidAmount("Confidential balance overflow".to_string()))?;
+    store.save()?;
+
+    Ok(root)
+}
+
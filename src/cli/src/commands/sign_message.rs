@@ -0,0 +1,30 @@
+//! Sign message command for the CLI wallet.
+
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the sign-message command.
+///
+/// Returns the signing address and the hex-encoded signature.
+pub async fn run<P: AsRef<Path>>(wallet_path: P, message: &str) -> Result<(String, String), WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let address = wallet.address()?;
+    info!("Signing message with address: {}", hex::encode(address));
+
+    let signature = wallet.sign_message(message.as_bytes())?;
+
+    Ok((format!("0x{}", hex::encode(address)), hex::encode(signature.0)))
+}
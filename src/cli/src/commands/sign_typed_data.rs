@@ -0,0 +1,62 @@
+//! Sign typed data command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use colored::Colorize;
+use core::typed_data::TypedData;
+use std::path::Path;
+use tracing::info;
+
+/// Runs the sign-typed-data command.
+///
+/// Reads a typed-data document from `data_path`, prints it in decoded form so
+/// the user can check what is being signed, and returns the signing address
+/// and the hex-encoded signature.
+pub async fn run<P: AsRef<Path>, D: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    data_path: D,
+) -> Result<(String, String), WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    // Load and check the typed data
+    let contents = std::fs::read_to_string(data_path)
+        .map_err(|e| WalletError::WalletError(format!("Failed to read typed data: {}", e)))?;
+    let data: TypedData = serde_json::from_str(&contents)
+        .map_err(|e| WalletError::WalletError(format!("Failed to parse typed data: {}", e)))?;
+    let signing_hash = data
+        .signing_hash()
+        .map_err(|e| WalletError::WalletError(e.to_string()))?;
+
+    // Show exactly what is being signed
+    println!("{}", "Signing typed data:".green());
+    print!("{}", data);
+    println!("{} 0x{}", "Hash:".green(), hex::encode(signing_hash));
+
+    if data.domain.network_id != config.network {
+        println!(
+            "{} the data is for network {}, but the wallet is configured for {}",
+            "Warning:".yellow(),
+            data.domain.network_id,
+            config.network
+        );
+    }
+
+    let address = wallet.address()?;
+    info!("Signing typed data with address: {}", hex::encode(address));
+
+    let signature = wallet.sign_typed_data(&data)?;
+
+    Ok((format!("0x{}", hex::encode(address)), hex::encode(signature.0)))
+}
@@ -0,0 +1,65 @@
+//! Standing orders command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+
+/// Runs the standing-orders command.
+///
+/// Prints the active standing orders the wallet pays or is paid by and
+/// returns how many there are.
+pub async fn run<P: AsRef<Path>>(config: &WalletConfig, wallet_path: P) -> Result<usize, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let address = hex::encode(wallet.address()?);
+    let result = rpc::call(&config.node, "getStandingOrders", serde_json::json!([address])).await?;
+    let orders = result
+        .as_array()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid standing order list: {}", result)))?;
+
+    for order in orders {
+        let order_id = order.get("order_id").and_then(|v| v.as_str()).unwrap_or("unknown");
+        let payer = order.get("payer").and_then(|v| v.as_str()).unwrap_or_default();
+        let payee = order.get("payee").and_then(|v| v.as_str()).unwrap_or_default();
+        let token_id = order.get("token_id").and_then(|v| v.as_u64()).unwrap_or(0);
+        let amount = order.get("amount").map(|v| v.to_string()).unwrap_or_default();
+        let period_secs = order.get("period_secs").and_then(|v| v.as_u64()).unwrap_or(0);
+
+        if payer == address {
+            println!(
+                "{} {} of token {} every {}s to 0x{} ({})",
+                "Paying".yellow(),
+                amount,
+                token_id,
+                period_secs,
+                payee,
+                order_id
+            );
+        } else {
+            println!(
+                "{} {} of token {} every {}s from 0x{} ({})",
+                "Collecting".green(),
+                amount,
+                token_id,
+                period_secs,
+                payer,
+                order_id
+            );
+        }
+    }
+
+    Ok(orders.len())
+}
@@ -0,0 +1,55 @@
+//! Start channel timeout command for the CLI wallet.
+
+use crate::commands::channel::parse_channel_id;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the start-channel-timeout command.
+///
+/// Starts the dispute timeout of a channel paid by the wallet and returns the
+/// new state root. The payee can still close the channel until the timeout
+/// ends.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    channel_id: &str,
+) -> Result<String, WalletError> {
+    let channel_id = parse_channel_id(channel_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let payer = wallet.address()?;
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    info!("Starting the timeout of channel {}", hex::encode(channel_id));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &payer, 0).await?;
+
+    let mut msg = SystemMsg::StartChannelTimeout {
+        payer,
+        channel_id,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,25 @@
+//! Stealth address command for the CLI wallet.
+
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use std::path::Path;
+
+/// Runs the stealth-address command.
+///
+/// Returns the wallet's stealth meta-address, which senders can pass to
+/// `send --to` instead of a plain address.
+pub async fn run<P: AsRef<Path>>(wallet_path: P) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    Ok(wallet.stealth_meta_address()?.to_string())
+}
@@ -0,0 +1,16 @@
+//! Token collateral command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use anyhow::Result;
+use core::collateral::TokenEscrow;
+use core::types::TokenId;
+
+/// Runs the token-collateral command.
+///
+/// Returns the escrow of a token once the node's proof has been checked, or
+/// `None` if the token was issued without collateral.
+pub async fn run(config: &WalletConfig, token_id: TokenId) -> Result<Option<TokenEscrow>, WalletError> {
+    rpc::get_token_collateral(&config.node, token_id).await
+}
@@ -0,0 +1,51 @@
+//! Transfer name command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the transfer-name command.
+///
+/// Hands a name owned by the wallet over to `new_owner_hex` and returns the
+/// new state root. The name keeps resolving to its current target.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    name: &str,
+    new_owner_hex: &str,
+) -> Result<String, WalletError> {
+    let new_owner = parse_address(new_owner_hex, "new owner")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let owner = wallet.address()?;
+    info!("Transferring {} from {} to {}", name, hex::encode(owner), hex::encode(new_owner));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &owner, 0).await?;
+
+    let mut msg = SystemMsg::TransferName {
+        owner,
+        name: name.to_string(),
+        new_owner,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
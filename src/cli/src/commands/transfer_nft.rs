@@ -0,0 +1,53 @@
+//! Transfer NFT command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the transfer-nft command.
+///
+/// Transfers an item owned by the wallet to `to_hex` and returns the new
+/// state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    collection_id: TokenId,
+    item_id: u64,
+    to_hex: &str,
+) -> Result<String, WalletError> {
+    let to = parse_address(to_hex, "recipient")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let from = wallet.address()?;
+    info!("Transferring item {} of collection {} from {} to {}", item_id, collection_id, hex::encode(from), hex::encode(to));
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, 0).await?;
+
+    let mut msg = SystemMsg::TransferNft {
+        from,
+        to,
+        collection_id,
+        item_id,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,77 @@
+//! Unshield command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::confidential::{self, ConfidentialStore};
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::types::{Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the unshield command.
+///
+/// Moves `amount` of the wallet's confidential balance back to its public
+/// balance and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: u64,
+    amount: u64,
+) -> Result<String, WalletError> {
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let from = wallet.address()?;
+    info!("Unshielding {} of token {} for {}", amount, token_id, hex::encode(from));
+
+    let mut store = ConfidentialStore::load_for_wallet(&wallet_path)?;
+    if confidential::sync(&config.node, &wallet, &mut store, token_id).await?.is_none() {
+        return Err(WalletError::InsufficientBalance(format!(
+            "No confidential balance for token {}",
+            token_id
+        )));
+    }
+
+    let balance = store.balance_mut(token_id);
+    let remaining = balance.value.checked_sub(amount).ok_or_else(|| {
+        WalletError::InsufficientBalance(format!(
+            "Insufficient confidential balance: {} (required: {})",
+            balance.value, amount
+        ))
+    })?;
+
+    // The unshielded amount is public, so the remaining balance keeps the blinding factor
+    let blinding = balance.blinding()?;
+    let (range_proof, commitments) = core::confidential::prove_range(&[remaining], &[blinding])
+        .map_err(|e| WalletError::ProofError(e.to_string()))?;
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &from, token_id).await?;
+
+    let mut msg = SystemMsg::Unshield {
+        from,
+        token_id,
+        amount,
+        new_commitment: commitments[0],
+        range_proof,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    let root = rpc::submit_message(&config.node, &msg).await?;
+
+    store.balance_mut(token_id).value = remaining;
+    store.save()?;
+
+    Ok(root)
+}
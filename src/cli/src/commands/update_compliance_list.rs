@@ -0,0 +1,65 @@
+//! Update compliance list command for the CLI wallet.
+
+use super::send::parse_address;
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::compliance::ComplianceList;
+use core::types::{Signature, SystemMsg, TokenId};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the update-compliance-list command.
+///
+/// Adds `address_hex` to, or with `remove` removes it from, the allow or
+/// deny list of a token issued by the wallet and returns the new state root.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    token_id: TokenId,
+    list: &str,
+    address_hex: &str,
+    remove: bool,
+) -> Result<String, WalletError> {
+    let list: ComplianceList = list
+        .parse()
+        .map_err(|e: core::CoreError| WalletError::TransactionError(e.to_string()))?;
+    let address = parse_address(address_hex, "listed")?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let issuer = wallet.address()?;
+    info!(
+        "{} {} the {} list of token {}",
+        if remove { "Removing" } else { "Adding" },
+        hex::encode(address),
+        list,
+        token_id
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &issuer, 0).await?;
+
+    let mut msg = SystemMsg::UpdateComplianceList {
+        issuer,
+        token_id,
+        list,
+        address,
+        listed: !remove,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await
+}
@@ -0,0 +1,47 @@
+//! Verify message command for the CLI wallet.
+
+use crate::errors::WalletError;
+use anyhow::Result;
+use tracing::debug;
+
+/// Runs the verify-message command.
+///
+/// Checks locally that `signature_hex` is a signed-message signature over
+/// `message` by the key controlling `address_hex`.
+pub async fn run(address_hex: &str, message: &str, signature_hex: &str) -> Result<bool, WalletError> {
+    // Parse the address
+    let address_bytes = hex::decode(address_hex.trim_start_matches("0x"))
+        .map_err(|e| WalletError::InvalidAddress(format!("Invalid address: {}", e)))?;
+
+    if address_bytes.len() != 32 {
+        return Err(WalletError::InvalidAddress(format!(
+            "Invalid address length: {} (expected 32)",
+            address_bytes.len()
+        )));
+    }
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&address_bytes);
+
+    // Parse the signature
+    let signature_bytes = hex::decode(signature_hex.trim_start_matches("0x"))
+        .map_err(|e| WalletError::WalletError(format!("Invalid signature: {}", e)))?;
+
+    if signature_bytes.len() != 64 {
+        return Err(WalletError::WalletError(format!(
+            "Invalid signature length: {} (expected 64)",
+            signature_bytes.len()
+        )));
+    }
+
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&signature_bytes);
+
+    match core::crypto::verify_signed_message(&address, message.as_bytes(), &core::types::Signature(signature)) {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            debug!("Message verification failed: {}", e);
+            Ok(false)
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Vote command for the CLI wallet.
+
+use crate::config::WalletConfig;
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use anyhow::Result;
+use core::governance::{ProposalId, ProposalStatus};
+use core::types::{Balance, Signature, SystemMsg};
+use std::path::Path;
+use tracing::info;
+
+/// Runs the vote command.
+///
+/// Votes on a proposal with the wallet's balance at the proposal's snapshot,
+/// after checking the node's snapshot proof, and returns the voting weight.
+pub async fn run<P: AsRef<Path>>(
+    config: &WalletConfig,
+    wallet_path: P,
+    proposal_id: &str,
+    support: bool,
+) -> Result<Balance, WalletError> {
+    let proposal_id = parse_proposal_id(proposal_id)?;
+
+    // Load the wallet
+    let wallet = match Wallet::load(&wallet_path) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            return Err(WalletError::WalletError(format!(
+                "Failed to load wallet: {}",
+                e
+            )));
+        }
+    };
+
+    let voter = wallet.address()?;
+
+    let proposal = rpc::get_proposal(&config.node, &proposal_id).await?;
+    if proposal.status != ProposalStatus::Open {
+        return Err(WalletError::TransactionError(format!("Proposal is already {}", proposal.status)));
+    }
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    if timestamp >= proposal.deadline {
+        return Err(WalletError::TransactionError(format!(
+            "Voting on the proposal closed at {}",
+            proposal.deadline
+        )));
+    }
+
+    let proof = rpc::get_voting_proof(&config.node, &proposal_id, &voter)
+        .await?
+        .ok_or_else(|| {
+            WalletError::InsufficientBalance(format!("No balance of token {} at the snapshot", proposal.token_id))
+        })?;
+    let weight = core::governance::verify_voting_weight(&proposal, &voter, &proof)
+        .map_err(|e| WalletError::ProofError(e.to_string()))?;
+
+    info!(
+        "Voting {} proposal {} with weight {}",
+        if support { "for" } else { "against" },
+        hex::encode(proposal_id),
+        weight
+    );
+
+    let nonce = rpc::get_nonce_with_token(&config.node, &voter, 0).await?;
+
+    let mut msg = SystemMsg::Vote {
+        voter,
+        proposal_id,
+        support,
+        proof,
+        timestamp,
+        nonce,
+        signature: Signature([0u8; 64]),
+    };
+    wallet.sign_system_msg(&mut msg)?;
+
+    rpc::submit_message(&config.node, &msg).await?;
+    Ok(weight)
+}
+
+/// Parses a hex-encoded proposal ID.
+pub fn parse_proposal_id(proposal_id: &str) -> Result<ProposalId, WalletError> {
+    hex::decode(proposal_id.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::TransactionError(format!("Invalid proposal ID: {}", proposal_id)))
+}
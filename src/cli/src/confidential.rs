@@ -0,0 +1,207 @@
+//! Local tracking of confidential balances.
+//!
+//! The node only knows the commitment to a confidential balance. The wallet
+//! keeps the value and blinding factor of each of its confidential balances
+//! in a file next to the wallet, and folds in incoming transfers by
+//! decrypting their notes with the wallet's viewing secret.
+
+use crate::errors::WalletError;
+use crate::rpc;
+use crate::wallet::Wallet;
+use core::confidential::{self, Blinding, EncryptedOpening};
+use core::types::{Address, TokenId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// The wallet's opening of one confidential balance.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfidentialBalance {
+    /// The balance
+    pub value: u64,
+    /// Hex-encoded blinding factor of the balance commitment
+    pub blinding: String,
+    /// Hex-encoded amount commitments of the incoming notes already counted
+    #[serde(default)]
+    pub seen_notes: Vec<String>,
+}
+
+impl ConfidentialBalance {
+    /// Returns the decoded blinding factor.
+    pub fn blinding(&self) -> Result<Blinding, WalletError> {
+        if self.blinding.is_empty() {
+            return Ok([0u8; 32]);
+        }
+
+        hex::decode(&self.blinding)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| WalletError::WalletError("Invalid blinding factor in confidential store".to_string()))
+    }
+
+    /// Sets the blinding factor.
+    pub fn set_blinding(&mut self, blinding: &Blinding) {
+        self.blinding = hex::encode(blinding);
+    }
+
+    /// Returns the commitment this opening corresponds to.
+    pub fn commitment(&self) -> Result<confidential::Commitment, WalletError> {
+        confidential::commit(self.value, &self.blinding()?)
+            .map_err(|e| WalletError::WalletError(e.to_string()))
+    }
+}
+
+/// The wallet's confidential balances, by token ID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConfidentialStore {
+    /// Balances by token ID
+    pub balances: BTreeMap<TokenId, ConfidentialBalance>,
+    /// Where the store is saved
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl ConfidentialStore {
+    /// Loads the store kept next to the given wallet file, or an empty one.
+    pub fn load_for_wallet<P: AsRef<Path>>(wallet_path: P) -> Result<Self, WalletError> {
+        let mut path = wallet_path.as_ref().as_os_str().to_owned();
+        path.push(".confidential");
+        let path = PathBuf::from(path);
+
+        let mut store = if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&contents)?
+        } else {
+            ConfidentialStore::default()
+        };
+
+        store.path = path;
+        Ok(store)
+    }
+
+    /// Saves the store.
+    pub fn save(&self) -> Result<(), WalletError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(&self.path, contents)?;
+        Ok(())
+    }
+
+    /// Returns the balance for a token, creating an empty one if needed.
+    pub fn balance_mut(&mut self, token_id: TokenId) -> &mut ConfidentialBalance {
+        self.balances.entry(token_id).or_default()
+    }
+}
+
+/// An account's confidential balance leaf as reported by the node.
+pub struct RemoteAccount {
+    /// Commitment to the balance
+    pub commitment: confidential::Commitment,
+    /// The account's viewing key
+    pub viewing_key: [u8; 32],
+}
+
+/// Gets an account's confidential balance from the node, if it has one.
+pub async fn get_account(
+    node_url: &str,
+    address: &Address,
+    token_id: TokenId,
+) -> Result<Option<RemoteAccount>, WalletError> {
+    let result = rpc::call(
+        node_url,
+        "getConfidentialAccount",
+        serde_json::json!([hex::encode(address), token_id]),
+    )
+    .await?;
+
+    if result.is_null() {
+        return Ok(None);
+    }
+
+    let field = |name: &str| -> Result<[u8; 32], WalletError> {
+        result
+            .get(name)
+            .and_then(|v| v.as_str())
+            .and_then(|s| hex::decode(s).ok())
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid confidential account: {}", result)))
+    };
+
+    Ok(Some(RemoteAccount {
+        commitment: field("commitment")?,
+        viewing_key: field("viewing_key")?,
+    }))
+}
+
+/// Folds newly received confidential transfers into the local balance of a
+/// token and checks the result against the node's commitment.
+///
+/// Returns the node's view of the account, or `None` if the wallet has no
+/// confidential balance for the token yet.
+pub async fn sync(
+    node_url: &str,
+    wallet: &Wallet,
+    store: &mut ConfidentialStore,
+    token_id: TokenId,
+) -> Result<Option<RemoteAccount>, WalletError> {
+    let address = wallet.address()?;
+    let view_secret = wallet.confidential_view_secret()?;
+
+    let notes = rpc::call(node_url, "getConfidentialNotes", serde_json::json!([hex::encode(address)])).await?;
+    let notes = notes
+        .as_array()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid notes format: {}", notes)))?;
+
+    let balance = store.balance_mut(token_id);
+    for note in notes {
+        if note.get("token_id").and_then(|v| v.as_u64()) != Some(token_id) {
+            continue;
+        }
+
+        let commitment = note.get("amount_commitment").and_then(|v| v.as_str()).unwrap_or_default();
+        if balance.seen_notes.iter().any(|seen| seen == commitment) {
+            continue;
+        }
+
+        let opening = EncryptedOpening {
+            ephemeral_key: note
+                .get("ephemeral_key")
+                .and_then(|v| v.as_str())
+                .and_then(|s| hex::decode(s).ok())
+                .and_then(|bytes| bytes.try_into().ok())
+                .unwrap_or([0u8; 32]),
+            ciphertext: note
+                .get("ciphertext")
+                .and_then(|v| v.as_str())
+                .and_then(|s| hex::decode(s).ok())
+                .unwrap_or_default(),
+        };
+
+        match confidential::decrypt_opening(&view_secret, &opening) {
+            Some((amount, blinding)) => {
+                debug!("Received confidential amount {} for token {}", amount, token_id);
+                balance.value = balance.value.checked_add(amount).ok_or_else(|| {
+                    WalletError::InvalidAmount("Confidential balance overflow".to_string())
+                })?;
+                let total = confidential::add_blindings(&balance.blinding()?, &blinding)
+                    .map_err(|e| WalletError::WalletError(e.to_string()))?;
+                balance.set_blinding(&total);
+            }
+            None => warn!("Could not decrypt confidential note {}", commitment),
+        }
+
+        balance.seen_notes.push(commitment.to_string());
+    }
+
+    let remote = get_account(node_url, &address, token_id).await?;
+    if let Some(remote) = &remote {
+        if remote.commitment != balance.commitment()? {
+            return Err(WalletError::WalletError(
+                "Local confidential balance does not match the node's commitment".to_string(),
+            ));
+        }
+    }
+
+    store.save()?;
+    Ok(remote)
+}
@@ -1,12 +1,16 @@
 //! CLI wallet for the chainless token transfer network.
 
+pub mod airdrop;
 pub mod commands;
+pub mod confidential;
 pub mod config;
 pub mod errors;
+pub mod rpc;
+pub mod stealth;
 pub mod wallet;
 
 // Re-export commonly used types and functions
-pub use commands::{balance, export_seed, init_seed, send};
+pub use commands::{balance, export_seed, history, init_seed, send};
 pub use config::WalletConfig;
 pub use errors::WalletError;
 pub use wallet::Wallet;
\ No newline at end of file
@@ -1,13 +1,17 @@
 //! CLI wallet for the chainless token transfer network.
 
+mod airdrop;
 mod commands;
+mod confidential;
 mod config;
 mod errors;
+mod rpc;
+mod stealth;
 mod wallet;
 
 use anyhow::Result;
 use colored::Colorize;
-use commands::{balance, export_seed, init_seed, mint, send, issue_token, mint_token};
+use commands::{airdrop_status, authorize_session_key, authorize_standing_order, balance, build_airdrop, burn, cancel_standing_order, channel, claim_airdrop, close_channel, compliance_status, confidential_balance, confidential_send, create_airdrop, export_seed, history, import_eth_key, init_seed, issue_nft_collection, mint, mint_nft, nft_owner, nfts, open_channel, pay_channel, proposal, propose, pull_payment, reclaim_airdrop, reclaim_channel, register_name, resolve_name, retire_token, revoke_session_key, scan, send, session_key, session_send, set_compliance_policy, set_name_target, shield, sign_message, sign_typed_data, standing_orders, start_channel_timeout, stealth_address, token_collateral, transfer_name, transfer_nft, unshield, update_compliance_list, verify_message, vote, issue_token, mint_token};
 use config::WalletConfig;
 use errors::WalletError;
 use std::path::PathBuf;
@@ -46,7 +50,7 @@ enum Command {
     /// Send tokens to another account
     #[structopt(name = "send")]
     Send {
-        /// Recipient address
+        /// Recipient address, name (e.g. alice.volt) or stealth meta-address
         #[structopt(long)]
         to: String,
 
@@ -57,8 +61,36 @@ enum Command {
         /// Amount to send
         #[structopt(long)]
         amount: u128,
+
+        /// Payment reference shown to the recipient (e.g. an invoice number)
+        #[structopt(long)]
+        memo: Option<String>,
+
+        /// Spend from one of the wallet's stealth addresses found by scan
+        #[structopt(long)]
+        from_stealth: Option<String>,
+
+        /// State root, obtained independently of the node, to check a name's resolution against
+        #[structopt(long)]
+        root: Option<String>,
+    },
+
+    /// Show sent and received transfers, including their memos
+    #[structopt(name = "history")]
+    History {
+        /// Only show received payments
+        #[structopt(long)]
+        received: bool,
     },
 
+    /// Show the stealth meta-address senders can use to pay this wallet privately
+    #[structopt(name = "stealth-address")]
+    StealthAddress,
+
+    /// Find stealth payments sent to this wallet
+    #[structopt(name = "scan")]
+    Scan,
+
     /// Mint new tokens (treasury only)
     #[structopt(name = "mint")]
     Mint {
@@ -79,6 +111,46 @@ enum Command {
     #[structopt(name = "export-seed")]
     ExportSeed,
 
+    /// Import an Ethereum (secp256k1) private key and use its account
+    #[structopt(name = "import-eth-key")]
+    ImportEthKey {
+        /// Hex-encoded private key
+        #[structopt(long)]
+        key: String,
+    },
+
+    /// Sign an arbitrary message with the wallet's key
+    #[structopt(name = "sign-message")]
+    SignMessage {
+        /// Message to sign
+        #[structopt(long)]
+        message: String,
+    },
+
+    /// Sign a typed structured-data document, showing its decoded contents first
+    #[structopt(name = "sign-typed-data")]
+    SignTypedData {
+        /// Path to the typed-data JSON document
+        #[structopt(long, parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Verify a message signature produced by sign-message
+    #[structopt(name = "verify-message")]
+    VerifyMessage {
+        /// Address of the signer
+        #[structopt(long)]
+        address: String,
+
+        /// Message that was signed
+        #[structopt(long)]
+        message: String,
+
+        /// Hex-encoded signature
+        #[structopt(long)]
+        signature: String,
+    },
+
     /// Issue a new token
     #[structopt(name = "issue-token")]
     IssueToken {
@@ -86,11 +158,91 @@ enum Command {
         #[structopt(long)]
         metadata: String,
 
-        /// Collateral amount (optional)
+        /// Native tokens to lock as collateral until the token is retired (optional)
         #[structopt(long)]
         collateral: Option<u128>,
     },
 
+    /// Burn tokens held by the wallet
+    #[structopt(name = "burn")]
+    Burn {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// Amount to burn
+        #[structopt(long)]
+        amount: u128,
+    },
+
+    /// Retire a token issued by the wallet once its supply is burned, releasing its collateral
+    #[structopt(name = "retire-token")]
+    RetireToken {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+    },
+
+    /// Show the collateral locked for a token, checking the node's proof
+    #[structopt(name = "token-collateral")]
+    TokenCollateral {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+    },
+
+    /// Build an airdrop distribution and its claim proofs from a CSV of address,amount lines
+    #[structopt(name = "build-airdrop")]
+    BuildAirdrop {
+        /// Path to the CSV file
+        #[structopt(long, parse(from_os_str))]
+        csv: PathBuf,
+
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// Path to write the distribution file to
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+    },
+
+    /// Create an airdrop from a distribution file, escrowing its total
+    #[structopt(name = "create-airdrop")]
+    CreateAirdrop {
+        /// Path to the distribution file
+        #[structopt(long, parse(from_os_str))]
+        distribution: PathBuf,
+
+        /// Time from which entries can no longer be claimed, in seconds since the Unix epoch
+        #[structopt(long)]
+        expires_at: u64,
+    },
+
+    /// Claim the wallet's entry of an airdrop
+    #[structopt(name = "claim-airdrop")]
+    ClaimAirdrop {
+        /// Path to the distribution file
+        #[structopt(long, parse(from_os_str))]
+        distribution: PathBuf,
+    },
+
+    /// Reclaim the unclaimed amount of an expired airdrop created by the wallet
+    #[structopt(name = "reclaim-airdrop")]
+    ReclaimAirdrop {
+        /// Path to the distribution file
+        #[structopt(long, parse(from_os_str))]
+        distribution: PathBuf,
+    },
+
+    /// Show how much of an airdrop has been claimed, checking the node's proof
+    #[structopt(name = "airdrop-status")]
+    AirdropStatus {
+        /// Path to the distribution file
+        #[structopt(long, parse(from_os_str))]
+        distribution: PathBuf,
+    },
+
     /// Mint tokens for a specific token ID
     #[structopt(name = "mint-token")]
     MintToken {
@@ -106,6 +258,422 @@ enum Command {
         #[structopt(long)]
         amount: u128,
     },
+
+    /// Issue a new NFT collection
+    #[structopt(name = "issue-nft-collection")]
+    IssueNftCollection {
+        /// Collection metadata (name, symbol, etc.)
+        #[structopt(long)]
+        metadata: String,
+    },
+
+    /// Mint an item of an NFT collection issued by the wallet
+    #[structopt(name = "mint-nft")]
+    MintNft {
+        /// Collection token ID
+        #[structopt(long)]
+        collection_id: u64,
+
+        /// Item ID, unique within the collection
+        #[structopt(long)]
+        item_id: u64,
+
+        /// Recipient address
+        #[structopt(long)]
+        to: String,
+
+        /// Where the item's metadata can be found
+        #[structopt(long)]
+        metadata_uri: String,
+
+        /// SHA-256 hash of the metadata (optional)
+        #[structopt(long)]
+        metadata_hash: Option<String>,
+    },
+
+    /// Transfer an NFT owned by the wallet
+    #[structopt(name = "transfer-nft")]
+    TransferNft {
+        /// Collection token ID
+        #[structopt(long)]
+        collection_id: u64,
+
+        /// Item ID
+        #[structopt(long)]
+        item_id: u64,
+
+        /// Recipient address
+        #[structopt(long)]
+        to: String,
+    },
+
+    /// Show the owner of an NFT, checking the node's ownership proof
+    #[structopt(name = "nft-owner")]
+    NftOwner {
+        /// Collection token ID
+        #[structopt(long)]
+        collection_id: u64,
+
+        /// Item ID
+        #[structopt(long)]
+        item_id: u64,
+    },
+
+    /// List the NFTs owned by the wallet
+    #[structopt(name = "nfts")]
+    Nfts,
+
+    /// Set who may hold a token issued by the wallet
+    #[structopt(name = "set-compliance-policy")]
+    SetCompliancePolicy {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// Policy: open, allow-list or deny-list
+        #[structopt(long)]
+        policy: String,
+    },
+
+    /// Propose a new max supply or compliance policy for a token the wallet holds
+    #[structopt(name = "propose")]
+    Propose {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// Proposed maximum supply
+        #[structopt(long)]
+        max_supply: Option<u128>,
+
+        /// Proposed compliance policy: open, allow-list or deny-list
+        #[structopt(long)]
+        compliance_policy: Option<String>,
+
+        /// How long votes are accepted, in seconds
+        #[structopt(long, default_value = "604800")]
+        voting_period_secs: u64,
+    },
+
+    /// Vote on a proposal with the wallet's balance at its snapshot
+    #[structopt(name = "vote")]
+    Vote {
+        /// Proposal ID (hex)
+        #[structopt(long)]
+        proposal_id: String,
+
+        /// Vote against the proposal instead of for it
+        #[structopt(long)]
+        against: bool,
+    },
+
+    /// Show a proposal and its tally, checking the node's proof
+    #[structopt(name = "proposal")]
+    Proposal {
+        /// Proposal ID (hex)
+        #[structopt(long)]
+        proposal_id: String,
+    },
+
+    /// Authorize a payee to pull a fixed amount from the wallet once per period
+    #[structopt(name = "authorize-standing-order")]
+    AuthorizeStandingOrder {
+        /// Payee address (hex)
+        #[structopt(long)]
+        payee: String,
+
+        /// Token ID
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+
+        /// Amount per period
+        #[structopt(long)]
+        amount: u128,
+
+        /// Period length in seconds
+        #[structopt(long)]
+        period_secs: u64,
+
+        /// Time from which no more pulls are allowed, in seconds since the Unix epoch
+        #[structopt(long)]
+        expiry: u64,
+    },
+
+    /// Cancel a standing order paid by the wallet
+    #[structopt(name = "cancel-standing-order")]
+    CancelStandingOrder {
+        /// Standing order ID (hex)
+        #[structopt(long)]
+        order_id: String,
+    },
+
+    /// Pull the current period's payment of a standing order paid to the wallet
+    #[structopt(name = "pull-payment")]
+    PullPayment {
+        /// Standing order ID (hex)
+        #[structopt(long)]
+        order_id: String,
+    },
+
+    /// List the active standing orders the wallet pays or is paid by
+    #[structopt(name = "standing-orders")]
+    StandingOrders,
+
+    /// Open a payment channel, escrowing a deposit from the wallet
+    #[structopt(name = "open-channel")]
+    OpenChannel {
+        /// Payee address (hex)
+        #[structopt(long)]
+        payee: String,
+
+        /// Token ID
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+
+        /// Amount to escrow
+        #[structopt(long)]
+        deposit: u128,
+
+        /// Dispute timeout in seconds
+        #[structopt(long, default_value = "86400")]
+        timeout_secs: u64,
+    },
+
+    /// Sign a voucher paying the payee of a channel, without sending anything
+    #[structopt(name = "pay-channel")]
+    PayChannel {
+        /// Channel ID (hex)
+        #[structopt(long)]
+        channel_id: String,
+
+        /// Total amount owed to the payee so far, including earlier vouchers
+        #[structopt(long)]
+        amount: u128,
+    },
+
+    /// Close a channel paid to the wallet with the payer's latest voucher
+    #[structopt(name = "close-channel")]
+    CloseChannel {
+        /// Voucher (hex), as printed by pay-channel
+        #[structopt(long)]
+        voucher: String,
+    },
+
+    /// Start the dispute timeout of a channel paid by the wallet
+    #[structopt(name = "start-channel-timeout")]
+    StartChannelTimeout {
+        /// Channel ID (hex)
+        #[structopt(long)]
+        channel_id: String,
+    },
+
+    /// Reclaim the deposit of a channel paid by the wallet after its timeout
+    #[structopt(name = "reclaim-channel")]
+    ReclaimChannel {
+        /// Channel ID (hex)
+        #[structopt(long)]
+        channel_id: String,
+    },
+
+    /// Show a payment channel, checking the node's proof
+    #[structopt(name = "channel")]
+    Channel {
+        /// Channel ID (hex)
+        #[structopt(long)]
+        channel_id: String,
+    },
+
+    /// Authorize a session key to send transfers from the wallet within spending limits
+    #[structopt(name = "authorize-session-key")]
+    AuthorizeSessionKey {
+        /// Session key address (hex)
+        #[structopt(long)]
+        session_key: String,
+
+        /// Spending limit per window as <token_id>:<amount> (can be repeated)
+        #[structopt(long = "limit", required = true)]
+        limits: Vec<String>,
+
+        /// Window length in seconds
+        #[structopt(long, default_value = "86400")]
+        window_secs: u64,
+
+        /// Time from which the key can no longer be used, in seconds since the Unix epoch
+        #[structopt(long)]
+        expiry: u64,
+    },
+
+    /// Revoke a session key of the wallet
+    #[structopt(name = "revoke-session-key")]
+    RevokeSessionKey {
+        /// Session key address (hex)
+        #[structopt(long)]
+        session_key: String,
+    },
+
+    /// Send tokens from an owner's account, signing with the wallet as its session key
+    #[structopt(name = "session-send")]
+    SessionSend {
+        /// Owner address (hex)
+        #[structopt(long)]
+        owner: String,
+
+        /// Recipient address (hex)
+        #[structopt(long)]
+        to: String,
+
+        /// Amount to send
+        #[structopt(long)]
+        amount: u128,
+
+        /// Token ID
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+    },
+
+    /// Show a session key's limits, checking the node's proof
+    #[structopt(name = "session-key")]
+    SessionKey {
+        /// Owner address (hex)
+        #[structopt(long)]
+        owner: String,
+
+        /// Session key address (hex)
+        #[structopt(long)]
+        session_key: String,
+    },
+
+    /// Add an address to, or remove it from, a token's allow or deny list
+    #[structopt(name = "update-compliance-list")]
+    UpdateComplianceList {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// List to change: allow or deny
+        #[structopt(long)]
+        list: String,
+
+        /// Address to add or remove
+        #[structopt(long)]
+        address: String,
+
+        /// Remove the address instead of adding it
+        #[structopt(long)]
+        remove: bool,
+    },
+
+    /// Check whether an address is on a token's allow or deny list, verifying the node's proof
+    #[structopt(name = "compliance-status")]
+    ComplianceStatus {
+        /// Token ID
+        #[structopt(long)]
+        token_id: u64,
+
+        /// List to check: allow or deny
+        #[structopt(long)]
+        list: String,
+
+        /// Address to check
+        #[structopt(long)]
+        address: String,
+    },
+
+    /// Move public balance into the wallet's confidential balance
+    #[structopt(name = "shield")]
+    Shield {
+        /// Token ID (defaults to 0 for native token)
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+
+        /// Amount to shield
+        #[structopt(long)]
+        amount: u64,
+    },
+
+    /// Move confidential balance back to the wallet's public balance
+    #[structopt(name = "unshield")]
+    Unshield {
+        /// Token ID (defaults to 0 for native token)
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+
+        /// Amount to unshield
+        #[structopt(long)]
+        amount: u64,
+    },
+
+    /// Send a hidden amount from the wallet's confidential balance
+    #[structopt(name = "confidential-send")]
+    ConfidentialSend {
+        /// Recipient address
+        #[structopt(long)]
+        to: String,
+
+        /// Token ID (defaults to 0 for native token)
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+
+        /// Amount to send
+        #[structopt(long)]
+        amount: u64,
+    },
+
+    /// Get the wallet's confidential balance, including received transfers
+    #[structopt(name = "confidential-balance")]
+    ConfidentialBalance {
+        /// Token ID (defaults to 0 for native token)
+        #[structopt(long, default_value = "0")]
+        token_id: u64,
+    },
+
+    /// Register a name (e.g. alice.volt) for the wallet
+    #[structopt(name = "register-name")]
+    RegisterName {
+        /// The name to register
+        #[structopt(long)]
+        name: String,
+
+        /// Address the name resolves to (defaults to the wallet's address)
+        #[structopt(long)]
+        target: Option<String>,
+    },
+
+    /// Hand a name owned by the wallet over to another address
+    #[structopt(name = "transfer-name")]
+    TransferName {
+        /// The name to transfer
+        #[structopt(long)]
+        name: String,
+
+        /// New owner address
+        #[structopt(long)]
+        to: String,
+    },
+
+    /// Point a name owned by the wallet at another address
+    #[structopt(name = "set-name-target")]
+    SetNameTarget {
+        /// The name to update
+        #[structopt(long)]
+        name: String,
+
+        /// Address the name should resolve to
+        #[structopt(long)]
+        target: String,
+    },
+
+    /// Resolve a name to an address, checking the node's proof
+    #[structopt(name = "resolve-name")]
+    ResolveName {
+        /// The name to resolve
+        #[structopt(long)]
+        name: String,
+
+        /// State root, obtained independently of the node, to check the resolution against
+        #[structopt(long)]
+        root: Option<String>,
+    },
 }
 
 #[tokio::main]
@@ -152,10 +720,35 @@ async fn main() -> Result<()> {
             let balance = balance::run(&config, &wallet_file).await?;
             println!("{} {}", "Balance:".green(), balance);
         }
-        Command::Send { to, token_id, amount } => {
-            let tx_hash = send::run(&config, &wallet_file, &to, token_id, amount).await?;
+        Command::Send { to, token_id, amount, memo, from_stealth, root } => {
+            if core::names::is_name(&to) && root.is_none() {
+                println!("{} {} was resolved by the node without --root, trusting the node", "Warning:".yellow(), to);
+            }
+            let tx_hash = send::run(
+                &config,
+                &wallet_file,
+                &to,
+                token_id,
+                amount,
+                memo.as_deref(),
+                from_stealth.as_deref(),
+                root.as_deref(),
+            )
+            .await?;
             println!("{} {}", "Transaction sent:".green(), tx_hash);
         }
+        Command::History { received } => {
+            let count = history::run(&config, &wallet_file, received).await?;
+            println!("{} {}", "Transactions:".green(), count);
+        }
+        Command::StealthAddress => {
+            let meta_address = stealth_address::run(&wallet_file).await?;
+            println!("{} {}", "Stealth address:".green(), meta_address);
+        }
+        Command::Scan => {
+            let found = scan::run(&config, &wallet_file).await?;
+            println!("{} {}", "New stealth payments:".green(), found);
+        }
         Command::Mint { to, amount } => {
             let tx_hash = mint::run(&config, &wallet_file, &to, amount).await?;
             println!("{} {}", "Tokens minted:".green(), tx_hash);
@@ -169,14 +762,269 @@ async fn main() -> Result<()> {
             println!("{} {}", "Seed:".green(), seed);
             println!("{}", "WARNING: Keep this seed safe and private!".red());
         }
+        Command::ImportEthKey { key } => {
+            let address = import_eth_key::run(&wallet_file, &key).await?;
+            println!("{} {}", "Using secp256k1 account:".green(), address);
+        }
+        Command::SignMessage { message } => {
+            let (address, signature) = sign_message::run(&wallet_file, &message).await?;
+            println!("{} {}", "Address:".green(), address);
+            println!("{} {}", "Signature:".green(), signature);
+        }
+        Command::SignTypedData { file } => {
+            let (address, signature) = sign_typed_data::run(&config, &wallet_file, &file).await?;
+            println!("{} {}", "Address:".green(), address);
+            println!("{} {}", "Signature:".green(), signature);
+        }
+        Command::VerifyMessage { address, message, signature } => {
+            if verify_message::run(&address, &message, &signature).await? {
+                println!("{}", "Signature is valid".green());
+            } else {
+                println!("{}", "Signature is NOT valid".red());
+            }
+        }
         Command::IssueToken { metadata, collateral } => {
             let token_id = issue_token::run(&config, &wallet_file, &metadata, collateral).await?;
             println!("{} {}", "Token issued:".green(), token_id);
         }
+        Command::Burn { token_id, amount } => {
+            let root = burn::run(&config, &wallet_file, token_id, amount).await?;
+            println!("{} {}", "Tokens burned, new root:".green(), root);
+        }
+        Command::RetireToken { token_id } => {
+            let root = retire_token::run(&config, &wallet_file, token_id).await?;
+            println!("{} {}", "Token retired, new root:".green(), root);
+        }
+        Command::TokenCollateral { token_id } => {
+            let escrow = token_collateral::run(&config, token_id).await?;
+            println!("{} {}", "Collateral:".green(), escrow.as_ref().map_or(0, |escrow| escrow.collateral));
+            println!("{} {}", "Retired:".green(), escrow.is_some_and(|escrow| escrow.retired));
+        }
+        Command::BuildAirdrop { csv, token_id, out } => {
+            let distribution = build_airdrop::run(&csv, token_id, &out)?;
+            println!("{} {}", "Merkle root:".green(), distribution.merkle_root);
+            println!("{} {}", "Recipients:".green(), distribution.claims.len());
+            println!("{} {}", "Total:".green(), distribution.total);
+        }
+        Command::CreateAirdrop { distribution, expires_at } => {
+            let airdrop_id = create_airdrop::run(&config, &wallet_file, &distribution, expires_at).await?;
+            println!("{} {}", "Airdrop created:".green(), airdrop_id);
+        }
+        Command::ClaimAirdrop { distribution } => {
+            let amount = claim_airdrop::run(&config, &wallet_file, &distribution).await?;
+            println!("{} {}", "Airdrop claimed:".green(), amount);
+        }
+        Command::ReclaimAirdrop { distribution } => {
+            let root = reclaim_airdrop::run(&config, &wallet_file, &distribution).await?;
+            println!("{} {}", "Airdrop reclaimed, new root:".green(), root);
+        }
+        Command::AirdropStatus { distribution } => {
+            let airdrop = airdrop_status::run(&config, &distribution).await?;
+            println!("{} {}", "Token ID:".green(), airdrop.token_id);
+            println!("{} {} of {}", "Claimed:".green(), airdrop.claimed, airdrop.total);
+            println!("{} {}", "Expires at:".green(), airdrop.expires_at);
+            println!("{} {}", "Reclaimed:".green(), airdrop.reclaimed);
+        }
         Command::MintToken { token_id, to, amount } => {
             let tx_hash = mint_token::run(&config, &wallet_file, token_id, &to, amount).await?;
             println!("{} {}", "Tokens minted:".green(), tx_hash);
         }
+        Command::IssueNftCollection { metadata } => {
+            let collection_id = issue_nft_collection::run(&config, &wallet_file, &metadata).await?;
+            println!("{} {}", "Collection issued:".green(), collection_id);
+        }
+        Command::MintNft { collection_id, item_id, to, metadata_uri, metadata_hash } => {
+            let root = mint_nft::run(
+                &config,
+                &wallet_file,
+                collection_id,
+                item_id,
+                &to,
+                &metadata_uri,
+                metadata_hash.as_deref(),
+            )
+            .await?;
+            println!("{} {}", "NFT minted, new root:".green(), root);
+        }
+        Command::TransferNft { collection_id, item_id, to } => {
+            let root = transfer_nft::run(&config, &wallet_file, collection_id, item_id, &to).await?;
+            println!("{} {}", "NFT transferred, new root:".green(), root);
+        }
+        Command::NftOwner { collection_id, item_id } => {
+            let item = nft_owner::run(&config, collection_id, item_id).await?;
+            println!("{} {}", "Owner:".green(), hex::encode(item.owner));
+            println!("{} {}", "Metadata URI:".green(), item.metadata_uri);
+            if let Some(hash) = item.metadata_hash {
+                println!("{} {}", "Metadata hash:".green(), hex::encode(hash));
+            }
+        }
+        Command::Nfts => {
+            let count = nfts::run(&config, &wallet_file).await?;
+            println!("{} {}", "NFTs:".green(), count);
+        }
+        Command::SetCompliancePolicy { token_id, policy } => {
+            let root = set_compliance_policy::run(&config, &wallet_file, token_id, &policy).await?;
+            println!("{} {}", "Compliance policy set, new root:".green(), root);
+        }
+        Command::Propose { token_id, max_supply, compliance_policy, voting_period_secs } => {
+            let proposal_id = propose::run(
+                &config,
+                &wallet_file,
+                token_id,
+                max_supply,
+                compliance_policy.as_deref(),
+                voting_period_secs,
+            )
+            .await?;
+            println!("{} {}", "Proposal created:".green(), proposal_id);
+        }
+        Command::Vote { proposal_id, against } => {
+            let weight = vote::run(&config, &wallet_file, &proposal_id, !against).await?;
+            println!("{} {}", "Voted with weight:".green(), weight);
+        }
+        Command::Proposal { proposal_id } => {
+            let proposal = proposal::run(&config, &proposal_id).await?;
+            println!("{} {}", "Action:".green(), proposal.action);
+            println!("{} {}", "Status:".green(), proposal.status);
+            println!("{} {}", "For:".green(), proposal.votes_for);
+            println!("{} {}", "Against:".green(), proposal.votes_against);
+            println!("{} {}", "Quorum:".green(), proposal.quorum);
+            println!("{} {}", "Voting closes at:".green(), proposal.deadline);
+        }
+        Command::AuthorizeStandingOrder { payee, token_id, amount, period_secs, expiry } => {
+            let order_id = authorize_standing_order::run(
+                &config,
+                &wallet_file,
+                &payee,
+                token_id,
+                amount,
+                period_secs,
+                expiry,
+            )
+            .await?;
+            println!("{} {}", "Standing order authorized:".green(), order_id);
+        }
+        Command::CancelStandingOrder { order_id } => {
+            let root = cancel_standing_order::run(&config, &wallet_file, &order_id).await?;
+            println!("{} {}", "Standing order cancelled, new root:".green(), root);
+        }
+        Command::PullPayment { order_id } => {
+            let root = pull_payment::run(&config, &wallet_file, &order_id).await?;
+            println!("{} {}", "Payment pulled, new root:".green(), root);
+        }
+        Command::StandingOrders => {
+            let count = standing_orders::run(&config, &wallet_file).await?;
+            println!("{} {}", "Standing orders:".green(), count);
+        }
+        Command::OpenChannel { payee, token_id, deposit, timeout_secs } => {
+            let channel_id = open_channel::run(&config, &wallet_file, &payee, token_id, deposit, timeout_secs).await?;
+            println!("{} {}", "Channel opened:".green(), channel_id);
+        }
+        Command::PayChannel { channel_id, amount } => {
+            let voucher = pay_channel::run(&config, &wallet_file, &channel_id, amount).await?;
+            println!("{} {}", "Voucher:".green(), voucher);
+        }
+        Command::CloseChannel { voucher } => {
+            let root = close_channel::run(&config, &wallet_file, &voucher).await?;
+            println!("{} {}", "Channel closed, new root:".green(), root);
+        }
+        Command::StartChannelTimeout { channel_id } => {
+            let root = start_channel_timeout::run(&config, &wallet_file, &channel_id).await?;
+            println!("{} {}", "Channel timeout started, new root:".green(), root);
+        }
+        Command::ReclaimChannel { channel_id } => {
+            let root = reclaim_channel::run(&config, &wallet_file, &channel_id).await?;
+            println!("{} {}", "Channel reclaimed, new root:".green(), root);
+        }
+        Command::Channel { channel_id } => {
+            let channel = channel::run(&config, &channel_id).await?;
+            println!("{} {}", "Open:".green(), channel.is_some());
+            if let Some(channel) = channel {
+                println!("{} 0x{}", "Payer:".green(), hex::encode(channel.payer));
+                println!("{} 0x{}", "Payee:".green(), hex::encode(channel.payee));
+                println!("{} {}", "Token ID:".green(), channel.token_id);
+                println!("{} {}", "Deposit:".green(), channel.deposit);
+                println!("{} {}", "Timeout (seconds):".green(), channel.timeout_secs);
+                if let Some(closes_at) = channel.closes_at {
+                    println!("{} {}", "Reclaimable at:".green(), closes_at);
+                }
+            }
+        }
+        Command::AuthorizeSessionKey { session_key, limits, window_secs, expiry } => {
+            let root =
+                authorize_session_key::run(&config, &wallet_file, &session_key, &limits, window_secs, expiry).await?;
+            println!("{} {}", "Session key authorized, new root:".green(), root);
+        }
+        Command::RevokeSessionKey { session_key } => {
+            let root = revoke_session_key::run(&config, &wallet_file, &session_key).await?;
+            println!("{} {}", "Session key revoked, new root:".green(), root);
+        }
+        Command::SessionSend { owner, to, amount, token_id } => {
+            let root = session_send::run(&config, &wallet_file, &owner, &to, token_id, amount).await?;
+            println!("{} {}", "Transaction sent, new root:".green(), root);
+        }
+        Command::SessionKey { owner, session_key } => {
+            let delegation = session_key::run(&config, &owner, &session_key).await?;
+            println!("{} {}", "Authorized:".green(), delegation.is_some());
+            if let Some(delegation) = delegation {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or_default();
+                for limit in &delegation.limits {
+                    println!(
+                        "{} {} of token {} per window, {} remaining",
+                        "Limit:".green(),
+                        limit.amount,
+                        limit.token_id,
+                        delegation.remaining(limit.token_id, now)
+                    );
+                }
+                println!("{} {}", "Window (seconds):".green(), delegation.window_secs);
+                println!("{} {}", "Expiry:".green(), delegation.expiry);
+            }
+        }
+        Command::UpdateComplianceList { token_id, list, address, remove } => {
+            let root = update_compliance_list::run(&config, &wallet_file, token_id, &list, &address, remove).await?;
+            println!("{} {}", "Compliance list updated, new root:".green(), root);
+        }
+        Command::ComplianceStatus { token_id, list, address } => {
+            let listed = compliance_status::run(&config, token_id, &list, &address).await?;
+            println!("{} {}", "Listed:".green(), listed);
+        }
+        Command::Shield { token_id, amount } => {
+            let root = shield::run(&config, &wallet_file, token_id, amount).await?;
+            println!("{} {}", "Shielded, new root:".green(), root);
+        }
+        Command::Unshield { token_id, amount } => {
+            let root = unshield::run(&config, &wallet_file, token_id, amount).await?;
+            println!("{} {}", "Unshielded, new root:".green(), root);
+        }
+        Command::ConfidentialSend { to, token_id, amount } => {
+            let root = confidential_send::run(&config, &wallet_file, &to, token_id, amount).await?;
+            println!("{} {}", "Confidential transfer sent, new root:".green(), root);
+        }
+        Command::ConfidentialBalance { token_id } => {
+            let balance = confidential_balance::run(&config, &wallet_file, token_id).await?;
+            println!("{} {}", "Confidential balance:".green(), balance);
+        }
+        Command::RegisterName { name, target } => {
+            let root = register_name::run(&config, &wallet_file, &name, target.as_deref()).await?;
+            println!("{} {}", "Name registered, new root:".green(), root);
+        }
+        Command::TransferName { name, to } => {
+            let root = transfer_name::run(&config, &wallet_file, &name, &to).await?;
+            println!("{} {}", "Name transferred, new root:".green(), root);
+        }
+        Command::SetNameTarget { name, target } => {
+            let root = set_name_target::run(&config, &wallet_file, &name, &target).await?;
+            println!("{} {}", "Name target updated, new root:".green(), root);
+        }
+        Command::ResolveName { name, root } => {
+            let record = resolve_name::run(&config, &name, root.as_deref()).await?;
+            println!("{} {}", "Target:".green(), hex::encode(record.target));
+            println!("{} {}", "Owner:".green(), hex::encode(record.owner));
+        }
     }
 
     Ok(())
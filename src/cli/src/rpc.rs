@@ -0,0 +1,311 @@
+//! JSON-RPC helpers shared by the wallet commands.
+//!
+//! Getters for proven state check the node's proof against the state root
+//! returned with it. This catches a proof that does not match that root, but
+//! the root comes from the same node, so a node that lies about its state
+//! root is not detected. `resolve_name` can instead check against a root the
+//! caller got elsewhere.
+
+use crate::errors::WalletError;
+use core::airdrop::{Airdrop, AirdropId};
+use core::channels::{ChannelId, PaymentChannel};
+use core::collateral::TokenEscrow;
+use core::compliance::ComplianceList;
+use core::governance::{Proposal, ProposalId};
+use core::names::NameRecord;
+use core::nft::NftItem;
+use core::session_keys::SessionKey;
+use core::smt::{AccountProof, DataProof};
+use core::types::{Address, SystemMsg, TokenId};
+use tracing::debug;
+
+/// Calls a JSON-RPC method on the node and returns its result.
+pub async fn call(
+    node_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, WalletError> {
+    // Create the JSON-RPC request
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": params,
+        "id": 1
+    });
+
+    // Make sure to append /rpc to the node URL
+    let rpc_url = if node_url.ends_with("/rpc") {
+        node_url.to_string()
+    } else {
+        format!("{}/rpc", node_url)
+    };
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(&rpc_url)
+        .json(&request)
+        .send()
+        .await
+        .map_err(|e| WalletError::NetworkError(e.to_string()))?;
+
+    let response_text = response.text().await
+        .map_err(|e| WalletError::NetworkError(format!("Failed to get response text: {}", e)))?;
+    debug!("Raw {} response: {}", method, response_text);
+
+    // If the response is empty, return an error
+    if response_text.is_empty() {
+        return Err(WalletError::NetworkError("Empty response from node".to_string()));
+    }
+
+    // Parse the response
+    let response: serde_json::Value = serde_json::from_str(&response_text)
+        .map_err(|e| WalletError::NetworkError(format!("Failed to parse response: {}", e)))?;
+
+    // Check for errors in the response
+    if let Some(error) = response.get("error") {
+        if !error.is_null() {
+            return Err(WalletError::NodeRequestFailed(error.to_string()));
+        }
+    }
+
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("No result in response: {}", response_text)))
+}
+
+/// Gets the nonce of an account for a token.
+pub async fn get_nonce_with_token(node_url: &str, address: &Address, token_id: TokenId) -> Result<u64, WalletError> {
+    let result = call(node_url, "get_nonce_with_token", serde_json::json!([hex::encode(address), token_id])).await?;
+
+    result
+        .as_u64()
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid nonce: {}", result)))
+}
+
+/// Submits a signed system message and returns the new state root.
+pub async fn submit_message(node_url: &str, message: &SystemMsg) -> Result<String, WalletError> {
+    let result = submit(node_url, message).await?;
+
+    result
+        .get("root")
+        .and_then(|v| v.as_str())
+        .map(|root| root.to_string())
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid submit response: {}", result)))
+}
+
+/// Submits a signed `IssueNftCollection` message and returns the token ID
+/// of the new collection.
+pub async fn submit_nft_collection(node_url: &str, message: &SystemMsg) -> Result<TokenId, WalletError> {
+    let result = submit(node_url, message).await?;
+
+    result
+        .get("collection_id")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid submit response: {}", result)))
+}
+
+/// Submits a signed `CreateProposal` message and returns the hex-encoded ID
+/// of the new proposal.
+pub async fn submit_proposal(node_url: &str, message: &SystemMsg) -> Result<String, WalletError> {
+    let result = submit(node_url, message).await?;
+
+    result
+        .get("proposal_id")
+        .and_then(|v| v.as_str())
+        .map(|id| id.to_string())
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid submit response: {}", result)))
+}
+
+/// Sends a signed system message to the submitMessage method.
+async fn submit(node_url: &str, message: &SystemMsg) -> Result<serde_json::Value, WalletError> {
+    let message_bytes = bincode::serialize(message)
+        .map_err(|e| WalletError::TransactionError(format!("Failed to serialize message: {}", e)))?;
+
+    call(node_url, "submitMessage", serde_json::json!([hex::encode(message_bytes)])).await
+}
+
+/// Resolves a name through the node and checks the returned proof.
+///
+/// With `trusted_root`, a state root the caller got independently of this
+/// node, the proof is checked against it, so the node cannot redirect the
+/// name. Without it the proof is checked against the returned root, which
+/// only shows that the response is consistent and trusts the node.
+pub async fn resolve_name(node_url: &str, name: &str, trusted_root: Option<&[u8; 32]>) -> Result<NameRecord, WalletError> {
+    core::names::validate_name(name).map_err(|e| WalletError::InvalidAddress(e.to_string()))?;
+
+    let result = call(node_url, "resolveName", serde_json::json!([name])).await?;
+    if result.is_null() {
+        return Err(WalletError::InvalidAddress(format!("Name {} is not registered", name)));
+    }
+
+    let (root, proof) = parse_data_proof(&result)?;
+    if let Some(trusted_root) = trusted_root {
+        if root != *trusted_root {
+            return Err(WalletError::ProofError(format!(
+                "Node resolved {} against state root {}, not the trusted root {}",
+                name,
+                hex::encode(root),
+                hex::encode(trusted_root)
+            )));
+        }
+    }
+
+    core::names::verify_resolution(name, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid resolution proof for {}: {}", name, e)))?
+        .ok_or_else(|| WalletError::InvalidAddress(format!("Name {} is not registered", name)))
+}
+
+/// Gets an NFT through the node and checks the returned ownership proof
+/// against the returned state root.
+pub async fn get_nft(node_url: &str, collection_id: TokenId, item_id: u64) -> Result<NftItem, WalletError> {
+    let not_found = || WalletError::NodeRequestFailed(format!("Item {} of collection {} not found", item_id, collection_id));
+
+    let result = call(node_url, "getNft", serde_json::json!([collection_id, item_id])).await?;
+    if result.is_null() {
+        return Err(not_found());
+    }
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::nft::verify_ownership(collection_id, item_id, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid ownership proof: {}", e)))?
+        .ok_or_else(not_found)
+}
+
+/// Gets whether an address is on one of a token's compliance lists through
+/// the node, checking the membership proof against the returned state root.
+pub async fn get_compliance_membership(
+    node_url: &str,
+    token_id: TokenId,
+    list: ComplianceList,
+    address: &Address,
+) -> Result<bool, WalletError> {
+    let result = call(
+        node_url,
+        "getComplianceMembership",
+        serde_json::json!([token_id, list.to_string(), hex::encode(address)]),
+    )
+    .await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::compliance::verify_membership(token_id, list, address, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid membership proof: {}", e)))
+}
+
+/// Gets a token's escrow through the node, checking the escrow proof
+/// against the returned state root. Returns `None` for a token issued
+/// without collateral that has not been retired.
+pub async fn get_token_collateral(node_url: &str, token_id: TokenId) -> Result<Option<TokenEscrow>, WalletError> {
+    let result = call(node_url, "getTokenCollateral", serde_json::json!([token_id])).await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::collateral::verify_escrow(token_id, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid escrow proof: {}", e)))
+}
+
+/// Gets an airdrop through the node and checks the returned proof against
+/// the returned state root.
+pub async fn get_airdrop(node_url: &str, airdrop_id: &AirdropId) -> Result<Airdrop, WalletError> {
+    let result = call(node_url, "getAirdrop", serde_json::json!([hex::encode(airdrop_id)])).await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::airdrop::verify_airdrop(airdrop_id, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid airdrop proof: {}", e)))?
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Airdrop {} not found", hex::encode(airdrop_id))))
+}
+
+/// Gets whether an airdrop entry has been claimed through the node and
+/// checks the returned proof against the returned state root.
+pub async fn is_airdrop_claimed(node_url: &str, airdrop_id: &AirdropId, index: u64) -> Result<bool, WalletError> {
+    let result = call(node_url, "isAirdropClaimed", serde_json::json!([hex::encode(airdrop_id), index])).await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::airdrop::verify_claimed(airdrop_id, index, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid claims proof: {}", e)))
+}
+
+/// Gets a proposal through the node and checks the returned proof against
+/// the returned state root.
+pub async fn get_proposal(node_url: &str, proposal_id: &ProposalId) -> Result<Proposal, WalletError> {
+    let result = call(node_url, "getProposal", serde_json::json!([hex::encode(proposal_id)])).await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::governance::verify_proposal(proposal_id, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid proposal proof: {}", e)))?
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Proposal {} not found", hex::encode(proposal_id))))
+}
+
+/// Gets a payment channel through the node, or `None` if it is not open,
+/// and checks the returned proof against the returned state root.
+pub async fn get_channel(node_url: &str, channel_id: &ChannelId) -> Result<Option<PaymentChannel>, WalletError> {
+    let result = call(node_url, "getChannel", serde_json::json!([hex::encode(channel_id)])).await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::channels::verify_channel(channel_id, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid channel proof: {}", e)))
+}
+
+/// Gets an owner's delegation to a session key through the node, or `None`
+/// if the key is not authorized, and checks the returned proof against the
+/// returned state root.
+pub async fn get_session_key(
+    node_url: &str,
+    owner: &Address,
+    session_key: &Address,
+) -> Result<Option<SessionKey>, WalletError> {
+    let result = call(
+        node_url,
+        "getSessionKey",
+        serde_json::json!([hex::encode(owner), hex::encode(session_key)]),
+    )
+    .await?;
+
+    let (root, proof) = parse_data_proof(&result)?;
+    core::session_keys::verify_session_key(owner, session_key, &proof, &root)
+        .map_err(|e| WalletError::NodeRequestFailed(format!("Invalid session key proof: {}", e)))
+}
+
+/// Gets an address's balance proof against a proposal's snapshot, or `None`
+/// if the node has no proof for it.
+pub async fn get_voting_proof(
+    node_url: &str,
+    proposal_id: &ProposalId,
+    address: &Address,
+) -> Result<Option<AccountProof>, WalletError> {
+    let result = call(
+        node_url,
+        "getVotingProof",
+        serde_json::json!([hex::encode(proposal_id), hex::encode(address)]),
+    )
+    .await?;
+    if result.is_null() {
+        return Ok(None);
+    }
+
+    result
+        .get("proof")
+        .and_then(|v| v.as_str())
+        .and_then(|v| hex::decode(v).ok())
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .map(Some)
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid proof in response: {}", result)))
+}
+
+/// Extracts the state root and data proof from a node response.
+fn parse_data_proof(result: &serde_json::Value) -> Result<([u8; 32], DataProof), WalletError> {
+    let root: [u8; 32] = result
+        .get("root")
+        .and_then(|v| v.as_str())
+        .and_then(|v| hex::decode(v).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid root in response: {}", result)))?;
+
+    let proof: DataProof = result
+        .get("proof")
+        .and_then(|v| v.as_str())
+        .and_then(|v| hex::decode(v).ok())
+        .and_then(|bytes| bincode::deserialize(&bytes).ok())
+        .ok_or_else(|| WalletError::NodeRequestFailed(format!("Invalid proof in response: {}", result)))?;
+
+    Ok((root, proof))
+}
@@ -0,0 +1,94 @@
+//! Local tracking of received stealth payments.
+//!
+//! `scan` finds the stealth payments addressed to the wallet and records, for
+//! each one-time address, the tweak that together with the wallet's spend
+//! secret controls it. The records live in a file next to the wallet.
+
+use crate::errors::WalletError;
+use crate::wallet::Wallet;
+use core::stealth::StealthSecret;
+use core::types::Address;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A stealth payment received by the wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StealthOutput {
+    /// Hex-encoded one-time address
+    pub address: String,
+    /// Hex-encoded one-time tweak
+    pub tweak: String,
+    /// The token ID
+    pub token_id: u64,
+    /// The amount received
+    pub amount: String,
+    /// Hex-encoded transaction hash
+    pub tx_hash: String,
+}
+
+/// The wallet's received stealth payments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StealthStore {
+    /// Timestamp of the newest payment seen by the last scan
+    pub last_scanned: u64,
+    /// Payments found so far
+    pub outputs: Vec<StealthOutput>,
+    /// Where the store is saved
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl StealthStore {
+    /// Loads the store kept next to the given wallet file, or an empty one.
+    pub fn load_for_wallet<P: AsRef<Path>>(wallet_path: P) -> Result<Self, WalletError> {
+        let mut path = wallet_path.as_ref().as_os_str().to_owned();
+        path.push(".stealth");
+        let path = PathBuf::from(path);
+
+        let mut store = if path.exists() {
+            let contents = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&contents)?
+        } else {
+            StealthStore::default()
+        };
+
+        store.path = path;
+        Ok(store)
+    }
+
+    /// Saves the store.
+    pub fn save(&self) -> Result<(), WalletError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(&self.path, contents)?;
+        Ok(())
+    }
+
+    /// Returns whether a transaction has already been recorded.
+    pub fn contains(&self, tx_hash: &str) -> bool {
+        self.outputs.iter().any(|output| output.tx_hash == tx_hash)
+    }
+
+    /// Computes the secret controlling a one-time address found by a scan.
+    pub fn one_time_secret(&self, wallet: &Wallet, address: &Address) -> Result<StealthSecret, WalletError> {
+        let address_hex = hex::encode(address);
+        let output = self
+            .outputs
+            .iter()
+            .find(|output| output.address == address_hex)
+            .ok_or_else(|| {
+                WalletError::InvalidAddress(format!(
+                    "0x{} is not a stealth address of this wallet (run scan first)",
+                    address_hex
+                ))
+            })?;
+
+        let tweak: StealthSecret = hex::decode(&output.tweak)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| WalletError::WalletError("Invalid tweak in stealth store".to_string()))?;
+
+        let (_, spend_secret) = wallet.stealth_secrets()?;
+        core::stealth::one_time_secret(&spend_secret, &tweak)
+            .map_err(|e| WalletError::WalletError(e.to_string()))
+    }
+}
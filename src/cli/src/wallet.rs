@@ -2,11 +2,14 @@
 
 use crate::errors::WalletError;
 use bip32::{Mnemonic, XPrv};
-use core::types::Address;
+use core::stealth::{StealthMetaAddress, StealthSecret};
+use core::typed_data::TypedData;
+use core::types::{Address, SignatureScheme, SystemMsg};
 use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer};
+use k256::ecdsa::SigningKey;
 use rand::rngs::OsRng;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
 use std::fs::{self, File};
 use std::io::{Read, Write};
 use std::path::Path;
@@ -18,6 +21,10 @@ pub struct Wallet {
     mnemonic: String,
     /// The current account index
     account_index: u32,
+    /// Hex-encoded secp256k1 (Ethereum) private key; when set, the wallet
+    /// controls the secp256k1 account derived from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secp256k1_key: Option<String>,
 }
 
 impl Wallet {
@@ -28,6 +35,7 @@ impl Wallet {
         Ok(Self {
             mnemonic: mnemonic.phrase().to_string(),
             account_index: 0,
+            secp256k1_key: None,
         })
     }
 
@@ -71,6 +79,33 @@ impl Wallet {
         self.account_index = index;
     }
 
+    /// Gets the signature scheme of the current account.
+    pub fn scheme(&self) -> SignatureScheme {
+        if self.secp256k1_key.is_some() {
+            SignatureScheme::Secp256k1
+        } else {
+            SignatureScheme::Ed25519
+        }
+    }
+
+    /// Imports an Ethereum private key, switching the wallet to its secp256k1 account.
+    pub fn import_eth_key(&mut self, key_hex: &str) -> Result<Address, WalletError> {
+        let key_hex = key_hex.trim_start_matches("0x");
+        parse_secp256k1_key(key_hex)?;
+        self.secp256k1_key = Some(key_hex.to_lowercase());
+        self.address()
+    }
+
+    /// Gets the secp256k1 signing key, if one has been imported.
+    pub fn secp256k1_key(&self) -> Result<SigningKey, WalletError> {
+        match &self.secp256k1_key {
+            Some(key_hex) => parse_secp256k1_key(key_hex),
+            None => Err(WalletError::WalletError(
+                "No secp256k1 key has been imported into this wallet".to_string(),
+            )),
+        }
+    }
+
     /// Gets the keypair for the current account.
     pub fn keypair(&self) -> Result<Keypair, WalletError> {
         // Parse the mnemonic
@@ -102,6 +137,12 @@ impl Wallet {
 
     /// Gets the address for the current account.
     pub fn address(&self) -> Result<Address, WalletError> {
+        if self.scheme() == SignatureScheme::Secp256k1 {
+            let key = self.secp256k1_key()?;
+            let eth_address = core::crypto::eth_address_from_key(key.verifying_key());
+            return Ok(core::types::address_from_eth(&eth_address));
+        }
+
         let keypair = self.keypair()?;
         let public_key = keypair.public.to_bytes();
         
@@ -118,12 +159,94 @@ impl Wallet {
         Ok(address)
     }
 
-    /// Signs a message with the current account's private key.
+    /// Signs a message with the current account's Ed25519 private key.
     pub fn sign(&self, message: &[u8]) -> Result<Signature, WalletError> {
+        if self.scheme() != SignatureScheme::Ed25519 {
+            return Err(WalletError::WalletError(
+                "This operation requires an ed25519 account".to_string(),
+            ));
+        }
+
         let keypair = self.keypair()?;
         let signature = keypair.sign(message);
         Ok(signature)
     }
+
+    /// Signs a message with the current account's key, whichever scheme it uses.
+    pub fn sign_with_scheme(&self, message: &[u8]) -> Result<core::types::Signature, WalletError> {
+        match self.scheme() {
+            SignatureScheme::Ed25519 => Ok(core::types::Signature(self.sign(message)?.to_bytes())),
+            SignatureScheme::Secp256k1 => core::crypto::sign_secp256k1(&self.secp256k1_key()?, message)
+                .map_err(|e| WalletError::TransactionError(e.to_string())),
+        }
+    }
+
+    /// Signs an off-chain message (login, proof of ownership) in the domain-prefixed format.
+    pub fn sign_message(&self, message: &[u8]) -> Result<core::types::Signature, WalletError> {
+        self.sign_with_scheme(&core::crypto::signed_message_bytes(message))
+    }
+
+    /// Signs a typed structured-data document.
+    pub fn sign_typed_data(&self, data: &TypedData) -> Result<core::types::Signature, WalletError> {
+        let bytes = data
+            .signing_bytes()
+            .map_err(|e| WalletError::WalletError(e.to_string()))?;
+        self.sign_with_scheme(&bytes)
+    }
+
+    /// Signs a system message in place.
+    pub fn sign_system_msg(&self, msg: &mut SystemMsg) -> Result<(), WalletError> {
+        let bytes = msg
+            .signing_bytes()
+            .map_err(|e| WalletError::TransactionError(e.to_string()))?;
+        *msg.signature_mut() = self.sign_with_scheme(&bytes)?;
+        Ok(())
+    }
+
+    /// Derives the secret used to decrypt confidential transfers sent to the current account.
+    pub fn confidential_view_secret(&self) -> Result<core::confidential::Blinding, WalletError> {
+        Ok(core::confidential::blinding_from_bytes(&self.derive_secret(b"volt-confidential-view")?))
+    }
+
+    /// Derives the scan and spend secrets of the current account's stealth meta-address.
+    pub fn stealth_secrets(&self) -> Result<(StealthSecret, StealthSecret), WalletError> {
+        Ok((
+            core::stealth::secret_from_bytes(&self.derive_secret(b"volt-stealth-scan")?),
+            core::stealth::secret_from_bytes(&self.derive_secret(b"volt-stealth-spend")?),
+        ))
+    }
+
+    /// Gets the stealth meta-address senders use to pay the current account privately.
+    pub fn stealth_meta_address(&self) -> Result<StealthMetaAddress, WalletError> {
+        let (scan_secret, spend_secret) = self.stealth_secrets()?;
+        StealthMetaAddress::from_secrets(&scan_secret, &spend_secret)
+            .map_err(|e| WalletError::WalletError(e.to_string()))
+    }
+
+    /// Derives 64 bytes for a purpose-specific secret from the current account's key.
+    fn derive_secret(&self, label: &[u8]) -> Result<[u8; 64], WalletError> {
+        let secret = match self.scheme() {
+            SignatureScheme::Ed25519 => self.keypair()?.secret.to_bytes().to_vec(),
+            SignatureScheme::Secp256k1 => self.secp256k1_key()?.to_bytes().to_vec(),
+        };
+
+        let mut hasher = Sha512::new();
+        hasher.update(label);
+        hasher.update(&secret);
+
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(&hasher.finalize());
+        Ok(wide)
+    }
+}
+
+/// Parses a hex-encoded secp256k1 private key.
+fn parse_secp256k1_key(key_hex: &str) -> Result<SigningKey, WalletError> {
+    let key_bytes = hex::decode(key_hex.trim_start_matches("0x"))
+        .map_err(|e| WalletError::WalletError(format!("Invalid secp256k1 key: {}", e)))?;
+
+    SigningKey::from_slice(&key_bytes)
+        .map_err(|e| WalletError::WalletError(format!("Invalid secp256k1 key: {}", e)))
 }
 
 #[cfg(test)]
@@ -175,4 +298,62 @@ mod tests {
         let keypair = wallet.keypair().unwrap();
         keypair.verify(message, &signature).unwrap();
     }
+
+    #[test]
+    fn test_wallet_secp256k1_account() {
+        let mut wallet = Wallet::new().unwrap();
+        let address = wallet.import_eth_key(&hex::encode([7u8; 32])).unwrap();
+
+        assert_eq!(wallet.scheme(), SignatureScheme::Secp256k1);
+        assert_eq!(SignatureScheme::of_address(&address), SignatureScheme::Secp256k1);
+
+        let message = b"Hello, world!";
+        let signature = wallet.sign_with_scheme(message).unwrap();
+        core::crypto::verify_signature(SignatureScheme::Secp256k1, &address, message, &signature).unwrap();
+    }
+
+    #[test]
+    fn test_wallet_sign_message() {
+        let wallet = Wallet::new().unwrap();
+        let address = wallet.address().unwrap();
+        let message = b"Log in to example.com";
+
+        let signature = wallet.sign_message(message).unwrap();
+        core::crypto::verify_signed_message(&address, message, &signature).unwrap();
+
+        // A message signature is not a signature over the raw bytes
+        assert!(core::crypto::verify_signature(SignatureScheme::Ed25519, &address, message, &signature).is_err());
+    }
+
+    #[test]
+    fn test_wallet_confidential_view_secret() {
+        let wallet = Wallet::new().unwrap();
+        let view_secret = wallet.confidential_view_secret().unwrap();
+        assert_eq!(view_secret, wallet.confidential_view_secret().unwrap());
+
+        let viewing_key = core::confidential::viewing_key(&view_secret).unwrap();
+        let opening = core::confidential::encrypt_opening(&viewing_key, 25, &[0u8; 32], &[3u8; 64]).unwrap();
+        assert_eq!(core::confidential::decrypt_opening(&view_secret, &opening), Some((25, [0u8; 32])));
+
+        // Another wallet cannot read the opening
+        let other = Wallet::new().unwrap().confidential_view_secret().unwrap();
+        assert_eq!(core::confidential::decrypt_opening(&other, &opening), None);
+    }
+
+    #[test]
+    fn test_wallet_stealth_payment() {
+        let wallet = Wallet::new().unwrap();
+        let meta = wallet.stealth_meta_address().unwrap();
+        let (scan_secret, spend_secret) = wallet.stealth_secrets().unwrap();
+
+        let (address, ephemeral_key) = core::stealth::derive_one_time_address(&meta, &[8u8; 64]).unwrap();
+        let tweak = core::stealth::scan(&scan_secret, &meta.spend_key, &address, &ephemeral_key).unwrap();
+        let secret = core::stealth::one_time_secret(&spend_secret, &tweak).unwrap();
+        assert_eq!(core::stealth::public_key(&secret).unwrap(), address);
+
+        // Payments to another wallet are not picked up
+        let other = Wallet::new().unwrap().stealth_meta_address().unwrap();
+        let (address, ephemeral_key) = core::stealth::derive_one_time_address(&other, &[8u8; 64]).unwrap();
+        assert!(core::stealth::scan(&scan_secret, &meta.spend_key, &address, &ephemeral_key).is_none());
+    }
 }
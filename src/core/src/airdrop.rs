@@ -0,0 +1,547 @@
+//! Merkle-distributor airdrops.
+//!
+//! Instead of minting to every recipient, an issuer commits the Merkle root
+//! of a list of `(address, amount)` entries and escrows their total from its
+//! own balance. Each recipient then claims its entry with an inclusion
+//! proof. Claims are recorded in a bitmap, 256 entries per data leaf, so an
+//! entry can only be claimed once.
+//!
+//! The tree is not checked against the escrowed total, so the total caps
+//! what can be claimed: if the entries add up to more, the claims that would
+//! exceed it fail even with valid proofs.
+//!
+//! Claims are accepted until the airdrop expires. After that the issuer can
+//! reclaim whatever has not been claimed, which closes the airdrop.
+//!
+//! The distribution tree hashes each entry together with its index and pads
+//! the leaves with zero hashes to a power of two, so a proof is simply the
+//! list of siblings from the leaf up to the root.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{AccountLeaf, Address, Balance, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data leaf kind of airdrops.
+pub const AIRDROP_LEAF_KIND: &str = "airdrop";
+
+/// Data leaf kind of claimed bitmaps.
+pub const CLAIMS_LEAF_KIND: &str = "airdrop_claims";
+
+/// Number of entries tracked by one claimed-bitmap leaf.
+pub const CLAIMS_PER_LEAF: u64 = 256;
+
+/// Domain label of distribution tree leaves.
+const LEAF_LABEL: &[u8] = b"volt-airdrop-leaf";
+
+/// Domain label of distribution tree nodes.
+const NODE_LABEL: &[u8] = b"volt-airdrop-node";
+
+/// Identifier of an airdrop.
+pub type AirdropId = [u8; 32];
+
+/// An entry of a distribution.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AirdropEntry {
+    /// The recipient
+    pub address: Address,
+    /// The amount the recipient can claim
+    pub amount: Balance,
+}
+
+/// A committed airdrop.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Airdrop {
+    /// The airdrop ID
+    pub airdrop_id: AirdropId,
+    /// The issuer that funded the airdrop
+    pub issuer: Address,
+    /// The token being distributed
+    pub token_id: TokenId,
+    /// Root of the distribution tree
+    pub merkle_root: [u8; 32],
+    /// Number of entries in the distribution
+    pub entries: u64,
+    /// Total amount escrowed
+    pub total: Balance,
+    /// Amount claimed so far
+    pub claimed: Balance,
+    /// Time from which entries can no longer be claimed, in seconds since
+    /// the Unix epoch
+    pub expires_at: u64,
+    /// Whether the issuer has reclaimed the unclaimed amount
+    pub reclaimed: bool,
+}
+
+/// A distribution tree built from a list of entries.
+#[derive(Clone, Debug)]
+pub struct AirdropTree {
+    /// Levels of the tree, from the padded leaves up to the root
+    levels: Vec<Vec<[u8; 32]>>,
+    /// The number of real entries
+    entries: u64,
+}
+
+impl AirdropTree {
+    /// Builds the tree of a distribution.
+    pub fn build(entries: &[AirdropEntry]) -> Result<Self, CoreError> {
+        if entries.is_empty() {
+            return Err(CoreError::InvalidAirdrop("Distribution has no entries".to_string()));
+        }
+
+        let width = entries.len().next_power_of_two();
+        let mut leaves: Vec<[u8; 32]> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| leaf_hash(index as u64, entry))
+            .collect();
+        leaves.resize(width, [0u8; 32]);
+
+        let mut levels = vec![leaves];
+        while levels.last().map_or(0, |level| level.len()) > 1 {
+            let level = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| node_hash(&pair[0], &pair[1]))
+                .collect();
+            levels.push(level);
+        }
+
+        Ok(Self {
+            levels,
+            entries: entries.len() as u64,
+        })
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> [u8; 32] {
+        self.levels.last().unwrap()[0]
+    }
+
+    /// Returns the number of entries.
+    pub fn entries(&self) -> u64 {
+        self.entries
+    }
+
+    /// Returns the inclusion proof of the entry at `index`.
+    pub fn proof(&self, index: u64) -> Result<Vec<[u8; 32]>, CoreError> {
+        if index >= self.entries {
+            return Err(CoreError::InvalidAirdrop(format!("No entry at index {}", index)));
+        }
+
+        let mut position = index as usize;
+        let mut proof = Vec::with_capacity(self.levels.len() - 1);
+        for level in &self.levels[..self.levels.len() - 1] {
+            proof.push(level[position ^ 1]);
+            position /= 2;
+        }
+        Ok(proof)
+    }
+}
+
+/// Computes the ID of an airdrop from its issuer, token and distribution root.
+pub fn airdrop_id(issuer: &Address, token_id: TokenId, merkle_root: &[u8; 32]) -> AirdropId {
+    let mut hasher = Sha256::new();
+    hasher.update(issuer);
+    hasher.update(token_id.to_le_bytes());
+    hasher.update(merkle_root);
+
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Computes the tree key of an airdrop.
+pub fn airdrop_key(airdrop_id: &AirdropId) -> [u8; 32] {
+    data_leaf_key(AIRDROP_LEAF_KIND, airdrop_id)
+}
+
+/// Computes the tree key of the claimed-bitmap leaf covering `index`.
+pub fn claims_key(airdrop_id: &AirdropId, index: u64) -> [u8; 32] {
+    let mut id = [0u8; 40];
+    id[..32].copy_from_slice(airdrop_id);
+    id[32..].copy_from_slice(&(index / CLAIMS_PER_LEAF).to_le_bytes());
+    data_leaf_key(CLAIMS_LEAF_KIND, &id)
+}
+
+/// Checks an airdrop proof against a state root and returns the proven
+/// airdrop, or `None` if the proof shows it does not exist.
+pub fn verify_airdrop(airdrop_id: &AirdropId, proof: &DataProof, root: &[u8; 32]) -> Result<Option<Airdrop>, CoreError> {
+    if proof.key != airdrop_key(airdrop_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for airdrop {}",
+            hex::encode(airdrop_id)
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(AIRDROP_LEAF_KIND)
+}
+
+/// Checks a claimed-bitmap proof against a state root and returns whether
+/// the entry at `index` has been claimed.
+pub fn verify_claimed(airdrop_id: &AirdropId, index: u64, proof: &DataProof, root: &[u8; 32]) -> Result<bool, CoreError> {
+    if proof.key != claims_key(airdrop_id, index) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for entry {} of airdrop {}",
+            index,
+            hex::encode(airdrop_id)
+        )));
+    }
+    proof.verify(root)?;
+    let bitmap: [u8; 32] = proof.value(CLAIMS_LEAF_KIND)?.unwrap_or_default();
+    Ok(is_claimed(&bitmap, index))
+}
+
+/// Checks that an entry is part of a distribution with `entries` entries.
+pub fn verify_inclusion(
+    merkle_root: &[u8; 32],
+    entries: u64,
+    index: u64,
+    entry: &AirdropEntry,
+    proof: &[[u8; 32]],
+) -> Result<(), CoreError> {
+    let depth = entries.next_power_of_two().trailing_zeros() as usize;
+    if index >= entries || proof.len() != depth {
+        return Err(CoreError::InvalidAirdropProof);
+    }
+
+    let mut hash = leaf_hash(index, entry);
+    let mut position = index;
+    for sibling in proof {
+        hash = if position & 1 == 0 {
+            node_hash(&hash, sibling)
+        } else {
+            node_hash(sibling, &hash)
+        };
+        position /= 2;
+    }
+
+    if hash != *merkle_root {
+        return Err(CoreError::InvalidAirdropProof);
+    }
+    Ok(())
+}
+
+/// Returns whether the bit of `index` is set in its claimed bitmap.
+fn is_claimed(bitmap: &[u8; 32], index: u64) -> bool {
+    let bit = (index % CLAIMS_PER_LEAF) as usize;
+    bitmap[bit / 8] & (1 << (bit % 8)) != 0
+}
+
+/// Hashes an entry and its index to a tree leaf.
+fn leaf_hash(index: u64, entry: &AirdropEntry) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(LEAF_LABEL);
+    hasher.update(index.to_le_bytes());
+    hasher.update(entry.address);
+    hasher.update(entry.amount.to_le_bytes());
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+/// Hashes two children to their parent node.
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(NODE_LABEL);
+    hasher.update(left);
+    hasher.update(right);
+
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&hasher.finalize());
+    hash
+}
+
+impl SMT {
+    /// Gets an airdrop, if created.
+    pub fn get_airdrop(&self, airdrop_id: &AirdropId) -> Result<Option<Airdrop>, CoreError> {
+        self.get_data_leaf(AIRDROP_LEAF_KIND, &airdrop_key(airdrop_id))
+    }
+
+    /// Returns whether the entry at `index` of an airdrop has been claimed.
+    pub fn is_airdrop_claimed(&self, airdrop_id: &AirdropId, index: u64) -> Result<bool, CoreError> {
+        let bitmap: [u8; 32] = self
+            .get_data_leaf(CLAIMS_LEAF_KIND, &claims_key(airdrop_id, index))?
+            .unwrap_or_default();
+        Ok(is_claimed(&bitmap, index))
+    }
+
+    /// Creates an airdrop, escrowing `total` of the issuer's balance of the
+    /// token until `expires_at`. Returns the airdrop ID.
+    ///
+    /// `total` is not checked against the entries of the tree and caps the
+    /// claims, so it should be the sum of their amounts.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_airdrop(
+        &mut self,
+        issuer: &Address,
+        token_id: TokenId,
+        merkle_root: [u8; 32],
+        entries: u64,
+        total: Balance,
+        expires_at: u64,
+        nonce: Nonce,
+    ) -> Result<AirdropId, CoreError> {
+        if entries == 0 || total == 0 {
+            return Err(CoreError::InvalidAirdrop("Airdrop must have entries and a total".to_string()));
+        }
+
+        let id = airdrop_id(issuer, token_id, &merkle_root);
+        if self.get_airdrop(&id)?.is_some() {
+            return Err(CoreError::InvalidAirdrop(format!("Airdrop {} already exists", hex::encode(id))));
+        }
+
+        let account = self.get_account_with_token(issuer, token_id)?;
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+        if account.bal < total {
+            return Err(CoreError::InsufficientBalance {
+                required: total,
+                available: account.bal,
+            });
+        }
+
+        let airdrop = Airdrop {
+            airdrop_id: id,
+            issuer: *issuer,
+            token_id,
+            merkle_root,
+            entries,
+            total,
+            claimed: 0,
+            expires_at,
+            reclaimed: false,
+        };
+        self.put_data_leaf(AIRDROP_LEAF_KIND, airdrop_key(&id), &airdrop)?;
+        self.update(AccountLeaf::new(*issuer, account.bal - total, account.nonce + 1, token_id))?;
+
+        Ok(id)
+    }
+
+    /// Pays out an airdrop entry to its recipient, if the airdrop has not
+    /// expired at `time`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn claim_airdrop(
+        &mut self,
+        claimant: &Address,
+        airdrop_id: &AirdropId,
+        index: u64,
+        amount: Balance,
+        proof: &[[u8; 32]],
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        let mut airdrop = self.airdrop_leaf(airdrop_id)?;
+
+        if airdrop.reclaimed || time >= airdrop.expires_at {
+            return Err(CoreError::InvalidAirdrop(format!(
+                "Airdrop {} expired at {}",
+                hex::encode(airdrop_id),
+                airdrop.expires_at
+            )));
+        }
+
+        if self.is_airdrop_claimed(airdrop_id, index)? {
+            return Err(CoreError::AirdropAlreadyClaimed { index });
+        }
+
+        let entry = AirdropEntry {
+            address: *claimant,
+            amount,
+        };
+        verify_inclusion(&airdrop.merkle_root, airdrop.entries, index, &entry, proof)?;
+
+        // The tree is not checked against the total, so the escrow caps the claims
+        let claimed = airdrop
+            .claimed
+            .checked_add(amount)
+            .filter(|claimed| *claimed <= airdrop.total)
+            .ok_or_else(|| CoreError::InvalidAirdrop("Claims exceed the escrowed total".to_string()))?;
+
+        self.check_compliance(airdrop.token_id, claimant)?;
+        self.use_native_nonce(claimant, nonce)?;
+
+        // Mark the entry as claimed
+        let key = claims_key(airdrop_id, index);
+        let mut bitmap: [u8; 32] = self.get_data_leaf(CLAIMS_LEAF_KIND, &key)?.unwrap_or_default();
+        let bit = (index % CLAIMS_PER_LEAF) as usize;
+        bitmap[bit / 8] |= 1 << (bit % 8);
+        self.put_data_leaf(CLAIMS_LEAF_KIND, key, &bitmap)?;
+
+        airdrop.claimed = claimed;
+        self.put_data_leaf(AIRDROP_LEAF_KIND, airdrop_key(airdrop_id), &airdrop)?;
+
+        let receiver = self
+            .get_account_with_token(claimant, airdrop.token_id)
+            .unwrap_or_else(|_| AccountLeaf::new_empty(*claimant, airdrop.token_id));
+        let balance = receiver.bal.checked_add(amount).ok_or(CoreError::SupplyOverflow)?;
+        self.update(AccountLeaf::new(*claimant, balance, receiver.nonce, airdrop.token_id))
+    }
+
+    /// Refunds the unclaimed amount of an expired airdrop to its issuer and
+    /// closes it to further claims. Returns the amount refunded.
+    pub fn reclaim_airdrop(
+        &mut self,
+        issuer: &Address,
+        airdrop_id: &AirdropId,
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<Balance, CoreError> {
+        let mut airdrop = self.airdrop_leaf(airdrop_id)?;
+
+        if airdrop.issuer != *issuer {
+            return Err(CoreError::Unauthorized(
+                "Only the issuer can reclaim an airdrop".to_string(),
+            ));
+        }
+        if airdrop.reclaimed {
+            return Err(CoreError::InvalidAirdrop(format!(
+                "Airdrop {} has already been reclaimed",
+                hex::encode(airdrop_id)
+            )));
+        }
+        if time < airdrop.expires_at {
+            return Err(CoreError::InvalidAirdrop(format!(
+                "Airdrop {} expires at {}",
+                hex::encode(airdrop_id),
+                airdrop.expires_at
+            )));
+        }
+
+        self.use_native_nonce(issuer, nonce)?;
+
+        let unclaimed = airdrop.total - airdrop.claimed;
+        airdrop.reclaimed = true;
+        self.put_data_leaf(AIRDROP_LEAF_KIND, airdrop_key(airdrop_id), &airdrop)?;
+
+        let account = self
+            .get_account_with_token(issuer, airdrop.token_id)
+            .unwrap_or_else(|_| AccountLeaf::new_empty(*issuer, airdrop.token_id));
+        let balance = account.bal.checked_add(unclaimed).ok_or(CoreError::SupplyOverflow)?;
+        self.update(AccountLeaf::new(*issuer, balance, account.nonce, airdrop.token_id))?;
+
+        Ok(unclaimed)
+    }
+
+    /// Gets an airdrop or fails.
+    fn airdrop_leaf(&self, airdrop_id: &AirdropId) -> Result<Airdrop, CoreError> {
+        self.get_airdrop(airdrop_id)?
+            .ok_or_else(|| CoreError::InvalidAirdrop(format!("Airdrop {} not found", hex::encode(airdrop_id))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_airdrop_claims() {
+        let mut smt = SMT::new_zero();
+        let issuer = [1u8; 32];
+        let token_id = smt.register_token(&issuer, "Drop|DRP|0".to_string()).unwrap();
+        smt.update(AccountLeaf::new(issuer, 1000, 0, token_id)).unwrap();
+
+        let entries: Vec<AirdropEntry> = (0..5u8)
+            .map(|i| AirdropEntry {
+                address: [10 + i; 32],
+                amount: 10 * (i as u128 + 1),
+            })
+            .collect();
+        let tree = AirdropTree::build(&entries).unwrap();
+        let total = entries.iter().map(|entry| entry.amount).sum();
+
+        let id = smt.create_airdrop(&issuer, token_id, tree.root(), tree.entries(), total, 2000, 0).unwrap();
+        assert_eq!(smt.get_account_with_token(&issuer, token_id).unwrap().bal, 1000 - total);
+
+        let proof = tree.proof(3).unwrap();
+        let claimant = entries[3].address;
+
+        // The amount must match the committed entry
+        assert!(matches!(
+            smt.claim_airdrop(&claimant, &id, 3, 1000, &proof, 1000, 0),
+            Err(CoreError::InvalidAirdropProof)
+        ));
+
+        smt.claim_airdrop(&claimant, &id, 3, 40, &proof, 1000, 0).unwrap();
+        assert_eq!(smt.get_account_with_token(&claimant, token_id).unwrap().bal, 40);
+        assert!(smt.is_airdrop_claimed(&id, 3).unwrap());
+        assert!(!smt.is_airdrop_claimed(&id, 2).unwrap());
+
+        let claims = smt.gen_data_proof(&claims_key(&id, 3)).unwrap();
+        assert!(verify_claimed(&id, 3, &claims, &smt.root()).unwrap());
+
+        assert!(matches!(
+            smt.claim_airdrop(&claimant, &id, 3, 40, &proof, 1000, 1),
+            Err(CoreError::AirdropAlreadyClaimed { index: 3 })
+        ));
+
+        // Proofs cannot be reused by another address or index
+        assert!(smt.claim_airdrop(&entries[2].address, &id, 3, 40, &proof, 1000, 0).is_err());
+        assert_eq!(smt.get_airdrop(&id).unwrap().unwrap().claimed, 40);
+    }
+
+    #[test]
+    fn test_airdrop_expiry_and_reclaim() {
+        let mut smt = SMT::new_zero();
+        let issuer = [1u8; 32];
+        let token_id = smt.register_token(&issuer, "Drop|DRP|0".to_string()).unwrap();
+        smt.update(AccountLeaf::new(issuer, 100, 0, token_id)).unwrap();
+
+        let entries = vec![
+            AirdropEntry { address: [10u8; 32], amount: 30 },
+            AirdropEntry { address: [11u8; 32], amount: 70 },
+        ];
+        let tree = AirdropTree::build(&entries).unwrap();
+        let id = smt.create_airdrop(&issuer, token_id, tree.root(), tree.entries(), 100, 2000, 0).unwrap();
+
+        smt.claim_airdrop(&entries[0].address, &id, 0, 30, &tree.proof(0).unwrap(), 1999, 0).unwrap();
+
+        // Nothing can be reclaimed before the expiry, or by anyone but the issuer
+        assert!(smt.reclaim_airdrop(&issuer, &id, 1999, 0).is_err());
+        assert!(matches!(
+            smt.reclaim_airdrop(&entries[1].address, &id, 2000, 0),
+            Err(CoreError::Unauthorized(_))
+        ));
+
+        // Claims stop at the expiry
+        assert!(smt.claim_airdrop(&entries[1].address, &id, 1, 70, &tree.proof(1).unwrap(), 2000, 0).is_err());
+
+        assert_eq!(smt.reclaim_airdrop(&issuer, &id, 2000, 0).unwrap(), 70);
+        assert_eq!(smt.get_account_with_token(&issuer, token_id).unwrap().bal, 70);
+        assert!(smt.get_airdrop(&id).unwrap().unwrap().reclaimed);
+
+        // A reclaimed airdrop is closed for good
+        assert!(smt.reclaim_airdrop(&issuer, &id, 3000, 1).is_err());
+        assert!(smt.claim_airdrop(&entries[1].address, &id, 1, 70, &tree.proof(1).unwrap(), 1000, 0).is_err());
+    }
+
+    #[test]
+    fn test_airdrop_claims_are_capped_by_total() {
+        let mut smt = SMT::new_zero();
+        let issuer = [1u8; 32];
+        let token_id = smt.register_token(&issuer, "Drop|DRP|0".to_string()).unwrap();
+        smt.update(AccountLeaf::new(issuer, 100, 0, token_id)).unwrap();
+
+        // The entries add up to more than is escrowed
+        let entries = vec![
+            AirdropEntry { address: [10u8; 32], amount: 60 },
+            AirdropEntry { address: [11u8; 32], amount: 60 },
+        ];
+        let tree = AirdropTree::build(&entries).unwrap();
+        let id = smt.create_airdrop(&issuer, token_id, tree.root(), tree.entries(), 100, 2000, 0).unwrap();
+
+        smt.claim_airdrop(&entries[0].address, &id, 0, 60, &tree.proof(0).unwrap(), 1000, 0).unwrap();
+        assert!(matches!(
+            smt.claim_airdrop(&entries[1].address, &id, 1, 60, &tree.proof(1).unwrap(), 1000, 0),
+            Err(CoreError::InvalidAirdrop(_))
+        ));
+        assert_eq!(smt.get_airdrop(&id).unwrap().unwrap().claimed, 60);
+    }
+}
@@ -0,0 +1,325 @@
+//! Unidirectional payment channels.
+//!
+//! Opening a channel escrows a deposit from the payer in a data leaf. The
+//! payer then pays the payee off-network by handing out signed vouchers,
+//! each carrying the cumulative amount owed so far. The payee settles by
+//! closing the channel with the latest voucher, which pays it that amount
+//! and refunds the rest of the deposit to the payer.
+//!
+//! If the payee never closes, the payer starts the channel's dispute
+//! timeout. The payee can still close until the timeout ends; after that the
+//! payer reclaims the whole deposit. Timeout messages carry the payer's
+//! claimed time, which the node they are submitted to checks against its own
+//! clock.
+
+use crate::crypto::verify_signed_message;
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{AccountLeaf, Address, Balance, Nonce, Signature, TokenId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data leaf kind of payment channels.
+pub const CHANNEL_LEAF_KIND: &str = "payment_channel";
+
+/// Domain label of voucher signing payloads.
+const VOUCHER_LABEL: &[u8] = b"volt-channel-voucher";
+
+/// Identifier of a payment channel.
+pub type ChannelId = [u8; 32];
+
+/// An open payment channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentChannel {
+    /// The channel ID
+    pub channel_id: ChannelId,
+    /// The address paying through the channel
+    pub payer: Address,
+    /// The address paid through the channel
+    pub payee: Address,
+    /// The token paid
+    pub token_id: TokenId,
+    /// Amount escrowed from the payer
+    pub deposit: Balance,
+    /// Length of the dispute timeout in seconds
+    pub timeout_secs: u64,
+    /// End of the dispute timeout, once the payer has started it
+    pub closes_at: Option<u64>,
+}
+
+/// A payer's off-network promise of the cumulative amount owed through a
+/// channel.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelVoucher {
+    /// The channel ID
+    pub channel_id: ChannelId,
+    /// Cumulative amount owed to the payee
+    pub amount: Balance,
+    /// The payer's signature over [`ChannelVoucher::signing_bytes`], in the
+    /// signed-message format
+    pub signature: Signature,
+}
+
+impl ChannelVoucher {
+    /// Returns the message the payer signs for a voucher.
+    pub fn signing_bytes(channel_id: &ChannelId, amount: Balance) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(VOUCHER_LABEL.len() + 48);
+        bytes.extend_from_slice(VOUCHER_LABEL);
+        bytes.extend_from_slice(channel_id);
+        bytes.extend_from_slice(&amount.to_le_bytes());
+        bytes
+    }
+
+    /// Verifies that the voucher is signed by the channel's payer.
+    pub fn verify(&self, payer: &Address) -> Result<(), CoreError> {
+        verify_signed_message(payer, &Self::signing_bytes(&self.channel_id, self.amount), &self.signature)
+    }
+}
+
+/// Computes the ID of a channel from its parties, token and the payer's
+/// nonce.
+pub fn channel_id(payer: &Address, payee: &Address, token_id: TokenId, nonce: Nonce) -> ChannelId {
+    let mut hasher = Sha256::new();
+    hasher.update(payer);
+    hasher.update(payee);
+    hasher.update(token_id.to_le_bytes());
+    hasher.update(nonce.to_le_bytes());
+
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Computes the tree key of a channel.
+pub fn channel_key(channel_id: &ChannelId) -> [u8; 32] {
+    data_leaf_key(CHANNEL_LEAF_KIND, channel_id)
+}
+
+/// Checks a channel proof against a state root and returns the proven
+/// channel, or `None` if the proof shows it is not open.
+pub fn verify_channel(channel_id: &ChannelId, proof: &DataProof, root: &[u8; 32]) -> Result<Option<PaymentChannel>, CoreError> {
+    if proof.key != channel_key(channel_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for channel {}",
+            hex::encode(channel_id)
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(CHANNEL_LEAF_KIND)
+}
+
+impl SMT {
+    /// Gets a channel, if open.
+    pub fn get_channel(&self, channel_id: &ChannelId) -> Result<Option<PaymentChannel>, CoreError> {
+        self.get_data_leaf(CHANNEL_LEAF_KIND, &channel_key(channel_id))
+    }
+
+    /// Opens a channel, escrowing `deposit` of the payer's balance of the
+    /// token. Returns the channel ID.
+    pub fn open_channel(
+        &mut self,
+        payer: &Address,
+        payee: &Address,
+        token_id: TokenId,
+        deposit: Balance,
+        timeout_secs: u64,
+        nonce: Nonce,
+    ) -> Result<ChannelId, CoreError> {
+        if payer == payee {
+            return Err(CoreError::InvalidChannel("Payer and payee must differ".to_string()));
+        }
+        if deposit == 0 || timeout_secs == 0 {
+            return Err(CoreError::InvalidChannel("Deposit and timeout must be positive".to_string()));
+        }
+
+        self.check_transfer_compliance(token_id, payer, payee)?;
+
+        let account = self.get_account_with_token(payer, token_id)?;
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+        if account.bal < deposit {
+            return Err(CoreError::InsufficientBalance {
+                required: deposit,
+                available: account.bal,
+            });
+        }
+
+        let id = channel_id(payer, payee, token_id, nonce);
+        let channel = PaymentChannel {
+            channel_id: id,
+            payer: *payer,
+            payee: *payee,
+            token_id,
+            deposit,
+            timeout_secs,
+            closes_at: None,
+        };
+        self.put_data_leaf(CHANNEL_LEAF_KIND, channel_key(&id), &channel)?;
+        self.update(AccountLeaf::new(*payer, account.bal - deposit, account.nonce + 1, token_id))?;
+
+        Ok(id)
+    }
+
+    /// Closes a channel with a voucher signed by its payer, paying the
+    /// voucher's amount to the payee and refunding the rest to the payer.
+    pub fn close_channel(&mut self, payee: &Address, voucher: &ChannelVoucher, nonce: Nonce) -> Result<(), CoreError> {
+        let channel = self.open_channel_leaf(&voucher.channel_id)?;
+
+        if channel.payee != *payee {
+            return Err(CoreError::Unauthorized(
+                "Only the payee can close a channel with a voucher".to_string(),
+            ));
+        }
+        if voucher.amount > channel.deposit {
+            return Err(CoreError::InsufficientBalance {
+                required: voucher.amount,
+                available: channel.deposit,
+            });
+        }
+        voucher.verify(&channel.payer).map_err(|_| CoreError::InvalidVoucher)?;
+
+        self.use_native_nonce(payee, nonce)?;
+        self.remove_data_leaf(&channel_key(&channel.channel_id))?;
+
+        self.credit(payee, channel.token_id, voucher.amount)?;
+        self.credit(&channel.payer, channel.token_id, channel.deposit - voucher.amount)
+    }
+
+    /// Starts a channel's dispute timeout (can only be done by its payer).
+    /// Returns the time at which the payer can reclaim the deposit.
+    pub fn start_channel_timeout(
+        &mut self,
+        payer: &Address,
+        channel_id: &ChannelId,
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<u64, CoreError> {
+        let mut channel = self.open_channel_leaf(channel_id)?;
+
+        if channel.payer != *payer {
+            return Err(CoreError::Unauthorized(
+                "Only the payer can start a channel's timeout".to_string(),
+            ));
+        }
+        if channel.closes_at.is_some() {
+            return Err(CoreError::InvalidChannel("The channel's timeout has already started".to_string()));
+        }
+
+        let closes_at = time.checked_add(channel.timeout_secs).ok_or_else(|| {
+            CoreError::InvalidChannel("The channel's timeout ends too late".to_string())
+        })?;
+
+        self.use_native_nonce(payer, nonce)?;
+        channel.closes_at = Some(closes_at);
+        self.put_data_leaf(CHANNEL_LEAF_KIND, channel_key(channel_id), &channel)?;
+
+        Ok(closes_at)
+    }
+
+    /// Refunds a channel's whole deposit to its payer once the dispute
+    /// timeout has ended. Returns the amount refunded.
+    pub fn reclaim_channel(
+        &mut self,
+        payer: &Address,
+        channel_id: &ChannelId,
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<Balance, CoreError> {
+        let channel = self.open_channel_leaf(channel_id)?;
+
+        if channel.payer != *payer {
+            return Err(CoreError::Unauthorized(
+                "Only the payer can reclaim a channel".to_string(),
+            ));
+        }
+        match channel.closes_at {
+            Some(closes_at) if time >= closes_at => {}
+            Some(closes_at) => {
+                return Err(CoreError::InvalidChannel(format!(
+                    "The channel's timeout ends at {}",
+                    closes_at
+                )));
+            }
+            None => {
+                return Err(CoreError::InvalidChannel("The channel's timeout has not started".to_string()));
+            }
+        }
+
+        self.use_native_nonce(payer, nonce)?;
+        self.remove_data_leaf(&channel_key(channel_id))?;
+        self.credit(payer, channel.token_id, channel.deposit)?;
+
+        Ok(channel.deposit)
+    }
+
+    /// Gets an open channel or fails.
+    fn open_channel_leaf(&self, channel_id: &ChannelId) -> Result<PaymentChannel, CoreError> {
+        self.get_channel(channel_id)?
+            .ok_or_else(|| CoreError::InvalidChannel(format!("Channel {} is not open", hex::encode(channel_id))))
+    }
+
+    /// Adds to an account's token balance, keeping its nonce.
+    fn credit(&mut self, addr: &Address, token_id: TokenId, amount: Balance) -> Result<(), CoreError> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        let account = self
+            .get_account_with_token(addr, token_id)
+            .unwrap_or_else(|_| AccountLeaf::new_empty(*addr, token_id));
+        let balance = account.bal.checked_add(amount).ok_or(CoreError::SupplyOverflow)?;
+        self.update(AccountLeaf::new(*addr, balance, account.nonce, token_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, Signer};
+
+    fn voucher(keypair: &Keypair, channel_id: ChannelId, amount: Balance) -> ChannelVoucher {
+        let message = crate::crypto::signed_message_bytes(&ChannelVoucher::signing_bytes(&channel_id, amount));
+        ChannelVoucher {
+            channel_id,
+            amount,
+            signature: Signature(keypair.sign(&message).to_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_channel_close_and_reclaim() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let keypair = Keypair { secret, public };
+        let payer = keypair.public.to_bytes();
+        let payee = [2u8; 32];
+
+        let mut smt = SMT::new_zero();
+        smt.update(AccountLeaf::new(payer, 100, 0, 0)).unwrap();
+
+        let id = smt.open_channel(&payer, &payee, 0, 80, 60, 0).unwrap();
+        assert_eq!(smt.get_account(&payer).unwrap().bal, 20);
+
+        // Vouchers must be signed by the payer and within the deposit
+        let forged = ChannelVoucher { signature: Signature([1u8; 64]), ..voucher(&keypair, id, 30) };
+        assert!(matches!(smt.close_channel(&payee, &forged, 0), Err(CoreError::InvalidVoucher)));
+        assert!(smt.close_channel(&payee, &voucher(&keypair, id, 81), 0).is_err());
+
+        smt.close_channel(&payee, &voucher(&keypair, id, 30), 0).unwrap();
+        assert_eq!(smt.get_account(&payee).unwrap().bal, 30);
+        assert_eq!(smt.get_account(&payer).unwrap().bal, 70);
+        assert!(smt.get_channel(&id).unwrap().is_none());
+
+        // Without a close, the payer reclaims after the timeout
+        let id = smt.open_channel(&payer, &payee, 0, 50, 60, 1).unwrap();
+        assert!(smt.reclaim_channel(&payer, &id, 1000, 2).is_err());
+        assert_eq!(smt.start_channel_timeout(&payer, &id, 1000, 2).unwrap(), 1060);
+        assert!(smt.reclaim_channel(&payer, &id, 1059, 3).is_err());
+        assert_eq!(smt.reclaim_channel(&payer, &id, 1060, 3).unwrap(), 50);
+        assert_eq!(smt.get_account(&payer).unwrap().bal, 70);
+    }
+}
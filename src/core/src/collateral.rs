@@ -0,0 +1,202 @@
+//! Collateralized token issuance.
+//!
+//! An issuer can lock native tokens as collateral when issuing a token. The
+//! collateral is held in an escrow leaf tied to the token ID, so anyone can
+//! prove how much backs a token. It is only released back to the issuer
+//! when the token is retired, which requires its whole supply to have been
+//! burned. A retired token can no longer be minted.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{AccountLeaf, Address, Balance, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+
+/// Data leaf kind of token escrows.
+pub const ESCROW_LEAF_KIND: &str = "token_escrow";
+
+/// The collateral locked for a token.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenEscrow {
+    /// The token ID
+    pub token_id: TokenId,
+    /// The issuer the collateral is returned to
+    pub issuer: Address,
+    /// Native tokens currently locked
+    pub collateral: Balance,
+    /// Whether the token has been retired
+    pub retired: bool,
+}
+
+/// Computes the tree key of a token's escrow.
+pub fn escrow_key(token_id: TokenId) -> [u8; 32] {
+    data_leaf_key(ESCROW_LEAF_KIND, &token_id.to_le_bytes())
+}
+
+/// Checks an escrow proof against a state root and returns the proven
+/// escrow, or `None` if the proof shows the token has no escrow.
+pub fn verify_escrow(token_id: TokenId, proof: &DataProof, root: &[u8; 32]) -> Result<Option<TokenEscrow>, CoreError> {
+    if proof.key != escrow_key(token_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for the escrow of token {}",
+            token_id
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(ESCROW_LEAF_KIND)
+}
+
+impl SMT {
+    /// Gets a token's escrow, or `None` if it was issued without collateral
+    /// and has not been retired.
+    pub fn get_token_escrow(&self, token_id: TokenId) -> Result<Option<TokenEscrow>, CoreError> {
+        self.get_data_leaf(ESCROW_LEAF_KIND, &escrow_key(token_id))
+    }
+
+    /// Returns whether a token has been retired.
+    pub fn is_token_retired(&self, token_id: TokenId) -> Result<bool, CoreError> {
+        Ok(self.get_token_escrow(token_id)?.is_some_and(|escrow| escrow.retired))
+    }
+
+    /// Issues a new token, locking `collateral` native tokens of the issuer
+    /// in escrow, and returns its token ID.
+    pub fn issue_token(
+        &mut self,
+        issuer: &Address,
+        metadata: String,
+        collateral: Balance,
+        nonce: Nonce,
+    ) -> Result<TokenId, CoreError> {
+        // Get the issuer account (using native token)
+        let issuer_account = self.get_account(issuer)?;
+
+        if issuer_account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: issuer_account.nonce,
+                actual: nonce,
+            });
+        }
+
+        if issuer_account.bal < collateral {
+            return Err(CoreError::InsufficientBalance {
+                required: collateral,
+                available: issuer_account.bal,
+            });
+        }
+
+        let token_id = self.register_token(issuer, metadata)?;
+
+        if collateral > 0 {
+            let escrow = TokenEscrow {
+                token_id,
+                issuer: *issuer,
+                collateral,
+                retired: false,
+            };
+            self.put_data_leaf(ESCROW_LEAF_KIND, escrow_key(token_id), &escrow)?;
+        }
+
+        // Debit the collateral and increment the nonce
+        self.update(AccountLeaf::new(
+            *issuer,
+            issuer_account.bal - collateral,
+            issuer_account.nonce + 1,
+            self.native_token_id,
+        ))?;
+
+        Ok(token_id)
+    }
+
+    /// Retires a token whose supply has been fully burned, releasing its
+    /// collateral to the issuer. Returns the amount released.
+    pub fn retire_token(&mut self, issuer: &Address, token_id: TokenId, nonce: Nonce) -> Result<Balance, CoreError> {
+        let token_info = self.get_token(token_id)?;
+        if token_info.issuer != *issuer {
+            return Err(CoreError::Unauthorized(format!(
+                "Only the issuer of token {} can retire it",
+                token_id
+            )));
+        }
+
+        if self.is_token_retired(token_id)? {
+            return Err(CoreError::TokenRetired(token_id));
+        }
+
+        if token_info.total_supply > 0 {
+            return Err(CoreError::SupplyOutstanding {
+                token_id,
+                supply: token_info.total_supply,
+            });
+        }
+
+        let released = self.get_token_escrow(token_id)?.map_or(0, |escrow| escrow.collateral);
+
+        let issuer_account = self.get_account(issuer)?;
+        if issuer_account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: issuer_account.nonce,
+                actual: nonce,
+            });
+        }
+
+        // Keep the emptied escrow to record the retirement
+        let escrow = TokenEscrow {
+            token_id,
+            issuer: *issuer,
+            collateral: 0,
+            retired: true,
+        };
+        self.put_data_leaf(ESCROW_LEAF_KIND, escrow_key(token_id), &escrow)?;
+
+        let balance = issuer_account
+            .bal
+            .checked_add(released)
+            .ok_or(CoreError::SupplyOverflow)?;
+        self.update(AccountLeaf::new(*issuer, balance, issuer_account.nonce + 1, self.native_token_id))?;
+
+        Ok(released)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collateral_lifecycle() {
+        let mut smt = SMT::new_zero();
+        let (issuer, holder) = ([1u8; 32], [2u8; 32]);
+        smt.update(AccountLeaf::new(issuer, 1000, 0, 0)).unwrap();
+
+        assert!(matches!(
+            smt.issue_token(&issuer, "Backed|BKD|0".to_string(), 2000, 0),
+            Err(CoreError::InsufficientBalance { .. })
+        ));
+
+        let token_id = smt.issue_token(&issuer, "Backed|BKD|0".to_string(), 600, 0).unwrap();
+        assert_eq!(smt.get_account(&issuer).unwrap().bal, 400);
+
+        // The escrow is provable against the root
+        let proof = smt.gen_data_proof(&escrow_key(token_id)).unwrap();
+        let escrow = verify_escrow(token_id, &proof, &smt.root()).unwrap().unwrap();
+        assert_eq!(escrow.collateral, 600);
+
+        // Collateral stays locked while any supply is outstanding
+        smt.update(AccountLeaf::new(issuer, 0, 0, token_id)).unwrap();
+        smt.mint_token(&issuer, &holder, token_id, 50, 0).unwrap();
+        assert!(matches!(
+            smt.retire_token(&issuer, token_id, 1),
+            Err(CoreError::SupplyOutstanding { .. })
+        ));
+
+        smt.burn_token(&holder, token_id, 50, 0).unwrap();
+        assert_eq!(smt.retire_token(&issuer, token_id, 1).unwrap(), 600);
+        assert_eq!(smt.get_account(&issuer).unwrap().bal, 1000);
+
+        // A retired token cannot be minted or retired again
+        assert!(matches!(
+            smt.mint_token(&issuer, &holder, token_id, 1, 1),
+            Err(CoreError::TokenRetired(_))
+        ));
+        assert!(matches!(smt.retire_token(&issuer, token_id, 2), Err(CoreError::TokenRetired(_))));
+    }
+}
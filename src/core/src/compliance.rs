@@ -0,0 +1,297 @@
+//! Per-token compliance policies.
+//!
+//! The issuer of a token can restrict who may hold it. A token's policy is
+//! open (the default), allow-list only, or deny-list. Each token has an
+//! allow list and a deny list, stored as data leaves per `(token, address)`
+//! so that list membership, or its absence, can be proven against the state
+//! root. Only the list named by the active policy is enforced; the issuer
+//! itself is always exempt. Policy and list updates use the issuer's
+//! native-token nonce.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{Address, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Data leaf kind of compliance policies.
+pub const POLICY_LEAF_KIND: &str = "compliance_policy";
+
+/// Data leaf kind of allow list entries.
+pub const ALLOW_LIST_LEAF_KIND: &str = "compliance_allow";
+
+/// Data leaf kind of deny list entries.
+pub const DENY_LIST_LEAF_KIND: &str = "compliance_deny";
+
+/// Who may hold a token.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompliancePolicy {
+    /// Anyone
+    #[default]
+    Open,
+    /// Only addresses on the allow list
+    AllowList,
+    /// Anyone not on the deny list
+    DenyList,
+}
+
+impl fmt::Display for CompliancePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompliancePolicy::Open => write!(f, "open"),
+            CompliancePolicy::AllowList => write!(f, "allow-list"),
+            CompliancePolicy::DenyList => write!(f, "deny-list"),
+        }
+    }
+}
+
+impl FromStr for CompliancePolicy {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(CompliancePolicy::Open),
+            "allow-list" => Ok(CompliancePolicy::AllowList),
+            "deny-list" => Ok(CompliancePolicy::DenyList),
+            _ => Err(CoreError::InvalidCompliancePolicy(s.to_string())),
+        }
+    }
+}
+
+/// One of a token's address lists.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComplianceList {
+    /// Addresses allowed under the allow-list policy
+    Allow,
+    /// Addresses refused under the deny-list policy
+    Deny,
+}
+
+impl ComplianceList {
+    /// The data leaf kind of the list's entries.
+    pub fn leaf_kind(&self) -> &'static str {
+        match self {
+            ComplianceList::Allow => ALLOW_LIST_LEAF_KIND,
+            ComplianceList::Deny => DENY_LIST_LEAF_KIND,
+        }
+    }
+}
+
+impl fmt::Display for ComplianceList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComplianceList::Allow => write!(f, "allow"),
+            ComplianceList::Deny => write!(f, "deny"),
+        }
+    }
+}
+
+impl FromStr for ComplianceList {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "allow" => Ok(ComplianceList::Allow),
+            "deny" => Ok(ComplianceList::Deny),
+            _ => Err(CoreError::InvalidCompliancePolicy(format!("Unknown list {}", s))),
+        }
+    }
+}
+
+/// An address on one of a token's lists.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListEntry {
+    /// The token ID
+    pub token_id: TokenId,
+    /// The listed address
+    pub address: Address,
+}
+
+/// Computes the tree key of a token's policy.
+pub fn policy_key(token_id: TokenId) -> [u8; 32] {
+    data_leaf_key(POLICY_LEAF_KIND, &token_id.to_le_bytes())
+}
+
+/// Computes the tree key of an address on one of a token's lists.
+pub fn list_key(token_id: TokenId, list: ComplianceList, address: &Address) -> [u8; 32] {
+    let mut id = [0u8; 40];
+    id[..8].copy_from_slice(&token_id.to_le_bytes());
+    id[8..].copy_from_slice(address);
+    data_leaf_key(list.leaf_kind(), &id)
+}
+
+/// Checks a membership proof against a state root and returns whether the
+/// address is on the list.
+pub fn verify_membership(
+    token_id: TokenId,
+    list: ComplianceList,
+    address: &Address,
+    proof: &DataProof,
+    root: &[u8; 32],
+) -> Result<bool, CoreError> {
+    if proof.key != list_key(token_id, list, address) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for the {} list of token {}",
+            list, token_id
+        )));
+    }
+    proof.verify(root)?;
+
+    let entry: Option<ListEntry> = proof.value(list.leaf_kind())?;
+    Ok(entry.is_some())
+}
+
+impl SMT {
+    /// Gets a token's compliance policy.
+    pub fn get_compliance_policy(&self, token_id: TokenId) -> Result<CompliancePolicy, CoreError> {
+        Ok(self
+            .get_data_leaf(POLICY_LEAF_KIND, &policy_key(token_id))?
+            .unwrap_or_default())
+    }
+
+    /// Returns whether an address is on one of a token's lists.
+    pub fn is_listed(&self, token_id: TokenId, list: ComplianceList, address: &Address) -> Result<bool, CoreError> {
+        let entry: Option<ListEntry> = self.get_data_leaf(list.leaf_kind(), &list_key(token_id, list, address))?;
+        Ok(entry.is_some())
+    }
+
+    /// Sets a token's compliance policy.
+    pub fn set_compliance_policy(
+        &mut self,
+        issuer: &Address,
+        token_id: TokenId,
+        policy: CompliancePolicy,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        self.check_token_issuer(issuer, token_id)?;
+        self.use_native_nonce(issuer, nonce)?;
+        self.put_compliance_policy(token_id, policy)
+    }
+
+    /// Stores a token's compliance policy without any authorization checks.
+    pub(crate) fn put_compliance_policy(&mut self, token_id: TokenId, policy: CompliancePolicy) -> Result<(), CoreError> {
+        if policy == CompliancePolicy::Open {
+            return self.remove_data_leaf(&policy_key(token_id));
+        }
+        self.put_data_leaf(POLICY_LEAF_KIND, policy_key(token_id), &policy)
+    }
+
+    /// Adds an address to, or removes it from, one of a token's lists.
+    pub fn update_compliance_list(
+        &mut self,
+        issuer: &Address,
+        token_id: TokenId,
+        list: ComplianceList,
+        address: Address,
+        listed: bool,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        self.check_token_issuer(issuer, token_id)?;
+        self.use_native_nonce(issuer, nonce)?;
+
+        let key = list_key(token_id, list, &address);
+        if listed {
+            self.put_data_leaf(list.leaf_kind(), key, &ListEntry { token_id, address })
+        } else {
+            self.remove_data_leaf(&key)
+        }
+    }
+
+    /// Checks that an address may hold a token under its policy.
+    pub fn check_compliance(&self, token_id: TokenId, address: &Address) -> Result<(), CoreError> {
+        let policy = self.get_compliance_policy(token_id)?;
+        if policy == CompliancePolicy::Open {
+            return Ok(());
+        }
+
+        // The issuer can always hold and distribute its own token
+        if self.get_token(token_id)?.issuer == *address {
+            return Ok(());
+        }
+
+        match policy {
+            CompliancePolicy::AllowList if !self.is_listed(token_id, ComplianceList::Allow, address)? => {
+                Err(CoreError::NotAllowListed {
+                    token_id,
+                    address: hex::encode(address),
+                })
+            }
+            CompliancePolicy::DenyList if self.is_listed(token_id, ComplianceList::Deny, address)? => {
+                Err(CoreError::DenyListed {
+                    token_id,
+                    address: hex::encode(address),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that both parties of a transfer may hold the token.
+    pub fn check_transfer_compliance(&self, token_id: TokenId, from: &Address, to: &Address) -> Result<(), CoreError> {
+        self.check_compliance(token_id, from)?;
+        self.check_compliance(token_id, to)
+    }
+
+    /// Checks that `issuer` issued the token.
+    fn check_token_issuer(&self, issuer: &Address, token_id: TokenId) -> Result<(), CoreError> {
+        if self.get_token(token_id)?.issuer != *issuer {
+            return Err(CoreError::Unauthorized(format!(
+                "Only the issuer of token {} can manage its compliance policy",
+                token_id
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AccountLeaf;
+
+    #[test]
+    fn test_compliance_policies() {
+        let mut smt = SMT::new_zero();
+        let (issuer, alice, bob) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        let token_id = smt.register_token(&issuer, "Regulated|REG|0".to_string()).unwrap();
+        smt.update(AccountLeaf::new(issuer, 0, 0, token_id)).unwrap();
+
+        // Only the issuer manages the policy
+        assert!(matches!(
+            smt.set_compliance_policy(&alice, token_id, CompliancePolicy::AllowList, 0),
+            Err(CoreError::Unauthorized(_))
+        ));
+
+        smt.set_compliance_policy(&issuer, token_id, CompliancePolicy::AllowList, 0).unwrap();
+        assert!(matches!(
+            smt.mint_token(&issuer, &alice, token_id, 10, 0),
+            Err(CoreError::NotAllowListed { .. })
+        ));
+
+        smt.update_compliance_list(&issuer, token_id, ComplianceList::Allow, alice, true, 1).unwrap();
+        smt.mint_token(&issuer, &alice, token_id, 10, 0).unwrap();
+        assert!(matches!(
+            smt.transfer_token(&alice, &bob, token_id, 5, 0),
+            Err(CoreError::NotAllowListed { .. })
+        ));
+
+        // Under a deny list only listed addresses are refused
+        smt.set_compliance_policy(&issuer, token_id, CompliancePolicy::DenyList, 2).unwrap();
+        smt.transfer_token(&alice, &bob, token_id, 5, 0).unwrap();
+        smt.update_compliance_list(&issuer, token_id, ComplianceList::Deny, bob, true, 3).unwrap();
+        assert!(matches!(
+            smt.transfer_token(&alice, &bob, token_id, 1, 1),
+            Err(CoreError::DenyListed { .. })
+        ));
+
+        // Membership and non-membership are provable against the root
+        let key = list_key(token_id, ComplianceList::Deny, &bob);
+        let proof = smt.gen_data_proof(&key).unwrap();
+        assert!(verify_membership(token_id, ComplianceList::Deny, &bob, &proof, &smt.root()).unwrap());
+
+        let key = list_key(token_id, ComplianceList::Deny, &alice);
+        let proof = smt.gen_data_proof(&key).unwrap();
+        assert!(!verify_membership(token_id, ComplianceList::Deny, &alice, &proof, &smt.root()).unwrap());
+    }
+}
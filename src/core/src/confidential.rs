@@ -0,0 +1,457 @@
+//! Confidential balances based on Pedersen commitments.
+//!
+//! An account can hold, next to its public balance, a confidential balance for
+//! each token: a data leaf whose balance is a Pedersen commitment
+//! `v * G + r * H` to the value `v` with blinding factor `r`. Only the owner
+//! knows `v` and `r`.
+//!
+//! Funds enter with a [`Shield`](crate::types::SystemMsg::Shield), which
+//! reveals the amount moved out of the public balance, and leave with an
+//! [`Unshield`](crate::types::SystemMsg::Unshield). A
+//! [`ConfidentialTransfer`](crate::types::SystemMsg::ConfidentialTransfer)
+//! moves a hidden amount: the sender publishes a commitment to the amount and
+//! to its own new balance, the node checks that the new balance is exactly the
+//! old one minus the amount, and a Bulletproofs range proof shows that neither
+//! is negative. The amount and its blinding factor are encrypted to the
+//! recipient's viewing key so that the recipient can track its own balance.
+//!
+//! Confidential amounts are limited to 64 bits by the range proof.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, SMT};
+use crate::types::{AccountLeaf, Address, TokenId};
+use bulletproofs::{BulletproofGens, PedersenGens, RangeProof};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce as AeadNonce};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::{CompressedRistretto, RistrettoPoint};
+use curve25519_dalek::scalar::Scalar;
+use merlin::Transcript;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// A compressed Pedersen commitment.
+pub type Commitment = [u8; 32];
+
+/// A blinding factor (canonical scalar encoding).
+pub type Blinding = [u8; 32];
+
+/// Data leaf kind of confidential balances.
+pub const CONFIDENTIAL_LEAF_KIND: &str = "confidential";
+
+/// Bit size of confidential amounts.
+pub const RANGE_PROOF_BITS: usize = 64;
+
+/// Domain label of the range proof transcript.
+const TRANSCRIPT_LABEL: &[u8] = b"volt-confidential-v1";
+
+/// Domain label of the opening encryption key.
+const NOTE_KEY_LABEL: &[u8] = b"volt-confidential-note";
+
+/// A confidential balance leaf.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidentialLeaf {
+    /// The account address
+    pub addr: Address,
+    /// The token ID
+    pub token_id: TokenId,
+    /// Commitment to the confidential balance
+    pub commitment: Commitment,
+    /// Public key that incoming amounts are encrypted to
+    pub viewing_key: [u8; 32],
+}
+
+/// An amount and blinding factor, encrypted to a recipient's viewing key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedOpening {
+    /// The sender's one-time public key
+    pub ephemeral_key: [u8; 32],
+    /// The encrypted amount and blinding factor
+    pub ciphertext: Vec<u8>,
+}
+
+/// A record of an incoming confidential transfer, kept by nodes so that the
+/// recipient can recover the amount and blinding factor.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidentialNote {
+    /// The sender's address
+    pub from: Address,
+    /// The recipient's address
+    pub to: Address,
+    /// The token ID
+    pub token_id: TokenId,
+    /// Commitment to the amount
+    pub amount_commitment: Commitment,
+    /// The amount and its blinding factor, encrypted to the recipient
+    pub encrypted_opening: EncryptedOpening,
+    /// Unix timestamp (seconds) at which the node applied the transfer
+    pub timestamp: u64,
+}
+
+/// Computes the tree key of an account's confidential balance leaf.
+pub fn leaf_key(addr: &Address, token_id: TokenId) -> [u8; 32] {
+    let mut id = addr.to_vec();
+    id.extend_from_slice(&token_id.to_le_bytes());
+    data_leaf_key(CONFIDENTIAL_LEAF_KIND, &id)
+}
+
+/// Reduces 64 uniformly random bytes to a blinding factor.
+pub fn blinding_from_bytes(bytes: &[u8; 64]) -> Blinding {
+    Scalar::from_bytes_mod_order_wide(bytes).to_bytes()
+}
+
+/// Adds two blinding factors.
+pub fn add_blindings(a: &Blinding, b: &Blinding) -> Result<Blinding, CoreError> {
+    Ok((scalar(a)? + scalar(b)?).to_bytes())
+}
+
+/// Subtracts blinding factor `b` from `a`.
+pub fn sub_blindings(a: &Blinding, b: &Blinding) -> Result<Blinding, CoreError> {
+    Ok((scalar(a)? - scalar(b)?).to_bytes())
+}
+
+/// Commits to a value with the given blinding factor.
+pub fn commit(value: u64, blinding: &Blinding) -> Result<Commitment, CoreError> {
+    let point = PedersenGens::default().commit(Scalar::from(value), scalar(blinding)?);
+    Ok(point.compress().to_bytes())
+}
+
+/// Adds two commitments, committing to the sum of their values.
+pub fn add_commitments(a: &Commitment, b: &Commitment) -> Result<Commitment, CoreError> {
+    Ok((point(a)? + point(b)?).compress().to_bytes())
+}
+
+/// Subtracts commitment `b` from `a`, committing to the difference of their values.
+pub fn sub_commitments(a: &Commitment, b: &Commitment) -> Result<Commitment, CoreError> {
+    Ok((point(a)? - point(b)?).compress().to_bytes())
+}
+
+/// Proves that each value lies in `[0, 2^64)`.
+///
+/// The number of values must be a power of two. Returns the serialized proof
+/// and the commitments it covers.
+pub fn prove_range(values: &[u64], blindings: &[Blinding]) -> Result<(Vec<u8>, Vec<Commitment>), CoreError> {
+    let blindings = blindings.iter().map(scalar).collect::<Result<Vec<_>, _>>()?;
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+
+    let (proof, commitments) = RangeProof::prove_multiple(
+        &BulletproofGens::new(RANGE_PROOF_BITS, values.len()),
+        &PedersenGens::default(),
+        &mut transcript,
+        values,
+        &blindings,
+        RANGE_PROOF_BITS,
+    )
+    .map_err(|e| CoreError::InvalidRangeProof(e.to_string()))?;
+
+    Ok((proof.to_bytes(), commitments.iter().map(|c| c.to_bytes()).collect()))
+}
+
+/// Verifies a range proof over the given commitments.
+pub fn verify_range(proof: &[u8], commitments: &[Commitment]) -> Result<(), CoreError> {
+    let proof = RangeProof::from_bytes(proof).map_err(|e| CoreError::InvalidRangeProof(e.to_string()))?;
+    let commitments: Vec<CompressedRistretto> = commitments.iter().map(|c| CompressedRistretto(*c)).collect();
+    let mut transcript = Transcript::new(TRANSCRIPT_LABEL);
+
+    proof
+        .verify_multiple(
+            &BulletproofGens::new(RANGE_PROOF_BITS, commitments.len()),
+            &PedersenGens::default(),
+            &mut transcript,
+            &commitments,
+            RANGE_PROOF_BITS,
+        )
+        .map_err(|e| CoreError::InvalidRangeProof(e.to_string()))
+}
+
+/// Derives the public viewing key of a viewing secret.
+pub fn viewing_key(view_secret: &Blinding) -> Result<[u8; 32], CoreError> {
+    Ok((scalar(view_secret)? * RISTRETTO_BASEPOINT_POINT).compress().to_bytes())
+}
+
+/// Encrypts an amount and its blinding factor to a viewing key.
+///
+/// `ephemeral_bytes` must be fresh random bytes; they are reduced to the
+/// sender's one-time secret.
+pub fn encrypt_opening(
+    viewing_key: &[u8; 32],
+    amount: u64,
+    blinding: &Blinding,
+    ephemeral_bytes: &[u8; 64],
+) -> Result<EncryptedOpening, CoreError> {
+    let ephemeral_secret = Scalar::from_bytes_mod_order_wide(ephemeral_bytes);
+    let ephemeral_key = (ephemeral_secret * RISTRETTO_BASEPOINT_POINT).compress().to_bytes();
+    let shared = (ephemeral_secret * point(viewing_key)?).compress().to_bytes();
+
+    let mut plaintext = amount.to_le_bytes().to_vec();
+    plaintext.extend_from_slice(blinding);
+
+    let ciphertext = note_cipher(&shared, &ephemeral_key)
+        .encrypt(AeadNonce::from_slice(&[0u8; 12]), plaintext.as_ref())
+        .map_err(|e| CoreError::SerializationError(format!("Failed to encrypt opening: {}", e)))?;
+
+    Ok(EncryptedOpening { ephemeral_key, ciphertext })
+}
+
+/// Decrypts an opening with a viewing secret.
+///
+/// Returns `None` if the opening was not encrypted to this viewing key.
+pub fn decrypt_opening(view_secret: &Blinding, opening: &EncryptedOpening) -> Option<(u64, Blinding)> {
+    let ephemeral = point(&opening.ephemeral_key).ok()?;
+    let shared = (scalar(view_secret).ok()? * ephemeral).compress().to_bytes();
+
+    let plaintext = note_cipher(&shared, &opening.ephemeral_key)
+        .decrypt(AeadNonce::from_slice(&[0u8; 12]), opening.ciphertext.as_ref())
+        .ok()?;
+    if plaintext.len() != 40 {
+        return None;
+    }
+
+    let mut amount = [0u8; 8];
+    amount.copy_from_slice(&plaintext[..8]);
+    let mut blinding = [0u8; 32];
+    blinding.copy_from_slice(&plaintext[8..]);
+    Some((u64::from_le_bytes(amount), blinding))
+}
+
+/// Builds the cipher for an opening. Every opening uses a fresh ephemeral key,
+/// so the derived key is never reused and a fixed nonce is safe.
+fn note_cipher(shared: &[u8; 32], ephemeral_key: &[u8; 32]) -> ChaCha20Poly1305 {
+    let mut hasher = Sha256::new();
+    hasher.update(NOTE_KEY_LABEL);
+    hasher.update(shared);
+    hasher.update(ephemeral_key);
+    ChaCha20Poly1305::new(Key::from_slice(&hasher.finalize()))
+}
+
+/// Decodes a canonical scalar.
+fn scalar(bytes: &[u8; 32]) -> Result<Scalar, CoreError> {
+    Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes))
+        .ok_or_else(|| CoreError::SerializationError("Invalid blinding factor".to_string()))
+}
+
+/// Decodes a compressed Ristretto point.
+fn point(bytes: &[u8; 32]) -> Result<RistrettoPoint, CoreError> {
+    CompressedRistretto(*bytes)
+        .decompress()
+        .ok_or_else(|| CoreError::SerializationError("Invalid commitment or key".to_string()))
+}
+
+impl SMT {
+    /// Gets an account's confidential balance leaf, if it has one.
+    pub fn get_confidential_account(
+        &self,
+        addr: &Address,
+        token_id: TokenId,
+    ) -> Result<Option<ConfidentialLeaf>, CoreError> {
+        self.get_data_leaf(CONFIDENTIAL_LEAF_KIND, &leaf_key(addr, token_id))
+    }
+
+    /// Moves public balance into an account's confidential balance.
+    ///
+    /// The confidential leaf is created on first use with the given viewing key.
+    pub fn shield(
+        &mut self,
+        from: &Address,
+        token_id: TokenId,
+        amount: u64,
+        viewing_key: [u8; 32],
+        nonce: u64,
+    ) -> Result<(), CoreError> {
+        let account = self.get_account_with_token(from, token_id)?;
+
+        if account.bal < amount as u128 {
+            return Err(CoreError::InsufficientBalance {
+                required: amount as u128,
+                available: account.bal,
+            });
+        }
+
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+
+        let mut leaf = match self.get_confidential_account(from, token_id)? {
+            Some(leaf) => leaf,
+            None => {
+                point(&viewing_key)?;
+                ConfidentialLeaf {
+                    addr: *from,
+                    token_id,
+                    commitment: commit(0, &[0u8; 32])?,
+                    viewing_key,
+                }
+            }
+        };
+
+        // The amount is public, so it is committed with a zero blinding factor
+        leaf.commitment = add_commitments(&leaf.commitment, &commit(amount, &[0u8; 32])?)?;
+
+        self.update(AccountLeaf::new(*from, account.bal - amount as u128, account.nonce + 1, token_id))?;
+        self.put_data_leaf(CONFIDENTIAL_LEAF_KIND, leaf_key(from, token_id), &leaf)
+    }
+
+    /// Moves confidential balance back to an account's public balance.
+    ///
+    /// `new_commitment` must commit to the remaining confidential balance and
+    /// be covered by `range_proof`.
+    pub fn unshield(
+        &mut self,
+        from: &Address,
+        token_id: TokenId,
+        amount: u64,
+        new_commitment: Commitment,
+        range_proof: &[u8],
+        nonce: u64,
+    ) -> Result<(), CoreError> {
+        let account = self.get_account_with_token(from, token_id)?;
+
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+
+        let mut leaf = self
+            .get_confidential_account(from, token_id)?
+            .ok_or_else(|| CoreError::ConfidentialAccountNotFound(hex::encode(from)))?;
+
+        // Balance conservation: the remaining balance is the old one minus the amount
+        let expected = sub_commitments(&leaf.commitment, &commit(amount, &[0u8; 32])?)?;
+        if expected != new_commitment {
+            return Err(CoreError::BalanceNotConserved);
+        }
+        verify_range(range_proof, &[new_commitment])?;
+
+        leaf.commitment = new_commitment;
+
+        let bal = account.bal.checked_add(amount as u128).ok_or(CoreError::SupplyOverflow)?;
+        self.update(AccountLeaf::new(*from, bal, account.nonce + 1, token_id))?;
+        self.put_data_leaf(CONFIDENTIAL_LEAF_KIND, leaf_key(from, token_id), &leaf)
+    }
+
+    /// Transfers a hidden amount between confidential balances.
+    ///
+    /// `amount_commitment` commits to the amount and `new_sender_commitment` to
+    /// the sender's remaining balance; `range_proof` must cover both, in that order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn confidential_transfer(
+        &mut self,
+        from: &Address,
+        to: &Address,
+        token_id: TokenId,
+        amount_commitment: Commitment,
+        new_sender_commitment: Commitment,
+        range_proof: &[u8],
+        nonce: u64,
+    ) -> Result<(), CoreError> {
+        if from == to {
+            return Err(CoreError::Unauthorized("Cannot transfer to the same confidential account".to_string()));
+        }
+        self.check_transfer_compliance(token_id, from, to)?;
+
+        let account = self.get_account_with_token(from, token_id)?;
+
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+
+        let mut sender = self
+            .get_confidential_account(from, token_id)?
+            .ok_or_else(|| CoreError::ConfidentialAccountNotFound(hex::encode(from)))?;
+        let mut receiver = self
+            .get_confidential_account(to, token_id)?
+            .ok_or_else(|| CoreError::ConfidentialAccountNotFound(hex::encode(to)))?;
+
+        // Balance conservation: old balance = new balance + amount
+        let expected = sub_commitments(&sender.commitment, &amount_commitment)?;
+        if expected != new_sender_commitment {
+            return Err(CoreError::BalanceNotConserved);
+        }
+        verify_range(range_proof, &[amount_commitment, new_sender_commitment])?;
+
+        sender.commitment = new_sender_commitment;
+        receiver.commitment = add_commitments(&receiver.commitment, &amount_commitment)?;
+
+        self.update(AccountLeaf::new(*from, account.bal, account.nonce + 1, token_id))?;
+        self.put_data_leaf(CONFIDENTIAL_LEAF_KIND, leaf_key(from, token_id), &sender)?;
+        self.put_data_leaf(CONFIDENTIAL_LEAF_KIND, leaf_key(to, token_id), &receiver)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blinding(seed: u8) -> Blinding {
+        blinding_from_bytes(&[seed; 64])
+    }
+
+    #[test]
+    fn test_commitments_are_homomorphic() {
+        let (r1, r2) = (blinding(1), blinding(2));
+        let sum = add_commitments(&commit(30, &r1).unwrap(), &commit(12, &r2).unwrap()).unwrap();
+        assert_eq!(sum, commit(42, &add_blindings(&r1, &r2).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_range_proof() {
+        let (proof, commitments) = prove_range(&[5, 1000], &[blinding(1), blinding(2)]).unwrap();
+        assert!(verify_range(&proof, &commitments).is_ok());
+
+        // The proof does not cover other commitments
+        let other = [commitments[0], commit(1000, &blinding(3)).unwrap()];
+        assert!(verify_range(&proof, &other).is_err());
+    }
+
+    #[test]
+    fn test_encrypted_opening() {
+        let view_secret = blinding(9);
+        let key = viewing_key(&view_secret).unwrap();
+        let opening = encrypt_opening(&key, 77, &blinding(4), &[5u8; 64]).unwrap();
+
+        assert_eq!(decrypt_opening(&view_secret, &opening), Some((77, blinding(4))));
+        assert_eq!(decrypt_opening(&blinding(10), &opening), None);
+    }
+
+    #[test]
+    fn test_confidential_transfer() {
+        let (alice, bob) = ([1u8; 32], [2u8; 32]);
+        let mut smt = SMT::new_zero();
+        smt.update(AccountLeaf::new(alice, 100, 0, 0)).unwrap();
+        smt.update(AccountLeaf::new(bob, 0, 0, 0)).unwrap();
+
+        smt.shield(&alice, 0, 60, viewing_key(&blinding(7)).unwrap(), 0).unwrap();
+        smt.shield(&bob, 0, 0, viewing_key(&blinding(8)).unwrap(), 0).unwrap();
+        assert_eq!(smt.get_account(&alice).unwrap().bal, 40);
+
+        // Alice's balance is committed with a zero blinding factor after shielding
+        let (amount_blinding, remaining_blinding) = (blinding(1), sub_blindings(&[0u8; 32], &blinding(1)).unwrap());
+        let (proof, commitments) = prove_range(&[25, 35], &[amount_blinding, remaining_blinding]).unwrap();
+
+        // Claiming a different remaining balance breaks conservation
+        let (bad_proof, bad_commitments) = prove_range(&[25, 36], &[amount_blinding, remaining_blinding]).unwrap();
+        assert!(matches!(
+            smt.confidential_transfer(&alice, &bob, 0, bad_commitments[0], bad_commitments[1], &bad_proof, 1),
+            Err(CoreError::BalanceNotConserved)
+        ));
+
+        smt.confidential_transfer(&alice, &bob, 0, commitments[0], commitments[1], &proof, 1).unwrap();
+
+        let bob_leaf = smt.get_confidential_account(&bob, 0).unwrap().unwrap();
+        assert_eq!(bob_leaf.commitment, commit(25, &amount_blinding).unwrap());
+
+        // Bob moves part of it back to his public balance
+        let remaining = commit(10, &amount_blinding).unwrap();
+        let (proof, _) = prove_range(&[10], &[amount_blinding]).unwrap();
+        smt.unshield(&bob, 0, 15, remaining, &proof, 1).unwrap();
+        assert_eq!(smt.get_account(&bob).unwrap().bal, 15);
+    }
+}
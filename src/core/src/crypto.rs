@@ -0,0 +1,203 @@
+//! Signature verification for the supported account schemes.
+//!
+//! Ed25519 accounts use their public key as the address. secp256k1 accounts
+//! use an address derived from their Ethereum address (see
+//! [`address_from_eth`](crate::types::address_from_eth)); their signatures are
+//! 64-byte `r || s` ECDSA signatures over the Keccak-256 hash of the message,
+//! and the public key is recovered from the signature.
+//!
+//! Arbitrary off-chain messages (logins, proofs of ownership) are signed over
+//! [`signed_message_bytes`], which adds a domain prefix so that such a
+//! signature can never be replayed as a transaction signature.
+
+use crate::errors::CoreError;
+use crate::types::{eth_address_of, Address, Signature, SignatureScheme, SystemMsg};
+use ed25519_dalek::Verifier;
+use k256::ecdsa::{RecoveryId, Signature as EcdsaSignature, SigningKey, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+/// Domain prefix of the off-chain signed-message format.
+///
+/// Transaction signing payloads are JSON objects (starting with `{`) or bincode
+/// encodings that start with an address or a small enum index, so no transaction
+/// encoding can start with this prefix.
+pub const SIGNED_MESSAGE_PREFIX: &[u8] = b"\x19Volt Signed Message:\n";
+
+/// Builds the bytes that are actually signed for an off-chain message:
+/// the domain prefix, the message length in decimal, then the message.
+pub fn signed_message_bytes(message: &[u8]) -> Vec<u8> {
+    let length = message.len().to_string();
+
+    let mut bytes = Vec::with_capacity(SIGNED_MESSAGE_PREFIX.len() + length.len() + message.len());
+    bytes.extend_from_slice(SIGNED_MESSAGE_PREFIX);
+    bytes.extend_from_slice(length.as_bytes());
+    bytes.extend_from_slice(message);
+    bytes
+}
+
+/// Verifies an off-chain message signature made by the key controlling `addr`.
+pub fn verify_signed_message(addr: &Address, message: &[u8], signature: &Signature) -> Result<(), CoreError> {
+    verify_signature(
+        SignatureScheme::of_address(addr),
+        addr,
+        &signed_message_bytes(message),
+        signature,
+    )
+}
+
+/// Verifies that a system message is signed by its signer.
+pub fn verify_system_msg(msg: &SystemMsg) -> Result<(), CoreError> {
+    let signer = msg.signer();
+    verify_signature(
+        SignatureScheme::of_address(&signer),
+        &signer,
+        &msg.signing_bytes()?,
+        msg.signature(),
+    )
+}
+
+/// Computes the Keccak-256 hash of a message.
+pub fn keccak256(message: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Keccak256::digest(message));
+    hash
+}
+
+/// Computes the Ethereum address of a secp256k1 public key.
+pub fn eth_address_from_key(key: &VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    let hash = keccak256(&point.as_bytes()[1..]);
+
+    let mut eth_address = [0u8; 20];
+    eth_address.copy_from_slice(&hash[12..]);
+    eth_address
+}
+
+/// Signs a message with a secp256k1 key, producing a 64-byte `r || s` signature.
+pub fn sign_secp256k1(key: &SigningKey, message: &[u8]) -> Result<Signature, CoreError> {
+    let (signature, _) = key
+        .sign_prehash_recoverable(&keccak256(message))
+        .map_err(|e| CoreError::SignatureVerificationFailed(e.to_string()))?;
+
+    let mut bytes = [0u8; 64];
+    bytes.copy_from_slice(&signature.to_bytes());
+    Ok(Signature(bytes))
+}
+
+/// Verifies that `signature` over `message` was produced by the key controlling `addr`.
+///
+/// The scheme is taken from the address, and must match the scheme the message
+/// claims to be signed with.
+pub fn verify_signature(
+    scheme: SignatureScheme,
+    addr: &Address,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<(), CoreError> {
+    if SignatureScheme::of_address(addr) != scheme {
+        return Err(CoreError::SignatureVerificationFailed(format!(
+            "Address is controlled by {} but the message is signed with {}",
+            SignatureScheme::of_address(addr),
+            scheme
+        )));
+    }
+
+    match scheme {
+        SignatureScheme::Ed25519 => verify_ed25519(addr, message, signature),
+        SignatureScheme::Secp256k1 => verify_secp256k1(addr, message, signature),
+    }
+}
+
+/// Verifies an Ed25519 signature, using the address as the public key.
+fn verify_ed25519(addr: &Address, message: &[u8], signature: &Signature) -> Result<(), CoreError> {
+    let public_key = ed25519_dalek::PublicKey::from_bytes(addr)
+        .map_err(|e| CoreError::SignatureVerificationFailed(format!("Invalid public key: {}", e)))?;
+
+    let signature = ed25519_dalek::Signature::from_bytes(&signature.0)
+        .map_err(|e| CoreError::SignatureVerificationFailed(format!("Invalid signature format: {}", e)))?;
+
+    public_key
+        .verify(message, &signature)
+        .map_err(|e| CoreError::SignatureVerificationFailed(e.to_string()))
+}
+
+/// Verifies a secp256k1 signature by recovering the signer's Ethereum address.
+fn verify_secp256k1(addr: &Address, message: &[u8], signature: &Signature) -> Result<(), CoreError> {
+    let expected = eth_address_of(addr).ok_or_else(|| {
+        CoreError::SignatureVerificationFailed("Not a secp256k1 address".to_string())
+    })?;
+
+    let signature = EcdsaSignature::from_slice(&signature.0)
+        .map_err(|e| CoreError::SignatureVerificationFailed(format!("Invalid signature format: {}", e)))?;
+
+    let prehash = keccak256(message);
+
+    // The signature carries no recovery ID, so try both candidates
+    for recovery_byte in 0..2u8 {
+        let recovery_id = match RecoveryId::from_byte(recovery_byte) {
+            Some(id) => id,
+            None => continue,
+        };
+
+        if let Ok(key) = VerifyingKey::recover_from_prehash(&prehash, &signature, recovery_id) {
+            if eth_address_from_key(&key) == expected {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(CoreError::SignatureVerificationFailed(
+        "Signature does not match the secp256k1 address".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::address_from_eth;
+    use ed25519_dalek::Signer;
+
+    #[test]
+    fn test_secp256k1_sign_and_verify() {
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let addr = address_from_eth(&eth_address_from_key(key.verifying_key()));
+        let message = b"transfer";
+
+        let signature = sign_secp256k1(&key, message).unwrap();
+        assert!(verify_signature(SignatureScheme::Secp256k1, &addr, message, &signature).is_ok());
+
+        // Wrong message or wrong claimed scheme must fail
+        assert!(verify_signature(SignatureScheme::Secp256k1, &addr, b"other", &signature).is_err());
+        assert!(verify_signature(SignatureScheme::Ed25519, &addr, message, &signature).is_err());
+    }
+
+    #[test]
+    fn test_ed25519_verify() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[9u8; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let keypair = ed25519_dalek::Keypair { secret, public };
+        let message = b"transfer";
+
+        let signature = Signature(keypair.sign(message).to_bytes());
+        let addr = public.to_bytes();
+
+        assert!(verify_signature(SignatureScheme::Ed25519, &addr, message, &signature).is_ok());
+        assert!(verify_signature(SignatureScheme::Ed25519, &addr, b"other", &signature).is_err());
+    }
+
+    #[test]
+    fn test_signed_message() {
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let addr = address_from_eth(&eth_address_from_key(key.verifying_key()));
+        let message = b"login:nonce=42";
+
+        assert!(signed_message_bytes(message).starts_with(SIGNED_MESSAGE_PREFIX));
+
+        let signature = sign_secp256k1(&key, &signed_message_bytes(message)).unwrap();
+        assert!(verify_signed_message(&addr, message, &signature).is_ok());
+
+        // A signature over the raw message is not a valid signed message
+        let raw_signature = sign_secp256k1(&key, message).unwrap();
+        assert!(verify_signed_message(&addr, message, &raw_signature).is_err());
+    }
+}
@@ -79,4 +79,166 @@ pub enum CoreError {
         /// The actual token ID
         actual: u64,
     },
+
+    /// Error when a transfer memo exceeds the maximum size.
+    #[error("Memo too long: {len} bytes (maximum {max})")]
+    MemoTooLong {
+        /// The memo length
+        len: usize,
+        /// The maximum allowed length
+        max: usize,
+    },
+
+    /// Error when a typed-data document does not match its declared types.
+    #[error("Invalid typed data: {0}")]
+    InvalidTypedData(String),
+
+    /// Error when a range proof is malformed or does not verify.
+    #[error("Invalid range proof: {0}")]
+    InvalidRangeProof(String),
+
+    /// Error when the commitments of a confidential operation do not balance.
+    #[error("Confidential balance not conserved")]
+    BalanceNotConserved,
+
+    /// Error when an account has no confidential balance for the token.
+    #[error("Confidential account not found: {0}")]
+    ConfidentialAccountNotFound(String),
+
+    /// Error when a stealth meta-address or key is malformed.
+    #[error("Invalid stealth address: {0}")]
+    InvalidStealthAddress(String),
+
+    /// Error when a name is malformed.
+    #[error("Invalid name: {0}")]
+    InvalidName(String),
+
+    /// Error when registering a name that is already taken.
+    #[error("Name already registered: {0}")]
+    NameAlreadyRegistered(String),
+
+    /// Error when a name is not registered.
+    #[error("Name not found: {0}")]
+    NameNotFound(String),
+
+    /// Error when a token is used as an NFT collection but is not one.
+    #[error("Token {0} is not an NFT collection")]
+    NotAnNftCollection(u64),
+
+    /// Error when a fungible operation targets an NFT collection.
+    #[error("Token {0} is an NFT collection and has no fungible balances")]
+    NotFungible(u64),
+
+    /// Error when minting an item ID that already exists.
+    #[error("Item {item_id} of collection {collection_id} already minted")]
+    NftAlreadyMinted {
+        /// The collection's token ID
+        collection_id: u64,
+        /// The item ID
+        item_id: u64,
+    },
+
+    /// Error when an item does not exist.
+    #[error("Item {item_id} of collection {collection_id} not found")]
+    NftNotFound {
+        /// The collection's token ID
+        collection_id: u64,
+        /// The item ID
+        item_id: u64,
+    },
+
+    /// Error when an item's metadata is invalid.
+    #[error("Invalid NFT metadata: {0}")]
+    InvalidNftMetadata(String),
+
+    /// Error when a compliance policy or list name is not recognised.
+    #[error("Invalid compliance policy: {0}")]
+    InvalidCompliancePolicy(String),
+
+    /// Error when an address is not on the allow list of an allow-list token.
+    #[error("Address {address} is not on the allow list of token {token_id}")]
+    NotAllowListed {
+        /// The token ID
+        token_id: u64,
+        /// The hex-encoded address
+        address: String,
+    },
+
+    /// Error when an address is on the deny list of a deny-list token.
+    #[error("Address {address} is on the deny list of token {token_id}")]
+    DenyListed {
+        /// The token ID
+        token_id: u64,
+        /// The hex-encoded address
+        address: String,
+    },
+
+    /// Error when minting or retiring a token that has been retired.
+    #[error("Token {0} has been retired")]
+    TokenRetired(u64),
+
+    /// Error when retiring a token whose supply has not been fully burned.
+    #[error("Token {token_id} still has a supply of {supply}")]
+    SupplyOutstanding {
+        /// The token ID
+        token_id: u64,
+        /// The outstanding supply
+        supply: u128,
+    },
+
+    /// Error when an airdrop is invalid or does not exist.
+    #[error("Invalid airdrop: {0}")]
+    InvalidAirdrop(String),
+
+    /// Error when an airdrop claim's inclusion proof does not verify.
+    #[error("Invalid airdrop inclusion proof")]
+    InvalidAirdropProof,
+
+    /// Error when an airdrop entry has already been claimed.
+    #[error("Airdrop entry {index} already claimed")]
+    AirdropAlreadyClaimed {
+        /// The entry index
+        index: u64,
+    },
+
+    /// Error when a governance proposal is invalid or does not exist.
+    #[error("Invalid proposal: {0}")]
+    InvalidProposal(String),
+
+    /// Error when voting on a proposal whose tally is closed.
+    #[error("Proposal is already {0}")]
+    ProposalClosed(String),
+
+    /// Error when an address votes twice on a proposal.
+    #[error("Address has already voted on this proposal")]
+    AlreadyVoted,
+
+    /// Error when a standing order is invalid or does not exist.
+    #[error("Invalid standing order: {0}")]
+    InvalidStandingOrder(String),
+
+    /// Error when a standing order's period has already been pulled.
+    #[error("Period {0} of the standing order has already been pulled")]
+    PeriodAlreadyPulled(u64),
+
+    /// Error when a payment channel is invalid or not open.
+    #[error("Invalid payment channel: {0}")]
+    InvalidChannel(String),
+
+    /// Error when a channel voucher is not signed by the channel's payer.
+    #[error("Channel voucher is not signed by the channel's payer")]
+    InvalidVoucher,
+
+    /// Error when a session key delegation is invalid or not in force.
+    #[error("Invalid session key: {0}")]
+    InvalidSessionKey(String),
+
+    /// Error when a session key spends more than its limit for the window.
+    #[error("Session key spending limit exceeded for token {token_id}: {remaining} remaining in this window")]
+    SpendingLimitExceeded {
+        /// The token spent
+        token_id: u64,
+        /// Amount still spendable in the window
+        remaining: u128,
+    },
 }
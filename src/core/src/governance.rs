@@ -0,0 +1,422 @@
+//! Token-weighted governance.
+//!
+//! Holders of a token can propose a change to one of its parameters, such as
+//! its maximum supply or compliance policy. Creating a proposal records the
+//! current state root and the token's supply as its snapshot. Each holder
+//! then votes once, weighted by its balance proven against the snapshot
+//! root, so tokens moved after the snapshot cannot vote twice.
+//!
+//! The tally is closed by the vote that brings turnout to the quorum. The
+//! proposal passes if more weight voted for it than against it, and its
+//! change is executed right away. Votes are only accepted before the
+//! proposal's deadline, so a proposal that misses quorum by then stays
+//! open without ever executing. Proposals and votes use the sender's
+//! native-token nonce.
+
+use crate::compliance::CompliancePolicy;
+use crate::errors::CoreError;
+use crate::smt::{compute_leaf_key, data_leaf_key, AccountProof, DataProof, SMT};
+use crate::types::{Address, Balance, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Data leaf kind of proposals.
+pub const PROPOSAL_LEAF_KIND: &str = "governance_proposal";
+
+/// Data leaf kind of cast votes.
+pub const VOTE_LEAF_KIND: &str = "governance_vote";
+
+/// Data leaf kind of governed maximum supplies.
+pub const MAX_SUPPLY_LEAF_KIND: &str = "token_max_supply";
+
+/// Share of the snapshot supply, in basis points, that must vote to close a
+/// proposal.
+pub const QUORUM_BPS: u128 = 5000;
+
+/// Identifier of a proposal.
+pub type ProposalId = [u8; 32];
+
+/// A parameter change a proposal executes when it passes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalAction {
+    /// Set the token's maximum supply
+    SetMaxSupply(Balance),
+    /// Set the token's compliance policy
+    SetCompliancePolicy(CompliancePolicy),
+}
+
+impl fmt::Display for ProposalAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposalAction::SetMaxSupply(max_supply) => write!(f, "set max supply to {}", max_supply),
+            ProposalAction::SetCompliancePolicy(policy) => write!(f, "set compliance policy to {}", policy),
+        }
+    }
+}
+
+/// The state of a proposal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalStatus {
+    /// Accepting votes
+    Open,
+    /// Reached quorum with a majority for, and executed
+    Passed,
+    /// Reached quorum without a majority for
+    Rejected,
+}
+
+impl fmt::Display for ProposalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProposalStatus::Open => write!(f, "open"),
+            ProposalStatus::Passed => write!(f, "passed"),
+            ProposalStatus::Rejected => write!(f, "rejected"),
+        }
+    }
+}
+
+/// A governance proposal.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proposal {
+    /// The proposal ID
+    pub proposal_id: ProposalId,
+    /// The governed token
+    pub token_id: TokenId,
+    /// The address that created the proposal
+    pub proposer: Address,
+    /// The change to execute if the proposal passes
+    pub action: ProposalAction,
+    /// State root that voting weights are proven against
+    pub snapshot_root: [u8; 32],
+    /// Supply of the token at the snapshot
+    pub snapshot_supply: Balance,
+    /// Turnout that closes the tally
+    pub quorum: Balance,
+    /// Unix time from which votes are no longer accepted
+    pub deadline: u64,
+    /// Weight voted for
+    pub votes_for: Balance,
+    /// Weight voted against
+    pub votes_against: Balance,
+    /// The state of the proposal
+    pub status: ProposalStatus,
+}
+
+impl Proposal {
+    /// Returns the status the current tally leads to.
+    pub fn tally(&self) -> ProposalStatus {
+        let turnout = self.votes_for.saturating_add(self.votes_against);
+        if turnout < self.quorum {
+            ProposalStatus::Open
+        } else if self.votes_for > self.votes_against {
+            ProposalStatus::Passed
+        } else {
+            ProposalStatus::Rejected
+        }
+    }
+}
+
+/// A cast vote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CastVote {
+    /// Whether the vote is for the proposal
+    pub support: bool,
+    /// The voter's balance at the snapshot
+    pub weight: Balance,
+}
+
+/// Computes the ID of a proposal from its proposer, token and the
+/// proposer's nonce.
+pub fn proposal_id(proposer: &Address, token_id: TokenId, nonce: Nonce) -> ProposalId {
+    let mut hasher = Sha256::new();
+    hasher.update(proposer);
+    hasher.update(token_id.to_le_bytes());
+    hasher.update(nonce.to_le_bytes());
+
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Computes the tree key of a proposal.
+pub fn proposal_key(proposal_id: &ProposalId) -> [u8; 32] {
+    data_leaf_key(PROPOSAL_LEAF_KIND, proposal_id)
+}
+
+/// Computes the tree key of a voter's vote on a proposal.
+pub fn vote_key(proposal_id: &ProposalId, voter: &Address) -> [u8; 32] {
+    let mut id = [0u8; 64];
+    id[..32].copy_from_slice(proposal_id);
+    id[32..].copy_from_slice(voter);
+    data_leaf_key(VOTE_LEAF_KIND, &id)
+}
+
+/// Computes the tree key of a token's governed maximum supply.
+pub fn max_supply_key(token_id: TokenId) -> [u8; 32] {
+    data_leaf_key(MAX_SUPPLY_LEAF_KIND, &token_id.to_le_bytes())
+}
+
+/// Checks a proposal proof against a state root and returns the proven
+/// proposal, or `None` if the proof shows it does not exist.
+pub fn verify_proposal(proposal_id: &ProposalId, proof: &DataProof, root: &[u8; 32]) -> Result<Option<Proposal>, CoreError> {
+    if proof.key != proposal_key(proposal_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for proposal {}",
+            hex::encode(proposal_id)
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(PROPOSAL_LEAF_KIND)
+}
+
+/// Checks a voter's balance proof against a proposal's snapshot and returns
+/// the voting weight.
+pub fn verify_voting_weight(proposal: &Proposal, voter: &Address, proof: &AccountProof) -> Result<Balance, CoreError> {
+    if proof.key != compute_leaf_key(voter, proposal.token_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for the balance of {} in token {}",
+            hex::encode(voter),
+            proposal.token_id
+        )));
+    }
+    proof.verify(&proposal.snapshot_root)?;
+    Ok(proof.balance())
+}
+
+impl SMT {
+    /// Gets a proposal, if created.
+    pub fn get_proposal(&self, proposal_id: &ProposalId) -> Result<Option<Proposal>, CoreError> {
+        self.get_data_leaf(PROPOSAL_LEAF_KIND, &proposal_key(proposal_id))
+    }
+
+    /// Gets a voter's vote on a proposal, if cast.
+    pub fn get_vote(&self, proposal_id: &ProposalId, voter: &Address) -> Result<Option<CastVote>, CoreError> {
+        self.get_data_leaf(VOTE_LEAF_KIND, &vote_key(proposal_id, voter))
+    }
+
+    /// Gets a token's governed maximum supply, if one has been set.
+    pub fn get_max_supply(&self, token_id: TokenId) -> Result<Option<Balance>, CoreError> {
+        self.get_data_leaf(MAX_SUPPLY_LEAF_KIND, &max_supply_key(token_id))
+    }
+
+    /// Creates a proposal to change a token parameter. Returns the proposal
+    /// ID.
+    ///
+    /// The snapshot is the state root before the proposal is applied, so
+    /// voting proofs must be generated from that state. Votes are accepted
+    /// for `voting_period_secs` from `time`.
+    pub fn create_proposal(
+        &mut self,
+        proposer: &Address,
+        token_id: TokenId,
+        action: ProposalAction,
+        time: u64,
+        voting_period_secs: u64,
+        nonce: Nonce,
+    ) -> Result<ProposalId, CoreError> {
+        if token_id == self.native_token_id {
+            return Err(CoreError::InvalidProposal("The native token is not governed".to_string()));
+        }
+        if voting_period_secs == 0 {
+            return Err(CoreError::InvalidProposal("Voting period must be non-zero".to_string()));
+        }
+        let deadline = time
+            .checked_add(voting_period_secs)
+            .ok_or_else(|| CoreError::InvalidProposal("Voting deadline overflows".to_string()))?;
+        let snapshot_root = self.root();
+
+        let token_info = self.get_token(token_id)?;
+        if self.get_nft_collection(token_id)?.is_some() {
+            return Err(CoreError::NotFungible(token_id));
+        }
+        if self.is_token_retired(token_id)? {
+            return Err(CoreError::TokenRetired(token_id));
+        }
+        if token_info.total_supply == 0 {
+            return Err(CoreError::InvalidProposal(format!("Token {} has no supply to vote with", token_id)));
+        }
+
+        let holds_token = self
+            .get_account_with_token(proposer, token_id)
+            .is_ok_and(|account| account.bal > 0);
+        if !holds_token && token_info.issuer != *proposer {
+            return Err(CoreError::Unauthorized(format!(
+                "Only holders of token {} can create proposals",
+                token_id
+            )));
+        }
+
+        let id = proposal_id(proposer, token_id, nonce);
+        self.use_native_nonce(proposer, nonce)?;
+
+        let quorum = (token_info.total_supply.saturating_mul(QUORUM_BPS).saturating_add(9999) / 10000).max(1);
+        let proposal = Proposal {
+            proposal_id: id,
+            token_id,
+            proposer: *proposer,
+            action,
+            snapshot_root,
+            snapshot_supply: token_info.total_supply,
+            quorum,
+            deadline,
+            votes_for: 0,
+            votes_against: 0,
+            status: ProposalStatus::Open,
+        };
+        self.put_data_leaf(PROPOSAL_LEAF_KIND, proposal_key(&id), &proposal)?;
+
+        Ok(id)
+    }
+
+    /// Casts a vote weighted by the voter's balance at the proposal's
+    /// snapshot. Executes the proposal if the vote closes the tally with a
+    /// majority for. Returns the proposal's new status.
+    ///
+    /// Every check, including the voter's nonce, runs before the state is
+    /// changed, so a rejected vote leaves the tree untouched.
+    pub fn vote(
+        &mut self,
+        voter: &Address,
+        proposal_id: &ProposalId,
+        support: bool,
+        proof: &AccountProof,
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<ProposalStatus, CoreError> {
+        let mut proposal = self
+            .get_proposal(proposal_id)?
+            .ok_or_else(|| CoreError::InvalidProposal(format!("Proposal {} not found", hex::encode(proposal_id))))?;
+
+        if proposal.status != ProposalStatus::Open {
+            return Err(CoreError::ProposalClosed(proposal.status.to_string()));
+        }
+        if time >= proposal.deadline {
+            return Err(CoreError::ProposalClosed(format!("past its deadline of {}", proposal.deadline)));
+        }
+        if self.get_vote(proposal_id, voter)?.is_some() {
+            return Err(CoreError::AlreadyVoted);
+        }
+
+        let weight = verify_voting_weight(&proposal, voter, proof)?;
+        if weight == 0 {
+            return Err(CoreError::Unauthorized(format!(
+                "{} held no token {} at the snapshot",
+                hex::encode(voter),
+                proposal.token_id
+            )));
+        }
+
+        self.check_native_nonce(voter, nonce)?;
+
+        if support {
+            proposal.votes_for = proposal.votes_for.saturating_add(weight);
+        } else {
+            proposal.votes_against = proposal.votes_against.saturating_add(weight);
+        }
+        proposal.status = proposal.tally();
+
+        if proposal.status == ProposalStatus::Passed {
+            self.execute_proposal(&proposal)?;
+        }
+
+        self.use_native_nonce(voter, nonce)?;
+        self.put_data_leaf(VOTE_LEAF_KIND, vote_key(proposal_id, voter), &CastVote { support, weight })?;
+        self.put_data_leaf(PROPOSAL_LEAF_KIND, proposal_key(proposal_id), &proposal)?;
+
+        Ok(proposal.status)
+    }
+
+    /// Applies the parameter change of a passed proposal.
+    fn execute_proposal(&mut self, proposal: &Proposal) -> Result<(), CoreError> {
+        match proposal.action {
+            ProposalAction::SetMaxSupply(max_supply) => {
+                self.put_data_leaf(MAX_SUPPLY_LEAF_KIND, max_supply_key(proposal.token_id), &max_supply)
+            }
+            ProposalAction::SetCompliancePolicy(policy) => self.put_compliance_policy(proposal.token_id, policy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AccountLeaf;
+
+    #[test]
+    fn test_governance_vote_executes_change() {
+        let mut smt = SMT::new_zero();
+        let issuer = [1u8; 32];
+        let alice = [2u8; 32];
+        let bob = [3u8; 32];
+        let token_id = smt.register_token(&issuer, "Gov|GOV|0".to_string()).unwrap();
+        smt.update(AccountLeaf::new(issuer, 0, 0, token_id)).unwrap();
+        smt.mint_token(&issuer, &alice, token_id, 600, 0).unwrap();
+        smt.mint_token(&issuer, &bob, token_id, 400, 1).unwrap();
+
+        // Voting proofs come from the state the proposal snapshots
+        let alice_proof = smt.gen_account_proof(&alice, token_id).unwrap();
+        let bob_proof = smt.gen_account_proof(&bob, token_id).unwrap();
+
+        let action = ProposalAction::SetMaxSupply(5000);
+        assert!(matches!(
+            smt.create_proposal(&bob, token_id, action, 100, 0, 0),
+            Err(CoreError::InvalidProposal(_))
+        ));
+        let id = smt.create_proposal(&bob, token_id, action, 100, 1000, 0).unwrap();
+        let proposal = smt.get_proposal(&id).unwrap().unwrap();
+        assert_eq!(proposal.quorum, 500);
+        assert_eq!(proposal.deadline, 1100);
+
+        // Balances moved after the snapshot do not change voting weight
+        let alice_account = smt.get_account_with_token(&alice, token_id).unwrap();
+        smt.update(AccountLeaf::new(alice, 0, alice_account.nonce, token_id)).unwrap();
+        smt.update(AccountLeaf::new(bob, 1000, 0, token_id)).unwrap();
+
+        assert!(matches!(
+            smt.vote(&bob, &id, true, &alice_proof, 200, 1),
+            Err(CoreError::ProofVerificationFailed(_))
+        ));
+
+        // A rejected vote does not use the voter's nonce
+        assert_eq!(smt.get_account(&bob).unwrap().nonce, 1);
+
+        // Bob alone does not reach quorum
+        assert_eq!(smt.vote(&bob, &id, true, &bob_proof, 200, 1).unwrap(), ProposalStatus::Open);
+        assert!(matches!(smt.vote(&bob, &id, true, &bob_proof, 200, 2), Err(CoreError::AlreadyVoted)));
+        assert_eq!(smt.get_max_supply(token_id).unwrap(), None);
+
+        // Votes after the deadline are refused
+        assert!(matches!(
+            smt.vote(&alice, &id, false, &alice_proof, 1100, 0),
+            Err(CoreError::ProposalClosed(_))
+        ));
+
+        // Alice closes the tally against the proposal
+        assert_eq!(smt.vote(&alice, &id, false, &alice_proof, 1099, 0).unwrap(), ProposalStatus::Rejected);
+        assert_eq!(smt.get_max_supply(token_id).unwrap(), None);
+
+        assert!(matches!(
+            smt.vote(&alice, &id, true, &alice_proof, 1099, 1),
+            Err(CoreError::ProposalClosed(_))
+        ));
+
+        // Only holders can propose, and only holders at the snapshot can vote
+        let policy = ProposalAction::SetCompliancePolicy(CompliancePolicy::DenyList);
+        assert!(matches!(
+            smt.create_proposal(&alice, token_id, policy, 100, 1000, 1),
+            Err(CoreError::Unauthorized(_))
+        ));
+        let alice_proof = smt.gen_account_proof(&alice, token_id).unwrap();
+        let bob_proof = smt.gen_account_proof(&bob, token_id).unwrap();
+        let id = smt.create_proposal(&bob, token_id, policy, 100, 1000, 2).unwrap();
+        assert!(matches!(
+            smt.vote(&alice, &id, true, &alice_proof, 200, 1),
+            Err(CoreError::Unauthorized(_))
+        ));
+
+        // Bob now holds enough to pass it alone, which executes the change
+        assert_eq!(smt.vote(&bob, &id, true, &bob_proof, 200, 3).unwrap(), ProposalStatus::Passed);
+        assert_eq!(smt.get_compliance_policy(token_id).unwrap(), CompliancePolicy::DenyList);
+    }
+}
@@ -4,13 +4,26 @@
 //! transfer network, including Sparse Merkle Trees, cryptographic proofs, and
 //! account management.
 
+pub mod airdrop;
+pub mod channels;
+pub mod collateral;
+pub mod compliance;
+pub mod confidential;
+pub mod crypto;
 pub mod errors;
+pub mod governance;
+pub mod names;
+pub mod nft;
 pub mod proofs;
+pub mod session_keys;
 pub mod smt;
+pub mod standing_orders;
+pub mod stealth;
+pub mod typed_data;
 pub mod types;
 
 // Re-export commonly used types
 pub use errors::CoreError;
 pub use proofs::Proof;
 pub use smt::SMT;
-pub use types::{AccountLeaf, Address, Balance, Nonce};
+pub use types::{AccountLeaf, Address, Balance, Nonce, SignatureScheme, TxRecord};
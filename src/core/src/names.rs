@@ -0,0 +1,192 @@
+//! On-network name service.
+//!
+//! Names such as `alice.volt` are data leaves mapping the name to its owner
+//! and to the address payments should go to (the target). The owner can
+//! point the name elsewhere or hand it to a new owner. Name operations use
+//! the owner's native-token nonce.
+//!
+//! Because names live in the state tree, a resolution comes with a
+//! [`DataProof`](crate::smt::DataProof) that wallets check against the state
+//! root before paying the target.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{Address, Nonce};
+use serde::{Deserialize, Serialize};
+
+/// Data leaf kind of name records.
+pub const NAME_LEAF_KIND: &str = "name";
+
+/// Suffix every name ends with.
+pub const NAME_SUFFIX: &str = ".volt";
+
+/// Maximum length of a name, suffix included.
+pub const MAX_NAME_LEN: usize = 64;
+
+/// A registered name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameRecord {
+    /// The name, including the suffix
+    pub name: String,
+    /// The address allowed to update or transfer the name
+    pub owner: Address,
+    /// The address the name resolves to
+    pub target: Address,
+}
+
+/// Returns whether a string looks like a name rather than a hex address.
+pub fn is_name(s: &str) -> bool {
+    s.ends_with(NAME_SUFFIX)
+}
+
+/// Checks that a name is well-formed: a label of lowercase letters, digits
+/// and inner hyphens, followed by the `.volt` suffix.
+pub fn validate_name(name: &str) -> Result<(), CoreError> {
+    if name.len() > MAX_NAME_LEN {
+        return Err(CoreError::InvalidName(format!(
+            "{} is longer than {} characters",
+            name, MAX_NAME_LEN
+        )));
+    }
+
+    let label = name
+        .strip_suffix(NAME_SUFFIX)
+        .ok_or_else(|| CoreError::InvalidName(format!("{} does not end with {}", name, NAME_SUFFIX)))?;
+
+    if label.is_empty() || label.starts_with('-') || label.ends_with('-') {
+        return Err(CoreError::InvalidName(format!("{} has an invalid label", name)));
+    }
+
+    if !label.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+        return Err(CoreError::InvalidName(format!(
+            "{} may only contain lowercase letters, digits and hyphens",
+            name
+        )));
+    }
+
+    Ok(())
+}
+
+/// Computes the tree key of a name record.
+pub fn name_key(name: &str) -> [u8; 32] {
+    data_leaf_key(NAME_LEAF_KIND, name.as_bytes())
+}
+
+/// Checks a resolution proof for `name` against a state root and returns the
+/// proven record, or `None` if the proof shows the name is not registered.
+pub fn verify_resolution(name: &str, proof: &DataProof, root: &[u8; 32]) -> Result<Option<NameRecord>, CoreError> {
+    if proof.key != name_key(name) {
+        return Err(CoreError::ProofVerificationFailed(format!("Proof is not for {}", name)));
+    }
+    proof.verify(root)?;
+
+    let record: Option<NameRecord> = proof.value(NAME_LEAF_KIND)?;
+    if let Some(record) = &record {
+        if record.name != name {
+            return Err(CoreError::ProofVerificationFailed(format!("Proof is not for {}", name)));
+        }
+    }
+    Ok(record)
+}
+
+impl SMT {
+    /// Gets the record of a name, if registered.
+    pub fn get_name(&self, name: &str) -> Result<Option<NameRecord>, CoreError> {
+        self.get_data_leaf(NAME_LEAF_KIND, &name_key(name))
+    }
+
+    /// Registers an unclaimed name for `owner`, resolving to `target`.
+    pub fn register_name(&mut self, owner: &Address, name: &str, target: Address, nonce: Nonce) -> Result<(), CoreError> {
+        validate_name(name)?;
+
+        if self.get_name(name)?.is_some() {
+            return Err(CoreError::NameAlreadyRegistered(name.to_string()));
+        }
+
+        self.use_native_nonce(owner, nonce)?;
+        let record = NameRecord {
+            name: name.to_string(),
+            owner: *owner,
+            target,
+        };
+        self.put_data_leaf(NAME_LEAF_KIND, name_key(name), &record)
+    }
+
+    /// Hands a name over to a new owner. The target is left unchanged.
+    pub fn transfer_name(&mut self, owner: &Address, name: &str, new_owner: Address, nonce: Nonce) -> Result<(), CoreError> {
+        let mut record = self.owned_name(owner, name)?;
+
+        self.use_native_nonce(owner, nonce)?;
+        record.owner = new_owner;
+        self.put_data_leaf(NAME_LEAF_KIND, name_key(name), &record)
+    }
+
+    /// Points a name at a new target address.
+    pub fn set_name_target(&mut self, owner: &Address, name: &str, target: Address, nonce: Nonce) -> Result<(), CoreError> {
+        let mut record = self.owned_name(owner, name)?;
+
+        self.use_native_nonce(owner, nonce)?;
+        record.target = target;
+        self.put_data_leaf(NAME_LEAF_KIND, name_key(name), &record)
+    }
+
+    /// Gets a name record, checking that `owner` owns it.
+    fn owned_name(&self, owner: &Address, name: &str) -> Result<NameRecord, CoreError> {
+        let record = self
+            .get_name(name)?
+            .ok_or_else(|| CoreError::NameNotFound(name.to_string()))?;
+
+        if record.owner != *owner {
+            return Err(CoreError::Unauthorized(format!("{} is owned by another address", name)));
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AccountLeaf;
+
+    #[test]
+    fn test_name_validation() {
+        assert!(validate_name("alice.volt").is_ok());
+        assert!(validate_name("shop-42.volt").is_ok());
+
+        for name in ["alice", ".volt", "-alice.volt", "Alice.volt", "al ice.volt", "a.b.volt"] {
+            assert!(matches!(validate_name(name), Err(CoreError::InvalidName(_))), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_name_lifecycle() {
+        let mut smt = SMT::new_zero();
+        let (alice, bob, shop) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        smt.update(AccountLeaf::new(alice, 10, 0, 0)).unwrap();
+        smt.update(AccountLeaf::new(bob, 10, 0, 0)).unwrap();
+
+        smt.register_name(&alice, "alice.volt", alice, 0).unwrap();
+        assert!(matches!(
+            smt.register_name(&bob, "alice.volt", bob, 0),
+            Err(CoreError::NameAlreadyRegistered(_))
+        ));
+
+        // Only the owner can change the name
+        assert!(matches!(
+            smt.set_name_target(&bob, "alice.volt", bob, 0),
+            Err(CoreError::Unauthorized(_))
+        ));
+        smt.set_name_target(&alice, "alice.volt", shop, 1).unwrap();
+        smt.transfer_name(&alice, "alice.volt", bob, 2).unwrap();
+        smt.set_name_target(&bob, "alice.volt", bob, 0).unwrap();
+
+        // The resolution is provable against the root
+        let proof = smt.gen_data_proof(&name_key("alice.volt")).unwrap();
+        let record = verify_resolution("alice.volt", &proof, &smt.root()).unwrap().unwrap();
+        assert_eq!((record.owner, record.target), (bob, bob));
+        assert!(verify_resolution("bob.volt", &proof, &smt.root()).is_err());
+
+        let missing = smt.gen_data_proof(&name_key("bob.volt")).unwrap();
+        assert_eq!(verify_resolution("bob.volt", &missing, &smt.root()).unwrap(), None);
+    }
+}
@@ -0,0 +1,244 @@
+//! Non-fungible tokens.
+//!
+//! A collection is a token issued like any other: it gets a token ID from the
+//! registry and its issuer is the only address allowed to mint into it. The
+//! collection is marked non-fungible by a data leaf, so it has no balances;
+//! instead each item is a data leaf holding its owner and metadata. The
+//! token's total supply counts the minted items. Collection and item
+//! messages use the sender's native-token nonce.
+//!
+//! Because items live in the state tree, ownership of an item is proven with
+//! a [`DataProof`](crate::smt::DataProof) against the state root.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{Address, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+
+/// Data leaf kind of NFT collections.
+pub const NFT_COLLECTION_LEAF_KIND: &str = "nft_collection";
+
+/// Data leaf kind of NFT items.
+pub const NFT_ITEM_LEAF_KIND: &str = "nft_item";
+
+/// Maximum length of an item's metadata URI.
+pub const MAX_METADATA_URI_LEN: usize = 256;
+
+/// An NFT collection.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftCollection {
+    /// The collection's token ID
+    pub collection_id: TokenId,
+    /// The address allowed to mint items
+    pub issuer: Address,
+    /// Number of items minted so far
+    pub items: u64,
+}
+
+/// A minted NFT.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftItem {
+    /// The collection's token ID
+    pub collection_id: TokenId,
+    /// The item ID, unique within the collection
+    pub item_id: u64,
+    /// The current owner
+    pub owner: Address,
+    /// Where the item's metadata can be found
+    pub metadata_uri: String,
+    /// Hash of the metadata, if the issuer committed to it
+    pub metadata_hash: Option<[u8; 32]>,
+}
+
+/// Computes the tree key of a collection.
+pub fn collection_key(collection_id: TokenId) -> [u8; 32] {
+    data_leaf_key(NFT_COLLECTION_LEAF_KIND, &collection_id.to_le_bytes())
+}
+
+/// Computes the tree key of an item.
+pub fn item_key(collection_id: TokenId, item_id: u64) -> [u8; 32] {
+    let mut id = [0u8; 16];
+    id[..8].copy_from_slice(&collection_id.to_le_bytes());
+    id[8..].copy_from_slice(&item_id.to_le_bytes());
+    data_leaf_key(NFT_ITEM_LEAF_KIND, &id)
+}
+
+/// Checks an ownership proof for an item against a state root and returns
+/// the proven item, or `None` if the proof shows the item does not exist.
+pub fn verify_ownership(
+    collection_id: TokenId,
+    item_id: u64,
+    proof: &DataProof,
+    root: &[u8; 32],
+) -> Result<Option<NftItem>, CoreError> {
+    if proof.key != item_key(collection_id, item_id) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for item {} of collection {}",
+            item_id, collection_id
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(NFT_ITEM_LEAF_KIND)
+}
+
+impl SMT {
+    /// Gets a collection, or `None` if the token is not a collection.
+    pub fn get_nft_collection(&self, collection_id: TokenId) -> Result<Option<NftCollection>, CoreError> {
+        self.get_data_leaf(NFT_COLLECTION_LEAF_KIND, &collection_key(collection_id))
+    }
+
+    /// Gets an item, if minted.
+    pub fn get_nft(&self, collection_id: TokenId, item_id: u64) -> Result<Option<NftItem>, CoreError> {
+        self.get_data_leaf(NFT_ITEM_LEAF_KIND, &item_key(collection_id, item_id))
+    }
+
+    /// Gets all items owned by an address.
+    pub fn get_nfts_by_owner(&self, owner: &Address) -> Result<Vec<NftItem>, CoreError> {
+        let mut items: Vec<NftItem> = self
+            .get_data_leaves::<NftItem>(NFT_ITEM_LEAF_KIND)?
+            .into_iter()
+            .filter(|item| item.owner == *owner)
+            .collect();
+        items.sort_by_key(|item| (item.collection_id, item.item_id));
+        Ok(items)
+    }
+
+    /// Issues a new collection and returns its token ID.
+    pub fn issue_nft_collection(&mut self, issuer: &Address, metadata: String, nonce: Nonce) -> Result<TokenId, CoreError> {
+        self.use_native_nonce(issuer, nonce)?;
+
+        let collection_id = self.register_token(issuer, metadata)?;
+        let collection = NftCollection {
+            collection_id,
+            issuer: *issuer,
+            items: 0,
+        };
+        self.put_data_leaf(NFT_COLLECTION_LEAF_KIND, collection_key(collection_id), &collection)?;
+
+        Ok(collection_id)
+    }
+
+    /// Mints a new item of a collection to `to`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mint_nft(
+        &mut self,
+        issuer: &Address,
+        collection_id: TokenId,
+        item_id: u64,
+        to: Address,
+        metadata_uri: String,
+        metadata_hash: Option<[u8; 32]>,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        let mut collection = self
+            .get_nft_collection(collection_id)?
+            .ok_or(CoreError::NotAnNftCollection(collection_id))?;
+
+        if collection.issuer != *issuer {
+            return Err(CoreError::Unauthorized(format!(
+                "Only the collection issuer can mint items of collection {}",
+                collection_id
+            )));
+        }
+
+        if metadata_uri.len() > MAX_METADATA_URI_LEN {
+            return Err(CoreError::InvalidNftMetadata(format!(
+                "Metadata URI is {} bytes (maximum {})",
+                metadata_uri.len(),
+                MAX_METADATA_URI_LEN
+            )));
+        }
+
+        if self.get_nft(collection_id, item_id)?.is_some() {
+            return Err(CoreError::NftAlreadyMinted { collection_id, item_id });
+        }
+
+        self.use_native_nonce(issuer, nonce)?;
+
+        let item = NftItem {
+            collection_id,
+            item_id,
+            owner: to,
+            metadata_uri,
+            metadata_hash,
+        };
+        self.put_data_leaf(NFT_ITEM_LEAF_KIND, item_key(collection_id, item_id), &item)?;
+
+        collection.items += 1;
+        self.put_data_leaf(NFT_COLLECTION_LEAF_KIND, collection_key(collection_id), &collection)?;
+        self.update_token_supply(collection_id, 1, true)
+    }
+
+    /// Transfers an item from its owner to `to`.
+    pub fn transfer_nft(
+        &mut self,
+        from: &Address,
+        to: Address,
+        collection_id: TokenId,
+        item_id: u64,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        let mut item = self
+            .get_nft(collection_id, item_id)?
+            .ok_or(CoreError::NftNotFound { collection_id, item_id })?;
+
+        if item.owner != *from {
+            return Err(CoreError::Unauthorized(format!(
+                "Item {} of collection {} is owned by another address",
+                item_id, collection_id
+            )));
+        }
+
+        self.use_native_nonce(from, nonce)?;
+        item.owner = to;
+        self.put_data_leaf(NFT_ITEM_LEAF_KIND, item_key(collection_id, item_id), &item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nft_lifecycle() {
+        let mut smt = SMT::new_zero();
+        let (issuer, alice, bob) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+
+        let collection_id = smt.issue_nft_collection(&issuer, "Volt Punks".to_string(), 0).unwrap();
+        assert_eq!(smt.get_token(collection_id).unwrap().issuer, issuer);
+
+        // Only the issuer can mint, and each item ID only once
+        assert!(matches!(
+            smt.mint_nft(&alice, collection_id, 7, alice, "ipfs://punk/7".to_string(), None, 0),
+            Err(CoreError::Unauthorized(_))
+        ));
+        smt.mint_nft(&issuer, collection_id, 7, alice, "ipfs://punk/7".to_string(), Some([9u8; 32]), 1)
+            .unwrap();
+        assert!(matches!(
+            smt.mint_nft(&issuer, collection_id, 7, bob, "ipfs://punk/7".to_string(), None, 2),
+            Err(CoreError::NftAlreadyMinted { .. })
+        ));
+        assert_eq!(smt.get_token(collection_id).unwrap().total_supply, 1);
+
+        // Collections have no fungible balances
+        assert!(matches!(
+            smt.mint_token(&issuer, &alice, collection_id, 1, 0),
+            Err(CoreError::NotFungible(_))
+        ));
+
+        // Only the owner can transfer
+        assert!(matches!(
+            smt.transfer_nft(&bob, bob, collection_id, 7, 0),
+            Err(CoreError::Unauthorized(_))
+        ));
+        smt.transfer_nft(&alice, bob, collection_id, 7, 0).unwrap();
+        assert_eq!(smt.get_nfts_by_owner(&bob).unwrap().len(), 1);
+        assert!(smt.get_nfts_by_owner(&alice).unwrap().is_empty());
+
+        // Ownership is provable against the root
+        let proof = smt.gen_data_proof(&item_key(collection_id, 7)).unwrap();
+        let item = verify_ownership(collection_id, 7, &proof, &smt.root()).unwrap().unwrap();
+        assert_eq!(item.owner, bob);
+        assert!(verify_ownership(collection_id, 8, &proof, &smt.root()).is_err());
+    }
+}
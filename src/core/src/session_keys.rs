@@ -0,0 +1,337 @@
+//! Delegated session keys with spending limits.
+//!
+//! An account owner can authorize a secondary key to send transfers on its
+//! behalf without handing out the owner's own key. The delegation caps what
+//! the session key may spend of each listed token per time window, and lapses
+//! at its expiry; the owner can revoke it at any time, which removes it from
+//! the state.
+//!
+//! Windows are counted from the delegation's start. A session transfer
+//! carries the session key's claimed time, which the node it is submitted to
+//! checks against its own clock, and uses the owner's nonce for the token.
+//! Only the latest window of each token is recorded, so a transfer may not
+//! claim a window earlier than one the key has already spent in.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, DataProof, SMT};
+use crate::types::{Address, Balance, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Data leaf kind of session key delegations.
+pub const SESSION_KEY_LEAF_KIND: &str = "session_key";
+
+/// How much of a token a session key may spend per window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpendingLimit {
+    /// The token ID
+    pub token_id: TokenId,
+    /// Maximum amount per window
+    pub amount: Balance,
+}
+
+impl fmt::Display for SpendingLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.token_id, self.amount)
+    }
+}
+
+impl FromStr for SpendingLimit {
+    type Err = CoreError;
+
+    /// Parses a limit written as `<token_id>:<amount>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || CoreError::InvalidSessionKey(format!("Invalid spending limit {}", s));
+        let (token_id, amount) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(SpendingLimit {
+            token_id: token_id.parse().map_err(|_| invalid())?,
+            amount: amount.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// What a session key has spent of a token in its latest window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowSpend {
+    /// The token ID
+    pub token_id: TokenId,
+    /// The window the amount was spent in
+    pub window: u64,
+    /// Amount spent in that window
+    pub spent: Balance,
+}
+
+/// An owner's delegation to a session key.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionKey {
+    /// The delegating account
+    pub owner: Address,
+    /// The delegated key's address
+    pub session_key: Address,
+    /// Per-token spending limits; tokens not listed cannot be spent
+    pub limits: Vec<SpendingLimit>,
+    /// Window length in seconds
+    pub window_secs: u64,
+    /// Start of the first window, in seconds since the Unix epoch
+    pub start: u64,
+    /// Time from which the key can no longer be used, in seconds since the Unix epoch
+    pub expiry: u64,
+    /// Spending in the latest window of each token spent
+    pub spent: Vec<WindowSpend>,
+}
+
+impl SessionKey {
+    /// Returns the window containing `time`, or `None` if the delegation is
+    /// not in force at that time.
+    pub fn window_at(&self, time: u64) -> Option<u64> {
+        if time < self.start || time >= self.expiry {
+            return None;
+        }
+        Some((time - self.start) / self.window_secs)
+    }
+
+    /// Returns the limit on a token, if the key may spend it.
+    pub fn limit(&self, token_id: TokenId) -> Option<Balance> {
+        self.limits
+            .iter()
+            .find(|limit| limit.token_id == token_id)
+            .map(|limit| limit.amount)
+    }
+
+    /// Returns the latest window in which the key spent a token.
+    pub fn latest_window(&self, token_id: TokenId) -> Option<u64> {
+        self.spent
+            .iter()
+            .find(|spend| spend.token_id == token_id)
+            .map(|spend| spend.window)
+    }
+
+    /// Returns how much of a token has been spent in `window`.
+    pub fn spent_in(&self, token_id: TokenId, window: u64) -> Balance {
+        self.spent
+            .iter()
+            .find(|spend| spend.token_id == token_id && spend.window == window)
+            .map_or(0, |spend| spend.spent)
+    }
+
+    /// Returns how much of a token the key may still spend at `time`.
+    pub fn remaining(&self, token_id: TokenId, time: u64) -> Balance {
+        match (self.window_at(time), self.limit(token_id)) {
+            (Some(window), Some(limit)) => limit.saturating_sub(self.spent_in(token_id, window)),
+            _ => 0,
+        }
+    }
+}
+
+/// Computes the tree key of an owner's delegation to a session key.
+pub fn session_key_key(owner: &Address, session_key: &Address) -> [u8; 32] {
+    let mut id = [0u8; 64];
+    id[..32].copy_from_slice(owner);
+    id[32..].copy_from_slice(session_key);
+    data_leaf_key(SESSION_KEY_LEAF_KIND, &id)
+}
+
+/// Checks a delegation proof against a state root and returns the proven
+/// delegation, or `None` if the proof shows there is none.
+pub fn verify_session_key(
+    owner: &Address,
+    session_key: &Address,
+    proof: &DataProof,
+    root: &[u8; 32],
+) -> Result<Option<SessionKey>, CoreError> {
+    if proof.key != session_key_key(owner, session_key) {
+        return Err(CoreError::ProofVerificationFailed(format!(
+            "Proof is not for session key {} of {}",
+            hex::encode(session_key),
+            hex::encode(owner)
+        )));
+    }
+    proof.verify(root)?;
+    proof.value(SESSION_KEY_LEAF_KIND)
+}
+
+impl SMT {
+    /// Gets an owner's delegation to a session key, if authorized and not
+    /// revoked.
+    pub fn get_session_key(&self, owner: &Address, session_key: &Address) -> Result<Option<SessionKey>, CoreError> {
+        self.get_data_leaf(SESSION_KEY_LEAF_KIND, &session_key_key(owner, session_key))
+    }
+
+    /// Authorizes a session key to spend up to the given limits per window,
+    /// replacing any earlier delegation to the same key.
+    #[allow(clippy::too_many_arguments)]
+    pub fn authorize_session_key(
+        &mut self,
+        owner: &Address,
+        session_key: &Address,
+        limits: Vec<SpendingLimit>,
+        window_secs: u64,
+        start: u64,
+        expiry: u64,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        if owner == session_key {
+            return Err(CoreError::InvalidSessionKey("An account cannot delegate to itself".to_string()));
+        }
+        if limits.is_empty() || window_secs == 0 {
+            return Err(CoreError::InvalidSessionKey("Limits and window must not be empty".to_string()));
+        }
+        if expiry <= start {
+            return Err(CoreError::InvalidSessionKey("Expiry must be after the start".to_string()));
+        }
+        for (i, limit) in limits.iter().enumerate() {
+            if limits[..i].iter().any(|other| other.token_id == limit.token_id) {
+                return Err(CoreError::InvalidSessionKey(format!(
+                    "Token {} is limited twice",
+                    limit.token_id
+                )));
+            }
+        }
+
+        self.use_native_nonce(owner, nonce)?;
+
+        let delegation = SessionKey {
+            owner: *owner,
+            session_key: *session_key,
+            limits,
+            window_secs,
+            start,
+            expiry,
+            spent: Vec::new(),
+        };
+        self.put_data_leaf(SESSION_KEY_LEAF_KIND, session_key_key(owner, session_key), &delegation)
+    }
+
+    /// Revokes a session key.
+    pub fn revoke_session_key(&mut self, owner: &Address, session_key: &Address, nonce: Nonce) -> Result<(), CoreError> {
+        if self.get_session_key(owner, session_key)?.is_none() {
+            return Err(CoreError::InvalidSessionKey(format!(
+                "Session key {} is not authorized",
+                hex::encode(session_key)
+            )));
+        }
+
+        self.use_native_nonce(owner, nonce)?;
+        self.remove_data_leaf(&session_key_key(owner, session_key))
+    }
+
+    /// Transfers a token from the owner's account on behalf of a session key,
+    /// within the key's spending limit for the window containing `time`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn session_transfer(
+        &mut self,
+        owner: &Address,
+        session_key: &Address,
+        to: &Address,
+        token_id: TokenId,
+        amount: Balance,
+        time: u64,
+        nonce: Nonce,
+    ) -> Result<(), CoreError> {
+        let mut delegation = self.get_session_key(owner, session_key)?.ok_or_else(|| {
+            CoreError::Unauthorized(format!(
+                "Session key {} is not authorized by {}",
+                hex::encode(session_key),
+                hex::encode(owner)
+            ))
+        })?;
+
+        let window = delegation.window_at(time).ok_or_else(|| {
+            CoreError::InvalidSessionKey(format!("Session key is not in force at time {}", time))
+        })?;
+        if let Some(latest) = delegation.latest_window(token_id).filter(|latest| *latest > window) {
+            return Err(CoreError::InvalidSessionKey(format!(
+                "Window {} is before window {}, which the key has already spent in",
+                window, latest
+            )));
+        }
+        let limit = delegation.limit(token_id).ok_or_else(|| {
+            CoreError::Unauthorized(format!("Session key may not spend token {}", token_id))
+        })?;
+
+        let spent = delegation
+            .spent_in(token_id, window)
+            .checked_add(amount)
+            .ok_or(CoreError::SupplyOverflow)?;
+        if spent > limit {
+            return Err(CoreError::SpendingLimitExceeded {
+                token_id,
+                remaining: delegation.remaining(token_id, time),
+            });
+        }
+
+        self.transfer_token(owner, to, token_id, amount, nonce)?;
+
+        delegation.spent.retain(|spend| spend.token_id != token_id);
+        delegation.spent.push(WindowSpend { token_id, window, spent });
+        self.put_data_leaf(SESSION_KEY_LEAF_KIND, session_key_key(owner, session_key), &delegation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::AccountLeaf;
+
+    #[test]
+    fn test_session_key_limits() {
+        let mut smt = SMT::new_zero();
+        let (owner, session_key, payee) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        smt.update(AccountLeaf::new(owner, 100, 0, 0)).unwrap();
+
+        let limits = vec![SpendingLimit { token_id: 0, amount: 30 }];
+        smt.authorize_session_key(&owner, &session_key, limits, 60, 1000, 1180, 0).unwrap();
+
+        // Spending is capped per window and only while the delegation runs
+        smt.session_transfer(&owner, &session_key, &payee, 0, 20, 1000, 1).unwrap();
+        assert!(matches!(
+            smt.session_transfer(&owner, &session_key, &payee, 0, 11, 1059, 2),
+            Err(CoreError::SpendingLimitExceeded { remaining: 10, .. })
+        ));
+        smt.session_transfer(&owner, &session_key, &payee, 0, 10, 1059, 2).unwrap();
+        smt.session_transfer(&owner, &session_key, &payee, 0, 30, 1060, 3).unwrap();
+        assert!(smt.session_transfer(&owner, &session_key, &payee, 0, 1, 1180, 4).is_err());
+        assert!(matches!(
+            smt.session_transfer(&owner, &session_key, &payee, 1, 1, 1120, 4),
+            Err(CoreError::Unauthorized(_))
+        ));
+        assert_eq!(smt.get_account(&payee).unwrap().bal, 60);
+
+        // Revocation is provable against the root
+        smt.revoke_session_key(&owner, &session_key, 4).unwrap();
+        assert!(smt.session_transfer(&owner, &session_key, &payee, 0, 1, 1120, 5).is_err());
+
+        let proof = smt.gen_data_proof(&session_key_key(&owner, &session_key)).unwrap();
+        assert!(verify_session_key(&owner, &session_key, &proof, &smt.root()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_session_key_rejects_earlier_windows() {
+        let mut smt = SMT::new_zero();
+        let (owner, session_key, payee) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        smt.update(AccountLeaf::new(owner, 100, 0, 0)).unwrap();
+
+        let limits = vec![SpendingLimit { token_id: 0, amount: 30 }];
+        smt.authorize_session_key(&owner, &session_key, limits, 60, 1000, 1300, 0).unwrap();
+
+        // Spending the full limit just after a window boundary does not
+        // allow going back to the window before it
+        smt.session_transfer(&owner, &session_key, &payee, 0, 30, 1060, 1).unwrap();
+        assert!(matches!(
+            smt.session_transfer(&owner, &session_key, &payee, 0, 30, 1059, 2),
+            Err(CoreError::InvalidSessionKey(_))
+        ));
+        assert!(matches!(
+            smt.session_transfer(&owner, &session_key, &payee, 0, 1, 1119, 2),
+            Err(CoreError::SpendingLimitExceeded { remaining: 0, .. })
+        ));
+
+        // The next window starts afresh, and the earlier ones stay closed
+        smt.session_transfer(&owner, &session_key, &payee, 0, 30, 1120, 2).unwrap();
+        assert!(smt.session_transfer(&owner, &session_key, &payee, 0, 1, 1060, 3).is_err());
+        assert!(smt.session_transfer(&owner, &session_key, &payee, 0, 1, 1000, 3).is_err());
+        assert_eq!(smt.get_account(&payee).unwrap().bal, 60);
+        assert_eq!(smt.get_session_key(&owner, &session_key).unwrap().unwrap().latest_window(0), Some(2));
+    }
+}
@@ -2,14 +2,16 @@
 
 use crate::errors::CoreError;
 use crate::proofs::Proof;
-use crate::types::{AccountLeaf, Address, Balance, TokenId, TokenInfo, SystemMsg};
+use crate::types::{AccountLeaf, Address, Balance, Nonce, TokenId, TokenInfo, SystemMsg};
 use byteorder::{ByteOrder, LittleEndian};
 use rocksdb::{IteratorMode, DB};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sparse_merkle_tree::{
     default_store::DefaultStore,
-    traits::Hasher,
+    BranchKey,
+    CompiledMerkleProof,
+    traits::{Hasher, StoreReadOps},
     SparseMerkleTree as SMTree, H256,
 };
 use std::collections::HashMap;
@@ -70,6 +72,9 @@ pub struct SMT {
     /// Registry of tokens by token ID
     #[serde(skip)]
     token_registry: HashMap<TokenId, TokenInfo>,
+    /// Cache of auxiliary state leaves by leaf key
+    #[serde(skip)]
+    data_leaves: HashMap<[u8; 32], DataLeaf>,
     /// The root hash of the tree
     root: [u8; 32],
     /// The next available token ID
@@ -86,6 +91,270 @@ const ROOT_KEY: &[u8] = b"root";
 const ACCOUNT_PREFIX: &str = "account::";
 const TOKEN_PREFIX: &str = "token::";
 const NEXT_TOKEN_ID_KEY: &[u8] = b"next_token_id";
+const DATA_PREFIX: &str = "data::";
+
+/// An auxiliary state leaf, such as a confidential balance.
+///
+/// Data leaves live in the same tree as account leaves, under keys derived
+/// with [`data_leaf_key`], so they share the state root and can be proven
+/// with [`SMT::gen_data_proof`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataLeaf {
+    /// The kind of state this leaf holds
+    pub kind: String,
+    /// The bincode-serialized leaf value
+    pub data: Vec<u8>,
+}
+
+impl DataLeaf {
+    /// Computes the hash of this data leaf.
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.kind.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(&self.data);
+
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&hasher.finalize());
+        hash
+    }
+}
+
+/// A Merkle proof that a data leaf is, or is not, part of the state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataProof {
+    /// The tree key of the leaf
+    pub key: [u8; 32],
+    /// The leaf, or `None` if the key is empty
+    pub leaf: Option<DataLeaf>,
+    /// The compiled sparse Merkle proof
+    pub proof: Vec<u8>,
+}
+
+impl DataProof {
+    /// Verifies the proof against a state root.
+    pub fn verify(&self, root: &[u8; 32]) -> Result<(), CoreError> {
+        let value = match &self.leaf {
+            Some(leaf) => H256::from(leaf.hash()),
+            None => H256::zero(),
+        };
+
+        let valid = CompiledMerkleProof(self.proof.clone())
+            .verify::<Sha256Hasher>(&H256::from(*root), vec![(H256::from(self.key), value)])
+            .map_err(|e| CoreError::ProofVerificationFailed(e.to_string()))?;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CoreError::ProofVerificationFailed(
+                "Data proof does not match the root".to_string(),
+            ))
+        }
+    }
+
+    /// Decodes the proven leaf value, checking its kind.
+    pub fn value<T: DeserializeOwned>(&self, kind: &str) -> Result<Option<T>, CoreError> {
+        match &self.leaf {
+            Some(leaf) if leaf.kind != kind => Err(CoreError::SerializationError(format!(
+                "Expected a {} leaf, found {}",
+                kind, leaf.kind
+            ))),
+            Some(leaf) => bincode::deserialize(&leaf.data)
+                .map(Some)
+                .map_err(|e| CoreError::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A Merkle proof that an account leaf is, or is not, part of the state.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountProof {
+    /// The tree key of the account
+    pub key: [u8; 32],
+    /// The account leaf, or `None` if the account does not exist
+    pub leaf: Option<AccountLeaf>,
+    /// The compiled sparse Merkle proof
+    pub proof: Vec<u8>,
+}
+
+impl AccountProof {
+    /// Verifies the proof against a state root.
+    pub fn verify(&self, root: &[u8; 32]) -> Result<(), CoreError> {
+        if let Some(leaf) = &self.leaf {
+            if compute_leaf_key(&leaf.addr, leaf.token_id) != self.key {
+                return Err(CoreError::ProofVerificationFailed(
+                    "Account leaf does not match the proof key".to_string(),
+                ));
+            }
+        }
+
+        let value = match &self.leaf {
+            Some(leaf) => H256::from(leaf.hash()),
+            None => H256::zero(),
+        };
+
+        let valid = CompiledMerkleProof(self.proof.clone())
+            .verify::<Sha256Hasher>(&H256::from(*root), vec![(H256::from(self.key), value)])
+            .map_err(|e| CoreError::ProofVerificationFailed(e.to_string()))?;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CoreError::ProofVerificationFailed(
+                "Account proof does not match the root".to_string(),
+            ))
+        }
+    }
+
+    /// Returns the proven balance, which is zero for a missing account.
+    pub fn balance(&self) -> Balance {
+        self.leaf.as_ref().map_or(0, |leaf| leaf.bal)
+    }
+}
+
+/// A subtree of the state tree: the keys whose top `depth` bits match `path`.
+///
+/// Bits are numbered as in the tree, from bit 255 at the top down to bit 0,
+/// so the children of a subtree split on bit `255 - depth`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Subtree {
+    /// A key with the subtree's path in its top `depth` bits
+    pub path: [u8; 32],
+    /// The number of bits fixed by the path
+    pub depth: u8,
+}
+
+impl Subtree {
+    /// Returns the whole tree.
+    pub fn root() -> Self {
+        Subtree { path: [0u8; 32], depth: 0 }
+    }
+
+    /// Returns the left and right children of the subtree, or `None` at the
+    /// maximum depth.
+    pub fn children(&self) -> Option<[Subtree; 2]> {
+        let depth = self.depth.checked_add(1)?;
+        let bit = u8::MAX - self.depth;
+
+        let mut left = H256::from(self.path);
+        left.clear_bit(bit);
+        let mut right = left;
+        right.set_bit(bit);
+
+        Some([
+            Subtree { path: left.into(), depth },
+            Subtree { path: right.into(), depth },
+        ])
+    }
+
+    /// Returns whether a tree key lies in the subtree.
+    pub fn contains(&self, key: &[u8; 32]) -> bool {
+        if self.depth == 0 {
+            return true;
+        }
+        let start = (256 - self.depth as u16) as u8;
+        H256::from(*key).copy_bits(start) == H256::from(self.path).copy_bits(start)
+    }
+}
+
+/// A leaf of the state tree, as transferred by state sync.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateLeaf {
+    /// An account leaf
+    Account(AccountLeaf),
+    /// A data leaf under its tree key
+    Data([u8; 32], DataLeaf),
+}
+
+impl StateLeaf {
+    /// Returns the tree key of the leaf.
+    pub fn key(&self) -> [u8; 32] {
+        match self {
+            StateLeaf::Account(leaf) => compute_leaf_key(&leaf.addr, leaf.token_id),
+            StateLeaf::Data(key, _) => *key,
+        }
+    }
+
+    /// Returns the value the tree stores for the leaf.
+    pub fn hash(&self) -> [u8; 32] {
+        match self {
+            StateLeaf::Account(leaf) => leaf.hash(),
+            StateLeaf::Data(_, leaf) => leaf.hash(),
+        }
+    }
+}
+
+/// A run of state leaves in tree key order, with one proof covering them all.
+///
+/// A chunk proves that its leaves are part of the state, but not that no
+/// leaf was left out; a complete sync is checked by rebuilding the root with
+/// [`SMT::set_synced_state`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateChunk {
+    /// The leaves, ordered by tree key
+    pub leaves: Vec<StateLeaf>,
+    /// The compiled sparse Merkle proof of all the leaves
+    pub proof: Vec<u8>,
+    /// Whether no leaves follow this chunk
+    pub last: bool,
+}
+
+impl StateChunk {
+    /// Verifies the chunk against a state root, checking that its leaves lie
+    /// in `subtree` and follow `after` in key order.
+    pub fn verify(&self, root: &[u8; 32], subtree: &Subtree, after: Option<&[u8; 32]>) -> Result<(), CoreError> {
+        let mut previous = after.copied();
+        for leaf in &self.leaves {
+            let key = leaf.key();
+            if previous.is_some_and(|previous| key <= previous) {
+                return Err(CoreError::ProofVerificationFailed(
+                    "State chunk leaves are out of order".to_string(),
+                ));
+            }
+            if !subtree.contains(&key) {
+                return Err(CoreError::ProofVerificationFailed(
+                    "State chunk leaf is outside the subtree".to_string(),
+                ));
+            }
+            previous = Some(key);
+        }
+
+        if self.leaves.is_empty() {
+            return Ok(());
+        }
+
+        let leaves = self
+            .leaves
+            .iter()
+            .map(|leaf| (H256::from(leaf.key()), H256::from(leaf.hash())))
+            .collect();
+        let valid = CompiledMerkleProof(self.proof.clone())
+            .verify::<Sha256Hasher>(&H256::from(*root), leaves)
+            .map_err(|e| CoreError::ProofVerificationFailed(e.to_string()))?;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CoreError::ProofVerificationFailed(
+                "State chunk does not match the root".to_string(),
+            ))
+        }
+    }
+}
+
+/// Computes the tree key of a data leaf of the given kind.
+pub fn data_leaf_key(kind: &str, id: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(DATA_PREFIX.as_bytes());
+    hasher.update(kind.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(id);
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hasher.finalize());
+    key
+}
 
 impl Clone for SMT {
     fn clone(&self) -> Self {
@@ -121,6 +390,14 @@ impl Clone for SMT {
             let _ = smt.tree.update(addr_h256, value_h256);
         }
 
+        // Copy the data leaves
+        for (key, leaf) in &self.data_leaves {
+            smt.data_leaves.insert(*key, leaf.clone());
+
+            // Ignore errors during cloning
+            let _ = smt.tree.update(H256::from(*key), H256::from(leaf.hash()));
+        }
+
         // Share the same DB reference if available
         if let Some(db) = &self.db {
             smt.db = Some(Arc::clone(db));
@@ -131,7 +408,7 @@ impl Clone for SMT {
 }
 
 /// Computes a unique key for a (address, token_id) pair.
-fn compute_leaf_key(addr: &Address, token_id: TokenId) -> [u8; 32] {
+pub fn compute_leaf_key(addr: &Address, token_id: TokenId) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(addr);
     
@@ -145,6 +422,13 @@ fn compute_leaf_key(addr: &Address, token_id: TokenId) -> [u8; 32] {
     key
 }
 
+/// Computes the RocksDB key of a data leaf.
+fn data_db_key(key: &[u8; 32]) -> Vec<u8> {
+    let mut db_key = DATA_PREFIX.as_bytes().to_vec();
+    db_key.extend_from_slice(key);
+    db_key
+}
+
 impl SMT {
     /// Creates a new empty Sparse Merkle Tree.
     pub fn new_zero() -> Self {
@@ -158,6 +442,7 @@ impl SMT {
             tree,
             accounts: HashMap::new(),
             token_registry: HashMap::new(),
+            data_leaves: HashMap::new(),
             root,
             next_token_id: 1, // Start from 1, 0 is reserved for the native token
             native_token_id: 0,
@@ -230,6 +515,13 @@ impl SMT {
                 .map_err(|e| CoreError::SMTError(format!("Failed to persist token: {}", e)))?;
         }
         
+        // Persist data leaves in the meta column family
+        for (key, leaf) in &self.data_leaves {
+            db.put_cf(&cf_meta, data_db_key(key), bincode::serialize(leaf)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?)
+                .map_err(|e| CoreError::SMTError(format!("Failed to persist data leaf: {}", e)))?;
+        }
+        
         Ok(())
     }
 
@@ -320,6 +612,34 @@ impl SMT {
             smt.token_registry.insert(token_info.token_id, token_info);
         }
         
+        // Load data leaves from meta column family
+        let data_prefix = DATA_PREFIX.as_bytes();
+        let iter = db.iterator_cf(&cf_meta, IteratorMode::From(data_prefix, rocksdb::Direction::Forward));
+        
+        for item in iter {
+            let (key, value) = item.map_err(|e| CoreError::SMTError(format!("Failed to iterate data leaves: {}", e)))?;
+            
+            if !key.starts_with(data_prefix) {
+                // We've moved past the data prefix
+                break;
+            }
+            
+            let mut leaf_key = [0u8; 32];
+            if key.len() != data_prefix.len() + 32 {
+                warn!("Skipping malformed data leaf key");
+                continue;
+            }
+            leaf_key.copy_from_slice(&key[data_prefix.len()..]);
+            
+            let leaf: DataLeaf = bincode::deserialize(&value)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?;
+            
+            if let Err(e) = smt.tree.update(H256::from(leaf_key), H256::from(leaf.hash())) {
+                warn!("Failed to update tree during loading: {}", e);
+            }
+            smt.data_leaves.insert(leaf_key, leaf);
+        }
+        
         // Ensure the native token exists
         if !smt.token_registry.contains_key(&0) {
             let native_token = TokenInfo {
@@ -389,9 +709,14 @@ impl SMT {
     pub fn get_token_registry(&self) -> Result<&std::collections::HashMap<TokenId, TokenInfo>, CoreError> {
         Ok(&self.token_registry)
     }
+
+    /// Returns the token ID the next issued token will get.
+    pub fn next_token_id(&self) -> TokenId {
+        self.next_token_id
+    }
     
     /// Updates a token's total supply.
-    fn update_token_supply(&mut self, token_id: TokenId, amount: Balance, is_mint: bool) -> Result<(), CoreError> {
+    pub(crate) fn update_token_supply(&mut self, token_id: TokenId, amount: Balance, is_mint: bool) -> Result<(), CoreError> {
         let mut token_info = self.get_token(token_id)?;
         
         if is_mint {
@@ -605,6 +930,9 @@ impl SMT {
         amount: Balance,
         nonce: u64,
     ) -> Result<(), CoreError> {
+        // Both parties must be allowed to hold the token
+        self.check_transfer_compliance(token_id, from, to)?;
+
         // Get the sender account
         let sender = self.get_account_with_token(from, token_id)?;
 
@@ -742,6 +1070,29 @@ impl SMT {
     ) -> Result<Balance, CoreError> {
         // Get the token info
         let token_info = self.get_token(token_id)?;
+
+        // Items of NFT collections are minted with mint_nft
+        if self.get_nft_collection(token_id)?.is_some() {
+            return Err(CoreError::NotFungible(token_id));
+        }
+
+        if self.is_token_retired(token_id)? {
+            return Err(CoreError::TokenRetired(token_id));
+        }
+
+        // Holders may have capped the supply through governance
+        if let Some(max_supply) = self.get_max_supply(token_id)? {
+            if token_info.total_supply.checked_add(amount).ok_or(CoreError::SupplyOverflow)? > max_supply {
+                return Err(CoreError::ExceedsMaxSupply {
+                    max_supply,
+                    current_supply: token_info.total_supply,
+                    mint_amount: amount,
+                });
+            }
+        }
+
+        // The recipient must be allowed to hold the token
+        self.check_compliance(token_id, to)?;
         
         // Check if the issuer is authorized to mint this token
         info!("Checking if issuer {:?} is authorized to mint token {} with issuer {:?}",
@@ -1048,6 +1399,12 @@ impl SMT {
             debug!("Successfully updated tree for account: {:?}", leaf.addr);
         }
         
+        // Data leaves are not part of the account state, so keep them in the tree
+        for (key, leaf) in &self.data_leaves {
+            self.tree.update(H256::from(*key), H256::from(leaf.hash()))
+                .map_err(|e| CoreError::SMTError(format!("Failed to update tree: {}", e)))?;
+        }
+        
         Ok(())
     }
     
@@ -1085,14 +1442,34 @@ impl SMT {
             batch.put_cf(&cf_leaves, key.as_ref(), &serialized);
         }
         
-        // 3. Update the root (do this last so readers never see a half-applied state)
+        // 3. Replace the data leaves, which rebuilding keeps in the tree
         let cf_meta = db.cf_handle("meta").ok_or_else(|| {
             CoreError::SMTError("Column family 'meta' not found".to_string())
         })?;
         
+        let data_prefix = DATA_PREFIX.as_bytes();
+        let iter = db.iterator_cf(&cf_meta, IteratorMode::From(data_prefix, rocksdb::Direction::Forward));
+        for result in iter {
+            let (key, _) = result.map_err(|e| {
+                CoreError::SMTError(format!("Failed to iterate over data leaves: {}", e))
+            })?;
+            if !key.starts_with(data_prefix) {
+                break;
+            }
+            batch.delete_cf(&cf_meta, &key);
+        }
+        
+        for (key, leaf) in &self.data_leaves {
+            let serialized = bincode::serialize(leaf)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?;
+            
+            batch.put_cf(&cf_meta, data_db_key(key), &serialized);
+        }
+        
+        // 4. Update the root (do this last so readers never see a half-applied state)
         batch.put_cf(&cf_meta, ROOT_KEY, &root);
         
-        // 4. Write the batch atomically
+        // 5. Write the batch atomically
         db.write(batch).map_err(|e| {
             CoreError::SMTError(format!("Failed to write batch to RocksDB: {}", e))
         })?;
@@ -1160,6 +1537,347 @@ impl SMT {
         Ok(token_info.total_supply)
     }
     
+    /// Gets a data leaf of the given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the data leaf
+    /// * `key` - The leaf key, from [`data_leaf_key`]
+    ///
+    /// # Returns
+    ///
+    /// The decoded value if the leaf exists, `None` otherwise
+    pub fn get_data_leaf<T: DeserializeOwned>(&self, kind: &str, key: &[u8; 32]) -> Result<Option<T>, CoreError> {
+        match self.data_leaves.get(key) {
+            Some(leaf) if leaf.kind == kind => bincode::deserialize(&leaf.data)
+                .map(Some)
+                .map_err(|e| CoreError::SerializationError(e.to_string())),
+            Some(leaf) => Err(CoreError::SMTError(format!(
+                "Data leaf has kind {}, expected {}",
+                leaf.kind, kind
+            ))),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns all data leaves of the given kind.
+    pub fn get_data_leaves<T: DeserializeOwned>(&self, kind: &str) -> Result<Vec<T>, CoreError> {
+        self.data_leaves
+            .values()
+            .filter(|leaf| leaf.kind == kind)
+            .map(|leaf| {
+                bincode::deserialize(&leaf.data).map_err(|e| CoreError::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Inserts or replaces a data leaf.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the data leaf
+    /// * `key` - The leaf key, from [`data_leaf_key`]
+    /// * `value` - The value to store
+    ///
+    /// # Returns
+    ///
+    /// `Ok(())` if the update was successful, `Err(CoreError)` otherwise
+    pub fn put_data_leaf<T: Serialize>(&mut self, kind: &str, key: [u8; 32], value: &T) -> Result<(), CoreError> {
+        let leaf = DataLeaf {
+            kind: kind.to_string(),
+            data: bincode::serialize(value).map_err(|e| CoreError::SerializationError(e.to_string()))?,
+        };
+
+        self.tree
+            .update(H256::from(key), H256::from(leaf.hash()))
+            .map_err(|e| CoreError::SMTError(e.to_string()))?;
+        self.root.copy_from_slice(self.tree.root().as_slice());
+
+        if let Some(db) = &self.db {
+            let cf_meta = db.cf_handle("meta").ok_or_else(|| {
+                CoreError::SMTError("Column family 'meta' not found".to_string())
+            })?;
+
+            db.put_cf(&cf_meta, data_db_key(&key), bincode::serialize(&leaf)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?)
+                .map_err(|e| CoreError::SMTError(format!("Failed to persist data leaf: {}", e)))?;
+            db.put_cf(&cf_meta, ROOT_KEY, bincode::serialize(&self.root)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?)
+                .map_err(|e| CoreError::SMTError(format!("Failed to persist root: {}", e)))?;
+        }
+
+        self.data_leaves.insert(key, leaf);
+        Ok(())
+    }
+
+    /// Removes a data leaf from the tree, if present.
+    pub fn remove_data_leaf(&mut self, key: &[u8; 32]) -> Result<(), CoreError> {
+        if self.data_leaves.remove(key).is_none() {
+            return Ok(());
+        }
+
+        // Setting a leaf to zero removes it from the tree
+        self.tree
+            .update(H256::from(*key), H256::zero())
+            .map_err(|e| CoreError::SMTError(e.to_string()))?;
+        self.root.copy_from_slice(self.tree.root().as_slice());
+
+        if let Some(db) = &self.db {
+            let cf_meta = db.cf_handle("meta").ok_or_else(|| {
+                CoreError::SMTError("Column family 'meta' not found".to_string())
+            })?;
+
+            db.delete_cf(&cf_meta, data_db_key(key))
+                .map_err(|e| CoreError::SMTError(format!("Failed to delete data leaf: {}", e)))?;
+            db.put_cf(&cf_meta, ROOT_KEY, bincode::serialize(&self.root)
+                .map_err(|e| CoreError::SerializationError(e.to_string()))?)
+                .map_err(|e| CoreError::SMTError(format!("Failed to persist root: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Generates a Merkle proof for a data leaf.
+    ///
+    /// For a missing leaf the proof shows that the key is empty.
+    pub fn gen_data_proof(&self, key: &[u8; 32]) -> Result<DataProof, CoreError> {
+        let proof = self.tree
+            .merkle_proof(vec![H256::from(*key)])
+            .and_then(|proof| proof.compile(vec![H256::from(*key)]))
+            .map_err(|e| CoreError::SMTError(e.to_string()))?;
+
+        Ok(DataProof {
+            key: *key,
+            leaf: self.data_leaves.get(key).cloned(),
+            proof: proof.0,
+        })
+    }
+
+    /// Generates a Merkle proof for an account leaf.
+    ///
+    /// For a missing account the proof shows that the key is empty.
+    pub fn gen_account_proof(&self, addr: &Address, token_id: TokenId) -> Result<AccountProof, CoreError> {
+        let key = compute_leaf_key(addr, token_id);
+        let proof = self.tree
+            .merkle_proof(vec![H256::from(key)])
+            .and_then(|proof| proof.compile(vec![H256::from(key)]))
+            .map_err(|e| CoreError::SMTError(e.to_string()))?;
+
+        Ok(AccountProof {
+            key,
+            leaf: self.get_account_with_token(addr, token_id).ok(),
+            proof: proof.0,
+        })
+    }
+
+    /// Returns the keys of the state leaves in a subtree in key order, with
+    /// the account leaf of each account key.
+    fn state_keys(&self, subtree: &Subtree) -> Vec<([u8; 32], Option<&AccountLeaf>)> {
+        let mut keys: Vec<([u8; 32], Option<&AccountLeaf>)> = self
+            .accounts
+            .values()
+            .map(|leaf| (compute_leaf_key(&leaf.addr, leaf.token_id), Some(leaf)))
+            .chain(self.data_leaves.keys().map(|key| (*key, None)))
+            .filter(|(key, _)| subtree.contains(key))
+            .collect();
+        keys.sort_unstable_by_key(|(key, _)| *key);
+        keys
+    }
+
+    /// Returns the state leaves in a subtree, ordered by tree key.
+    pub fn state_leaves(&self, subtree: &Subtree) -> Vec<StateLeaf> {
+        self.state_keys(subtree)
+            .into_iter()
+            .map(|(key, account)| match account {
+                Some(leaf) => StateLeaf::Account(leaf.clone()),
+                None => StateLeaf::Data(key, self.data_leaves[&key].clone()),
+            })
+            .collect()
+    }
+
+    /// Returns the number of state leaves in each subtree.
+    pub fn subtree_sizes(&self, subtrees: &[Subtree]) -> Vec<usize> {
+        let keys: Vec<[u8; 32]> = self
+            .accounts
+            .values()
+            .map(|leaf| compute_leaf_key(&leaf.addr, leaf.token_id))
+            .chain(self.data_leaves.keys().copied())
+            .collect();
+
+        subtrees
+            .iter()
+            .map(|subtree| keys.iter().filter(|key| subtree.contains(key)).count())
+            .collect()
+    }
+
+    /// Returns the hash of a subtree, which is zero for an empty subtree.
+    ///
+    /// Two trees have the same leaves under a subtree exactly when its hash
+    /// is the same in both.
+    pub fn subtree_hash(&self, subtree: &Subtree) -> Result<[u8; 32], CoreError> {
+        if subtree.depth == 0 {
+            return Ok((*self.tree.root()).into());
+        }
+
+        // The subtree is a child of the branch at its lowest fixed bit
+        let height = (256 - subtree.depth as u16) as u8;
+        let path = H256::from(subtree.path);
+        let branch = self
+            .tree
+            .store()
+            .get_branch(&BranchKey::new(height, path.parent_path(height)))
+            .map_err(|e| CoreError::SMTError(e.to_string()))?;
+
+        Ok(match branch {
+            Some(branch) if path.is_right(height) => branch.right.hash::<Sha256Hasher>().into(),
+            Some(branch) => branch.left.hash::<Sha256Hasher>().into(),
+            None => [0u8; 32],
+        })
+    }
+
+    /// Returns up to `limit` state leaves of a subtree following `after` in
+    /// tree key order, with a proof of them against the current root.
+    pub fn gen_state_chunk(
+        &self,
+        subtree: &Subtree,
+        after: Option<&[u8; 32]>,
+        limit: usize,
+    ) -> Result<StateChunk, CoreError> {
+        let mut keys = self.state_keys(subtree);
+        keys.retain(|(key, _)| after.is_none_or(|after| key > after));
+
+        let last = keys.len() <= limit;
+        keys.truncate(limit);
+
+        let leaves: Vec<StateLeaf> = keys
+            .into_iter()
+            .map(|(key, account)| match account {
+                Some(leaf) => StateLeaf::Account(leaf.clone()),
+                None => StateLeaf::Data(key, self.data_leaves[&key].clone()),
+            })
+            .collect();
+
+        let proof = if leaves.is_empty() {
+            Vec::new()
+        } else {
+            let keys: Vec<H256> = leaves.iter().map(|leaf| H256::from(leaf.key())).collect();
+            self.tree
+                .merkle_proof(keys.clone())
+                .and_then(|proof| proof.compile(keys))
+                .map_err(|e| CoreError::SMTError(e.to_string()))?
+                .0
+        };
+
+        Ok(StateChunk { leaves, proof, last })
+    }
+
+    /// Replaces the leaves under some subtrees with leaves received by state
+    /// sync, keeping the rest of the state.
+    ///
+    /// The result must be exactly the given root; otherwise the state is
+    /// left unchanged.
+    pub fn replace_subtrees(
+        &mut self,
+        subtrees: &[Subtree],
+        leaves: Vec<StateLeaf>,
+        root: [u8; 32],
+    ) -> Result<(), CoreError> {
+        if let Some(leaf) = leaves.iter().find(|leaf| !subtrees.iter().any(|subtree| subtree.contains(&leaf.key()))) {
+            return Err(CoreError::ProofVerificationFailed(format!(
+                "Leaf {} is outside the replaced subtrees",
+                hex::encode(leaf.key())
+            )));
+        }
+
+        let mut state: Vec<StateLeaf> = self
+            .state_leaves(&Subtree::root())
+            .into_iter()
+            .filter(|leaf| !subtrees.iter().any(|subtree| subtree.contains(&leaf.key())))
+            .collect();
+        state.extend(leaves);
+
+        self.set_synced_state(state, root)
+    }
+
+    /// Builds a detached tree from state leaves, without a database.
+    ///
+    /// Lets callers copy the leaves out of a shared tree and generate proofs
+    /// against that state without holding its lock.
+    pub fn from_state_leaves(leaves: Vec<StateLeaf>) -> Result<Self, CoreError> {
+        let mut smt = SMT::new_zero();
+        for leaf in leaves {
+            smt.tree
+                .update(H256::from(leaf.key()), H256::from(leaf.hash()))
+                .map_err(|e| CoreError::SMTError(format!("Failed to update tree: {}", e)))?;
+            match leaf {
+                StateLeaf::Account(leaf) => {
+                    smt.accounts.insert((leaf.addr, leaf.token_id), leaf);
+                }
+                StateLeaf::Data(key, leaf) => {
+                    smt.data_leaves.insert(key, leaf);
+                }
+            }
+        }
+        smt.root.copy_from_slice(smt.tree.root().as_slice());
+        Ok(smt)
+    }
+
+    /// Replaces the state with leaves received by state sync.
+    ///
+    /// The leaves must rebuild exactly the given root, which proves that none
+    /// were altered or left out; otherwise the state is left unchanged.
+    pub fn set_synced_state(&mut self, leaves: Vec<StateLeaf>, root: [u8; 32]) -> Result<(), CoreError> {
+        let mut tree: SMTree<Sha256Hasher, H256, DefaultStore<H256>> = SMTree::default();
+        let mut accounts = Vec::new();
+        let mut data_leaves = HashMap::new();
+
+        for leaf in leaves {
+            tree.update(H256::from(leaf.key()), H256::from(leaf.hash()))
+                .map_err(|e| CoreError::SMTError(format!("Failed to update tree: {}", e)))?;
+            match leaf {
+                StateLeaf::Account(leaf) => accounts.push(leaf),
+                StateLeaf::Data(key, leaf) => {
+                    data_leaves.insert(key, leaf);
+                }
+            }
+        }
+
+        if tree.root().as_slice() != root {
+            return Err(CoreError::ProofVerificationFailed(
+                "Synced state does not match the root".to_string(),
+            ));
+        }
+
+        self.data_leaves = data_leaves;
+        self.set_full_state(accounts, root)
+    }
+
+    /// Checks an account's native-token nonce without using it, and returns
+    /// the account.
+    pub(crate) fn check_native_nonce(&self, addr: &Address, nonce: Nonce) -> Result<AccountLeaf, CoreError> {
+        let account = self
+            .get_account(addr)
+            .unwrap_or_else(|_| AccountLeaf::new_empty(*addr, self.native_token_id));
+
+        if account.nonce != nonce {
+            return Err(CoreError::InvalidNonce {
+                expected: account.nonce,
+                actual: nonce,
+            });
+        }
+
+        Ok(account)
+    }
+
+    /// Checks and increments an account's native-token nonce.
+    ///
+    /// Used by messages that act on data leaves rather than on a token
+    /// balance. An address without a native account starts at nonce 0.
+    pub(crate) fn use_native_nonce(&mut self, addr: &Address, nonce: Nonce) -> Result<(), CoreError> {
+        let account = self.check_native_nonce(addr, nonce)?;
+        self.update(AccountLeaf::new(*addr, account.bal, account.nonce + 1, account.token_id))
+    }
+
     /// Applies a system message to the state tree.
     ///
     /// # Arguments
@@ -1171,7 +1889,8 @@ impl SMT {
     /// `Ok(())` if the message was applied successfully, `Err(CoreError)` otherwise
     pub fn apply(&mut self, msg: SystemMsg) -> Result<(), CoreError> {
         match msg {
-            SystemMsg::Transfer { from, to, token_id, amount, nonce, .. } => {
+            SystemMsg::Transfer { from, to, token_id, amount, nonce, memo, .. } => {
+                crate::types::validate_memo(&memo)?;
                 self.transfer_token(&from, &to, token_id, amount, nonce)?;
             }
             SystemMsg::Mint { from, to, token_id, amount, nonce, .. } => {
@@ -1180,31 +1899,105 @@ impl SMT {
             SystemMsg::Burn { from, token_id, amount, nonce, .. } => {
                 self.burn_token(&from, token_id, amount, nonce)?;
             }
-            SystemMsg::IssueToken { issuer, token_id: _, metadata, nonce, .. } => {
-                // Get the issuer account (using native token)
-                let issuer_account = self.get_account(&issuer)?;
-                
-                // Check nonce
-                if issuer_account.nonce != nonce {
-                    return Err(CoreError::InvalidNonce {
-                        expected: issuer_account.nonce,
-                        actual: nonce,
-                    });
-                }
-                
-                // Register the new token
-                let _token_id = self.register_token(&issuer, metadata)?;
-                
-                // Update issuer account (increment nonce)
-                let new_issuer = AccountLeaf::new(
-                    issuer,
-                    issuer_account.bal,
-                    issuer_account.nonce + 1,
-                    self.native_token_id, // Use native token for the issuer account
-                );
-                
-                // Update the tree
-                self.update(new_issuer)?;
+            SystemMsg::IssueToken { issuer, token_id: _, metadata, collateral, nonce, .. } => {
+                self.issue_token(&issuer, metadata, collateral, nonce)?;
+            }
+            SystemMsg::Shield { from, token_id, amount, viewing_key, nonce, .. } => {
+                self.shield(&from, token_id, amount, viewing_key, nonce)?;
+            }
+            SystemMsg::Unshield { from, token_id, amount, new_commitment, range_proof, nonce, .. } => {
+                self.unshield(&from, token_id, amount, new_commitment, &range_proof, nonce)?;
+            }
+            SystemMsg::ConfidentialTransfer {
+                from, to, token_id, amount_commitment, new_sender_commitment, range_proof, nonce, ..
+            } => {
+                self.confidential_transfer(
+                    &from,
+                    &to,
+                    token_id,
+                    amount_commitment,
+                    new_sender_commitment,
+                    &range_proof,
+                    nonce,
+                )?;
+            }
+            SystemMsg::RegisterName { owner, name, target, nonce, .. } => {
+                self.register_name(&owner, &name, target, nonce)?;
+            }
+            SystemMsg::TransferName { owner, name, new_owner, nonce, .. } => {
+                self.transfer_name(&owner, &name, new_owner, nonce)?;
+            }
+            SystemMsg::SetNameTarget { owner, name, target, nonce, .. } => {
+                self.set_name_target(&owner, &name, target, nonce)?;
+            }
+            SystemMsg::IssueNftCollection { issuer, metadata, nonce, .. } => {
+                self.issue_nft_collection(&issuer, metadata, nonce)?;
+            }
+            SystemMsg::MintNft {
+                issuer, collection_id, item_id, to, metadata_uri, metadata_hash, nonce, ..
+            } => {
+                self.mint_nft(&issuer, collection_id, item_id, to, metadata_uri, metadata_hash, nonce)?;
+            }
+            SystemMsg::TransferNft { from, to, collection_id, item_id, nonce, .. } => {
+                self.transfer_nft(&from, to, collection_id, item_id, nonce)?;
+            }
+            SystemMsg::SetCompliancePolicy { issuer, token_id, policy, nonce, .. } => {
+                self.set_compliance_policy(&issuer, token_id, policy, nonce)?;
+            }
+            SystemMsg::UpdateComplianceList { issuer, token_id, list, address, listed, nonce, .. } => {
+                self.update_compliance_list(&issuer, token_id, list, address, listed, nonce)?;
+            }
+            SystemMsg::RetireToken { issuer, token_id, nonce, .. } => {
+                self.retire_token(&issuer, token_id, nonce)?;
+            }
+            SystemMsg::CreateAirdrop { issuer, token_id, merkle_root, entries, total, expires_at, nonce, .. } => {
+                self.create_airdrop(&issuer, token_id, merkle_root, entries, total, expires_at, nonce)?;
+            }
+            SystemMsg::ClaimAirdrop { claimant, airdrop_id, index, amount, proof, timestamp, nonce, .. } => {
+                self.claim_airdrop(&claimant, &airdrop_id, index, amount, &proof, timestamp, nonce)?;
+            }
+            SystemMsg::ReclaimAirdrop { issuer, airdrop_id, timestamp, nonce, .. } => {
+                self.reclaim_airdrop(&issuer, &airdrop_id, timestamp, nonce)?;
+            }
+            SystemMsg::CreateProposal { proposer, token_id, action, timestamp, voting_period_secs, nonce, .. } => {
+                self.create_proposal(&proposer, token_id, action, timestamp, voting_period_secs, nonce)?;
+            }
+            SystemMsg::Vote { voter, proposal_id, support, proof, timestamp, nonce, .. } => {
+                self.vote(&voter, &proposal_id, support, &proof, timestamp, nonce)?;
+            }
+            SystemMsg::AuthorizeStandingOrder {
+                payer, payee, token_id, amount, period_secs, start, expiry, nonce, ..
+            } => {
+                self.authorize_standing_order(&payer, &payee, token_id, amount, period_secs, start, expiry, nonce)?;
+            }
+            SystemMsg::CancelStandingOrder { payer, order_id, nonce, .. } => {
+                self.cancel_standing_order(&payer, &order_id, nonce)?;
+            }
+            SystemMsg::PullPayment { payee, order_id, timestamp, nonce, .. } => {
+                self.pull_payment(&payee, &order_id, timestamp, nonce)?;
+            }
+            SystemMsg::OpenChannel { payer, payee, token_id, deposit, timeout_secs, nonce, .. } => {
+                self.open_channel(&payer, &payee, token_id, deposit, timeout_secs, nonce)?;
+            }
+            SystemMsg::CloseChannel { payee, voucher, nonce, .. } => {
+                self.close_channel(&payee, &voucher, nonce)?;
+            }
+            SystemMsg::StartChannelTimeout { payer, channel_id, timestamp, nonce, .. } => {
+                self.start_channel_timeout(&payer, &channel_id, timestamp, nonce)?;
+            }
+            SystemMsg::ReclaimChannel { payer, channel_id, timestamp, nonce, .. } => {
+                self.reclaim_channel(&payer, &channel_id, timestamp, nonce)?;
+            }
+            SystemMsg::AuthorizeSessionKey {
+                owner, session_key, limits, window_secs, start, expiry, nonce, ..
+            } => {
+                self.authorize_session_key(&owner, &session_key, limits, window_secs, start, expiry, nonce)?;
+            }
+            SystemMsg::RevokeSessionKey { owner, session_key, nonce, .. } => {
+                self.revoke_session_key(&owner, &session_key, nonce)?;
+            }
+            SystemMsg::SessionTransfer { owner, session_key, to, token_id, amount, timestamp, nonce, .. } => {
+                self.session_transfer(&owner, &session_key, &to, token_id, amount, timestamp, nonce)?;
             }
         }
         
@@ -1415,4 +2208,101 @@ mod tests {
         // Should fail with InvalidNonce error
         assert!(matches!(result, Err(CoreError::InvalidNonce { .. })));
     }
+
+    #[test]
+    fn test_data_leaves() {
+        let mut smt = SMT::new_zero();
+        smt.update(AccountLeaf::new([1u8; 32], 10, 0, 0)).unwrap();
+        let root_before = smt.root();
+
+        let key = data_leaf_key("test", b"entry");
+        smt.put_data_leaf("test", key, &42u64).unwrap();
+        assert_ne!(smt.root(), root_before);
+        assert_eq!(smt.get_data_leaf::<u64>("test", &key).unwrap(), Some(42));
+        assert!(smt.get_data_leaf::<u64>("other", &key).is_err());
+
+        // Data leaves survive cloning and can be proven against the root
+        let cloned = smt.clone();
+        assert_eq!(cloned.root(), smt.root());
+        let proof = smt.gen_data_proof(&key).unwrap();
+        proof.verify(&smt.root()).unwrap();
+        assert_eq!(proof.value::<u64>("test").unwrap(), Some(42));
+        assert!(proof.verify(&root_before).is_err());
+
+        // So can their absence
+        let missing = smt.gen_data_proof(&data_leaf_key("test", b"missing")).unwrap();
+        missing.verify(&smt.root()).unwrap();
+        assert_eq!(missing.value::<u64>("test").unwrap(), None);
+
+        smt.remove_data_leaf(&key).unwrap();
+        assert_eq!(smt.root(), root_before);
+        assert_eq!(smt.get_data_leaf::<u64>("test", &key).unwrap(), None);
+    }
+
+    #[test]
+    fn test_state_chunks() {
+        let mut smt = SMT::new_zero();
+        for i in 1..=4u8 {
+            smt.update(AccountLeaf::new([i; 32], i as u128 * 10, 0, 0)).unwrap();
+        }
+        smt.put_data_leaf("test", data_leaf_key("test", b"entry"), &42u64).unwrap();
+        let root = smt.root();
+
+        // Chunks page through every leaf, each proven against the root
+        let mut leaves = Vec::new();
+        let mut after = None;
+        loop {
+            let chunk = smt.gen_state_chunk(&Subtree::root(), after.as_ref(), 2).unwrap();
+            chunk.verify(&root, &Subtree::root(), after.as_ref()).unwrap();
+            after = chunk.leaves.last().map(StateLeaf::key);
+            leaves.extend(chunk.leaves);
+            if chunk.last {
+                break;
+            }
+        }
+        assert_eq!(leaves.len(), 5);
+
+        // A chunk does not verify against another root or out of order
+        let chunk = smt.gen_state_chunk(&Subtree::root(), None, 2).unwrap();
+        assert!(chunk.verify(&SMT::new_zero().root(), &Subtree::root(), None).is_err());
+        assert!(chunk.verify(&root, &Subtree::root(), Some(&[0xff; 32])).is_err());
+
+        // The leaves rebuild the state only if none were left out
+        let mut synced = SMT::new_zero();
+        assert!(synced.set_synced_state(leaves[1..].to_vec(), root).is_err());
+        synced.set_synced_state(leaves, root).unwrap();
+        assert_eq!(synced.root(), root);
+        assert_eq!(synced.get_account(&[3u8; 32]).unwrap().bal, 30);
+        assert_eq!(synced.get_data_leaf::<u64>("test", &data_leaf_key("test", b"entry")).unwrap(), Some(42));
+    }
+
+    #[test]
+    fn test_subtree_diff() {
+        let mut smt = SMT::new_zero();
+        for i in 1..=8u8 {
+            smt.update(AccountLeaf::new([i; 32], 10, 0, 0)).unwrap();
+        }
+        let mut other = smt.clone();
+        other.update(AccountLeaf::new([5u8; 32], 20, 1, 0)).unwrap();
+        let changed = compute_leaf_key(&[5u8; 32], 0);
+        assert_eq!(smt.subtree_hash(&Subtree::root()).unwrap(), smt.root());
+
+        // Subtrees hash the same in both trees unless they hold the changed leaf
+        let mut subtree = Subtree::root();
+        for _ in 0..8 {
+            let [left, right] = subtree.children().unwrap();
+            for child in [left, right] {
+                let same = smt.subtree_hash(&child).unwrap() == other.subtree_hash(&child).unwrap();
+                assert_eq!(same, !child.contains(&changed));
+            }
+            subtree = if left.contains(&changed) { left } else { right };
+        }
+
+        // Replacing just the differing subtree reproduces the other tree
+        let mut synced = smt.clone();
+        assert!(synced.replace_subtrees(&[subtree], Vec::new(), other.root()).is_err());
+        synced.replace_subtrees(&[subtree], other.state_leaves(&subtree), other.root()).unwrap();
+        assert_eq!(synced.root(), other.root());
+        assert_eq!(synced.get_account(&[5u8; 32]).unwrap().bal, 20);
+    }
 }
@@ -0,0 +1,251 @@
+//! Recurring payment authorizations.
+//!
+//! A standing order lets a payee pull a fixed amount of a token from the
+//! payer once per period, without a fresh signature from the payer each
+//! time. The payer signs the order once and can cancel it at any time, which
+//! removes it from the state.
+//!
+//! Periods are counted from the order's start time. A pull carries the
+//! payee's claimed time, which the node it is submitted to checks against
+//! its own clock. Each period can be pulled at most once; a period that is not
+//! pulled lapses.
+
+use crate::errors::CoreError;
+use crate::smt::{data_leaf_key, SMT};
+use crate::types::{AccountLeaf, Address, Balance, Nonce, TokenId};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data leaf kind of standing orders.
+pub const ORDER_LEAF_KIND: &str = "standing_order";
+
+/// Identifier of a standing order.
+pub type OrderId = [u8; 32];
+
+/// A payer's authorization for a payee to pull a fixed amount each period.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StandingOrder {
+    /// The order ID
+    pub order_id: OrderId,
+    /// The address paying
+    pub payer: Address,
+    /// The address allowed to pull
+    pub payee: Address,
+    /// The token paid
+    pub token_id: TokenId,
+    /// Amount that can be pulled each period
+    pub amount: Balance,
+    /// Period length in seconds
+    pub period_secs: u64,
+    /// Start of the first period, in seconds since the Unix epoch
+    pub start: u64,
+    /// Time from which no more pulls are allowed, in seconds since the Unix epoch
+    pub expiry: u64,
+    /// The last period pulled, if any
+    pub last_period: Option<u64>,
+}
+
+impl StandingOrder {
+    /// Returns the period containing `time`, or `None` if the order is not
+    /// running at that time.
+    pub fn period_at(&self, time: u64) -> Option<u64> {
+        if time < self.start || time >= self.expiry {
+            return None;
+        }
+        Some((time - self.start) / self.period_secs)
+    }
+
+    /// Returns whether the order allows pulls at `time` or later.
+    pub fn is_active(&self, time: u64) -> bool {
+        time < self.expiry
+    }
+}
+
+/// Computes the ID of a standing order from its parties, token and the
+/// payer's nonce.
+pub fn order_id(payer: &Address, payee: &Address, token_id: TokenId, nonce: Nonce) -> OrderId {
+    let mut hasher = Sha256::new();
+    hasher.update(payer);
+    hasher.update(payee);
+    hasher.update(token_id.to_le_bytes());
+    hasher.update(nonce.to_le_bytes());
+
+    let mut id = [0u8; 32];
+    id.copy_from_slice(&hasher.finalize());
+    id
+}
+
+/// Computes the tree key of a standing order.
+pub fn order_key(order_id: &OrderId) -> [u8; 32] {
+    data_leaf_key(ORDER_LEAF_KIND, order_id)
+}
+
+impl SMT {
+    /// Gets a standing order, if authorized and not cancelled.
+    pub fn get_standing_order(&self, order_id: &OrderId) -> Result<Option<StandingOrder>, CoreError> {
+        self.get_data_leaf(ORDER_LEAF_KIND, &order_key(order_id))
+    }
+
+    /// Gets the standing orders an address pays or is paid by that are still
+    /// active at `time`.
+    pub fn get_standing_orders(&self, address: &Address, time: u64) -> Result<Vec<StandingOrder>, CoreError> {
+        let mut orders: Vec<StandingOrder> = self
+            .get_data_leaves::<StandingOrder>(ORDER_LEAF_KIND)?
+            .into_iter()
+            .filter(|order| (order.payer == *address || order.payee == *address) && order.is_active(time))
+            .collect();
+        orders.sort_by_key(|order| (order.start, order.order_id));
+        Ok(orders)
+    }
+
+    /// Authorizes a payee to pull `amount` of a token once per period.
+    /// Returns the order ID.
+    #[allow(clippy::too_many_arguments)]
+    pub fn authorize_standing_order(
+        &mut self,
+        payer: &Address,
+        payee: &Address,
+        token_id: TokenId,
+        amount: Balance,
+        period_secs: u64,
+        start: u64,
+        expiry: u64,
+        nonce: Nonce,
+    ) -> Result<OrderId, CoreError> {
+        if payer == payee {
+            return Err(CoreError::InvalidStandingOrder("Payer and payee must differ".to_string()));
+        }
+        if amount == 0 || period_secs == 0 {
+            return Err(CoreError::InvalidStandingOrder("Amount and period must be positive".to_string()));
+        }
+        if expiry <= start {
+            return Err(CoreError::InvalidStandingOrder("Expiry must be after the start".to_string()));
+        }
+
+        // Both parties must be allowed to hold the token
+        self.get_token(token_id)?;
+        self.check_transfer_compliance(token_id, payer, payee)?;
+
+        let id = order_id(payer, payee, token_id, nonce);
+        self.use_native_nonce(payer, nonce)?;
+
+        let order = StandingOrder {
+            order_id: id,
+            payer: *payer,
+            payee: *payee,
+            token_id,
+            amount,
+            period_secs,
+            start,
+            expiry,
+            last_period: None,
+        };
+        self.put_data_leaf(ORDER_LEAF_KIND, order_key(&id), &order)?;
+
+        Ok(id)
+    }
+
+    /// Cancels a standing order (can only be done by its payer).
+    pub fn cancel_standing_order(&mut self, payer: &Address, order_id: &OrderId, nonce: Nonce) -> Result<(), CoreError> {
+        let order = self.get_standing_order(order_id)?.ok_or_else(|| {
+            CoreError::InvalidStandingOrder(format!("Standing order {} not found", hex::encode(order_id)))
+        })?;
+
+        if order.payer != *payer {
+            return Err(CoreError::Unauthorized(
+                "Only the payer can cancel a standing order".to_string(),
+            ));
+        }
+
+        self.use_native_nonce(payer, nonce)?;
+        self.remove_data_leaf(&order_key(order_id))
+    }
+
+    /// Pulls one period's payment of a standing order to its payee. Returns
+    /// the period pulled.
+    pub fn pull_payment(&mut self, payee: &Address, order_id: &OrderId, time: u64, nonce: Nonce) -> Result<u64, CoreError> {
+        let mut order = self.get_standing_order(order_id)?.ok_or_else(|| {
+            CoreError::InvalidStandingOrder(format!("Standing order {} not found", hex::encode(order_id)))
+        })?;
+
+        if order.payee != *payee {
+            return Err(CoreError::Unauthorized(
+                "Only the payee can pull a standing order".to_string(),
+            ));
+        }
+
+        let period = order.period_at(time).ok_or_else(|| {
+            CoreError::InvalidStandingOrder(format!("Standing order is not running at time {}", time))
+        })?;
+        if order.last_period.is_some_and(|last| last >= period) {
+            return Err(CoreError::PeriodAlreadyPulled(period));
+        }
+
+        self.check_transfer_compliance(order.token_id, &order.payer, payee)?;
+
+        let payer_account = self.get_account_with_token(&order.payer, order.token_id)?;
+        if payer_account.bal < order.amount {
+            return Err(CoreError::InsufficientBalance {
+                required: order.amount,
+                available: payer_account.bal,
+            });
+        }
+
+        self.use_native_nonce(payee, nonce)?;
+
+        let receiver = self
+            .get_account_with_token(payee, order.token_id)
+            .unwrap_or_else(|_| AccountLeaf::new_empty(*payee, order.token_id));
+        let balance = receiver.bal.checked_add(order.amount).ok_or(CoreError::SupplyOverflow)?;
+
+        self.update(AccountLeaf::new(
+            order.payer,
+            payer_account.bal - order.amount,
+            payer_account.nonce,
+            order.token_id,
+        ))?;
+        self.update(AccountLeaf::new(*payee, balance, receiver.nonce, order.token_id))?;
+
+        order.last_period = Some(period);
+        self.put_data_leaf(ORDER_LEAF_KIND, order_key(order_id), &order)?;
+
+        Ok(period)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standing_order_pulls() {
+        let mut smt = SMT::new_zero();
+        let (payer, payee) = ([1u8; 32], [2u8; 32]);
+        smt.update(AccountLeaf::new(payer, 100, 0, 0)).unwrap();
+
+        let id = smt.authorize_standing_order(&payer, &payee, 0, 30, 60, 1000, 1180, 0).unwrap();
+        assert_eq!(smt.get_standing_orders(&payee, 1000).unwrap().len(), 1);
+
+        // Only the payee pulls, only while the order runs, once per period
+        assert!(matches!(smt.pull_payment(&payer, &id, 1000, 1), Err(CoreError::Unauthorized(_))));
+        assert!(smt.pull_payment(&payee, &id, 999, 0).is_err());
+        assert_eq!(smt.pull_payment(&payee, &id, 1010, 0).unwrap(), 0);
+        assert!(matches!(smt.pull_payment(&payee, &id, 1059, 1), Err(CoreError::PeriodAlreadyPulled(0))));
+        assert_eq!(smt.pull_payment(&payee, &id, 1150, 1).unwrap(), 2);
+        assert!(smt.pull_payment(&payee, &id, 1180, 2).is_err());
+
+        assert_eq!(smt.get_account(&payee).unwrap().bal, 60);
+        assert_eq!(smt.get_account(&payer).unwrap().bal, 40);
+        assert!(smt.get_standing_orders(&payer, 1180).unwrap().is_empty());
+
+        // Only the payer cancels
+        let id = smt.authorize_standing_order(&payer, &payee, 0, 10, 60, 2000, 3000, 1).unwrap();
+        assert!(matches!(
+            smt.cancel_standing_order(&payee, &id, 2),
+            Err(CoreError::Unauthorized(_))
+        ));
+        smt.cancel_standing_order(&payer, &id, 2).unwrap();
+        assert!(smt.get_standing_order(&id).unwrap().is_none());
+        assert!(smt.pull_payment(&payee, &id, 2000, 2).is_err());
+    }
+}
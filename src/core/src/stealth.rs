@@ -0,0 +1,227 @@
+//! Stealth addresses.
+//!
+//! A recipient publishes a [`StealthMetaAddress`]: a scan key `S = s * G` and
+//! a spend key `B = b * G` on the Ed25519 curve. To pay it, a sender picks a
+//! random `r`, publishes the ephemeral key `R = r * G` with the transfer and
+//! sends to the one-time address `P = H(r * S, R) * G + B`. `P` is an ordinary
+//! Ed25519 address, so observers cannot link payments to the recipient.
+//!
+//! The recipient recognises its payments by computing `P' = H(s * R, R) * G + B`
+//! for every transfer carrying an ephemeral key, and spends from `P` with the
+//! one-time secret `H(s * R, R) + b`. Holding only the scan secret is enough
+//! to find payments but not to spend them.
+
+use crate::errors::CoreError;
+use crate::types::{Address, Signature};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use sha2::{Digest, Sha512};
+use std::fmt;
+use std::str::FromStr;
+
+/// Prefix of the text form of a stealth meta-address.
+pub const STEALTH_ADDRESS_PREFIX: &str = "stealth:";
+
+/// The ephemeral public key published with a stealth transfer.
+pub type EphemeralKey = [u8; 32];
+
+/// A stealth secret key (canonical scalar encoding).
+pub type StealthSecret = [u8; 32];
+
+/// Domain label of the one-time key derivation.
+const TWEAK_LABEL: &[u8] = b"volt-stealth-v1";
+
+/// Domain label of the nonce prefix used when signing with a one-time key.
+const NONCE_LABEL: &[u8] = b"volt-stealth-nonce";
+
+/// The public keys a recipient publishes to receive stealth payments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StealthMetaAddress {
+    /// Key senders derive the shared secret from
+    pub scan_key: [u8; 32],
+    /// Key the one-time addresses are built on
+    pub spend_key: [u8; 32],
+}
+
+impl StealthMetaAddress {
+    /// Builds the meta-address of a scan secret and a spend secret.
+    pub fn from_secrets(scan_secret: &StealthSecret, spend_secret: &StealthSecret) -> Result<Self, CoreError> {
+        Ok(Self {
+            scan_key: public_key(scan_secret)?,
+            spend_key: public_key(spend_secret)?,
+        })
+    }
+}
+
+impl fmt::Display for StealthMetaAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            STEALTH_ADDRESS_PREFIX,
+            hex::encode(self.scan_key),
+            hex::encode(self.spend_key)
+        )
+    }
+}
+
+impl FromStr for StealthMetaAddress {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys_hex = s
+            .strip_prefix(STEALTH_ADDRESS_PREFIX)
+            .ok_or_else(|| CoreError::InvalidStealthAddress(format!("Missing {} prefix", STEALTH_ADDRESS_PREFIX)))?;
+
+        let keys = hex::decode(keys_hex).map_err(|e| CoreError::InvalidStealthAddress(e.to_string()))?;
+        if keys.len() != 64 {
+            return Err(CoreError::InvalidStealthAddress(format!(
+                "Expected 64 key bytes, got {}",
+                keys.len()
+            )));
+        }
+
+        let mut meta = Self {
+            scan_key: [0u8; 32],
+            spend_key: [0u8; 32],
+        };
+        meta.scan_key.copy_from_slice(&keys[..32]);
+        meta.spend_key.copy_from_slice(&keys[32..]);
+
+        point(&meta.scan_key)?;
+        point(&meta.spend_key)?;
+        Ok(meta)
+    }
+}
+
+/// Reduces 64 uniformly random bytes to a stealth secret.
+pub fn secret_from_bytes(bytes: &[u8; 64]) -> StealthSecret {
+    Scalar::from_bytes_mod_order_wide(bytes).to_bytes()
+}
+
+/// Derives a one-time address for a recipient.
+///
+/// `ephemeral_bytes` must be fresh random bytes. Returns the one-time address
+/// and the ephemeral key to publish with the transfer.
+pub fn derive_one_time_address(
+    meta: &StealthMetaAddress,
+    ephemeral_bytes: &[u8; 64],
+) -> Result<(Address, EphemeralKey), CoreError> {
+    let ephemeral_secret = Scalar::from_bytes_mod_order_wide(ephemeral_bytes);
+    let ephemeral_key = EdwardsPoint::mul_base(&ephemeral_secret).compress().to_bytes();
+
+    let shared = (ephemeral_secret * point(&meta.scan_key)?).compress().to_bytes();
+    let tweak = tweak(&shared, &ephemeral_key);
+    let address = (EdwardsPoint::mul_base(&tweak) + point(&meta.spend_key)?)
+        .compress()
+        .to_bytes();
+
+    Ok((address, ephemeral_key))
+}
+
+/// Checks whether a transfer to `to` carrying `ephemeral_key` is a stealth
+/// payment to the owner of `scan_secret` and `spend_key`.
+///
+/// Returns the one-time tweak, which [`one_time_secret`] combines with the
+/// spend secret, or `None` if the payment is not for this recipient.
+pub fn scan(
+    scan_secret: &StealthSecret,
+    spend_key: &[u8; 32],
+    to: &Address,
+    ephemeral_key: &EphemeralKey,
+) -> Option<StealthSecret> {
+    let shared = (scalar(scan_secret).ok()? * point(ephemeral_key).ok()?)
+        .compress()
+        .to_bytes();
+    let tweak = tweak(&shared, ephemeral_key);
+    let expected = (EdwardsPoint::mul_base(&tweak) + point(spend_key).ok()?).compress();
+
+    (expected.as_bytes() == to).then(|| tweak.to_bytes())
+}
+
+/// Computes the secret controlling a one-time address.
+pub fn one_time_secret(spend_secret: &StealthSecret, tweak: &StealthSecret) -> Result<StealthSecret, CoreError> {
+    Ok((scalar(spend_secret)? + scalar(tweak)?).to_bytes())
+}
+
+/// Computes the address controlled by a secret.
+pub fn public_key(secret: &StealthSecret) -> Result<Address, CoreError> {
+    Ok(EdwardsPoint::mul_base(&scalar(secret)?).compress().to_bytes())
+}
+
+/// Signs a message with a one-time secret, producing an Ed25519 signature
+/// that verifies against the one-time address.
+pub fn sign_one_time(secret: &StealthSecret, message: &[u8]) -> Result<Signature, CoreError> {
+    let address = public_key(secret)?;
+
+    let mut hasher = Sha512::new();
+    hasher.update(NONCE_LABEL);
+    hasher.update(secret);
+    let nonce_prefix = hasher.finalize();
+
+    let mut expanded = [0u8; 64];
+    expanded[..32].copy_from_slice(secret);
+    expanded[32..].copy_from_slice(&nonce_prefix[..32]);
+
+    let expanded = ed25519_dalek::ExpandedSecretKey::from_bytes(&expanded)
+        .map_err(|e| CoreError::SignatureVerificationFailed(e.to_string()))?;
+    let public = ed25519_dalek::PublicKey::from_bytes(&address)
+        .map_err(|e| CoreError::SignatureVerificationFailed(e.to_string()))?;
+
+    Ok(Signature(expanded.sign(message, &public).to_bytes()))
+}
+
+/// Hashes a shared secret and ephemeral key to the one-time tweak.
+fn tweak(shared: &[u8; 32], ephemeral_key: &EphemeralKey) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(TWEAK_LABEL);
+    hasher.update(shared);
+    hasher.update(ephemeral_key);
+
+    let mut wide = [0u8; 64];
+    wide.copy_from_slice(&hasher.finalize());
+    Scalar::from_bytes_mod_order_wide(&wide)
+}
+
+/// Decodes a canonical scalar.
+fn scalar(bytes: &StealthSecret) -> Result<Scalar, CoreError> {
+    Option::<Scalar>::from(Scalar::from_canonical_bytes(*bytes))
+        .ok_or_else(|| CoreError::InvalidStealthAddress("Invalid secret".to_string()))
+}
+
+/// Decodes a compressed Edwards point.
+fn point(bytes: &[u8; 32]) -> Result<EdwardsPoint, CoreError> {
+    CompressedEdwardsY(*bytes)
+        .decompress()
+        .ok_or_else(|| CoreError::InvalidStealthAddress("Invalid key".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::verify_signature;
+    use crate::types::SignatureScheme;
+
+    #[test]
+    fn test_stealth_payment_roundtrip() {
+        let scan_secret = secret_from_bytes(&[1u8; 64]);
+        let spend_secret = secret_from_bytes(&[2u8; 64]);
+        let meta = StealthMetaAddress::from_secrets(&scan_secret, &spend_secret).unwrap();
+        assert_eq!(meta.to_string().parse::<StealthMetaAddress>().unwrap(), meta);
+
+        let (address, ephemeral_key) = derive_one_time_address(&meta, &[3u8; 64]).unwrap();
+        assert_ne!(address, meta.spend_key);
+
+        // The recipient finds the payment and can sign for the one-time address
+        let tweak = scan(&scan_secret, &meta.spend_key, &address, &ephemeral_key).unwrap();
+        let secret = one_time_secret(&spend_secret, &tweak).unwrap();
+        assert_eq!(public_key(&secret).unwrap(), address);
+
+        let signature = sign_one_time(&secret, b"transfer").unwrap();
+        assert!(verify_signature(SignatureScheme::Ed25519, &address, b"transfer", &signature).is_ok());
+
+        // Another recipient does not match the payment
+        let other_scan = secret_from_bytes(&[4u8; 64]);
+        assert!(scan(&other_scan, &meta.spend_key, &address, &ephemeral_key).is_none());
+    }
+}
@@ -1,5 +1,15 @@
 //! Core types for the chainless token transfer network.
 
+use crate::airdrop::AirdropId;
+use crate::channels::{ChannelId, ChannelVoucher};
+use crate::compliance::{ComplianceList, CompliancePolicy};
+use crate::confidential::{Commitment, EncryptedOpening};
+use crate::errors::CoreError;
+use crate::governance::{ProposalAction, ProposalId};
+use crate::session_keys::SpendingLimit;
+use crate::smt::AccountProof;
+use crate::standing_orders::OrderId;
+use crate::stealth::EphemeralKey;
 use byteorder::{ByteOrder, LittleEndian};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -17,6 +27,106 @@ pub type Nonce = u64;
 /// Token ID, used to identify different tokens in the system.
 pub type TokenId = u64;
 
+/// Prefix marking an address as controlled by a secp256k1 (Ethereum) key.
+///
+/// Such addresses are the 12-byte prefix followed by the 20-byte Ethereum address.
+pub const SECP256K1_ADDRESS_PREFIX: [u8; 12] = *b"volt-secp256";
+
+/// The signature scheme that controls an account.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    /// Ed25519; the address is the public key itself
+    #[default]
+    Ed25519,
+    /// secp256k1 ECDSA; the address embeds the Ethereum address of the key
+    Secp256k1,
+}
+
+impl SignatureScheme {
+    /// Returns the scheme that controls the given address.
+    pub fn of_address(addr: &Address) -> Self {
+        if addr[..12] == SECP256K1_ADDRESS_PREFIX {
+            SignatureScheme::Secp256k1
+        } else {
+            SignatureScheme::Ed25519
+        }
+    }
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureScheme::Ed25519 => write!(f, "ed25519"),
+            SignatureScheme::Secp256k1 => write!(f, "secp256k1"),
+        }
+    }
+}
+
+/// Derives the Volt address controlled by an Ethereum address's key.
+pub fn address_from_eth(eth_address: &[u8; 20]) -> Address {
+    let mut addr = [0u8; 32];
+    addr[..12].copy_from_slice(&SECP256K1_ADDRESS_PREFIX);
+    addr[12..].copy_from_slice(eth_address);
+    addr
+}
+
+/// Returns the Ethereum address embedded in a secp256k1 Volt address.
+pub fn eth_address_of(addr: &Address) -> Option<[u8; 20]> {
+    if SignatureScheme::of_address(addr) != SignatureScheme::Secp256k1 {
+        return None;
+    }
+    let mut eth_address = [0u8; 20];
+    eth_address.copy_from_slice(&addr[12..]);
+    Some(eth_address)
+}
+
+/// Maximum size of a transfer memo, in bytes.
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// Free-form payment reference attached to a transfer (e.g. an invoice number).
+pub type Memo = Vec<u8>;
+
+/// Checks that a memo fits within `MAX_MEMO_LEN`.
+pub fn validate_memo(memo: &[u8]) -> Result<(), CoreError> {
+    if memo.len() > MAX_MEMO_LEN {
+        return Err(CoreError::MemoTooLong {
+            len: memo.len(),
+            max: MAX_MEMO_LEN,
+        });
+    }
+    Ok(())
+}
+
+/// Builds the JSON payload a sender signs for a transfer.
+///
+/// The payload always holds `from` and `to` (hex-encoded), `token_id`,
+/// `amount` and `nonce`. The memo and stealth ephemeral key are hex-encoded
+/// and only included when present.
+pub fn transfer_payload(
+    from: &Address,
+    to: &Address,
+    token_id: TokenId,
+    amount: Balance,
+    nonce: Nonce,
+    memo: &[u8],
+    ephemeral_key: Option<&EphemeralKey>,
+) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "from": hex::encode(from),
+        "to": hex::encode(to),
+        "token_id": token_id,
+        "amount": amount,
+        "nonce": nonce
+    });
+    if !memo.is_empty() {
+        payload["memo"] = serde_json::Value::String(hex::encode(memo));
+    }
+    if let Some(ephemeral_key) = ephemeral_key {
+        payload["ephemeral_key"] = serde_json::Value::String(hex::encode(ephemeral_key));
+    }
+    payload
+}
+
 /// Signature, represented as a 64-byte array.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Signature(pub [u8; 64]);
@@ -95,6 +205,15 @@ pub enum SystemMsg {
         amount: Balance,
         /// The nonce for this transaction
         nonce: Nonce,
+        /// Optional payment reference, covered by the signature
+        #[serde(default)]
+        memo: Memo,
+        /// Ephemeral key of a stealth payment, covered by the signature
+        #[serde(default)]
+        ephemeral_key: Option<EphemeralKey>,
+        /// The scheme the sender signed with
+        #[serde(default)]
+        scheme: SignatureScheme,
         /// The signature of the sender
         signature: Signature,
     },
@@ -137,11 +256,605 @@ pub enum SystemMsg {
         token_id: TokenId,
         /// Token metadata (name, symbol, decimals, etc.)
         metadata: String,
+        /// Native tokens locked in escrow until the token is retired
+        collateral: Balance,
+        /// The nonce for this transaction
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Move public balance into the sender's confidential balance. The amount is public.
+    Shield {
+        /// The holder's address
+        from: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The amount to move
+        amount: u64,
+        /// The viewing key incoming confidential amounts are encrypted to
+        /// (only used when the confidential balance is first created)
+        viewing_key: [u8; 32],
+        /// The nonce for this transaction
+        nonce: Nonce,
+        /// The signature of the holder
+        signature: Signature,
+    },
+
+    /// Move confidential balance back to the sender's public balance. The amount is public.
+    Unshield {
+        /// The holder's address
+        from: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The amount to move
+        amount: u64,
+        /// Commitment to the remaining confidential balance
+        new_commitment: Commitment,
+        /// Range proof for the remaining balance
+        range_proof: Vec<u8>,
         /// The nonce for this transaction
         nonce: Nonce,
+        /// The signature of the holder
+        signature: Signature,
+    },
+
+    /// Transfer a hidden amount from one confidential balance to another.
+    ConfidentialTransfer {
+        /// The sender's address
+        from: Address,
+        /// The recipient's address
+        to: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// Commitment to the amount
+        amount_commitment: Commitment,
+        /// Commitment to the sender's remaining confidential balance
+        new_sender_commitment: Commitment,
+        /// Range proof for the amount and the remaining balance, in that order
+        range_proof: Vec<u8>,
+        /// The amount and its blinding factor, encrypted to the recipient
+        encrypted_opening: EncryptedOpening,
+        /// The nonce for this transaction
+        nonce: Nonce,
+        /// The signature of the sender
+        signature: Signature,
+    },
+
+    /// Register an unclaimed name.
+    RegisterName {
+        /// The address that will own the name
+        owner: Address,
+        /// The name, e.g. `alice.volt`
+        name: String,
+        /// The address the name resolves to
+        target: Address,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Hand a name over to a new owner.
+    TransferName {
+        /// The current owner
+        owner: Address,
+        /// The name
+        name: String,
+        /// The new owner
+        new_owner: Address,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Point a name at a new address.
+    SetNameTarget {
+        /// The owner
+        owner: Address,
+        /// The name
+        name: String,
+        /// The new address the name resolves to
+        target: Address,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Issue a new NFT collection (registers a new token ID).
+    IssueNftCollection {
+        /// The issuer's address
+        issuer: Address,
+        /// Collection metadata (name, symbol, etc.)
+        metadata: String,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Mint an item of an NFT collection (can only be sent by the collection issuer).
+    MintNft {
+        /// The issuer's address
+        issuer: Address,
+        /// The collection's token ID
+        collection_id: TokenId,
+        /// The item ID, unique within the collection
+        item_id: u64,
+        /// The first owner of the item
+        to: Address,
+        /// Where the item's metadata can be found
+        metadata_uri: String,
+        /// Hash of the metadata
+        metadata_hash: Option<[u8; 32]>,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Transfer an NFT (can only be sent by its owner).
+    TransferNft {
+        /// The owner's address
+        from: Address,
+        /// The recipient's address
+        to: Address,
+        /// The collection's token ID
+        collection_id: TokenId,
+        /// The item ID
+        item_id: u64,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Set who may hold a token (can only be sent by the token issuer).
+    SetCompliancePolicy {
+        /// The issuer's address
+        issuer: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The new policy
+        policy: CompliancePolicy,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Add an address to, or remove it from, a token's allow or deny list
+    /// (can only be sent by the token issuer).
+    UpdateComplianceList {
+        /// The issuer's address
+        issuer: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The list to change
+        list: ComplianceList,
+        /// The address to add or remove
+        address: Address,
+        /// Whether the address is added (true) or removed (false)
+        listed: bool,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Retire a token whose supply is fully burned, releasing its collateral
+    /// (can only be sent by the token issuer).
+    RetireToken {
+        /// The issuer's address
+        issuer: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Commit a distribution and escrow its total from the issuer's balance.
+    CreateAirdrop {
+        /// The issuer's address
+        issuer: Address,
+        /// The token to distribute
+        token_id: TokenId,
+        /// Root of the distribution tree
+        merkle_root: [u8; 32],
+        /// Number of entries in the distribution
+        entries: u64,
+        /// Sum of all entry amounts
+        total: Balance,
+        /// Time from which entries can no longer be claimed, in seconds since
+        /// the Unix epoch
+        expires_at: u64,
+        /// The issuer's nonce for the token
+        nonce: Nonce,
+        /// The signature of the issuer
+        signature: Signature,
+    },
+
+    /// Claim an airdrop entry (can only be sent by its recipient).
+    ClaimAirdrop {
+        /// The recipient of the entry
+        claimant: Address,
+        /// The airdrop ID
+        airdrop_id: AirdropId,
+        /// The entry's index in the distribution
+        index: u64,
+        /// The entry's amount
+        amount: Balance,
+        /// The entry's inclusion proof
+        proof: Vec<[u8; 32]>,
+        /// The claimant's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The claimant's native-token nonce
+        nonce: Nonce,
+        /// The signature of the claimant
+        signature: Signature,
+    },
+
+    /// Reclaim the unclaimed amount of an airdrop once it has expired (can
+    /// only be sent by its issuer).
+    ReclaimAirdrop {
+        /// The issuer's address
+        issuer: Address,
+        /// The airdrop ID
+        airdrop_id: AirdropId,
+        /// The issuer's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The issuer's native-token nonce
+        nonce: Nonce,
         /// The signature of the issuer
         signature: Signature,
     },
+
+    /// Propose a parameter change for a token (can only be sent by a holder
+    /// or the issuer).
+    CreateProposal {
+        /// The proposer's address
+        proposer: Address,
+        /// The governed token
+        token_id: TokenId,
+        /// The change to execute if the proposal passes
+        action: ProposalAction,
+        /// The proposer's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// How long votes are accepted from `timestamp`, in seconds
+        voting_period_secs: u64,
+        /// The proposer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the proposer
+        signature: Signature,
+    },
+
+    /// Vote on a proposal with the voter's balance at its snapshot.
+    Vote {
+        /// The voter's address
+        voter: Address,
+        /// The proposal ID
+        proposal_id: ProposalId,
+        /// Whether the vote is for the proposal
+        support: bool,
+        /// Proof of the voter's balance against the snapshot root
+        proof: AccountProof,
+        /// The voter's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The voter's native-token nonce
+        nonce: Nonce,
+        /// The signature of the voter
+        signature: Signature,
+    },
+
+    /// Authorize a payee to pull a fixed amount of a token once per period.
+    AuthorizeStandingOrder {
+        /// The payer's address
+        payer: Address,
+        /// The address allowed to pull
+        payee: Address,
+        /// The token paid
+        token_id: TokenId,
+        /// Amount that can be pulled each period
+        amount: Balance,
+        /// Period length in seconds
+        period_secs: u64,
+        /// Start of the first period, in seconds since the Unix epoch
+        start: u64,
+        /// Time from which no more pulls are allowed, in seconds since the Unix epoch
+        expiry: u64,
+        /// The payer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payer
+        signature: Signature,
+    },
+
+    /// Cancel a standing order (can only be sent by its payer).
+    CancelStandingOrder {
+        /// The payer's address
+        payer: Address,
+        /// The order ID
+        order_id: OrderId,
+        /// The payer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payer
+        signature: Signature,
+    },
+
+    /// Pull the current period's payment of a standing order (can only be
+    /// sent by its payee).
+    PullPayment {
+        /// The payee's address
+        payee: Address,
+        /// The order ID
+        order_id: OrderId,
+        /// The payee's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The payee's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payee
+        signature: Signature,
+    },
+
+    /// Open a payment channel, escrowing a deposit from the payer.
+    OpenChannel {
+        /// The payer's address
+        payer: Address,
+        /// The address paid through the channel
+        payee: Address,
+        /// The token paid
+        token_id: TokenId,
+        /// Amount escrowed from the payer
+        deposit: Balance,
+        /// Length of the dispute timeout in seconds
+        timeout_secs: u64,
+        /// The payer's nonce for the token
+        nonce: Nonce,
+        /// The signature of the payer
+        signature: Signature,
+    },
+
+    /// Close a payment channel with the payer's latest voucher (can only be
+    /// sent by its payee).
+    CloseChannel {
+        /// The payee's address
+        payee: Address,
+        /// The voucher signed by the payer
+        voucher: ChannelVoucher,
+        /// The payee's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payee
+        signature: Signature,
+    },
+
+    /// Start a payment channel's dispute timeout (can only be sent by its
+    /// payer).
+    StartChannelTimeout {
+        /// The payer's address
+        payer: Address,
+        /// The channel ID
+        channel_id: ChannelId,
+        /// The payer's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The payer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payer
+        signature: Signature,
+    },
+
+    /// Reclaim a payment channel's deposit once its dispute timeout has
+    /// ended (can only be sent by its payer).
+    ReclaimChannel {
+        /// The payer's address
+        payer: Address,
+        /// The channel ID
+        channel_id: ChannelId,
+        /// The payer's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The payer's native-token nonce
+        nonce: Nonce,
+        /// The signature of the payer
+        signature: Signature,
+    },
+
+    /// Authorize a session key to send transfers from the owner's account
+    /// within per-token spending limits.
+    AuthorizeSessionKey {
+        /// The owner's address
+        owner: Address,
+        /// The session key's address
+        session_key: Address,
+        /// Maximum amount of each token the key may spend per window
+        limits: Vec<SpendingLimit>,
+        /// Window length in seconds
+        window_secs: u64,
+        /// Start of the first window, in seconds since the Unix epoch
+        start: u64,
+        /// Time from which the key can no longer be used, in seconds since the Unix epoch
+        expiry: u64,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Revoke a session key (can only be sent by its owner).
+    RevokeSessionKey {
+        /// The owner's address
+        owner: Address,
+        /// The session key's address
+        session_key: Address,
+        /// The owner's native-token nonce
+        nonce: Nonce,
+        /// The signature of the owner
+        signature: Signature,
+    },
+
+    /// Transfer tokens from an owner's account, signed by one of its session
+    /// keys.
+    SessionTransfer {
+        /// The owner's address
+        owner: Address,
+        /// The session key's address
+        session_key: Address,
+        /// The recipient's address
+        to: Address,
+        /// The token ID
+        token_id: TokenId,
+        /// The amount to transfer
+        amount: Balance,
+        /// The session key's current time, in seconds since the Unix epoch
+        timestamp: u64,
+        /// The owner's nonce for the token
+        nonce: Nonce,
+        /// The signature of the session key
+        signature: Signature,
+    },
+}
+
+impl SystemMsg {
+    /// Returns the address that must sign this message.
+    pub fn signer(&self) -> Address {
+        match self {
+            SystemMsg::Transfer { from, .. }
+            | SystemMsg::Mint { from, .. }
+            | SystemMsg::Burn { from, .. }
+            | SystemMsg::Shield { from, .. }
+            | SystemMsg::Unshield { from, .. }
+            | SystemMsg::ConfidentialTransfer { from, .. } => *from,
+            SystemMsg::IssueToken { issuer, .. } => *issuer,
+            SystemMsg::RegisterName { owner, .. }
+            | SystemMsg::TransferName { owner, .. }
+            | SystemMsg::SetNameTarget { owner, .. } => *owner,
+            SystemMsg::IssueNftCollection { issuer, .. }
+            | SystemMsg::MintNft { issuer, .. }
+            | SystemMsg::SetCompliancePolicy { issuer, .. }
+            | SystemMsg::UpdateComplianceList { issuer, .. }
+            | SystemMsg::RetireToken { issuer, .. }
+            | SystemMsg::CreateAirdrop { issuer, .. }
+            | SystemMsg::ReclaimAirdrop { issuer, .. } => *issuer,
+            SystemMsg::ClaimAirdrop { claimant, .. } => *claimant,
+            SystemMsg::CreateProposal { proposer, .. } => *proposer,
+            SystemMsg::Vote { voter, .. } => *voter,
+            SystemMsg::AuthorizeStandingOrder { payer, .. } | SystemMsg::CancelStandingOrder { payer, .. } => *payer,
+            SystemMsg::PullPayment { payee, .. } | SystemMsg::CloseChannel { payee, .. } => *payee,
+            SystemMsg::OpenChannel { payer, .. }
+            | SystemMsg::StartChannelTimeout { payer, .. }
+            | SystemMsg::ReclaimChannel { payer, .. } => *payer,
+            SystemMsg::TransferNft { from, .. } => *from,
+            SystemMsg::AuthorizeSessionKey { owner, .. } | SystemMsg::RevokeSessionKey { owner, .. } => *owner,
+            SystemMsg::SessionTransfer { session_key, .. } => *session_key,
+        }
+    }
+
+    /// Returns the signature of this message.
+    pub fn signature(&self) -> &Signature {
+        match self {
+            SystemMsg::Transfer { signature, .. }
+            | SystemMsg::Mint { signature, .. }
+            | SystemMsg::Burn { signature, .. }
+            | SystemMsg::IssueToken { signature, .. }
+            | SystemMsg::Shield { signature, .. }
+            | SystemMsg::Unshield { signature, .. }
+            | SystemMsg::ConfidentialTransfer { signature, .. }
+            | SystemMsg::RegisterName { signature, .. }
+            | SystemMsg::TransferName { signature, .. }
+            | SystemMsg::SetNameTarget { signature, .. }
+            | SystemMsg::IssueNftCollection { signature, .. }
+            | SystemMsg::MintNft { signature, .. }
+            | SystemMsg::TransferNft { signature, .. }
+            | SystemMsg::SetCompliancePolicy { signature, .. }
+            | SystemMsg::UpdateComplianceList { signature, .. }
+            | SystemMsg::RetireToken { signature, .. }
+            | SystemMsg::CreateAirdrop { signature, .. }
+            | SystemMsg::ClaimAirdrop { signature, .. }
+            | SystemMsg::ReclaimAirdrop { signature, .. }
+            | SystemMsg::CreateProposal { signature, .. }
+            | SystemMsg::Vote { signature, .. }
+            | SystemMsg::AuthorizeStandingOrder { signature, .. }
+            | SystemMsg::CancelStandingOrder { signature, .. }
+            | SystemMsg::PullPayment { signature, .. }
+            | SystemMsg::OpenChannel { signature, .. }
+            | SystemMsg::CloseChannel { signature, .. }
+            | SystemMsg::StartChannelTimeout { signature, .. }
+            | SystemMsg::ReclaimChannel { signature, .. }
+            | SystemMsg::AuthorizeSessionKey { signature, .. }
+            | SystemMsg::RevokeSessionKey { signature, .. }
+            | SystemMsg::SessionTransfer { signature, .. } => signature,
+        }
+    }
+
+    /// Returns a mutable reference to the signature of this message.
+    pub fn signature_mut(&mut self) -> &mut Signature {
+        match self {
+            SystemMsg::Transfer { signature, .. }
+            | SystemMsg::Mint { signature, .. }
+            | SystemMsg::Burn { signature, .. }
+            | SystemMsg::IssueToken { signature, .. }
+            | SystemMsg::Shield { signature, .. }
+            | SystemMsg::Unshield { signature, .. }
+            | SystemMsg::ConfidentialTransfer { signature, .. }
+            | SystemMsg::RegisterName { signature, .. }
+            | SystemMsg::TransferName { signature, .. }
+            | SystemMsg::SetNameTarget { signature, .. }
+            | SystemMsg::IssueNftCollection { signature, .. }
+            | SystemMsg::MintNft { signature, .. }
+            | SystemMsg::TransferNft { signature, .. }
+            | SystemMsg::SetCompliancePolicy { signature, .. }
+            | SystemMsg::UpdateComplianceList { signature, .. }
+            | SystemMsg::RetireToken { signature, .. }
+            | SystemMsg::CreateAirdrop { signature, .. }
+            | SystemMsg::ClaimAirdrop { signature, .. }
+            | SystemMsg::ReclaimAirdrop { signature, .. }
+            | SystemMsg::CreateProposal { signature, .. }
+            | SystemMsg::Vote { signature, .. }
+            | SystemMsg::AuthorizeStandingOrder { signature, .. }
+            | SystemMsg::CancelStandingOrder { signature, .. }
+            | SystemMsg::PullPayment { signature, .. }
+            | SystemMsg::OpenChannel { signature, .. }
+            | SystemMsg::CloseChannel { signature, .. }
+            | SystemMsg::StartChannelTimeout { signature, .. }
+            | SystemMsg::ReclaimChannel { signature, .. }
+            | SystemMsg::AuthorizeSessionKey { signature, .. }
+            | SystemMsg::RevokeSessionKey { signature, .. }
+            | SystemMsg::SessionTransfer { signature, .. } => signature,
+        }
+    }
+
+    /// Returns the sender's claimed current time, for messages whose effect
+    /// depends on it.
+    pub fn claimed_time(&self) -> Option<u64> {
+        match self {
+            SystemMsg::ClaimAirdrop { timestamp, .. }
+            | SystemMsg::ReclaimAirdrop { timestamp, .. }
+            | SystemMsg::CreateProposal { timestamp, .. }
+            | SystemMsg::Vote { timestamp, .. }
+            | SystemMsg::PullPayment { timestamp, .. }
+            | SystemMsg::StartChannelTimeout { timestamp, .. }
+            | SystemMsg::ReclaimChannel { timestamp, .. }
+            | SystemMsg::SessionTransfer { timestamp, .. } => Some(*timestamp),
+            _ => None,
+        }
+    }
+
+    /// Builds the bytes the signer signs.
+    ///
+    /// Transfers sign [`transfer_payload`]; every other message signs its
+    /// bincode encoding with an all-zero signature.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, CoreError> {
+        if let SystemMsg::Transfer { from, to, token_id, amount, nonce, memo, ephemeral_key, .. } = self {
+            let payload = transfer_payload(from, to, *token_id, *amount, *nonce, memo, ephemeral_key.as_ref());
+            return serde_json::to_vec(&payload).map_err(|e| CoreError::SerializationError(e.to_string()));
+        }
+
+        let mut unsigned = self.clone();
+        *unsigned.signature_mut() = Signature([0u8; 64]);
+
+        bincode::serialize(&unsigned).map_err(|e| CoreError::SerializationError(e.to_string()))
+    }
 }
 
 /// Represents an account leaf in the Sparse Merkle Tree.
@@ -173,6 +886,11 @@ impl AccountLeaf {
         }
     }
 
+    /// Returns the signature scheme that controls this account.
+    pub fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::of_address(&self.addr)
+    }
+
     /// Computes the hash of this account leaf.
     pub fn hash(&self) -> [u8; 32] {
         let mut hasher = Sha256::new();
@@ -210,6 +928,85 @@ pub struct TokenInfo {
     pub total_supply: Balance,
 }
 
+/// A processed transfer, kept by nodes so recipients can reconcile payments.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxRecord {
+    /// The transaction hash
+    pub tx_hash: [u8; 32],
+    /// The sender's address
+    pub from: Address,
+    /// The recipient's address
+    pub to: Address,
+    /// The token ID
+    pub token_id: TokenId,
+    /// The amount transferred
+    pub amount: Balance,
+    /// The sender's nonce for this transaction
+    pub nonce: Nonce,
+    /// The payment reference attached by the sender
+    pub memo: Memo,
+    /// Ephemeral key of a stealth payment
+    pub ephemeral_key: Option<EphemeralKey>,
+    /// Unix timestamp (seconds) at which the node processed the transfer
+    pub timestamp: u64,
+}
+
+impl TxRecord {
+    /// Creates a record for a transfer processed now, deriving its transaction hash.
+    pub fn new(
+        from: Address,
+        to: Address,
+        token_id: TokenId,
+        amount: Balance,
+        nonce: Nonce,
+        memo: Memo,
+        signature: &Signature,
+    ) -> Self {
+        let tx_hash = Self::compute_hash(&from, &to, token_id, amount, nonce, &memo, signature);
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self { tx_hash, from, to, token_id, amount, nonce, memo, ephemeral_key: None, timestamp }
+    }
+
+    /// Computes the transaction hash of a transfer.
+    pub fn compute_hash(
+        from: &Address,
+        to: &Address,
+        token_id: TokenId,
+        amount: Balance,
+        nonce: Nonce,
+        memo: &[u8],
+        signature: &Signature,
+    ) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(from);
+        hasher.update(to);
+        hasher.update(token_id.to_be_bytes());
+        hasher.update(amount.to_be_bytes());
+        hasher.update(nonce.to_be_bytes());
+        hasher.update(memo);
+        hasher.update(signature.0);
+
+        let result = hasher.finalize();
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&result);
+        hash
+    }
+
+    /// Marks the record as a stealth payment with the given ephemeral key.
+    pub fn with_ephemeral_key(mut self, ephemeral_key: Option<EphemeralKey>) -> Self {
+        self.ephemeral_key = ephemeral_key;
+        self
+    }
+
+    /// Returns the memo as text, replacing invalid UTF-8 sequences.
+    pub fn memo_text(&self) -> String {
+        String::from_utf8_lossy(&self.memo).into_owned()
+    }
+}
+
 impl fmt::Display for AccountLeaf {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -233,11 +1030,11 @@ impl fmt::Display for TokenInfo {
 impl fmt::Display for SystemMsg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            SystemMsg::Transfer { from, to, token_id, amount, nonce, .. } => {
+            SystemMsg::Transfer { from, to, token_id, amount, nonce, memo, .. } => {
                 write!(
                     f,
-                    "Transfer {{ from: {:?}, to: {:?}, token_id: {}, amount: {}, nonce: {} }}",
-                    from, to, token_id, amount, nonce
+                    "Transfer {{ from: {:?}, to: {:?}, token_id: {}, amount: {}, nonce: {}, memo: {} bytes }}",
+                    from, to, token_id, amount, nonce, memo.len()
                 )
             }
             SystemMsg::Mint { from, to, token_id, amount, nonce, .. } => {
@@ -254,11 +1051,205 @@ impl fmt::Display for SystemMsg {
                     from, token_id, amount, nonce
                 )
             }
-            SystemMsg::IssueToken { issuer, token_id, metadata, nonce, .. } => {
+            SystemMsg::IssueToken { issuer, token_id, metadata, collateral, nonce, .. } => {
                 write!(
                     f,
-                    "IssueToken {{ issuer: {:?}, token_id: {}, metadata: {}, nonce: {} }}",
-                    issuer, token_id, metadata, nonce
+                    "IssueToken {{ issuer: {:?}, token_id: {}, metadata: {}, collateral: {}, nonce: {} }}",
+                    issuer, token_id, metadata, collateral, nonce
+                )
+            }
+            SystemMsg::Shield { from, token_id, amount, nonce, .. } => {
+                write!(
+                    f,
+                    "Shield {{ from: {:?}, token_id: {}, amount: {}, nonce: {} }}",
+                    from, token_id, amount, nonce
+                )
+            }
+            SystemMsg::Unshield { from, token_id, amount, nonce, .. } => {
+                write!(
+                    f,
+                    "Unshield {{ from: {:?}, token_id: {}, amount: {}, nonce: {} }}",
+                    from, token_id, amount, nonce
+                )
+            }
+            SystemMsg::ConfidentialTransfer { from, to, token_id, nonce, .. } => {
+                write!(
+                    f,
+                    "ConfidentialTransfer {{ from: {:?}, to: {:?}, token_id: {}, nonce: {} }}",
+                    from, to, token_id, nonce
+                )
+            }
+            SystemMsg::RegisterName { owner, name, target, nonce, .. } => {
+                write!(
+                    f,
+                    "RegisterName {{ owner: {:?}, name: {}, target: {:?}, nonce: {} }}",
+                    owner, name, target, nonce
+                )
+            }
+            SystemMsg::TransferName { owner, name, new_owner, nonce, .. } => {
+                write!(
+                    f,
+                    "TransferName {{ owner: {:?}, name: {}, new_owner: {:?}, nonce: {} }}",
+                    owner, name, new_owner, nonce
+                )
+            }
+            SystemMsg::SetNameTarget { owner, name, target, nonce, .. } => {
+                write!(
+                    f,
+                    "SetNameTarget {{ owner: {:?}, name: {}, target: {:?}, nonce: {} }}",
+                    owner, name, target, nonce
+                )
+            }
+            SystemMsg::IssueNftCollection { issuer, metadata, nonce, .. } => {
+                write!(
+                    f,
+                    "IssueNftCollection {{ issuer: {:?}, metadata: {}, nonce: {} }}",
+                    issuer, metadata, nonce
+                )
+            }
+            SystemMsg::MintNft { issuer, collection_id, item_id, to, nonce, .. } => {
+                write!(
+                    f,
+                    "MintNft {{ issuer: {:?}, collection_id: {}, item_id: {}, to: {:?}, nonce: {} }}",
+                    issuer, collection_id, item_id, to, nonce
+                )
+            }
+            SystemMsg::TransferNft { from, to, collection_id, item_id, nonce, .. } => {
+                write!(
+                    f,
+                    "TransferNft {{ from: {:?}, to: {:?}, collection_id: {}, item_id: {}, nonce: {} }}",
+                    from, to, collection_id, item_id, nonce
+                )
+            }
+            SystemMsg::SetCompliancePolicy { issuer, token_id, policy, nonce, .. } => {
+                write!(
+                    f,
+                    "SetCompliancePolicy {{ issuer: {:?}, token_id: {}, policy: {}, nonce: {} }}",
+                    issuer, token_id, policy, nonce
+                )
+            }
+            SystemMsg::UpdateComplianceList { issuer, token_id, list, address, listed, nonce, .. } => {
+                write!(
+                    f,
+                    "UpdateComplianceList {{ issuer: {:?}, token_id: {}, list: {}, address: {:?}, listed: {}, nonce: {} }}",
+                    issuer, token_id, list, address, listed, nonce
+                )
+            }
+            SystemMsg::RetireToken { issuer, token_id, nonce, .. } => {
+                write!(
+                    f,
+                    "RetireToken {{ issuer: {:?}, token_id: {}, nonce: {} }}",
+                    issuer, token_id, nonce
+                )
+            }
+            SystemMsg::CreateAirdrop { issuer, token_id, entries, total, expires_at, nonce, .. } => {
+                write!(
+                    f,
+                    "CreateAirdrop {{ issuer: {:?}, token_id: {}, entries: {}, total: {}, expires_at: {}, nonce: {} }}",
+                    issuer, token_id, entries, total, expires_at, nonce
+                )
+            }
+            SystemMsg::ClaimAirdrop { claimant, airdrop_id, index, amount, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "ClaimAirdrop {{ claimant: {:?}, airdrop_id: {}, index: {}, amount: {}, timestamp: {}, nonce: {} }}",
+                    claimant, hex::encode(airdrop_id), index, amount, timestamp, nonce
+                )
+            }
+            SystemMsg::ReclaimAirdrop { issuer, airdrop_id, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "ReclaimAirdrop {{ issuer: {:?}, airdrop_id: {}, timestamp: {}, nonce: {} }}",
+                    issuer, hex::encode(airdrop_id), timestamp, nonce
+                )
+            }
+            SystemMsg::CreateProposal { proposer, token_id, action, timestamp, voting_period_secs, nonce, .. } => {
+                write!(
+                    f,
+                    "CreateProposal {{ proposer: {:?}, token_id: {}, action: {}, timestamp: {}, voting_period_secs: {}, nonce: {} }}",
+                    proposer, token_id, action, timestamp, voting_period_secs, nonce
+                )
+            }
+            SystemMsg::Vote { voter, proposal_id, support, proof, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "Vote {{ voter: {:?}, proposal_id: {}, support: {}, weight: {}, timestamp: {}, nonce: {} }}",
+                    voter, hex::encode(proposal_id), support, proof.balance(), timestamp, nonce
+                )
+            }
+            SystemMsg::AuthorizeStandingOrder {
+                payer, payee, token_id, amount, period_secs, start, expiry, nonce, ..
+            } => {
+                write!(
+                    f,
+                    "AuthorizeStandingOrder {{ payer: {:?}, payee: {:?}, token_id: {}, amount: {}, period_secs: {}, start: {}, expiry: {}, nonce: {} }}",
+                    payer, payee, token_id, amount, period_secs, start, expiry, nonce
+                )
+            }
+            SystemMsg::CancelStandingOrder { payer, order_id, nonce, .. } => {
+                write!(
+                    f,
+                    "CancelStandingOrder {{ payer: {:?}, order_id: {}, nonce: {} }}",
+                    payer, hex::encode(order_id), nonce
+                )
+            }
+            SystemMsg::PullPayment { payee, order_id, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "PullPayment {{ payee: {:?}, order_id: {}, timestamp: {}, nonce: {} }}",
+                    payee, hex::encode(order_id), timestamp, nonce
+                )
+            }
+            SystemMsg::OpenChannel { payer, payee, token_id, deposit, timeout_secs, nonce, .. } => {
+                write!(
+                    f,
+                    "OpenChannel {{ payer: {:?}, payee: {:?}, token_id: {}, deposit: {}, timeout_secs: {}, nonce: {} }}",
+                    payer, payee, token_id, deposit, timeout_secs, nonce
+                )
+            }
+            SystemMsg::CloseChannel { payee, voucher, nonce, .. } => {
+                write!(
+                    f,
+                    "CloseChannel {{ payee: {:?}, channel_id: {}, amount: {}, nonce: {} }}",
+                    payee, hex::encode(voucher.channel_id), voucher.amount, nonce
+                )
+            }
+            SystemMsg::StartChannelTimeout { payer, channel_id, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "StartChannelTimeout {{ payer: {:?}, channel_id: {}, timestamp: {}, nonce: {} }}",
+                    payer, hex::encode(channel_id), timestamp, nonce
+                )
+            }
+            SystemMsg::ReclaimChannel { payer, channel_id, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "ReclaimChannel {{ payer: {:?}, channel_id: {}, timestamp: {}, nonce: {} }}",
+                    payer, hex::encode(channel_id), timestamp, nonce
+                )
+            }
+            SystemMsg::AuthorizeSessionKey {
+                owner, session_key, limits, window_secs, start, expiry, nonce, ..
+            } => {
+                let limits: Vec<String> = limits.iter().map(|limit| limit.to_string()).collect();
+                write!(
+                    f,
+                    "AuthorizeSessionKey {{ owner: {:?}, session_key: {:?}, limits: [{}], window_secs: {}, start: {}, expiry: {}, nonce: {} }}",
+                    owner, session_key, limits.join(", "), window_secs, start, expiry, nonce
+                )
+            }
+            SystemMsg::RevokeSessionKey { owner, session_key, nonce, .. } => {
+                write!(
+                    f,
+                    "RevokeSessionKey {{ owner: {:?}, session_key: {:?}, nonce: {} }}",
+                    owner, session_key, nonce
+                )
+            }
+            SystemMsg::SessionTransfer { owner, session_key, to, token_id, amount, timestamp, nonce, .. } => {
+                write!(
+                    f,
+                    "SessionTransfer {{ owner: {:?}, session_key: {:?}, to: {:?}, token_id: {}, amount: {}, timestamp: {}, nonce: {} }}",
+                    owner, session_key, to, token_id, amount, timestamp, nonce
                 )
             }
         }
@@ -308,4 +1299,48 @@ mod tests {
         assert_eq!(leaf.addr, addr);
         assert_eq!(leaf.token_id, token_id);
     }
+
+    #[test]
+    fn test_memo_validation_and_payload() {
+        assert!(validate_memo(&[0u8; MAX_MEMO_LEN]).is_ok());
+        assert!(matches!(
+            validate_memo(&[0u8; MAX_MEMO_LEN + 1]),
+            Err(CoreError::MemoTooLong { .. })
+        ));
+
+        let from = [1u8; 32];
+        let to = [2u8; 32];
+
+        // An empty memo leaves the signing payload unchanged
+        let plain = transfer_payload(&from, &to, 0, 10, 0, &[], None);
+        assert!(plain.get("memo").is_none());
+
+        // A memo is part of the signed payload
+        let with_memo = transfer_payload(&from, &to, 0, 10, 0, b"INV-42", None);
+        assert_eq!(with_memo["memo"], "494e562d3432");
+        assert!(with_memo.get("ephemeral_key").is_none());
+
+        // So is the ephemeral key of a stealth payment
+        let stealth = transfer_payload(&from, &to, 0, 10, 0, &[], Some(&[9u8; 32]));
+        assert_eq!(stealth["ephemeral_key"], hex::encode([9u8; 32]));
+        assert_ne!(
+            TxRecord::compute_hash(&from, &to, 0, 10, 0, b"", &Signature([0u8; 64])),
+            TxRecord::compute_hash(&from, &to, 0, 10, 0, b"INV-42", &Signature([0u8; 64]))
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_address_derivation() {
+        let eth_address = [0xabu8; 20];
+        let addr = address_from_eth(&eth_address);
+
+        assert_eq!(SignatureScheme::of_address(&addr), SignatureScheme::Secp256k1);
+        assert_eq!(eth_address_of(&addr), Some(eth_address));
+        assert_eq!(AccountLeaf::new_empty(addr, 0).scheme(), SignatureScheme::Secp256k1);
+
+        // Ordinary ed25519 addresses are untouched
+        let ed_addr = [7u8; 32];
+        assert_eq!(SignatureScheme::of_address(&ed_addr), SignatureScheme::Ed25519);
+        assert_eq!(eth_address_of(&ed_addr), None);
+    }
 }
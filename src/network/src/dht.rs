@@ -1,6 +1,7 @@
 //! DHT implementation for storing and retrieving proofs.
 
 use crate::errors::NetworkError;
+use crate::record_store::RocksRecordStore;
 use crate::types::{ProofRequest, ProofResponse};
 use core::{proofs::Proof, types::Address};
 use futures::channel::oneshot;
@@ -59,7 +60,7 @@ impl DHTManager {
     pub fn handle_event(
         &self,
         event: KademliaEvent,
-        kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+        kademlia: &mut Kademlia<RocksRecordStore>,
     ) {
         match event {
             KademliaEvent::OutboundQueryProgressed { id, result, .. } => {
@@ -123,7 +124,7 @@ impl DHTManager {
     pub fn handle_event_sync(
         &self,
         event: KademliaEvent,
-        kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+        kademlia: &mut Kademlia<RocksRecordStore>,
     ) {
         // This is already synchronous, so we can just call the regular handle_event
         self.handle_event(event, kademlia);
@@ -132,7 +133,7 @@ impl DHTManager {
     /// Puts a proof in the DHT.
     pub async fn put_proof(
         &self,
-        kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+        kademlia: &mut Kademlia<RocksRecordStore>,
         address: &Address,
         root: &[u8; 32],
         proof: &Proof,
@@ -145,7 +146,7 @@ impl DHTManager {
         let value = bincode::serialize(proof)
             .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
 
-        // Create a record
+        // Create a record, which expires after the record TTL of the store
         let record = Record {
             key,
             value,
@@ -189,7 +190,7 @@ impl DHTManager {
     /// Gets a proof from the DHT.
     pub async fn get_proof(
         &self,
-        kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+        kademlia: &mut Kademlia<RocksRecordStore>,
         address: &Address,
         root: &[u8; 32],
     ) -> Result<Proof, NetworkError> {
@@ -241,7 +242,7 @@ fn create_proof_key(address: &Address, root: &[u8; 32]) -> Vec<u8> {
 /// This is a convenience function that wraps the DHT manager's put_proof method.
 pub async fn put_proof(
     dht_manager: &DHTManager,
-    kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+    kademlia: &mut Kademlia<RocksRecordStore>,
     address: &Address,
     root: &[u8; 32],
     proof: &Proof,
@@ -254,7 +255,7 @@ pub async fn put_proof(
 /// This is a convenience function that wraps the DHT manager's get_proof method.
 pub async fn get_proof(
     dht_manager: &DHTManager,
-    kademlia: &mut Kademlia<libp2p::kad::store::MemoryStore>,
+    kademlia: &mut Kademlia<RocksRecordStore>,
     address: &Address,
     root: &[u8; 32],
 ) -> Result<Proof, NetworkError> {
@@ -32,6 +32,9 @@ pub enum NetworkError {
 
     /// Error when a message is invalid.
     InvalidMessage(String),
+
+    /// Error when a message has a version this node does not support.
+    UnsupportedVersion(u32),
 }
 
 impl fmt::Display for NetworkError {
@@ -46,6 +49,7 @@ impl fmt::Display for NetworkError {
             NetworkError::Timeout(msg) => write!(f, "Timeout waiting for {}", msg),
             NetworkError::PeerNotFound(msg) => write!(f, "Peer not found: {}", msg),
             NetworkError::InvalidMessage(msg) => write!(f, "Invalid message: {}", msg),
+            NetworkError::UnsupportedVersion(version) => write!(f, "Unsupported message version: {}", version),
         }
     }
 }
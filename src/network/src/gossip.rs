@@ -1,10 +1,21 @@
 //! Gossip implementation for broadcasting state updates.
+//!
+//! Every message is wrapped in a versioned [`GossipEnvelope`] and published
+//! on the topic of its kind, so nodes can subscribe to the kinds they handle
+//! and peers are scored per topic.
+//!
+//! Messages are validated manually: gossipsub only forwards a message once
+//! the node reports it as accepted, and peers that send rejected messages
+//! lose peer score until they are graylisted.
 
 use crate::errors::NetworkError;
-use crate::types::UpdateMsg;
+use crate::peer_guard::PeerGuard;
+use crate::transport::NetworkEvent;
+use crate::types::{GossipEnvelope, GossipMessage, UpdateMsg};
 use libp2p::gossipsub::{
     Behaviour as Gossipsub, ConfigBuilder as GossipsubConfigBuilder, Event as GossipsubEvent, IdentTopic,
-    MessageAuthenticity, MessageId, ValidationMode,
+    MessageAcceptance, MessageAuthenticity, MessageId, PeerScoreParams, PeerScoreThresholds, TopicScoreParams,
+    ValidationMode,
 };
 use libp2p::identity::Keypair;
 use libp2p::gossipsub;
@@ -12,24 +23,92 @@ use libp2p::PeerId;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
-/// The topic for state updates.
-pub const STATE_UPDATES_TOPIC: &str = "state_updates";
+/// The topic for token transfers.
+pub const TRANSFERS_TOPIC: &str = "stateless-token/transfers";
+
+/// The topic for mints.
+pub const MINTS_TOPIC: &str = "stateless-token/mints";
+
+/// The topic for burns.
+pub const BURNS_TOPIC: &str = "stateless-token/burns";
+
+/// The topic for token issuance.
+pub const TOKEN_ISSUANCE_TOPIC: &str = "stateless-token/token-issuance";
+
+/// The topic for state root attestations.
+pub const ATTESTATIONS_TOPIC: &str = "stateless-token/attestations";
+
+/// The topic for other signed system messages.
+pub const SYSTEM_TOPIC: &str = "stateless-token/system";
+
+/// All topics the node subscribes to.
+pub const TOPICS: [&str; 6] = [
+    TRANSFERS_TOPIC,
+    MINTS_TOPIC,
+    BURNS_TOPIC,
+    TOKEN_ISSUANCE_TOPIC,
+    ATTESTATIONS_TOPIC,
+    SYSTEM_TOPIC,
+];
+
+/// The peer score below which a peer's messages are no longer gossiped to.
+const GOSSIP_THRESHOLD: f64 = -10.0;
+
+/// The peer score below which a peer's published messages are not accepted.
+const PUBLISH_THRESHOLD: f64 = -50.0;
+
+/// The peer score below which all of a peer's messages are dropped.
+const GRAYLIST_THRESHOLD: f64 = -80.0;
+
+/// The score weight of each invalid message, squared by their count, so two
+/// rejected messages graylist a peer: -25 x 2^2 = -100.
+const INVALID_MESSAGE_WEIGHT: f64 = -25.0;
+
+/// How much of the invalid message count remains after each decay interval
+/// of one second, which halves it in about four minutes.
+const INVALID_MESSAGE_DECAY: f64 = 0.997;
+
+/// Mesh parameters of gossipsub.
+#[derive(Clone, Debug)]
+pub struct GossipConfig {
+    /// The minimum number of peers in the mesh before more are added
+    pub mesh_n_low: usize,
+    /// The target number of peers in the mesh
+    pub mesh_n: usize,
+    /// The maximum number of peers in the mesh before some are pruned
+    pub mesh_n_high: usize,
+    /// The number of peers outside the mesh to gossip to
+    pub gossip_lazy: usize,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            mesh_n_low: 0,
+            mesh_n: 1,
+            mesh_n_high: 2,
+            gossip_lazy: 1,
+        }
+    }
+}
 
 /// Creates a new Gossipsub instance.
 pub fn new_gossipsub(
     local_key: &Keypair,
     peer_id: &PeerId,
+    config: &GossipConfig,
 ) -> Result<Gossipsub, NetworkError> {
     // Create a Gossipsub configuration
     let gossipsub_config = GossipsubConfigBuilder::default()
         .heartbeat_interval(std::time::Duration::from_secs(10))
         .validation_mode(ValidationMode::Strict)
+        .validate_messages()  // Forward messages only once the node accepts them
         .message_id_fn(message_id_fn)
-        .mesh_n_low(0)        // Allow publishing with 0 peers (for testing)
-        .mesh_n(1)            // Target just 1 peer in mesh (minimum)
+        .mesh_n_low(config.mesh_n_low)
+        .mesh_n(config.mesh_n)
         .mesh_outbound_min(0) // Don't require any outbound peers
-        .mesh_n_high(2)       // Upper bound for mesh peers
-        .gossip_lazy(1)       // Gossip to at least 1 peer
+        .mesh_n_high(config.mesh_n_high)
+        .gossip_lazy(config.gossip_lazy)
         .history_length(5)    // Keep last 5 messages
         .history_gossip(1)    // Gossip to 1 peer
         .build()
@@ -44,56 +123,127 @@ pub fn new_gossipsub(
     )
     .map_err(|e| NetworkError::GossipError(e.to_string()))?;
 
-    // Subscribe to the state updates topic
-    let topic = IdentTopic::new(STATE_UPDATES_TOPIC);
-    match gossipsub.subscribe(&topic) {
-        Ok(_) => {
-            log::info!("Successfully subscribed to topic: {}", topic);
-        },
-        Err(e) => {
-            log::error!("Failed to subscribe to topic {}: {}", topic, e);
-            return Err(NetworkError::GossipError(e.to_string()));
+    // Score peers so that those sending invalid messages get graylisted
+    let topics: Vec<IdentTopic> = TOPICS.iter().map(|topic| IdentTopic::new(*topic)).collect();
+    let (params, thresholds) = peer_score_params(&topics);
+    gossipsub
+        .with_peer_score(params, thresholds)
+        .map_err(NetworkError::GossipError)?;
+
+    // Subscribe to the topic of every message kind
+    for topic in &topics {
+        match gossipsub.subscribe(topic) {
+            Ok(_) => {
+                log::info!("Successfully subscribed to topic: {}", topic);
+            },
+            Err(e) => {
+                log::error!("Failed to subscribe to topic {}: {}", topic, e);
+                return Err(NetworkError::GossipError(e.to_string()));
+            }
         }
     }
 
     Ok(gossipsub)
 }
 
+/// Returns the peer score parameters and thresholds.
+///
+/// Peers are only penalized for invalid messages. Mesh delivery penalties
+/// are disabled, since a quiet network would otherwise penalize honest peers.
+fn peer_score_params(topics: &[IdentTopic]) -> (PeerScoreParams, PeerScoreThresholds) {
+    let mut params = PeerScoreParams::default();
+    for topic in topics {
+        let topic_params = TopicScoreParams {
+            topic_weight: 1.0,
+            mesh_message_deliveries_weight: 0.0,
+            mesh_failure_penalty_weight: 0.0,
+            invalid_message_deliveries_weight: INVALID_MESSAGE_WEIGHT,
+            invalid_message_deliveries_decay: INVALID_MESSAGE_DECAY,
+            ..TopicScoreParams::default()
+        };
+        params.topics.insert(topic.hash(), topic_params);
+    }
+
+    let thresholds = PeerScoreThresholds {
+        gossip_threshold: GOSSIP_THRESHOLD,
+        publish_threshold: PUBLISH_THRESHOLD,
+        graylist_threshold: GRAYLIST_THRESHOLD,
+        ..PeerScoreThresholds::default()
+    };
+
+    (params, thresholds)
+}
+
+/// Returns the topic that messages of this kind are published on.
+pub fn message_topic(message: &GossipMessage) -> &'static str {
+    match message {
+        GossipMessage::Transfer(_) => TRANSFERS_TOPIC,
+        GossipMessage::Mint(_) => MINTS_TOPIC,
+        GossipMessage::Burn(_) => BURNS_TOPIC,
+        GossipMessage::IssueToken(_) => TOKEN_ISSUANCE_TOPIC,
+        GossipMessage::Attestation(_) => ATTESTATIONS_TOPIC,
+        GossipMessage::System(_) => SYSTEM_TOPIC,
+    }
+}
+
+/// Publishes a message in the latest envelope version on its topic.
+pub fn publish_message(
+    gossipsub: &mut Gossipsub,
+    message: GossipMessage,
+) -> Result<MessageId, NetworkError> {
+    let topic = IdentTopic::new(message_topic(&message));
+    let data = GossipEnvelope::new(message).encode()?;
+
+    gossipsub.publish(topic, data).map_err(|e| {
+        log::error!("Failed to publish gossip message: {}", e);
+        NetworkError::GossipError(e.to_string())
+    })
+}
+
 /// Broadcasts an update message to the network.
 pub async fn broadcast_update(
     gossipsub: &mut Gossipsub,
     update: &UpdateMsg,
 ) -> Result<(), NetworkError> {
     log::info!("Broadcasting update message: {:?}", update);
-    
-    // Serialize the update message
-    let data = bincode::serialize(update)
-        .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
-
-    // Create a topic
-    let topic = IdentTopic::new(STATE_UPDATES_TOPIC);
-    log::info!("Using topic: {}", topic);
-
-    // Log mesh information
-    log::info!("Attempting to publish to topic: {}", topic);
-
-    // Publish the message
-    match gossipsub.publish(topic, data) {
-        Ok(_) => {
-            log::info!("Successfully published update message to gossip network");
-            Ok(())
-        },
-        Err(e) => {
-            log::error!("Failed to publish update message: {}", e);
-            Err(NetworkError::GossipError(e.to_string()))
-        }
+
+    publish_message(gossipsub, GossipMessage::Transfer(update.clone()))?;
+    log::info!("Successfully published update message to gossip network");
+    Ok(())
+}
+
+/// Reports the validation result of a message to gossipsub, counting
+/// rejected messages towards banning their source.
+pub fn report_validation(
+    gossipsub: &mut Gossipsub,
+    guard: &mut PeerGuard,
+    message_id: &MessageId,
+    source: &PeerId,
+    acceptance: MessageAcceptance,
+) {
+    let rejected = matches!(acceptance, MessageAcceptance::Reject);
+    if let Err(e) = gossipsub.report_message_validation_result(message_id, source, acceptance) {
+        log::debug!("Failed to report validation of message {}: {}", message_id, e);
+    }
+
+    if rejected && guard.record_invalid_message(*source) {
+        gossipsub.remove_explicit_peer(source);
     }
 }
 
 /// Handles a Gossipsub event.
+///
+/// Decoded messages are returned for the node to validate and report with
+/// [`report_validation`]. Other messages are reported here: undecodable ones
+/// and those published on the wrong topic for their kind are rejected.
+/// Messages on unknown topics are ignored, and so are those of unknown
+/// envelope versions, which peers running a newer release send during a
+/// rolling upgrade.
 pub fn handle_gossipsub_event(
     event: GossipsubEvent,
-) -> Result<Option<UpdateMsg>, NetworkError> {
+    gossipsub: &mut Gossipsub,
+    guard: &mut PeerGuard,
+) -> Option<NetworkEvent> {
     match event {
         GossipsubEvent::Message {
             propagation_source,
@@ -102,43 +252,72 @@ pub fn handle_gossipsub_event(
         } => {
             log::info!("Received gossip message from {}, id: {}, topic: {}",
                       propagation_source, message_id, message.topic);
-            
-            // Check if the message is on the state updates topic
-            if message.topic.as_str() == STATE_UPDATES_TOPIC {
-                log::info!("Message is on state updates topic");
-                
-                // Deserialize the message
-                match bincode::deserialize::<UpdateMsg>(&message.data) {
-                    Ok(update) => {
+
+            let topic = message.topic.as_str();
+            let acceptance = if !TOPICS.contains(&topic) {
+                log::debug!("Ignoring message on unknown topic {}", topic);
+                MessageAcceptance::Ignore
+            } else {
+                match GossipEnvelope::decode(&message.data).map(GossipEnvelope::into_message) {
+                    Ok(message) if message_topic(&message) != topic => {
+                        log::warn!("Rejecting message from {} published on the wrong topic {}", propagation_source, topic);
+                        MessageAcceptance::Reject
+                    }
+                    Ok(GossipMessage::Transfer(update)) => {
                         log::info!("Successfully deserialized update message: {:?}", update);
-                        Ok(Some(update))
-                    },
+                        return Some(NetworkEvent::UpdateReceived(update, message_id, propagation_source));
+                    }
+                    Ok(GossipMessage::System(message)) => {
+                        log::info!("Successfully deserialized system message: {}", message);
+                        return Some(NetworkEvent::SystemMessageReceived(message, message_id, propagation_source));
+                    }
+                    Ok(GossipMessage::Mint(mint)) => {
+                        return Some(NetworkEvent::MintReceived(mint, message_id, propagation_source));
+                    }
+                    Ok(GossipMessage::Burn(burn)) => {
+                        return Some(NetworkEvent::BurnReceived(burn, message_id, propagation_source));
+                    }
+                    Ok(GossipMessage::IssueToken(issue)) => {
+                        return Some(NetworkEvent::IssueTokenReceived(issue, message_id, propagation_source));
+                    }
+                    Ok(GossipMessage::Attestation(attestation)) => {
+                        return Some(NetworkEvent::AttestationReceived(attestation, message_id, propagation_source));
+                    }
+                    Err(NetworkError::UnsupportedVersion(version)) => {
+                        log::info!(
+                            "Ignoring message from {} with envelope version {}, newer than this node's {}",
+                            propagation_source,
+                            version,
+                            GossipEnvelope::LATEST_VERSION
+                        );
+                        MessageAcceptance::Ignore
+                    }
                     Err(e) => {
-                        log::error!("Failed to deserialize update message: {}", e);
-                        Err(NetworkError::SerializationError(e.to_string()))
+                        log::warn!("Rejecting message from {}: {}", propagation_source, e);
+                        MessageAcceptance::Reject
                     }
                 }
-            } else {
-                log::debug!("Message is not on state updates topic");
-                Ok(None)
-            }
+            };
+
+            report_validation(gossipsub, guard, &message_id, &propagation_source, acceptance);
+            None
         }
         GossipsubEvent::Subscribed { peer_id, topic } => {
             log::info!("Peer {} subscribed to topic {}", peer_id, topic);
-            Ok(None)
+            None
         }
         GossipsubEvent::Unsubscribed { peer_id, topic } => {
             log::info!("Peer {} unsubscribed from topic {}", peer_id, topic);
-            Ok(None)
+            None
         }
         GossipsubEvent::GossipsubNotSupported { peer_id } => {
             log::warn!("Peer {} does not support gossipsub", peer_id);
-            Ok(None)
+            None
         }
         #[allow(unreachable_patterns)]
         _ => {
             log::debug!("Received other gossipsub event");
-            Ok(None)
+            None
         },
     }
 }
@@ -153,19 +332,165 @@ pub fn message_id_fn(message: &gossipsub::Message) -> MessageId {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::peer_guard::{ConnectionConfig, BAN_LIST_FILE};
+    use crate::types::AttestationMsg;
     use core::{proofs::Proof, types::Address};
     use libp2p::identity::Keypair;
     use rand::Rng;
+    use tempfile::tempdir;
 
     #[test]
     fn test_gossipsub_creation() {
         let local_key = Keypair::generate_ed25519();
         let peer_id = PeerId::from(local_key.public());
 
-        let gossipsub = new_gossipsub(&local_key, &peer_id);
+        let gossipsub = new_gossipsub(&local_key, &peer_id, &GossipConfig::default());
         assert!(gossipsub.is_ok());
     }
 
+    #[test]
+    fn test_undecodable_message_is_not_returned() {
+        let local_key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from(local_key.public());
+        let mut gossipsub = new_gossipsub(&local_key, &peer_id, &GossipConfig::default()).unwrap();
+        let dir = tempdir().unwrap();
+        let mut guard = PeerGuard::new(ConnectionConfig::default(), &dir.path().join(BAN_LIST_FILE)).unwrap();
+
+        let message = gossipsub::Message {
+            source: None,
+            data: vec![1, 2, 3],
+            sequence_number: None,
+            topic: IdentTopic::new(TRANSFERS_TOPIC).hash(),
+        };
+        let event = GossipsubEvent::Message {
+            propagation_source: PeerId::random(),
+            message_id: message_id_fn(&message),
+            message,
+        };
+
+        assert!(handle_gossipsub_event(event, &mut gossipsub, &mut guard).is_none());
+    }
+
+    fn attestation() -> GossipMessage {
+        GossipMessage::Attestation(AttestationMsg {
+            attester: [1u8; 32],
+            root: [2u8; 32],
+            timestamp: 1,
+            signature: core::types::Signature([3u8; 64]),
+        })
+    }
+
+    #[test]
+    fn test_envelope_roundtrip() {
+        let bytes = GossipEnvelope::new(attestation()).encode().unwrap();
+        let envelope = GossipEnvelope::decode(&bytes).unwrap();
+
+        assert_eq!(envelope.version(), GossipEnvelope::LATEST_VERSION);
+        assert_eq!(message_topic(envelope.message()), ATTESTATIONS_TOPIC);
+        match envelope.into_message() {
+            GossipMessage::Attestation(attestation) => assert_eq!(attestation.root, [2u8; 32]),
+            other => panic!("Unexpected message: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_envelope_version_is_rejected() {
+        let mut bytes = GossipEnvelope::new(attestation()).encode().unwrap();
+        bytes[..4].copy_from_slice(&1u32.to_le_bytes());
+
+        assert!(matches!(
+            GossipEnvelope::decode(&bytes),
+            Err(NetworkError::UnsupportedVersion(2))
+        ));
+        assert!(GossipEnvelope::decode(&[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_unknown_envelope_version_does_not_ban_the_sender() {
+        let local_key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from(local_key.public());
+        let mut gossipsub = new_gossipsub(&local_key, &peer_id, &GossipConfig::default()).unwrap();
+        let dir = tempdir().unwrap();
+        let config = ConnectionConfig::default();
+        let ban_threshold = config.ban_threshold;
+        let mut guard = PeerGuard::new(config, &dir.path().join(BAN_LIST_FILE)).unwrap();
+
+        let mut newer = GossipEnvelope::new(attestation()).encode().unwrap();
+        newer[..4].copy_from_slice(&1u32.to_le_bytes());
+
+        // A peer on a newer release is ignored, but a peer sending garbage
+        // is banned
+        let (upgraded, broken) = (PeerId::random(), PeerId::random());
+        for (source, data) in [(upgraded, newer), (broken, vec![1, 2, 3])] {
+            for _ in 0..ban_threshold {
+                let message = gossipsub::Message {
+                    source: None,
+                    data: data.clone(),
+                    sequence_number: None,
+                    topic: IdentTopic::new(ATTESTATIONS_TOPIC).hash(),
+                };
+                let event = GossipsubEvent::Message {
+                    propagation_source: source,
+                    message_id: message_id_fn(&message),
+                    message,
+                };
+                assert!(handle_gossipsub_event(event, &mut gossipsub, &mut guard).is_none());
+            }
+        }
+
+        assert!(!guard.is_banned(&upgraded));
+        assert!(guard.is_banned(&broken));
+    }
+
+    #[test]
+    fn test_message_on_wrong_topic_is_not_returned() {
+        let local_key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from(local_key.public());
+        let mut gossipsub = new_gossipsub(&local_key, &peer_id, &GossipConfig::default()).unwrap();
+        let dir = tempdir().unwrap();
+        let mut guard = PeerGuard::new(ConnectionConfig::default(), &dir.path().join(BAN_LIST_FILE)).unwrap();
+
+        let message = gossipsub::Message {
+            source: None,
+            data: GossipEnvelope::new(attestation()).encode().unwrap(),
+            sequence_number: None,
+            topic: IdentTopic::new(TRANSFERS_TOPIC).hash(),
+        };
+        let event = GossipsubEvent::Message {
+            propagation_source: PeerId::random(),
+            message_id: message_id_fn(&message),
+            message,
+        };
+
+        assert!(handle_gossipsub_event(event, &mut gossipsub, &mut guard).is_none());
+    }
+
+    #[test]
+    fn test_attestation_is_returned_for_validation() {
+        let local_key = Keypair::generate_ed25519();
+        let peer_id = PeerId::from(local_key.public());
+        let mut gossipsub = new_gossipsub(&local_key, &peer_id, &GossipConfig::default()).unwrap();
+        let dir = tempdir().unwrap();
+        let mut guard = PeerGuard::new(ConnectionConfig::default(), &dir.path().join(BAN_LIST_FILE)).unwrap();
+
+        let message = gossipsub::Message {
+            source: None,
+            data: GossipEnvelope::new(attestation()).encode().unwrap(),
+            sequence_number: None,
+            topic: IdentTopic::new(ATTESTATIONS_TOPIC).hash(),
+        };
+        let event = GossipsubEvent::Message {
+            propagation_source: PeerId::random(),
+            message_id: message_id_fn(&message),
+            message,
+        };
+
+        match handle_gossipsub_event(event, &mut gossipsub, &mut guard) {
+            Some(NetworkEvent::AttestationReceived(attestation, _, _)) => assert_eq!(attestation.root, [2u8; 32]),
+            _ => panic!("Attestation was not returned for validation"),
+        }
+    }
+
     #[test]
     fn test_message_id_fn() {
         let mut rng = rand::thread_rng();
@@ -174,7 +499,7 @@ mod tests {
         rng.fill(&mut data1);
         rng.fill(&mut data2);
 
-        let topic = gossipsub::IdentTopic::new(STATE_UPDATES_TOPIC);
+        let topic = gossipsub::IdentTopic::new(TRANSFERS_TOPIC);
 
         let message1 = gossipsub::Message {
             source: None,
@@ -0,0 +1,103 @@
+//! Persistent libp2p identity of a node.
+//!
+//! The identity key is stored in its libp2p protobuf encoding, so the node
+//! keeps the same `PeerId` across restarts and `/p2p/<PeerId>` bootstrap
+//! addresses stay valid.
+
+use crate::errors::NetworkError;
+use libp2p::identity::Keypair;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// The name of the identity key file in the data directory.
+pub const IDENTITY_FILE: &str = "identity.key";
+
+/// The environment variable holding a hex-encoded identity key to import.
+pub const IDENTITY_ENV: &str = "NODE_IDENTITY";
+
+/// Decodes an identity key from its protobuf encoding.
+pub fn decode_identity(bytes: &[u8]) -> Result<Keypair, NetworkError> {
+    Keypair::from_protobuf_encoding(bytes)
+        .map_err(|e| NetworkError::SerializationError(format!("Invalid identity key: {}", e)))
+}
+
+/// Decodes an identity key from the hex of its protobuf encoding.
+pub fn decode_identity_hex(hex_key: &str) -> Result<Keypair, NetworkError> {
+    let bytes = hex::decode(hex_key.trim())
+        .map_err(|e| NetworkError::SerializationError(format!("Invalid identity key hex: {}", e)))?;
+    decode_identity(&bytes)
+}
+
+/// Loads an identity key from a file.
+pub fn load_identity<P: AsRef<Path>>(path: P) -> Result<Keypair, NetworkError> {
+    let bytes = fs::read(path.as_ref()).map_err(|e| {
+        NetworkError::StorageError(format!("Failed to read identity key {}: {}", path.as_ref().display(), e))
+    })?;
+    decode_identity(&bytes)
+}
+
+/// Loads the identity key from a file, generating and saving a new Ed25519
+/// key if the file does not exist.
+pub fn load_or_create_identity<P: AsRef<Path>>(path: P) -> Result<Keypair, NetworkError> {
+    let path = path.as_ref();
+    if path.exists() {
+        return load_identity(path);
+    }
+
+    let keypair = Keypair::generate_ed25519();
+    let bytes = keypair
+        .to_protobuf_encoding()
+        .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+    // Keep the secret key readable by the node's user only, from the moment
+    // the file is created, and never overwrite an existing key
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(&bytes))
+        .map_err(|e| {
+            NetworkError::StorageError(format!("Failed to write identity key {}: {}", path.display(), e))
+        })?;
+
+    log::info!("Generated new node identity at {}", path.display());
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::PeerId;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_identity_persists() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(IDENTITY_FILE);
+
+        let created = load_or_create_identity(&path).unwrap();
+        let loaded = load_or_create_identity(&path).unwrap();
+        assert_eq!(PeerId::from(created.public()), PeerId::from(loaded.public()));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+
+        let imported = decode_identity_hex(&hex::encode(fs::read(&path).unwrap())).unwrap();
+        assert_eq!(PeerId::from(created.public()), PeerId::from(imported.public()));
+    }
+
+    #[test]
+    fn test_invalid_identity_is_rejected() {
+        assert!(decode_identity(&[1, 2, 3]).is_err());
+        assert!(decode_identity_hex("not hex").is_err());
+    }
+}
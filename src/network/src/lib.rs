@@ -1,12 +1,17 @@
 //! Network layer for the chainless token transfer network.
 //!
 //! This crate provides the P2P networking functionality for the stateless token
-//! transfer network, including DHT-based proof storage and retrieval, and
-//! gossip-based state updates.
+//! transfer network, including DHT-based proof storage and retrieval, proof
+//! requests and state sync between peers, and gossip-based state updates.
 
 pub mod dht;
 pub mod errors;
 pub mod gossip;
+pub mod identity;
+pub mod peer_guard;
+pub mod proof_exchange;
+pub mod record_store;
+pub mod state_sync;
 pub mod storage;
 pub mod transport;
 pub mod types;
@@ -14,6 +19,12 @@ pub mod types;
 // Re-export commonly used types and functions
 pub use dht::{get_proof, put_proof};
 pub use errors::NetworkError;
-pub use gossip::broadcast_update;
+pub use gossip::{broadcast_update, publish_message};
+pub use peer_guard::{ConnectionConfig, PeerGuard};
+pub use proof_exchange::{answer_proof_request, ProofExchange, PROOF_PROTOCOL};
+pub use record_store::{RecordStoreConfig, RocksRecordStore};
+pub use state_sync::{answer_state_sync_request, StateDiff, StateSync, STATE_SYNC_PROTOCOL};
 pub use transport::{init_swarm, NetworkEvent};
-pub use types::{ProofRequest, ProofResponse, UpdateMsg};
+pub use types::{
+    GossipEnvelope, GossipMessage, ProofRequest, ProofResponse, StateSyncRequest, StateSyncResponse, UpdateMsg,
+};
@@ -0,0 +1,416 @@
+//! Per-IP connection limits and a persistent ban list.
+//!
+//! The swarm's total, inbound and outbound connection limits are enforced by
+//! libp2p's connection limits behaviour. This behaviour adds a limit on the
+//! inbound connections from each IP address, and denies connections to and
+//! from banned peers. Bans are saved to a file, so they survive a restart,
+//! and expire after their duration.
+
+use crate::errors::NetworkError;
+use libp2p::core::{multiaddr::Protocol, Endpoint, Multiaddr};
+use libp2p::swarm::{
+    behaviour::ConnectionClosed, dummy, CloseConnection, ConnectionDenied, ConnectionId, FromSwarm,
+    NetworkBehaviour, PollParameters, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use void::Void;
+
+/// The name of the ban list file in the data directory.
+pub const BAN_LIST_FILE: &str = "banned_peers";
+
+/// How long rejected messages count towards an automatic ban.
+const INVALID_MESSAGE_WINDOW: Duration = Duration::from_secs(600);
+
+/// Connection limits of the node.
+#[derive(Clone, Debug)]
+pub struct ConnectionConfig {
+    /// The maximum number of established connections
+    pub max_peers: u32,
+    /// The maximum number of established inbound connections
+    pub max_inbound: u32,
+    /// The maximum number of established outbound connections
+    pub max_outbound: u32,
+    /// The maximum number of inbound connections from one IP address
+    pub max_per_ip: u32,
+    /// The number of rejected messages within ten minutes that bans a peer
+    pub ban_threshold: u32,
+    /// How long a peer is banned for automatically
+    pub ban_duration: Duration,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        Self {
+            max_peers: 50,
+            max_inbound: 40,
+            max_outbound: 20,
+            max_per_ip: 8,
+            ban_threshold: 5,
+            ban_duration: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/// A ban of a peer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ban {
+    /// Unix time at which the ban expires, in seconds
+    pub until: u64,
+    /// Why the peer was banned
+    pub reason: String,
+}
+
+/// A ban as saved in the ban list file.
+#[derive(Serialize, Deserialize)]
+struct BanRecord {
+    peer: Vec<u8>,
+    ban: Ban,
+}
+
+/// The error when a connection is denied because the peer is banned.
+#[derive(Debug)]
+pub struct Banned {
+    peer: PeerId,
+}
+
+impl fmt::Display for Banned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "peer {} is banned", self.peer)
+    }
+}
+
+impl std::error::Error for Banned {}
+
+/// The error when a connection is denied because its IP address has too many
+/// connections.
+#[derive(Debug)]
+pub struct IpLimitExceeded {
+    ip: IpAddr,
+    limit: u32,
+}
+
+impl fmt::Display for IpLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} already has {} inbound connections", self.ip, self.limit)
+    }
+}
+
+impl std::error::Error for IpLimitExceeded {}
+
+/// Enforces per-IP limits and the ban list.
+pub struct PeerGuard {
+    config: ConnectionConfig,
+    path: PathBuf,
+    bans: HashMap<PeerId, Ban>,
+    inbound_per_ip: HashMap<IpAddr, HashSet<ConnectionId>>,
+    invalid_messages: HashMap<PeerId, (u32, Instant)>,
+    close_connections: VecDeque<PeerId>,
+    waker: Option<Waker>,
+}
+
+impl PeerGuard {
+    /// Creates the guard, loading the ban list from `path` if it exists and
+    /// dropping expired bans.
+    pub fn new(config: ConnectionConfig, path: &Path) -> Result<Self, NetworkError> {
+        let mut bans = HashMap::new();
+        if path.exists() {
+            let bytes = fs::read(path).map_err(|e| {
+                NetworkError::StorageError(format!("Failed to read ban list {}: {}", path.display(), e))
+            })?;
+            let records: Vec<BanRecord> = bincode::deserialize(&bytes)
+                .map_err(|e| NetworkError::SerializationError(format!("Invalid ban list: {}", e)))?;
+            for record in records {
+                match PeerId::from_bytes(&record.peer) {
+                    Ok(peer) => {
+                        bans.insert(peer, record.ban);
+                    }
+                    Err(e) => log::warn!("Skipping invalid peer in ban list: {}", e),
+                }
+            }
+        }
+
+        let mut guard = Self {
+            config,
+            path: path.to_path_buf(),
+            bans,
+            inbound_per_ip: HashMap::new(),
+            invalid_messages: HashMap::new(),
+            close_connections: VecDeque::new(),
+            waker: None,
+        };
+        if guard.drop_expired() {
+            guard.save();
+        }
+        log::info!("Loaded {} peer bans", guard.bans.len());
+        Ok(guard)
+    }
+
+    /// Bans a peer for `duration` and closes its connections.
+    pub fn ban(&mut self, peer: PeerId, duration: Duration, reason: &str) {
+        let ban = Ban {
+            until: unix_time().saturating_add(duration.as_secs()),
+            reason: reason.to_string(),
+        };
+        log::warn!("Banning peer {} for {}s: {}", peer, duration.as_secs(), reason);
+        self.bans.insert(peer, ban);
+        self.invalid_messages.remove(&peer);
+        self.save();
+
+        self.close_connections.push_back(peer);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Lifts the ban of a peer, returning whether it was banned.
+    pub fn unban(&mut self, peer: &PeerId) -> bool {
+        let removed = self.bans.remove(peer).is_some();
+        if removed {
+            log::info!("Unbanned peer {}", peer);
+            self.save();
+        }
+        removed
+    }
+
+    /// Returns the peers that are currently banned.
+    pub fn bans(&mut self) -> Vec<(PeerId, Ban)> {
+        if self.drop_expired() {
+            self.save();
+        }
+        self.bans.iter().map(|(peer, ban)| (*peer, ban.clone())).collect()
+    }
+
+    /// Returns whether a peer is currently banned.
+    pub fn is_banned(&self, peer: &PeerId) -> bool {
+        self.bans.get(peer).is_some_and(|ban| ban.until > unix_time())
+    }
+
+    /// Counts a rejected message from a peer, banning it once it has sent
+    /// `ban_threshold` of them within ten minutes. Returns whether the peer
+    /// was banned.
+    pub fn record_invalid_message(&mut self, peer: PeerId) -> bool {
+        let now = Instant::now();
+        let (count, since) = self.invalid_messages.entry(peer).or_insert((0, now));
+        if now.duration_since(*since) > INVALID_MESSAGE_WINDOW {
+            *count = 0;
+            *since = now;
+        }
+        *count += 1;
+
+        if *count < self.config.ban_threshold {
+            return false;
+        }
+        let reason = format!("sent {} invalid messages", count);
+        self.ban(peer, self.config.ban_duration, &reason);
+        true
+    }
+
+    /// Drops expired bans, returning whether any were dropped.
+    fn drop_expired(&mut self) -> bool {
+        let now = unix_time();
+        let before = self.bans.len();
+        self.bans.retain(|_, ban| ban.until > now);
+        self.bans.len() != before
+    }
+
+    /// Saves the ban list. Failures are logged, since the bans still apply
+    /// until the node restarts.
+    fn save(&self) {
+        let records: Vec<BanRecord> = self
+            .bans
+            .iter()
+            .map(|(peer, ban)| BanRecord { peer: peer.to_bytes(), ban: ban.clone() })
+            .collect();
+        let result = bincode::serialize(&records)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| fs::write(&self.path, bytes).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            log::error!("Failed to save ban list {}: {}", self.path.display(), e);
+        }
+    }
+
+    fn enforce_ban(&self, peer: &PeerId) -> Result<(), ConnectionDenied> {
+        if self.is_banned(peer) {
+            return Err(ConnectionDenied::new(Banned { peer: *peer }));
+        }
+        Ok(())
+    }
+
+    fn enforce_ip_limit(&self, remote_addr: &Multiaddr) -> Result<(), ConnectionDenied> {
+        let ip = match ip_of(remote_addr) {
+            Some(ip) => ip,
+            None => return Ok(()),
+        };
+        let connections = self.inbound_per_ip.get(&ip).map_or(0, |connections| connections.len());
+        if connections >= self.config.max_per_ip as usize {
+            return Err(ConnectionDenied::new(IpLimitExceeded { ip, limit: self.config.max_per_ip }));
+        }
+        Ok(())
+    }
+}
+
+impl NetworkBehaviour for PeerGuard {
+    type ConnectionHandler = dummy::ConnectionHandler;
+    type ToSwarm = Void;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        _: ConnectionId,
+        _: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.enforce_ip_limit(remote_addr)
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        _: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.enforce_ban(&peer)?;
+        self.enforce_ip_limit(remote_addr)?;
+
+        if let Some(ip) = ip_of(remote_addr) {
+            self.inbound_per_ip.entry(ip).or_default().insert(connection_id);
+        }
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        _: ConnectionId,
+        peer: Option<PeerId>,
+        _: &[Multiaddr],
+        _: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        if let Some(peer) = peer {
+            self.enforce_ban(&peer)?;
+        }
+        Ok(vec![])
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        _: ConnectionId,
+        peer: PeerId,
+        _: &Multiaddr,
+        _: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.enforce_ban(&peer)?;
+        Ok(dummy::ConnectionHandler)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm<Self::ConnectionHandler>) {
+        if let FromSwarm::ConnectionClosed(ConnectionClosed { connection_id, endpoint, .. }) = event {
+            if let Some(ip) = ip_of(endpoint.get_remote_address()) {
+                if let Some(connections) = self.inbound_per_ip.get_mut(&ip) {
+                    connections.remove(&connection_id);
+                    if connections.is_empty() {
+                        self.inbound_per_ip.remove(&ip);
+                    }
+                }
+            }
+        }
+    }
+
+    fn on_connection_handler_event(&mut self, _: PeerId, _: ConnectionId, event: THandlerOutEvent<Self>) {
+        void::unreachable(event)
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+        _: &mut impl PollParameters,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if let Some(peer) = self.close_connections.pop_front() {
+            return Poll::Ready(ToSwarm::CloseConnection {
+                peer_id: peer,
+                connection: CloseConnection::All,
+            });
+        }
+
+        self.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Returns the IP address of a multiaddr, if it has one.
+fn ip_of(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+/// Returns the current Unix time in seconds.
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_bans_persist() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join(BAN_LIST_FILE);
+        let peer = PeerId::random();
+        let expired = PeerId::random();
+
+        let mut guard = PeerGuard::new(ConnectionConfig::default(), &path).unwrap();
+        guard.ban(peer, Duration::from_secs(60), "test");
+        guard.ban(expired, Duration::from_secs(0), "test");
+
+        let mut guard = PeerGuard::new(ConnectionConfig::default(), &path).unwrap();
+        assert!(guard.is_banned(&peer));
+        assert!(!guard.is_banned(&expired));
+        assert_eq!(guard.bans().len(), 1);
+
+        assert!(guard.unban(&peer));
+        let guard = PeerGuard::new(ConnectionConfig::default(), &path).unwrap();
+        assert!(!guard.is_banned(&peer));
+    }
+
+    #[test]
+    fn test_repeated_invalid_messages_ban_peer() {
+        let dir = tempdir().unwrap();
+        let config = ConnectionConfig { ban_threshold: 3, ..ConnectionConfig::default() };
+        let mut guard = PeerGuard::new(config, &dir.path().join(BAN_LIST_FILE)).unwrap();
+        let peer = PeerId::random();
+
+        assert!(!guard.record_invalid_message(peer));
+        assert!(!guard.record_invalid_message(peer));
+        assert!(guard.record_invalid_message(peer));
+        assert!(guard.is_banned(&peer));
+    }
+
+    #[test]
+    fn test_ip_limit() {
+        let dir = tempdir().unwrap();
+        let config = ConnectionConfig { max_per_ip: 1, ..ConnectionConfig::default() };
+        let mut guard = PeerGuard::new(config, &dir.path().join(BAN_LIST_FILE)).unwrap();
+        let local: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+        let remote: Multiaddr = "/ip4/10.0.0.1/tcp/4000".parse().unwrap();
+
+        assert!(guard
+            .handle_established_inbound_connection(ConnectionId::new_unchecked(1), PeerId::random(), &local, &remote)
+            .is_ok());
+        assert!(guard.handle_pending_inbound_connection(ConnectionId::new_unchecked(2), &local, &remote).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Request-response protocol for fetching account proofs from peers.
+
+use crate::errors::NetworkError;
+use crate::storage::ProofStore;
+use crate::transport::{NetworkEvent, NodeBehaviour};
+use crate::types::{ProofRequest, ProofResponse};
+use core::{proofs::Proof, smt::SMT, types::Address};
+use futures::channel::oneshot;
+use libp2p::request_response::{self, ProtocolSupport, RequestId};
+use libp2p::{PeerId, StreamProtocol, Swarm};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// The protocol name of proof requests, versioned so that incompatible
+/// encodings are never negotiated.
+pub const PROOF_PROTOCOL: &str = "/stateless-token/proofs/1.0.0";
+
+/// How long to wait for each peer's response, in seconds.
+const PROOF_REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// How many peers to ask before giving up.
+const PROOF_REQUEST_ATTEMPTS: usize = 3;
+
+/// The request-response behaviour for proofs, encoding messages as CBOR.
+pub type ProofBehaviour = request_response::cbor::Behaviour<ProofRequest, ProofResponse>;
+
+/// The events emitted by the proof behaviour.
+pub type ProofEvent = request_response::Event<ProofRequest, ProofResponse>;
+
+/// Creates the proof behaviour, serving and requesting proofs.
+pub fn new_proof_behaviour() -> ProofBehaviour {
+    let mut config = request_response::Config::default();
+    config.set_request_timeout(Duration::from_secs(PROOF_REQUEST_TIMEOUT_SECS));
+
+    ProofBehaviour::new([(StreamProtocol::new(PROOF_PROTOCOL), ProtocolSupport::Full)], config)
+}
+
+/// Answers a proof request from the proof store, or from the SMT if the
+/// request is for its current root.
+pub fn answer_proof_request(request: &ProofRequest, proof_store: &ProofStore, smt: &SMT) -> ProofResponse {
+    let proof = match proof_store.get_proof(&request.address, &request.root) {
+        Ok(proof) => Some(proof),
+        Err(_) if smt.root() == request.root => smt.gen_proof(&request.address).ok(),
+        Err(_) => None,
+    };
+
+    ProofResponse {
+        address: request.address,
+        root: request.root,
+        proof,
+    }
+}
+
+/// The sender for the response to a pending proof request.
+type ProofSender = oneshot::Sender<Result<ProofResponse, NetworkError>>;
+
+/// A manager for proof requests, matching responses to waiting callers.
+#[derive(Clone, Default)]
+pub struct ProofExchange {
+    /// Callers waiting for a response, by request ID
+    pending: Arc<Mutex<HashMap<RequestId, ProofSender>>>,
+}
+
+impl ProofExchange {
+    /// Creates a new proof exchange.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles an event of the proof behaviour.
+    ///
+    /// Responses and failures of pending requests go to their callers.
+    /// Inbound requests are returned for the node to answer, along with
+    /// responses that arrived after their caller gave up.
+    pub fn handle_event(&self, event: ProofEvent) -> Option<NetworkEvent> {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    Some(NetworkEvent::ProofRequestReceived(request, peer, channel))
+                }
+                request_response::Message::Response { request_id, response } => {
+                    match self.pending.lock().unwrap().remove(&request_id) {
+                        Some(sender) => {
+                            let _ = sender.send(Ok(response));
+                            None
+                        }
+                        None => Some(NetworkEvent::ProofResponseReceived(response)),
+                    }
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error } => {
+                if let Some(sender) = self.pending.lock().unwrap().remove(&request_id) {
+                    let _ = sender.send(Err(NetworkError::PeerNotFound(format!(
+                        "Proof request to {} failed: {}",
+                        peer, error
+                    ))));
+                }
+                None
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                log::debug!("Failed to answer proof request from {}: {}", peer, error);
+                None
+            }
+            request_response::Event::ResponseSent { .. } => None,
+        }
+    }
+
+    /// Requests the proof of an account at a root, asking `peer` first and
+    /// then other connected peers until one has it.
+    ///
+    /// Each peer gets [`PROOF_REQUEST_TIMEOUT_SECS`] to answer, and at most
+    /// [`PROOF_REQUEST_ATTEMPTS`] peers are asked. A proof that does not
+    /// verify against the requested root counts as a failed attempt, so one
+    /// peer answering with a bad proof does not stop the others being asked.
+    pub async fn request_proof(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        address: &Address,
+        root: &[u8; 32],
+    ) -> Result<Proof, NetworkError> {
+        let mut peers = vec![peer];
+        peers.extend(
+            swarm
+                .lock()
+                .unwrap()
+                .connected_peers()
+                .filter(|connected| **connected != peer)
+                .take(PROOF_REQUEST_ATTEMPTS - 1)
+                .copied(),
+        );
+
+        let mut last_error = NetworkError::ProofNotFound(*address);
+        for peer in peers {
+            match self.request_from(swarm, peer, address, root).await {
+                Ok(ProofResponse { address: a, root: r, proof: Some(proof) }) if a == *address && r == *root => {
+                    if proof.verify(*root, address) {
+                        return Ok(proof);
+                    }
+                    log::warn!("Peer {} sent an invalid proof for {}", peer, hex::encode(address));
+                    last_error = NetworkError::ProofNotFound(*address);
+                }
+                Ok(_) => {
+                    log::debug!("Peer {} has no proof for {}", peer, hex::encode(address));
+                    last_error = NetworkError::ProofNotFound(*address);
+                }
+                Err(e) => {
+                    log::debug!("Proof request to {} failed: {}", peer, e);
+                    last_error = e;
+                }
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Sends one proof request and waits for its response.
+    async fn request_from(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        address: &Address,
+        root: &[u8; 32],
+    ) -> Result<ProofResponse, NetworkError> {
+        let (sender, receiver) = oneshot::channel();
+
+        // Register the caller before the response can arrive
+        let request_id = {
+            let mut swarm = swarm.lock().unwrap();
+            let request = ProofRequest {
+                address: *address,
+                root: *root,
+            };
+            let request_id = swarm.behaviour_mut().proofs.send_request(&peer, request);
+            self.pending.lock().unwrap().insert(request_id, sender);
+            request_id
+        };
+
+        match timeout(Duration::from_secs(PROOF_REQUEST_TIMEOUT_SECS), receiver).await {
+            Ok(result) => result.map_err(|_| NetworkError::Timeout("request_proof".to_string()))?,
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request_id);
+                Err(NetworkError::Timeout("request_proof".to_string()))
+            }
+        }
+    }
+}
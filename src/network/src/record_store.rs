@@ -0,0 +1,618 @@
+//! Persistent Kademlia record store backed by RocksDB.
+//!
+//! Records survive restarts, expire after a configurable TTL and are bounded
+//! in number. Proof records are keyed by address and root, and once the store
+//! is full the records of the oldest roots are evicted first, where roots are
+//! aged by when the store first saw them. Provider records are short-lived and
+//! re-announced by their providers, so they are kept in memory.
+
+use crate::errors::NetworkError;
+use libp2p::kad::record::{Key, ProviderRecord, Record};
+use libp2p::kad::store::{self, MemoryStore, RecordStore};
+use libp2p::PeerId;
+use rocksdb::{Direction, IteratorMode, Options, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Key prefix for records, followed by the hex record key.
+const RECORD_PREFIX: &str = "rec::";
+
+/// Key prefix for the roots of stored records, followed by the hex root.
+const ROOT_PREFIX: &str = "root::";
+
+/// Key prefix for the eviction index, followed by
+/// `<16-digit hex root sequence>::<hex record key>`.
+const EVICT_PREFIX: &str = "evict::";
+
+/// Key prefix for the expiry index, followed by
+/// `<16-digit hex expiry in Unix milliseconds>::<hex record key>`.
+const EXPIRY_PREFIX: &str = "expiry::";
+
+/// Key of the sequence number given to the next new root.
+const NEXT_SEQ_KEY: &str = "meta::next_seq";
+
+/// The length of a proof record key, an address followed by a root.
+const PROOF_KEY_LEN: usize = 64;
+
+/// Configuration for the record store and the republishing of its records.
+#[derive(Clone, Debug)]
+pub struct RecordStoreConfig {
+    /// The maximum number of records stored
+    pub max_records: usize,
+    /// The maximum size of a record value in bytes
+    pub max_value_bytes: usize,
+    /// How long a record lives after it is stored
+    pub record_ttl: Duration,
+    /// How often records published by this node are republished
+    pub publication_interval: Duration,
+    /// How often stored records are replicated to the closest peers
+    pub replication_interval: Duration,
+}
+
+impl Default for RecordStoreConfig {
+    fn default() -> Self {
+        Self {
+            max_records: 65_536,
+            max_value_bytes: 64 * 1024,
+            record_ttl: Duration::from_secs(36 * 60 * 60),
+            publication_interval: Duration::from_secs(12 * 60 * 60),
+            replication_interval: Duration::from_secs(60 * 60),
+        }
+    }
+}
+
+/// A record as stored in RocksDB.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct StoredRecord {
+    /// The record value
+    value: Vec<u8>,
+    /// The publisher's peer ID, as bytes
+    publisher: Option<Vec<u8>>,
+    /// When the record expires, in Unix milliseconds
+    expires_at: Option<u64>,
+    /// The sequence number of the record's root
+    seq: u64,
+}
+
+/// A root with stored records.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RootEntry {
+    /// The sequence number of the root, in the order roots were first seen
+    seq: u64,
+    /// The number of stored records for the root
+    records: u64,
+}
+
+/// A Kademlia record store backed by RocksDB.
+pub struct RocksRecordStore {
+    /// The RocksDB instance
+    db: DB,
+    /// The store configuration
+    config: RecordStoreConfig,
+    /// The number of stored records
+    len: usize,
+    /// The sequence number given to the next new root
+    next_seq: u64,
+    /// The in-memory store for provider records
+    providers: MemoryStore,
+}
+
+impl RocksRecordStore {
+    /// Opens the record store at the given path, dropping expired records.
+    pub fn open<P: AsRef<Path>>(path: P, local_id: PeerId, config: RecordStoreConfig) -> Result<Self, NetworkError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+
+        let db = DB::open(&opts, path).map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+        let next_seq = match db.get(NEXT_SEQ_KEY).map_err(|e| NetworkError::StorageError(e.to_string()))? {
+            Some(bytes) => {
+                bincode::deserialize(&bytes).map_err(|e| NetworkError::SerializationError(e.to_string()))?
+            }
+            None => 0,
+        };
+
+        let mut record_store = Self {
+            db,
+            config,
+            len: 0,
+            next_seq,
+            providers: MemoryStore::new(local_id),
+        };
+
+        let expired = record_store.scan()?;
+        for key in expired {
+            record_store.delete(&key)?;
+        }
+        log::info!("Loaded {} DHT records", record_store.len);
+
+        Ok(record_store)
+    }
+
+    /// Returns the number of stored records.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no records are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Counts the stored records, returning the keys of expired ones.
+    ///
+    /// Also indexes every record by its expiry, for stores written before
+    /// the expiry index existed.
+    fn scan(&mut self) -> Result<Vec<Key>, NetworkError> {
+        let now = unix_millis(SystemTime::now());
+        let mut expired = Vec::new();
+        let mut len = 0;
+        let mut batch = WriteBatch::default();
+
+        let iter = self.db.iterator(IteratorMode::From(RECORD_PREFIX.as_bytes(), Direction::Forward));
+        for item in iter {
+            let (key, value) = item.map_err(|e| NetworkError::StorageError(e.to_string()))?;
+            let key_hex = match String::from_utf8_lossy(&key).strip_prefix(RECORD_PREFIX) {
+                Some(key_hex) => key_hex.to_string(),
+                None => break,
+            };
+            let stored: StoredRecord =
+                bincode::deserialize(&value).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+            len += 1;
+            let key = Key::from(hex::decode(key_hex).map_err(|e| NetworkError::SerializationError(e.to_string()))?);
+            if let Some(expires_at) = stored.expires_at {
+                batch.put(expiry_key(expires_at, &key), []);
+                if expires_at <= now {
+                    expired.push(key);
+                }
+            }
+        }
+
+        self.db.write(batch).map_err(|e| NetworkError::StorageError(e.to_string()))?;
+        self.len = len;
+        Ok(expired)
+    }
+
+    /// Returns the keys of the records that have expired by now, reading the
+    /// expiry index only up to the first record still alive.
+    fn expired(&self) -> Result<Vec<Key>, NetworkError> {
+        let now = unix_millis(SystemTime::now());
+        let mut expired = Vec::new();
+
+        let iter = self.db.iterator(IteratorMode::From(EXPIRY_PREFIX.as_bytes(), Direction::Forward));
+        for item in iter {
+            let (key, _) = item.map_err(|e| NetworkError::StorageError(e.to_string()))?;
+            let key_str = String::from_utf8_lossy(&key);
+            let (expires_hex, key_hex) =
+                match key_str.strip_prefix(EXPIRY_PREFIX).and_then(|rest| rest.split_once("::")) {
+                    Some(parts) => parts,
+                    None => break,
+                };
+            let expires_at =
+                u64::from_str_radix(expires_hex, 16).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+            if expires_at > now {
+                break;
+            }
+            let key = hex::decode(key_hex).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+            expired.push(Key::from(key));
+        }
+
+        Ok(expired)
+    }
+
+    /// Loads a stored record.
+    fn load(&self, key: &Key) -> Result<Option<StoredRecord>, NetworkError> {
+        self.db
+            .get(record_key(key))
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?
+            .map(|bytes| bincode::deserialize(&bytes).map_err(|e| NetworkError::SerializationError(e.to_string())))
+            .transpose()
+    }
+
+    /// Loads the entry of a root.
+    fn load_root(&self, root: &[u8]) -> Result<Option<RootEntry>, NetworkError> {
+        self.db
+            .get(root_key(root))
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?
+            .map(|bytes| bincode::deserialize(&bytes).map_err(|e| NetworkError::SerializationError(e.to_string())))
+            .transpose()
+    }
+
+    /// Returns the key of the record with the oldest root, and the sequence
+    /// number of that root.
+    fn oldest(&self) -> Result<Option<(u64, Key)>, NetworkError> {
+        let mut iter = self.db.iterator(IteratorMode::From(EVICT_PREFIX.as_bytes(), Direction::Forward));
+        let (key, _) = match iter.next() {
+            Some(item) => item.map_err(|e| NetworkError::StorageError(e.to_string()))?,
+            None => return Ok(None),
+        };
+
+        let key_str = String::from_utf8_lossy(&key);
+        let (seq_hex, key_hex) = match key_str.strip_prefix(EVICT_PREFIX).and_then(|rest| rest.split_once("::")) {
+            Some(parts) => parts,
+            None => return Ok(None),
+        };
+        let seq = u64::from_str_radix(seq_hex, 16).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+        let key = hex::decode(key_hex).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+        Ok(Some((seq, Key::from(key))))
+    }
+
+    /// Makes room for a record of a root with the given sequence number,
+    /// dropping expired records and then the records of older roots.
+    fn make_room(&mut self, seq: u64) -> Result<(), store::Error> {
+        if self.len < self.config.max_records {
+            return Ok(());
+        }
+
+        let expired = self.expired().map_err(storage_error)?;
+        for key in expired {
+            self.delete(&key).map_err(storage_error)?;
+        }
+
+        while self.len >= self.config.max_records {
+            match self.oldest().map_err(storage_error)? {
+                // Never evict a newer root for an older one
+                Some((oldest, key)) if oldest <= seq => {
+                    log::debug!("Evicting DHT record {} of an old root", hex::encode(&key));
+                    self.delete(&key).map_err(storage_error)?;
+                }
+                _ => return Err(store::Error::MaxRecords),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Stores a record, registering its root if it is new.
+    fn insert(&mut self, record: Record) -> Result<(), store::Error> {
+        if record.value.len() > self.config.max_value_bytes {
+            return Err(store::Error::ValueTooLarge);
+        }
+
+        // No record outlives the TTL, whatever its own expiry
+        let ttl_expiry = Instant::now() + self.config.record_ttl;
+        let expires = record.expires.map_or(ttl_expiry, |expires| expires.min(ttl_expiry));
+
+        let mut stored = StoredRecord {
+            value: record.value,
+            publisher: record.publisher.map(|publisher| publisher.to_bytes()),
+            expires_at: Some(instant_to_unix_millis(expires)),
+            seq: 0,
+        };
+        let key = record.key;
+
+        let mut batch = WriteBatch::default();
+        let is_new = match self.load(&key).map_err(storage_error)? {
+            Some(existing) => {
+                stored.seq = existing.seq;
+                if let Some(expires_at) = existing.expires_at {
+                    batch.delete(expiry_key(expires_at, &key));
+                }
+                false
+            }
+            None => {
+                let root = root_of(&key);
+                let mut entry = match self.load_root(root).map_err(storage_error)? {
+                    Some(entry) => entry,
+                    None => RootEntry {
+                        seq: self.next_seq,
+                        records: 0,
+                    },
+                };
+
+                self.make_room(entry.seq)?;
+
+                // The root may have been evicted along with its records
+                if let Some(current) = self.load_root(root).map_err(storage_error)? {
+                    entry = current;
+                }
+                if entry.seq == self.next_seq {
+                    self.next_seq += 1;
+                    batch.put(NEXT_SEQ_KEY, bincode::serialize(&self.next_seq).map_err(serialization_error)?);
+                }
+                entry.records += 1;
+                stored.seq = entry.seq;
+
+                batch.put(root_key(root), bincode::serialize(&entry).map_err(serialization_error)?);
+                batch.put(evict_key(entry.seq, &key), []);
+                true
+            }
+        };
+
+        if let Some(expires_at) = stored.expires_at {
+            batch.put(expiry_key(expires_at, &key), []);
+        }
+        batch.put(record_key(&key), bincode::serialize(&stored).map_err(serialization_error)?);
+        self.db.write(batch).map_err(|e| storage_error(NetworkError::StorageError(e.to_string())))?;
+        if is_new {
+            self.len += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Deletes a record along with its index entries.
+    fn delete(&mut self, key: &Key) -> Result<(), NetworkError> {
+        let stored = match self.load(key)? {
+            Some(stored) => stored,
+            None => return Ok(()),
+        };
+
+        let mut batch = WriteBatch::default();
+        batch.delete(record_key(key));
+        batch.delete(evict_key(stored.seq, key));
+        if let Some(expires_at) = stored.expires_at {
+            batch.delete(expiry_key(expires_at, key));
+        }
+
+        let root = root_of(key);
+        if let Some(mut entry) = self.load_root(root)? {
+            entry.records = entry.records.saturating_sub(1);
+            if entry.records == 0 {
+                batch.delete(root_key(root));
+            } else {
+                let bytes = bincode::serialize(&entry).map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+                batch.put(root_key(root), bytes);
+            }
+        }
+
+        self.db.write(batch).map_err(|e| NetworkError::StorageError(e.to_string()))?;
+        self.len = self.len.saturating_sub(1);
+
+        Ok(())
+    }
+}
+
+impl RecordStore for RocksRecordStore {
+    type RecordsIter<'a> = std::vec::IntoIter<Cow<'a, Record>>;
+    type ProvidedIter<'a> = <MemoryStore as RecordStore>::ProvidedIter<'a>;
+
+    fn get(&self, k: &Key) -> Option<Cow<'_, Record>> {
+        match self.load(k) {
+            Ok(stored) => stored.map(|stored| Cow::Owned(to_record(k.clone(), stored))),
+            Err(e) => {
+                log::error!("Failed to load DHT record {}: {}", hex::encode(k), e);
+                None
+            }
+        }
+    }
+
+    fn put(&mut self, r: Record) -> store::Result<()> {
+        self.insert(r)
+    }
+
+    fn remove(&mut self, k: &Key) {
+        if let Err(e) = self.delete(k) {
+            log::error!("Failed to remove DHT record {}: {}", hex::encode(k), e);
+        }
+    }
+
+    fn records(&self) -> Self::RecordsIter<'_> {
+        let mut records = Vec::with_capacity(self.len);
+        let iter = self.db.iterator(IteratorMode::From(RECORD_PREFIX.as_bytes(), Direction::Forward));
+        for item in iter {
+            let (key, value) = match item {
+                Ok(item) => item,
+                Err(e) => {
+                    log::error!("Failed to iterate DHT records: {}", e);
+                    break;
+                }
+            };
+            let key_hex = match String::from_utf8_lossy(&key).strip_prefix(RECORD_PREFIX) {
+                Some(key_hex) => key_hex.to_string(),
+                None => break,
+            };
+            match (hex::decode(key_hex), bincode::deserialize::<StoredRecord>(&value)) {
+                (Ok(key), Ok(stored)) => records.push(Cow::Owned(to_record(Key::from(key), stored))),
+                _ => log::warn!("Skipping corrupt DHT record"),
+            }
+        }
+
+        records.into_iter()
+    }
+
+    fn add_provider(&mut self, record: ProviderRecord) -> store::Result<()> {
+        self.providers.add_provider(record)
+    }
+
+    fn providers(&self, key: &Key) -> Vec<ProviderRecord> {
+        self.providers.providers(key)
+    }
+
+    fn provided(&self) -> Self::ProvidedIter<'_> {
+        self.providers.provided()
+    }
+
+    fn remove_provider(&mut self, k: &Key, p: &PeerId) {
+        self.providers.remove_provider(k, p)
+    }
+}
+
+/// Returns the root a record is grouped under for eviction: the root of a
+/// proof record, or the whole key of any other record.
+fn root_of(key: &Key) -> &[u8] {
+    let bytes = key.as_ref();
+    if bytes.len() == PROOF_KEY_LEN {
+        &bytes[32..]
+    } else {
+        bytes
+    }
+}
+
+/// Returns the RocksDB key of a record.
+fn record_key(key: &Key) -> String {
+    format!("{}{}", RECORD_PREFIX, hex::encode(key))
+}
+
+/// Returns the RocksDB key of a root entry.
+fn root_key(root: &[u8]) -> String {
+    format!("{}{}", ROOT_PREFIX, hex::encode(root))
+}
+
+/// Returns the RocksDB key of a record in the eviction index.
+fn evict_key(seq: u64, key: &Key) -> String {
+    format!("{}{:016x}::{}", EVICT_PREFIX, seq, hex::encode(key))
+}
+
+/// Returns the RocksDB key of a record in the expiry index.
+fn expiry_key(expires_at: u64, key: &Key) -> String {
+    format!("{}{:016x}::{}", EXPIRY_PREFIX, expires_at, hex::encode(key))
+}
+
+/// Converts a stored record back into a Kademlia record.
+fn to_record(key: Key, stored: StoredRecord) -> Record {
+    Record {
+        key,
+        value: stored.value,
+        publisher: stored.publisher.and_then(|bytes| PeerId::from_bytes(&bytes).ok()),
+        expires: stored.expires_at.map(unix_millis_to_instant),
+    }
+}
+
+/// Returns a time in Unix milliseconds.
+fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+/// Converts a monotonic instant into Unix milliseconds, so it survives a restart.
+fn instant_to_unix_millis(instant: Instant) -> u64 {
+    unix_millis(SystemTime::now() + instant.saturating_duration_since(Instant::now()))
+}
+
+/// Converts Unix milliseconds into a monotonic instant, which is now if the
+/// time has passed.
+fn unix_millis_to_instant(millis: u64) -> Instant {
+    let time = UNIX_EPOCH + Duration::from_millis(millis);
+    Instant::now() + time.duration_since(SystemTime::now()).unwrap_or_default()
+}
+
+/// Logs a storage failure and rejects the record.
+///
+/// `store::Error` has no variant for I/O failures. `MaxRecords` is kept for a
+/// store that is really full, so a failure rejects just this record as
+/// `ValueTooLarge`, and the log carries the actual cause.
+fn storage_error(e: NetworkError) -> store::Error {
+    log::error!("DHT record store failure: {}", e);
+    store::Error::ValueTooLarge
+}
+
+/// Logs a serialization failure and rejects the record.
+fn serialization_error(e: bincode::Error) -> store::Error {
+    storage_error(NetworkError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    /// Creates a proof record for an address and root.
+    fn proof_record(address: u8, root: u8) -> Record {
+        let mut key = vec![address; 32];
+        key.extend_from_slice(&[root; 32]);
+        Record::new(key, vec![address, root])
+    }
+
+    #[test]
+    fn test_record_store_persists_records() {
+        let dir = tempdir().unwrap();
+        let local_id = PeerId::random();
+        let record = proof_record(1, 1);
+
+        {
+            let mut store = RocksRecordStore::open(dir.path(), local_id, RecordStoreConfig::default()).unwrap();
+            let mut published = record.clone();
+            published.publisher = Some(local_id);
+            store.put(published).unwrap();
+        }
+
+        let store = RocksRecordStore::open(dir.path(), local_id, RecordStoreConfig::default()).unwrap();
+        assert_eq!(store.len(), 1);
+
+        let loaded = store.get(&record.key).unwrap().into_owned();
+        assert_eq!(loaded.value, record.value);
+        assert_eq!(loaded.publisher, Some(local_id));
+        assert_eq!(store.records().count(), 1);
+    }
+
+    #[test]
+    fn test_record_store_caps_expiry_at_ttl() {
+        let dir = tempdir().unwrap();
+        let config = RecordStoreConfig {
+            record_ttl: Duration::from_secs(60),
+            ..RecordStoreConfig::default()
+        };
+        let mut store = RocksRecordStore::open(dir.path(), PeerId::random(), config).unwrap();
+
+        let mut record = proof_record(1, 1);
+        record.expires = Some(Instant::now() + Duration::from_secs(3600));
+        store.put(record.clone()).unwrap();
+
+        let expires = store.get(&record.key).unwrap().expires.unwrap();
+        assert!(expires <= Instant::now() + Duration::from_secs(61));
+    }
+
+    #[test]
+    fn test_record_store_evicts_old_roots_first() {
+        let dir = tempdir().unwrap();
+        let config = RecordStoreConfig {
+            max_records: 3,
+            ..RecordStoreConfig::default()
+        };
+        let mut store = RocksRecordStore::open(dir.path(), PeerId::random(), config).unwrap();
+
+        store.put(proof_record(1, 1)).unwrap();
+        store.put(proof_record(2, 2)).unwrap();
+        store.put(proof_record(3, 2)).unwrap();
+
+        // A proof for a new root evicts the proof for the oldest root
+        store.put(proof_record(4, 3)).unwrap();
+        assert_eq!(store.len(), 3);
+        assert!(store.get(&proof_record(1, 1).key).is_none());
+        assert!(store.get(&proof_record(2, 2).key).is_some());
+        assert!(store.get(&proof_record(4, 3).key).is_some());
+
+        // Updating a stored record needs no room
+        store.put(proof_record(4, 3)).unwrap();
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_record_store_drops_expired_records_before_evicting() {
+        let dir = tempdir().unwrap();
+        let config = RecordStoreConfig {
+            max_records: 2,
+            ..RecordStoreConfig::default()
+        };
+        let mut store = RocksRecordStore::open(dir.path(), PeerId::random(), config).unwrap();
+
+        store.put(proof_record(1, 1)).unwrap();
+        let mut expiring = proof_record(2, 2);
+        expiring.expires = Some(Instant::now());
+        store.put(expiring.clone()).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+
+        // The expired record makes room, so the older root is kept
+        store.put(proof_record(3, 3)).unwrap();
+        assert_eq!(store.len(), 2);
+        assert!(store.get(&proof_record(1, 1).key).is_some());
+        assert!(store.get(&expiring.key).is_none());
+    }
+
+    #[test]
+    fn test_record_store_rejects_large_values() {
+        let dir = tempdir().unwrap();
+        let config = RecordStoreConfig {
+            max_value_bytes: 1,
+            ..RecordStoreConfig::default()
+        };
+        let mut store = RocksRecordStore::open(dir.path(), PeerId::random(), config).unwrap();
+
+        assert!(matches!(store.put(proof_record(1, 1)), Err(store::Error::ValueTooLarge)));
+        assert!(store.is_empty());
+    }
+}
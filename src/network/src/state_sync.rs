@@ -0,0 +1,346 @@
+//! Request-response protocol for syncing state from a peer.
+//!
+//! A node syncs by comparing subtree hashes with a peer from the root down,
+//! descending only into subtrees whose hashes differ, and then paging through
+//! the peer's leaves under the differing subtrees, verifying each chunk
+//! against the peer's root. The traffic is proportional to the difference
+//! between the two states rather than to their size. If the peer's root moves
+//! on mid-sync, the sync restarts at the new root.
+
+use crate::errors::NetworkError;
+use crate::transport::{NetworkEvent, NodeBehaviour};
+use crate::types::{StateAnswer, StateQuery, StateSyncRequest, StateSyncResponse};
+use core::errors::CoreError;
+use core::smt::{StateLeaf, Subtree, SMT};
+use futures::channel::oneshot;
+use libp2p::request_response::{self, ProtocolSupport, RequestId};
+use libp2p::{PeerId, StreamProtocol, Swarm};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// The protocol name of state sync, versioned so that incompatible encodings
+/// are never negotiated.
+pub const STATE_SYNC_PROTOCOL: &str = "/stateless-token/state-sync/2.0.0";
+
+/// The maximum number of leaves served in one chunk.
+pub const STATE_CHUNK_SIZE: u32 = 256;
+
+/// The maximum number of subtree hashes served in one response.
+pub const MAX_SUBTREE_HASHES: usize = 512;
+
+/// Subtrees with at most this many local leaves are fetched whole rather
+/// than compared further.
+const FETCH_THRESHOLD: usize = 8;
+
+/// The depth below which subtrees are fetched whole.
+const MAX_DIFF_DEPTH: u8 = 64;
+
+/// How long to wait for each response, in seconds.
+const STATE_SYNC_TIMEOUT_SECS: u64 = 30;
+
+/// How many times a sync restarts after the peer's root changes.
+const MAX_SYNC_RESTARTS: usize = 3;
+
+/// The request-response behaviour for state sync, encoding messages as CBOR.
+pub type StateSyncBehaviour = request_response::cbor::Behaviour<StateSyncRequest, StateSyncResponse>;
+
+/// The events emitted by the state sync behaviour.
+pub type StateSyncEvent = request_response::Event<StateSyncRequest, StateSyncResponse>;
+
+/// The sender for the response to a pending state sync request.
+type StateSyncSender = oneshot::Sender<Result<StateSyncResponse, NetworkError>>;
+
+/// Creates the state sync behaviour, serving and requesting state.
+pub fn new_state_sync_behaviour() -> StateSyncBehaviour {
+    let mut config = request_response::Config::default();
+    config.set_request_timeout(Duration::from_secs(STATE_SYNC_TIMEOUT_SECS));
+
+    StateSyncBehaviour::new([(StreamProtocol::new(STATE_SYNC_PROTOCOL), ProtocolSupport::Full)], config)
+}
+
+/// Answers a state sync request from the SMT.
+///
+/// Queries are only answered while the SMT is at the requested root.
+pub fn answer_state_sync_request(request: &StateSyncRequest, smt: &SMT) -> StateSyncResponse {
+    let root = smt.root();
+    let answer = if request.root.is_none_or(|requested| requested == root) {
+        let answer = match &request.query {
+            StateQuery::Hashes(subtrees) if subtrees.len() > MAX_SUBTREE_HASHES => {
+                Err(CoreError::SMTError(format!("Too many subtrees: {}", subtrees.len())))
+            }
+            StateQuery::Hashes(subtrees) => subtrees
+                .iter()
+                .map(|subtree| smt.subtree_hash(subtree))
+                .collect::<Result<_, _>>()
+                .map(StateAnswer::Hashes),
+            StateQuery::Leaves { subtree, after, limit } => {
+                let limit = (*limit).min(STATE_CHUNK_SIZE) as usize;
+                smt.gen_state_chunk(subtree, after.as_ref(), limit).map(StateAnswer::Leaves)
+            }
+        };
+        match answer {
+            Ok(answer) => Some(answer),
+            Err(e) => {
+                log::warn!("Failed to answer state sync request: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    StateSyncResponse { root, answer }
+}
+
+/// The part of a peer's state that differs from the local state.
+///
+/// [`SMT::replace_subtrees`] applies it, checking that the result is the
+/// peer's root.
+#[derive(Clone, Debug)]
+pub struct StateDiff {
+    /// The peer's root
+    pub root: [u8; 32],
+    /// The subtrees whose leaves differ
+    pub subtrees: Vec<Subtree>,
+    /// The peer's leaves under those subtrees
+    pub leaves: Vec<StateLeaf>,
+}
+
+/// The outcome of queries at a root the peer may have moved on from.
+enum AtRoot<T> {
+    /// The queries were answered at the root
+    Answered(T),
+    /// The peer moved on to this root
+    Moved([u8; 32]),
+}
+
+/// A manager for state sync requests, matching responses to waiting callers.
+#[derive(Clone, Default)]
+pub struct StateSync {
+    /// Callers waiting for a response, by request ID
+    pending: Arc<Mutex<HashMap<RequestId, StateSyncSender>>>,
+}
+
+impl StateSync {
+    /// Creates a new state sync manager.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles an event of the state sync behaviour.
+    ///
+    /// Responses and failures of pending requests go to their callers.
+    /// Inbound requests are returned for the node to answer.
+    pub fn handle_event(&self, event: StateSyncEvent) -> Option<NetworkEvent> {
+        match event {
+            request_response::Event::Message { peer, message } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    Some(NetworkEvent::StateSyncRequestReceived(request, peer, channel))
+                }
+                request_response::Message::Response { request_id, response } => {
+                    if let Some(sender) = self.pending.lock().unwrap().remove(&request_id) {
+                        let _ = sender.send(Ok(response));
+                    }
+                    None
+                }
+            },
+            request_response::Event::OutboundFailure { peer, request_id, error } => {
+                if let Some(sender) = self.pending.lock().unwrap().remove(&request_id) {
+                    let _ = sender.send(Err(NetworkError::PeerNotFound(format!(
+                        "State sync request to {} failed: {}",
+                        peer, error
+                    ))));
+                }
+                None
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                log::debug!("Failed to answer state sync request from {}: {}", peer, error);
+                None
+            }
+            request_response::Event::ResponseSent { .. } => None,
+        }
+    }
+
+    /// Fetches the part of a peer's state that differs from the local
+    /// state, or returns `None` if the two are the same.
+    pub async fn fetch_diff(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        smt: &Mutex<SMT>,
+    ) -> Result<Option<StateDiff>, NetworkError> {
+        // Ask for the peer's root without any hashes
+        let probe = StateSyncRequest {
+            root: None,
+            query: StateQuery::Hashes(Vec::new()),
+        };
+        let mut root = self.request(swarm, peer, probe).await?.root;
+
+        for _ in 0..=MAX_SYNC_RESTARTS {
+            if root == smt.lock().unwrap().root() {
+                return Ok(None);
+            }
+
+            match self.diff_at(swarm, peer, smt, root).await? {
+                AtRoot::Answered(diff) => return Ok(Some(diff)),
+                AtRoot::Moved(moved) => {
+                    log::debug!("Peer {} moved to root {} during sync", peer, hex::encode(moved));
+                    root = moved;
+                }
+            }
+        }
+
+        Err(NetworkError::Timeout(format!("a stable state root from {}", peer)))
+    }
+
+    /// Compares subtree hashes with a peer at a root, then fetches the
+    /// peer's leaves under the subtrees that differ.
+    async fn diff_at(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        smt: &Mutex<SMT>,
+        root: [u8; 32],
+    ) -> Result<AtRoot<StateDiff>, NetworkError> {
+        let mut subtrees = Vec::new();
+        let mut to_fetch = Vec::new();
+
+        // Walk down level by level from the root, which is known to differ
+        let mut frontier = vec![Subtree::root()];
+        while !frontier.is_empty() {
+            let sizes = smt.lock().unwrap().subtree_sizes(&frontier);
+            let mut children = Vec::new();
+            for (subtree, size) in frontier.into_iter().zip(sizes) {
+                match subtree.children() {
+                    Some(pair) if size > FETCH_THRESHOLD && subtree.depth < MAX_DIFF_DEPTH => {
+                        children.extend(pair);
+                    }
+                    _ => {
+                        subtrees.push(subtree);
+                        to_fetch.push(subtree);
+                    }
+                }
+            }
+
+            let mut differing = Vec::new();
+            for batch in children.chunks(MAX_SUBTREE_HASHES) {
+                let request = StateSyncRequest {
+                    root: Some(root),
+                    query: StateQuery::Hashes(batch.to_vec()),
+                };
+                let response = self.request(swarm, peer, request).await?;
+                if response.root != root {
+                    return Ok(AtRoot::Moved(response.root));
+                }
+                let hashes = match response.answer {
+                    Some(StateAnswer::Hashes(hashes)) if hashes.len() == batch.len() => hashes,
+                    _ => return Err(NetworkError::InvalidMessage(format!("Invalid subtree hashes from {}", peer))),
+                };
+
+                let local = {
+                    let smt = smt.lock().unwrap();
+                    batch
+                        .iter()
+                        .map(|subtree| smt.subtree_hash(subtree))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| NetworkError::StorageError(e.to_string()))?
+                };
+
+                for ((subtree, hash), local) in batch.iter().zip(hashes).zip(local) {
+                    if hash == local {
+                        continue;
+                    }
+                    if hash == [0u8; 32] {
+                        // The peer has no leaves here, so there is nothing to fetch
+                        subtrees.push(*subtree);
+                    } else {
+                        differing.push(*subtree);
+                    }
+                }
+            }
+            frontier = differing;
+        }
+
+        let mut leaves = Vec::new();
+        for subtree in &to_fetch {
+            match self.fetch_leaves(swarm, peer, root, subtree).await? {
+                AtRoot::Answered(fetched) => leaves.extend(fetched),
+                AtRoot::Moved(moved) => return Ok(AtRoot::Moved(moved)),
+            }
+        }
+
+        Ok(AtRoot::Answered(StateDiff { root, subtrees, leaves }))
+    }
+
+    /// Pages through a peer's leaves under a subtree at a root, verifying
+    /// each chunk against the root.
+    async fn fetch_leaves(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        root: [u8; 32],
+        subtree: &Subtree,
+    ) -> Result<AtRoot<Vec<StateLeaf>>, NetworkError> {
+        let mut leaves = Vec::new();
+        let mut after = None;
+        loop {
+            let request = StateSyncRequest {
+                root: Some(root),
+                query: StateQuery::Leaves {
+                    subtree: *subtree,
+                    after,
+                    limit: STATE_CHUNK_SIZE,
+                },
+            };
+            let response = self.request(swarm, peer, request).await?;
+            if response.root != root {
+                return Ok(AtRoot::Moved(response.root));
+            }
+            let chunk = match response.answer {
+                Some(StateAnswer::Leaves(chunk)) => chunk,
+                _ => return Err(NetworkError::InvalidMessage(format!("Invalid state chunk from {}", peer))),
+            };
+
+            chunk.verify(&root, subtree, after.as_ref()).map_err(|e| {
+                NetworkError::InvalidMessage(format!("Invalid state chunk from {}: {}", peer, e))
+            })?;
+            if chunk.leaves.is_empty() && !chunk.last {
+                return Err(NetworkError::InvalidMessage(format!("Empty state chunk from {}", peer)));
+            }
+
+            after = chunk.leaves.last().map(StateLeaf::key).or(after);
+            leaves.extend(chunk.leaves);
+            if chunk.last {
+                return Ok(AtRoot::Answered(leaves));
+            }
+        }
+    }
+
+    /// Sends one state sync request and waits for its response.
+    async fn request(
+        &self,
+        swarm: &Mutex<Swarm<NodeBehaviour>>,
+        peer: PeerId,
+        request: StateSyncRequest,
+    ) -> Result<StateSyncResponse, NetworkError> {
+        let (sender, receiver) = oneshot::channel();
+
+        // Register the caller before the response can arrive
+        let request_id = {
+            let mut swarm = swarm.lock().unwrap();
+            let request_id = swarm.behaviour_mut().state_sync.send_request(&peer, request);
+            self.pending.lock().unwrap().insert(request_id, sender);
+            request_id
+        };
+
+        match timeout(Duration::from_secs(STATE_SYNC_TIMEOUT_SECS), receiver).await {
+            Ok(result) => result.map_err(|_| NetworkError::Timeout("state sync".to_string()))?,
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request_id);
+                Err(NetworkError::Timeout("state sync".to_string()))
+            }
+        }
+    }
+}
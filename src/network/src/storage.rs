@@ -1,8 +1,14 @@
 //! Storage implementation for the network layer.
 
 use crate::errors::NetworkError;
-use core::{proofs::Proof, types::Address};
-use rocksdb::{Options, DB};
+use core::{
+    confidential::ConfidentialNote,
+    governance::ProposalId,
+    proofs::Proof,
+    smt::AccountProof,
+    types::{Address, TxRecord},
+};
+use rocksdb::{Direction, IteratorMode, Options, DB};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
@@ -16,6 +22,25 @@ struct ProofKey {
     root: [u8; 32],
 }
 
+/// Key prefix for transaction records, followed by the hex transaction hash.
+const TX_PREFIX: &str = "tx::";
+
+/// Key prefix for the per-address transaction index, followed by
+/// `<hex address>::<hex transaction hash>`.
+const TX_INDEX_PREFIX: &str = "txidx::";
+
+/// Key prefix for the index of stealth payments, followed by
+/// `<16-digit hex timestamp>::<hex transaction hash>`.
+const STEALTH_INDEX_PREFIX: &str = "stealthidx::";
+
+/// Key prefix for confidential transfer notes, followed by
+/// `<hex recipient>::<hex amount commitment>`.
+const NOTE_PREFIX: &str = "cnote::";
+
+/// Key prefix for governance snapshot balance proofs, followed by
+/// `<hex proposal ID>::<hex address>`.
+const VOTING_PROOF_PREFIX: &str = "vproof::";
+
 /// A wrapper around RocksDB for storing and retrieving proofs.
 #[derive(Clone)]
 pub struct ProofStore {
@@ -112,6 +137,188 @@ impl ProofStore {
         
         Ok(exists)
     }
+
+    /// Stores a transaction record and indexes it under both the sender and recipient.
+    ///
+    /// Stealth payments are also indexed by time, so that recipients can scan them.
+    pub fn put_tx_record(&self, record: &TxRecord) -> Result<(), NetworkError> {
+        let tx_hash_hex = hex::encode(record.tx_hash);
+        let record_bytes = bincode::serialize(record)
+            .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+        let db = self.db.lock().unwrap();
+        db.put(format!("{}{}", TX_PREFIX, tx_hash_hex), record_bytes)
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+        for address in [&record.from, &record.to] {
+            let index_key = format!("{}{}::{}", TX_INDEX_PREFIX, hex::encode(address), tx_hash_hex);
+            db.put(index_key, b"")
+                .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+        }
+
+        if record.ephemeral_key.is_some() {
+            let index_key = format!("{}{:016x}::{}", STEALTH_INDEX_PREFIX, record.timestamp, tx_hash_hex);
+            db.put(index_key, b"")
+                .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieves a transaction record by its hash.
+    pub fn get_tx_record(&self, tx_hash: &[u8; 32]) -> Result<Option<TxRecord>, NetworkError> {
+        let key = format!("{}{}", TX_PREFIX, hex::encode(tx_hash));
+
+        let record_bytes = self
+            .db
+            .lock()
+            .unwrap()
+            .get(key)
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+        match record_bytes {
+            Some(bytes) => bincode::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| NetworkError::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Retrieves all transaction records sent or received by an address, oldest first.
+    pub fn get_tx_records(&self, address: &Address) -> Result<Vec<TxRecord>, NetworkError> {
+        let prefix = format!("{}{}::", TX_INDEX_PREFIX, hex::encode(address));
+
+        let tx_hashes: Vec<String> = {
+            let db = self.db.lock().unwrap();
+            let iter = db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward));
+
+            let mut tx_hashes = Vec::new();
+            for item in iter {
+                let (key, _) = item.map_err(|e| NetworkError::StorageError(e.to_string()))?;
+                let key_str = String::from_utf8_lossy(&key);
+                match key_str.strip_prefix(prefix.as_str()) {
+                    Some(tx_hash_hex) => tx_hashes.push(tx_hash_hex.to_string()),
+                    None => break,
+                }
+            }
+            tx_hashes
+        };
+
+        self.get_tx_records_by_hash(tx_hashes)
+    }
+
+    /// Retrieves the stealth payments processed at or after `since` (Unix
+    /// seconds), oldest first.
+    pub fn get_stealth_tx_records(&self, since: u64) -> Result<Vec<TxRecord>, NetworkError> {
+        let start = format!("{}{:016x}::", STEALTH_INDEX_PREFIX, since);
+
+        let tx_hashes: Vec<String> = {
+            let db = self.db.lock().unwrap();
+            let iter = db.iterator(IteratorMode::From(start.as_bytes(), Direction::Forward));
+
+            let mut tx_hashes = Vec::new();
+            for item in iter {
+                let (key, _) = item.map_err(|e| NetworkError::StorageError(e.to_string()))?;
+                let key_str = String::from_utf8_lossy(&key);
+                match key_str.strip_prefix(STEALTH_INDEX_PREFIX).and_then(|rest| rest.split("::").nth(1)) {
+                    Some(tx_hash_hex) => tx_hashes.push(tx_hash_hex.to_string()),
+                    None => break,
+                }
+            }
+            tx_hashes
+        };
+
+        self.get_tx_records_by_hash(tx_hashes)
+    }
+
+    /// Loads the transaction records with the given hex hashes, oldest first.
+    fn get_tx_records_by_hash(&self, tx_hashes: Vec<String>) -> Result<Vec<TxRecord>, NetworkError> {
+        let mut records = Vec::with_capacity(tx_hashes.len());
+        for tx_hash_hex in tx_hashes {
+            let record_bytes = self
+                .db
+                .lock()
+                .unwrap()
+                .get(format!("{}{}", TX_PREFIX, tx_hash_hex))
+                .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+            if let Some(bytes) = record_bytes {
+                let record: TxRecord = bincode::deserialize(&bytes)
+                    .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+                records.push(record);
+            }
+        }
+
+        records.sort_by_key(|record| record.timestamp);
+        Ok(records)
+    }
+
+    /// Stores the note of a confidential transfer under its recipient.
+    pub fn put_confidential_note(&self, note: &ConfidentialNote) -> Result<(), NetworkError> {
+        let key = format!(
+            "{}{}::{}",
+            NOTE_PREFIX,
+            hex::encode(note.to),
+            hex::encode(note.amount_commitment)
+        );
+        let note_bytes = bincode::serialize(note)
+            .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+        let db = self.db.lock().unwrap();
+        db.put(key, note_bytes)
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Gets the confidential transfer notes received by an address, oldest first.
+    pub fn get_confidential_notes(&self, address: &Address) -> Result<Vec<ConfidentialNote>, NetworkError> {
+        let prefix = format!("{}{}::", NOTE_PREFIX, hex::encode(address));
+
+        let db = self.db.lock().unwrap();
+        let iter = db.iterator(IteratorMode::From(prefix.as_bytes(), Direction::Forward));
+
+        let mut notes = Vec::new();
+        for item in iter {
+            let (key, value) = item.map_err(|e| NetworkError::StorageError(e.to_string()))?;
+            if !key.starts_with(prefix.as_bytes()) {
+                break;
+            }
+
+            let note: ConfidentialNote = bincode::deserialize(&value)
+                .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+            notes.push(note);
+        }
+
+        notes.sort_by_key(|note| note.timestamp);
+        Ok(notes)
+    }
+
+    /// Stores a holder's balance proof against a proposal's snapshot.
+    pub fn put_voting_proof(&self, proposal_id: &ProposalId, address: &Address, proof: &AccountProof) -> Result<(), NetworkError> {
+        let key = format!("{}{}::{}", VOTING_PROOF_PREFIX, hex::encode(proposal_id), hex::encode(address));
+        let proof_bytes = bincode::serialize(proof)
+            .map_err(|e| NetworkError::SerializationError(e.to_string()))?;
+
+        let db = self.db.lock().unwrap();
+        db.put(key, proof_bytes)
+            .map_err(|e| NetworkError::StorageError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Gets a holder's balance proof against a proposal's snapshot.
+    pub fn get_voting_proof(&self, proposal_id: &ProposalId, address: &Address) -> Result<Option<AccountProof>, NetworkError> {
+        let key = format!("{}{}::{}", VOTING_PROOF_PREFIX, hex::encode(proposal_id), hex::encode(address));
+
+        let db = self.db.lock().unwrap();
+        match db.get(key).map_err(|e| NetworkError::StorageError(e.to_string()))? {
+            Some(bytes) => bincode::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| NetworkError::SerializationError(e.to_string())),
+            None => Ok(None),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -151,4 +358,40 @@ mod tests {
         assert_eq!(retrieved.siblings.len(), proof.siblings.len());
         assert_eq!(retrieved.path, proof.path);
     }
+
+    #[test]
+    fn test_tx_records() {
+        let dir = tempdir().unwrap();
+        let store = ProofStore::new(dir.path()).unwrap();
+
+        let from = [1u8; 32];
+        let to = [2u8; 32];
+        let other = [3u8; 32];
+
+        let record = TxRecord::new(
+            from,
+            to,
+            0,
+            100,
+            0,
+            b"INV-42".to_vec(),
+            &core::types::Signature([0u8; 64]),
+        );
+        store.put_tx_record(&record).unwrap();
+
+        // The record is reachable by hash and from both parties' histories
+        assert_eq!(store.get_tx_record(&record.tx_hash).unwrap(), Some(record.clone()));
+        assert_eq!(store.get_tx_records(&from).unwrap(), vec![record.clone()]);
+        assert_eq!(store.get_tx_records(&to).unwrap()[0].memo_text(), "INV-42");
+        assert!(store.get_tx_records(&other).unwrap().is_empty());
+
+        // Only stealth payments are in the stealth index
+        assert!(store.get_stealth_tx_records(0).unwrap().is_empty());
+
+        let stealth = TxRecord::new(from, other, 0, 5, 1, Vec::new(), &core::types::Signature([1u8; 64]))
+            .with_ephemeral_key(Some([4u8; 32]));
+        store.put_tx_record(&stealth).unwrap();
+        assert_eq!(store.get_stealth_tx_records(0).unwrap(), vec![stealth.clone()]);
+        assert!(store.get_stealth_tx_records(stealth.timestamp + 1).unwrap().is_empty());
+    }
 }
@@ -2,38 +2,61 @@
 
 use crate::dht::DHTManager;
 use crate::errors::NetworkError;
-use crate::gossip::{message_id_fn, new_gossipsub, STATE_UPDATES_TOPIC};
-use crate::types::{ProofRequest, ProofResponse, UpdateMsg};
+use crate::gossip::{message_id_fn, new_gossipsub, GossipConfig};
+use crate::peer_guard::{ConnectionConfig, PeerGuard};
+use crate::proof_exchange::{new_proof_behaviour, ProofBehaviour, ProofEvent, ProofExchange};
+use crate::record_store::{RecordStoreConfig, RocksRecordStore};
+use crate::state_sync::{new_state_sync_behaviour, StateSync, StateSyncBehaviour, StateSyncEvent};
+use crate::types::{
+    AttestationMsg, BurnMsg, IssueTokenMsg, MintMsg, ProofRequest, ProofResponse, StateSyncRequest, StateSyncResponse,
+    UpdateMsg,
+};
+use core::types::SystemMsg;
 use ::futures::StreamExt;
 use libp2p::{
+    connection_limits::{self, ConnectionLimits},
     core::{upgrade, transport::Transport},
     identify,
     identity::Keypair,
-    kad::{store::MemoryStore, Kademlia, KademliaConfig, KademliaEvent, record::Key as KadKey},
+    kad::{Kademlia, KademliaConfig, KademliaEvent, record::Key as KadKey},
+    mdns,
     noise,
     ping,
+    request_response::ResponseChannel,
     gossipsub::{Behaviour as Gossipsub, Event as GossipsubEvent, MessageId},
     Multiaddr, PeerId, Swarm,
-    swarm::{SwarmBuilder, SwarmEvent},
+    swarm::{behaviour::toggle::Toggle, SwarmBuilder, SwarmEvent},
     tcp, yamux,
 };
 use libp2p::swarm::derive_prelude::*;
 use std::collections::HashSet;
+use std::path::Path;
 use std::time::Duration;
+use void::Void;
 
 /// The network behavior for the node.
 use libp2p::swarm::NetworkBehaviour;
 #[derive(NetworkBehaviour)]
 #[behaviour(out_event = "NetworkBehaviourEvent")]
 pub struct NodeBehaviour {
+    /// Limits on the total, inbound and outbound connections
+    pub limits: connection_limits::Behaviour,
+    /// Per-IP connection limits and the ban list
+    pub guard: PeerGuard,
     /// Kademlia DHT for storing and retrieving proofs
-    pub kademlia: Kademlia<MemoryStore>,
+    pub kademlia: Kademlia<RocksRecordStore>,
     /// Gossipsub for broadcasting state updates
     pub gossipsub: Gossipsub,
     /// Ping for keeping connections alive
     pub ping: ping::Behaviour,
     /// Identify for discovering peer information
     pub identify: identify::Behaviour,
+    /// Request-response for exchanging account proofs
+    pub proofs: ProofBehaviour,
+    /// Request-response for syncing state from peers
+    pub state_sync: StateSyncBehaviour,
+    /// mDNS for discovering peers on the local network, if enabled
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
 }
 
 /// Events emitted by the network behavior.
@@ -47,6 +70,18 @@ pub enum NetworkBehaviourEvent {
     Ping(ping::Event),
     /// Identify event
     Identify(identify::Event),
+    /// Proof request-response event
+    Proofs(ProofEvent),
+    /// State sync request-response event
+    StateSync(StateSyncEvent),
+    /// mDNS event
+    Mdns(mdns::Event),
+}
+
+impl From<Void> for NetworkBehaviourEvent {
+    fn from(event: Void) -> Self {
+        void::unreachable(event)
+    }
 }
 
 impl From<KademliaEvent> for NetworkBehaviourEvent {
@@ -73,15 +108,52 @@ impl From<identify::Event> for NetworkBehaviourEvent {
     }
 }
 
+impl From<ProofEvent> for NetworkBehaviourEvent {
+    fn from(event: ProofEvent) -> Self {
+        NetworkBehaviourEvent::Proofs(event)
+    }
+}
+
+impl From<StateSyncEvent> for NetworkBehaviourEvent {
+    fn from(event: StateSyncEvent) -> Self {
+        NetworkBehaviourEvent::StateSync(event)
+    }
+}
+
+impl From<mdns::Event> for NetworkBehaviourEvent {
+    fn from(event: mdns::Event) -> Self {
+        NetworkBehaviourEvent::Mdns(event)
+    }
+}
+
+/// The maximum number of connections to one peer, which allows a
+/// simultaneous dial in each direction.
+const MAX_CONNECTIONS_PER_PEER: u32 = 2;
+
 /// Events emitted by the network.
 #[derive(Debug)]
 pub enum NetworkEvent {
-    /// A state update was received
-    UpdateReceived(UpdateMsg),
-    /// A proof request was received
-    ProofRequestReceived(ProofRequest, PeerId),
-    /// A proof response was received
+    /// A state update was received from a peer, to be validated and reported
+    /// to gossipsub by its message ID
+    UpdateReceived(UpdateMsg, MessageId, PeerId),
+    /// A system message was received from a peer, to be validated and
+    /// reported to gossipsub by its message ID
+    SystemMessageReceived(SystemMsg, MessageId, PeerId),
+    /// A treasury mint was received from a peer, to be validated and reported
+    MintReceived(MintMsg, MessageId, PeerId),
+    /// A burn was received from a peer, to be validated and reported
+    BurnReceived(BurnMsg, MessageId, PeerId),
+    /// A token issuance was received from a peer, to be validated and reported
+    IssueTokenReceived(IssueTokenMsg, MessageId, PeerId),
+    /// A state root attestation was received from a peer, to be validated
+    /// and reported
+    AttestationReceived(AttestationMsg, MessageId, PeerId),
+    /// A proof request was received, to be answered on the channel
+    ProofRequestReceived(ProofRequest, PeerId, ResponseChannel<ProofResponse>),
+    /// A proof response was received that no request was waiting for
     ProofResponseReceived(ProofResponse),
+    /// A state sync request was received, to be answered on the channel
+    StateSyncRequestReceived(StateSyncRequest, PeerId, ResponseChannel<StateSyncResponse>),
     /// A new peer was discovered
     PeerDiscovered(PeerId),
     /// A peer was disconnected
@@ -90,12 +162,29 @@ pub enum NetworkEvent {
     PeerIdentified(PeerId, Multiaddr),
 }
 
-/// Initializes the network swarm.
+impl NodeBehaviour {
+    /// Bans a peer for `duration`, closing its connections and removing it
+    /// from the explicit gossipsub peers.
+    pub fn ban_peer(&mut self, peer: PeerId, duration: Duration, reason: &str) {
+        self.guard.ban(peer, duration, reason);
+        self.gossipsub.remove_explicit_peer(&peer);
+    }
+}
+
+/// Initializes the network swarm with the node's identity key, storing DHT
+/// records at `dht_path` and the ban list at `ban_list_path`. With
+/// `enable_mdns`, peers on the local network are discovered over mDNS.
+#[allow(clippy::too_many_arguments)]
 pub async fn init_swarm(
+    local_key: Keypair,
     bootstrap_nodes: Vec<Multiaddr>,
-) -> Result<(Swarm<NodeBehaviour>, DHTManager), NetworkError> {
-    // Generate a random identity
-    let local_key = Keypair::generate_ed25519();
+    dht_path: &Path,
+    dht_config: RecordStoreConfig,
+    gossip_config: GossipConfig,
+    ban_list_path: &Path,
+    connection_config: ConnectionConfig,
+    enable_mdns: bool,
+) -> Result<(Swarm<NodeBehaviour>, DHTManager, ProofExchange, StateSync), NetworkError> {
     let local_peer_id = PeerId::from(local_key.public());
 
     // Create a custom transport with TCP, Noise, and Yamux
@@ -110,12 +199,16 @@ pub async fn init_swarm(
     // Create a Kademlia instance
     let mut kademlia_config = KademliaConfig::default();
     kademlia_config.set_query_timeout(Duration::from_secs(30));
+    kademlia_config.set_record_ttl(Some(dht_config.record_ttl));
+    kademlia_config.set_publication_interval(Some(dht_config.publication_interval));
+    kademlia_config.set_replication_interval(Some(dht_config.replication_interval));
 
-    let store = MemoryStore::new(local_peer_id);
+    let store = RocksRecordStore::open(dht_path, local_peer_id, dht_config)?;
     let mut kademlia = Kademlia::with_config(local_peer_id, store, kademlia_config);
 
     // Add bootstrap nodes
     use libp2p::multiaddr::Protocol;
+    let mut bootstrap_peers = Vec::new();
     for addr in bootstrap_nodes.iter() {
         // pull out any Protocol::P2p(peer_id) entries
         let maybe_peer = addr
@@ -132,12 +225,19 @@ pub async fn init_swarm(
 
         if let Some(peer_id) = maybe_peer {
             kademlia.add_address(&peer_id, addr.clone());
+            bootstrap_peers.push(peer_id);
             log::info!("Bootstrapped Kademlia to {} at {}", peer_id, addr);
         } else {
             log::warn!("Bootstrap address {} missing /p2p/<PeerId>", addr);
         }
     }    // Create a Gossipsub instance
-    let gossipsub = new_gossipsub(&local_key, &local_peer_id)?;
+    let mut gossipsub = new_gossipsub(&local_key, &local_peer_id, &gossip_config)?;
+
+    // Only configured bootstrap peers bypass peer scoring; discovered peers
+    // join the mesh through gossipsub and can be graylisted
+    for peer_id in &bootstrap_peers {
+        gossipsub.add_explicit_peer(peer_id);
+    }
 
     // Create a Ping instance
     let ping = ping::Behaviour::new(ping::Config::new());
@@ -148,12 +248,44 @@ pub async fn init_swarm(
         local_key.public(),
     ));
 
+    // Create a request-response instance for proofs
+    let proofs = new_proof_behaviour();
+
+    // Create a request-response instance for state sync
+    let state_sync = new_state_sync_behaviour();
+
+    // Limit connections, with inbound and outbound connections each capped
+    // below the total so neither direction can take all the slots
+    let limits = connection_limits::Behaviour::new(
+        ConnectionLimits::default()
+            .with_max_established(Some(connection_config.max_peers))
+            .with_max_established_incoming(Some(connection_config.max_inbound))
+            .with_max_established_outgoing(Some(connection_config.max_outbound))
+            .with_max_established_per_peer(Some(MAX_CONNECTIONS_PER_PEER)),
+    );
+    let guard = PeerGuard::new(connection_config, ban_list_path)?;
+
+    // Create an mDNS instance if local discovery is enabled
+    let mdns = if enable_mdns {
+        let mdns = mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id)
+            .map_err(|e| NetworkError::Libp2pError(format!("Failed to start mDNS: {}", e)))?;
+        log::info!("mDNS discovery enabled");
+        Some(mdns)
+    } else {
+        None
+    };
+
     // Create the network behavior
     let behaviour = NodeBehaviour {
+        limits,
+        guard,
         kademlia,
         gossipsub,
         ping,
         identify,
+        proofs,
+        state_sync,
+        mdns: mdns.into(),
     };
 
     // Create the swarm
@@ -178,27 +310,72 @@ pub async fn init_swarm(
      // Create the DHT manager
      let dht_manager = DHTManager::new();
  
-     Ok((swarm, dht_manager))
+     Ok((swarm, dht_manager, ProofExchange::new(), StateSync::new()))
+}
+
+/// Handles an mDNS event.
+///
+/// Discovered peers that are not banned are added to Kademlia and dialed if
+/// not yet connected, and expired addresses are removed from Kademlia.
+fn handle_mdns_event(event: mdns::Event, swarm: &mut Swarm<NodeBehaviour>) {
+    match event {
+        mdns::Event::Discovered(peers) => {
+            let mut to_dial = HashSet::new();
+            for (peer_id, addr) in peers {
+                let behaviour = swarm.behaviour_mut();
+                if behaviour.guard.is_banned(&peer_id) {
+                    log::debug!("Ignoring banned peer {} discovered over mDNS", peer_id);
+                    continue;
+                }
+                log::info!("Discovered peer {} at {} over mDNS", peer_id, addr);
+                behaviour.kademlia.add_address(&peer_id, addr);
+                if !swarm.is_connected(&peer_id) {
+                    to_dial.insert(peer_id);
+                }
+            }
+
+            for peer_id in to_dial {
+                if let Err(e) = swarm.dial(peer_id) {
+                    log::debug!("Failed to dial peer {} discovered over mDNS: {}", peer_id, e);
+                }
+            }
+        }
+        mdns::Event::Expired(peers) => {
+            for (peer_id, addr) in peers {
+                log::debug!("mDNS address {} of peer {} expired", addr, peer_id);
+                swarm.behaviour_mut().kademlia.remove_address(&peer_id, &addr);
+            }
+        }
+    }
 }
 
 /// Handles a network event.
 pub async fn handle_network_event(
     event: SwarmEvent<NetworkBehaviourEvent, impl std::fmt::Debug>,
     dht_manager: &DHTManager,
+    proof_exchange: &ProofExchange,
+    state_sync: &StateSync,
     known_peers: &mut HashSet<PeerId>,
     swarm: &mut Swarm<NodeBehaviour>,
 ) -> Result<Option<NetworkEvent>, NetworkError> {
     match event {
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Gossipsub(gossipsub_event)) => {
             log::debug!("Gossipsub event: {:?}", gossipsub_event);
-            if let Some(update) = crate::gossip::handle_gossipsub_event(gossipsub_event)? {
-                log::info!("Received update from gossip network: {:?}", update);
-                return Ok(Some(NetworkEvent::UpdateReceived(update)));
-            }
+            let behaviour = swarm.behaviour_mut();
+            return Ok(crate::gossip::handle_gossipsub_event(gossipsub_event, &mut behaviour.gossipsub, &mut behaviour.guard));
         }
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Kademlia(kademlia_event)) => {
             dht_manager.handle_event(kademlia_event, &mut swarm.behaviour_mut().kademlia);
         }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::Proofs(proof_event)) => {
+            return Ok(proof_exchange.handle_event(proof_event));
+        }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::StateSync(state_sync_event)) => {
+            return Ok(state_sync.handle_event(state_sync_event));
+        }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::Mdns(mdns_event)) => {
+            handle_mdns_event(mdns_event, swarm);
+        }
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Identify(identify::Event::Received {
             peer_id,
             info,
@@ -218,11 +395,7 @@ pub async fn handle_network_event(
             peer_id, endpoint, ..
         } => {
             log::info!("Connection established with peer: {} at {:?}", peer_id, endpoint);
-            
-            // Add the peer to the gossipsub mesh explicitly
-            swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
-            log::info!("Added peer {} to gossipsub mesh explicitly", peer_id);
-            
+
             if known_peers.insert(peer_id) {
                 return Ok(Some(NetworkEvent::PeerDiscovered(peer_id)));
             }
@@ -244,18 +417,28 @@ pub async fn handle_network_event(
 pub fn handle_network_event_sync(
     event: SwarmEvent<NetworkBehaviourEvent, impl std::fmt::Debug>,
     dht_manager: &DHTManager,
+    proof_exchange: &ProofExchange,
+    state_sync: &StateSync,
     known_peers: &mut HashSet<PeerId>,
     swarm: &mut Swarm<NodeBehaviour>,
 ) -> Result<Option<NetworkEvent>, NetworkError> {
     match event {
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Gossipsub(gossipsub_event)) => {
-            if let Some(update) = crate::gossip::handle_gossipsub_event(gossipsub_event)? {
-                return Ok(Some(NetworkEvent::UpdateReceived(update)));
-            }
+            let behaviour = swarm.behaviour_mut();
+            return Ok(crate::gossip::handle_gossipsub_event(gossipsub_event, &mut behaviour.gossipsub, &mut behaviour.guard));
         }
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Kademlia(kademlia_event)) => {
             dht_manager.handle_event_sync(kademlia_event, &mut swarm.behaviour_mut().kademlia);
         }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::Proofs(proof_event)) => {
+            return Ok(proof_exchange.handle_event(proof_event));
+        }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::StateSync(state_sync_event)) => {
+            return Ok(state_sync.handle_event(state_sync_event));
+        }
+        SwarmEvent::Behaviour(NetworkBehaviourEvent::Mdns(mdns_event)) => {
+            handle_mdns_event(mdns_event, swarm);
+        }
         SwarmEvent::Behaviour(NetworkBehaviourEvent::Identify(identify::Event::Received {
             peer_id,
             info,
@@ -270,10 +453,6 @@ pub fn handle_network_event_sync(
                     .kademlia
                     .add_address(&peer_id, addr.clone());
                 log::info!("Added peer {} address {} to Kademlia", peer_id, addr);
-                
-                // Add the peer to the gossipsub mesh explicitly
-                swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
-                log::info!("Added peer {} to gossipsub mesh explicitly", peer_id);
 
                 return Ok(Some(NetworkEvent::PeerIdentified(peer_id, addr)));
             }
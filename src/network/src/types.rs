@@ -1,6 +1,12 @@
 //! Network message types for the chainless token transfer network.
 
-use core::{proofs::Proof, types::Address};
+use crate::errors::NetworkError;
+use core::{
+    proofs::Proof,
+    smt::{StateChunk, Subtree},
+    stealth::EphemeralKey,
+    types::{Address, Memo, SignatureScheme, SystemMsg, TxRecord},
+};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -25,10 +31,35 @@ pub struct UpdateMsg {
     pub proof_to: Proof,
     /// The nonce for this transaction
     pub nonce: u64,
+    /// Optional payment reference, covered by the signature
+    #[serde(default)]
+    pub memo: Memo,
+    /// Ephemeral key of a stealth payment, covered by the signature
+    #[serde(default)]
+    pub ephemeral_key: Option<EphemeralKey>,
+    /// The scheme the sender signed with
+    #[serde(default)]
+    pub scheme: SignatureScheme,
     /// The signature of the sender
     pub signature: core::types::Signature,
 }
 
+impl UpdateMsg {
+    /// Builds the node's transaction record for this update.
+    pub fn to_tx_record(&self) -> TxRecord {
+        TxRecord::new(
+            self.from,
+            self.to,
+            self.token_id,
+            self.amount,
+            self.nonce,
+            self.memo.clone(),
+            &self.signature,
+        )
+        .with_ephemeral_key(self.ephemeral_key)
+    }
+}
+
 /// Message for minting new tokens (can only be sent by the treasury).
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MintMsg {
@@ -52,6 +83,29 @@ pub struct MintMsg {
     pub signature: core::types::Signature,
 }
 
+impl MintMsg {
+    /// Builds the bytes the treasury signs for a mint:
+    /// `mint:<from>:<to>:<token_id>:<amount>:<nonce>`, with addresses as
+    /// lowercase hex without a prefix. The treasury's nonce is signed, so a
+    /// mint applies at most once.
+    pub fn signing_message(from: &Address, to: &Address, token_id: u64, amount: u128, nonce: u64) -> Vec<u8> {
+        format!(
+            "mint:{}:{}:{}:{}:{}",
+            hex::encode(from),
+            hex::encode(to),
+            token_id,
+            amount,
+            nonce
+        )
+        .into_bytes()
+    }
+
+    /// Builds the bytes the treasury signed for this mint.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        Self::signing_message(&self.from, &self.to, self.token_id, self.amount, self.nonce)
+    }
+}
+
 impl fmt::Display for MintMsg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
@@ -62,6 +116,182 @@ impl fmt::Display for MintMsg {
     }
 }
 
+/// Message for burning tokens (can only be sent by the token holder).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BurnMsg {
+    /// The holder's address
+    pub from: Address,
+    /// The token ID
+    pub token_id: u64,
+    /// The amount to burn
+    pub amount: u128,
+    /// The nonce for this transaction
+    pub nonce: u64,
+    /// The signature of the holder
+    pub signature: core::types::Signature,
+}
+
+impl From<BurnMsg> for SystemMsg {
+    fn from(burn: BurnMsg) -> Self {
+        SystemMsg::Burn {
+            from: burn.from,
+            token_id: burn.token_id,
+            amount: burn.amount,
+            nonce: burn.nonce,
+            signature: burn.signature,
+        }
+    }
+}
+
+/// Message for issuing a new token.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IssueTokenMsg {
+    /// The issuer's address
+    pub issuer: Address,
+    /// The token ID
+    pub token_id: u64,
+    /// Token metadata (name, symbol, decimals, etc.)
+    pub metadata: String,
+    /// Native tokens locked in escrow until the token is retired
+    pub collateral: u128,
+    /// The nonce for this transaction
+    pub nonce: u64,
+    /// The signature of the issuer
+    pub signature: core::types::Signature,
+}
+
+impl From<IssueTokenMsg> for SystemMsg {
+    fn from(issue: IssueTokenMsg) -> Self {
+        SystemMsg::IssueToken {
+            issuer: issue.issuer,
+            token_id: issue.token_id,
+            metadata: issue.metadata,
+            collateral: issue.collateral,
+            nonce: issue.nonce,
+            signature: issue.signature,
+        }
+    }
+}
+
+/// A signed statement that the attester's state reached a root.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AttestationMsg {
+    /// The attester's address
+    pub attester: Address,
+    /// The attested root hash
+    pub root: [u8; 32],
+    /// Unix time of the attestation, in seconds
+    pub timestamp: u64,
+    /// The signature of the attester
+    pub signature: core::types::Signature,
+}
+
+/// A message published over gossip.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum GossipMessage {
+    /// A token transfer
+    Transfer(UpdateMsg),
+    /// A mint by the treasury
+    Mint(MintMsg),
+    /// A burn by a token holder
+    Burn(BurnMsg),
+    /// The issuance of a new token
+    IssueToken(IssueTokenMsg),
+    /// An attestation of a state root
+    Attestation(AttestationMsg),
+    /// A signed system message, such as a name registration or a vote
+    System(SystemMsg),
+}
+
+impl AttestationMsg {
+    /// Builds the bytes the attester signs: the bincode encoding of the
+    /// attestation with an all-zero signature.
+    pub fn signing_bytes(&self) -> Result<Vec<u8>, NetworkError> {
+        let mut unsigned = self.clone();
+        unsigned.signature = core::types::Signature([0u8; 64]);
+        bincode::serialize(&unsigned).map_err(|e| NetworkError::SerializationError(e.to_string()))
+    }
+}
+
+impl From<SystemMsg> for GossipMessage {
+    /// Wraps a system message for gossip, using the dedicated kind of burns
+    /// and token issuance.
+    fn from(message: SystemMsg) -> Self {
+        match message {
+            SystemMsg::Burn { from, token_id, amount, nonce, signature } => {
+                GossipMessage::Burn(BurnMsg { from, token_id, amount, nonce, signature })
+            }
+            SystemMsg::IssueToken { issuer, token_id, metadata, collateral, nonce, signature } => {
+                GossipMessage::IssueToken(IssueTokenMsg { issuer, token_id, metadata, collateral, nonce, signature })
+            }
+            message => GossipMessage::System(message),
+        }
+    }
+}
+
+/// The versioned wire format of gossip messages.
+///
+/// New versions are added as new variants, so that nodes reject messages of
+/// versions they do not know instead of misreading them.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum GossipEnvelope {
+    /// Version 1
+    V1(GossipMessage),
+}
+
+impl GossipEnvelope {
+    /// The newest envelope version this node understands.
+    pub const LATEST_VERSION: u32 = 1;
+
+    /// Wraps a message in the latest envelope version.
+    pub fn new(message: GossipMessage) -> Self {
+        GossipEnvelope::V1(message)
+    }
+
+    /// Returns the version of the envelope.
+    pub fn version(&self) -> u32 {
+        match self {
+            GossipEnvelope::V1(_) => 1,
+        }
+    }
+
+    /// Returns the message in the envelope.
+    pub fn message(&self) -> &GossipMessage {
+        match self {
+            GossipEnvelope::V1(message) => message,
+        }
+    }
+
+    /// Unwraps the message in the envelope.
+    pub fn into_message(self) -> GossipMessage {
+        match self {
+            GossipEnvelope::V1(message) => message,
+        }
+    }
+
+    /// Encodes the envelope for publishing.
+    pub fn encode(&self) -> Result<Vec<u8>, NetworkError> {
+        bincode::serialize(self).map_err(|e| NetworkError::SerializationError(e.to_string()))
+    }
+
+    /// Decodes an envelope, failing with
+    /// [`NetworkError::UnsupportedVersion`] if its version is unknown.
+    pub fn decode(bytes: &[u8]) -> Result<Self, NetworkError> {
+        // bincode writes the variant index as a little-endian u32 before the
+        // payload, so the version can be checked before the payload is decoded
+        let tag: [u8; 4] = bytes
+            .get(..4)
+            .and_then(|tag| tag.try_into().ok())
+            .ok_or_else(|| NetworkError::SerializationError("Gossip envelope is truncated".to_string()))?;
+        let version = u32::from_le_bytes(tag).saturating_add(1);
+        if version > Self::LATEST_VERSION {
+            return Err(NetworkError::UnsupportedVersion(version));
+        }
+
+        bincode::deserialize(bytes).map_err(|e| NetworkError::SerializationError(e.to_string()))
+    }
+}
+
 /// Request for a proof of an account.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ProofRequest {
@@ -78,16 +308,61 @@ pub struct ProofResponse {
     pub address: Address,
     /// The root hash for which the proof was generated
     pub root: [u8; 32],
-    /// The proof itself
-    pub proof: Proof,
+    /// The proof itself, or `None` if the responder has no proof for the
+    /// address at that root
+    pub proof: Option<Proof>,
+}
+
+/// Request for part of a peer's state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateSyncRequest {
+    /// The root to query, or `None` for the peer's current root
+    pub root: Option<[u8; 32]>,
+    /// What to return
+    pub query: StateQuery,
+}
+
+/// A query against a peer's state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StateQuery {
+    /// The hashes of some subtrees
+    Hashes(Vec<Subtree>),
+    /// The leaves of a subtree
+    Leaves {
+        /// The subtree to return leaves of
+        subtree: Subtree,
+        /// The tree key to continue after, or `None` to start from the beginning
+        after: Option<[u8; 32]>,
+        /// The maximum number of leaves to return
+        limit: u32,
+    },
+}
+
+/// Response containing part of a peer's state.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StateSyncResponse {
+    /// The peer's current root
+    pub root: [u8; 32],
+    /// The answer, or `None` if the peer's root is no longer the requested
+    /// one or the query could not be answered
+    pub answer: Option<StateAnswer>,
+}
+
+/// The answer to a [`StateQuery`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum StateAnswer {
+    /// The hashes of the requested subtrees, in order
+    Hashes(Vec<[u8; 32]>),
+    /// A chunk of the requested subtree's leaves
+    Leaves(StateChunk),
 }
 
 impl fmt::Display for UpdateMsg {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "UpdateMsg {{ from: {:?}, to: {:?}, token_id: {}, amount: {}, nonce: {} }}",
-            self.from, self.to, self.token_id, self.amount, self.nonce
+            "UpdateMsg {{ from: {:?}, to: {:?}, token_id: {}, amount: {}, nonce: {}, memo: {} bytes }}",
+            self.from, self.to, self.token_id, self.amount, self.nonce, self.memo.len()
         )
     }
 }
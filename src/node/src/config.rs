@@ -1,6 +1,9 @@
 //! Configuration for the node daemon.
 
+use crate::mempool::MempoolConfig;
 use anyhow::Result;
+use network::gossip::GossipConfig;
+use network::peer_guard::ConnectionConfig;
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::Read;
@@ -17,6 +20,9 @@ pub struct NodeConfig {
     pub metrics: MetricsConfig,
     /// Storage configuration
     pub storage: StorageConfig,
+    /// Transaction pool configuration
+    #[serde(default)]
+    pub mempool: MempoolConfig,
 }
 
 /// Network configuration.
@@ -28,6 +34,89 @@ pub struct NetworkConfig {
     pub bootstrap_nodes: Vec<String>,
     /// Maximum number of peers to connect to
     pub max_peers: usize,
+    /// Minimum number of peers in the gossipsub mesh
+    #[serde(default = "default_mesh_n_low")]
+    pub mesh_n_low: usize,
+    /// Target number of peers in the gossipsub mesh
+    #[serde(default = "default_mesh_n")]
+    pub mesh_n: usize,
+    /// Maximum number of peers in the gossipsub mesh
+    #[serde(default = "default_mesh_n_high")]
+    pub mesh_n_high: usize,
+    /// Maximum number of inbound connections
+    #[serde(default = "default_max_inbound")]
+    pub max_inbound: u32,
+    /// Maximum number of outbound connections
+    #[serde(default = "default_max_outbound")]
+    pub max_outbound: u32,
+    /// Maximum number of inbound connections from one IP address
+    #[serde(default = "default_max_per_ip")]
+    pub max_per_ip: u32,
+    /// Number of invalid messages within ten minutes after which a peer is banned
+    #[serde(default = "default_ban_threshold")]
+    pub ban_threshold: u32,
+    /// How long a peer is banned for after sending invalid messages, in seconds
+    #[serde(default = "default_ban_duration_secs")]
+    pub ban_duration_secs: u64,
+    /// Whether to discover peers on the local network over mDNS
+    #[serde(default)]
+    pub mdns: bool,
+}
+
+impl NetworkConfig {
+    /// Returns the gossipsub mesh parameters.
+    pub fn gossip_config(&self) -> GossipConfig {
+        GossipConfig {
+            mesh_n_low: self.mesh_n_low,
+            mesh_n: self.mesh_n,
+            mesh_n_high: self.mesh_n_high,
+            ..GossipConfig::default()
+        }
+    }
+
+    /// Returns the connection limits and ban settings.
+    pub fn connection_config(&self) -> ConnectionConfig {
+        ConnectionConfig {
+            max_peers: u32::try_from(self.max_peers).unwrap_or(u32::MAX),
+            max_inbound: self.max_inbound,
+            max_outbound: self.max_outbound,
+            max_per_ip: self.max_per_ip,
+            ban_threshold: self.ban_threshold,
+            ban_duration: std::time::Duration::from_secs(self.ban_duration_secs),
+        }
+    }
+}
+
+fn default_mesh_n_low() -> usize {
+    GossipConfig::default().mesh_n_low
+}
+
+fn default_mesh_n() -> usize {
+    GossipConfig::default().mesh_n
+}
+
+fn default_mesh_n_high() -> usize {
+    GossipConfig::default().mesh_n_high
+}
+
+fn default_max_inbound() -> u32 {
+    ConnectionConfig::default().max_inbound
+}
+
+fn default_max_outbound() -> u32 {
+    ConnectionConfig::default().max_outbound
+}
+
+fn default_max_per_ip() -> u32 {
+    ConnectionConfig::default().max_per_ip
+}
+
+fn default_ban_threshold() -> u32 {
+    ConnectionConfig::default().ban_threshold
+}
+
+fn default_ban_duration_secs() -> u64 {
+    ConnectionConfig::default().ban_duration.as_secs()
 }
 
 /// RPC configuration.
@@ -64,6 +153,15 @@ impl Default for NodeConfig {
                 listen_addr: "/ip4/0.0.0.0/tcp/9000".to_string(),
                 bootstrap_nodes: Vec::new(),
                 max_peers: 50,
+                mesh_n_low: default_mesh_n_low(),
+                mesh_n: default_mesh_n(),
+                mesh_n_high: default_mesh_n_high(),
+                max_inbound: default_max_inbound(),
+                max_outbound: default_max_outbound(),
+                max_per_ip: default_max_per_ip(),
+                ban_threshold: default_ban_threshold(),
+                ban_duration_secs: default_ban_duration_secs(),
+                mdns: false,
             },
             rpc: RpcConfig {
                 enabled: false,
@@ -77,6 +175,7 @@ impl Default for NodeConfig {
             storage: StorageConfig {
                 data_dir: "./data".to_string(),
             },
+            mempool: MempoolConfig::default(),
         }
     }
 }
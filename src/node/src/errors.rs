@@ -34,6 +34,14 @@ pub enum NodeError {
     
     /// Error when a nonce is invalid.
     InvalidNonce,
+
+    /// Error when a nonce is ahead of the account's next nonce.
+    FutureNonce {
+        /// The account's next nonce
+        expected: u64,
+        /// The nonce of the transaction
+        actual: u64,
+    },
     
     /// Error when an account has insufficient balance.
     InsufficientBalance,
@@ -46,6 +54,18 @@ pub enum NodeError {
     
     /// Error when state roots don't match.
     StateMismatch(String),
+
+    /// Error when a message is of a kind that cannot be sent this way.
+    InvalidMessage(String),
+
+    /// Error when the time a message claims is ahead of the node's clock or
+    /// too far behind it.
+    ClockSkew {
+        /// The time the message claims
+        timestamp: u64,
+        /// The node's time
+        node_time: u64,
+    },
 }
 
 impl fmt::Display for NodeError {
@@ -60,10 +80,17 @@ impl fmt::Display for NodeError {
             NodeError::InvalidSignature(msg) => write!(f, "Invalid signature: {}", msg),
             NodeError::Unauthorized(msg) => write!(f, "Unauthorized: {}", msg),
             NodeError::InvalidNonce => write!(f, "Invalid nonce"),
+            NodeError::FutureNonce { expected, actual } => {
+                write!(f, "Future nonce: expected {}, got {}", expected, actual)
+            }
             NodeError::InsufficientBalance => write!(f, "Insufficient balance"),
             NodeError::AccountNotFound(msg) => write!(f, "Account not found: {}", msg),
             NodeError::UpdateFailed(msg) => write!(f, "Update failed: {}", msg),
             NodeError::StateMismatch(msg) => write!(f, "State mismatch: {}", msg),
+            NodeError::InvalidMessage(msg) => write!(f, "Invalid message: {}", msg),
+            NodeError::ClockSkew { timestamp, node_time } => {
+                write!(f, "Message time {} is ahead of or too far behind the node's time {}", timestamp, node_time)
+            }
         }
     }
 }
@@ -2,6 +2,7 @@
 
 pub mod config;
 pub mod errors;
+pub mod mempool;
 pub mod metrics;
 pub mod rpc;
 pub mod tests;
@@ -149,6 +150,9 @@ pub async fn handle_update(
         proof_from: update.proof_from.clone(),
         proof_to: update.proof_to.clone(),
         nonce: update.nonce,
+        memo: update.memo.clone(),
+        ephemeral_key: update.ephemeral_key,
+        scheme: update.scheme,
         signature: core::types::Signature([0u8; 64]), // Empty signature for verification
     };
 
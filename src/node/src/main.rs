@@ -2,6 +2,7 @@
 
 mod config;
 mod errors;
+mod mempool;
 mod metrics;
 mod rpc;
 
@@ -11,22 +12,27 @@ pub mod main {
 
 use anyhow::Result;
 use config::NodeConfig;
-use core::{proofs::Proof, smt::SMT, types::Address};
+use core::{proofs::Proof, smt::{StateLeaf, SMT}, types::{Address, SystemMsg}};
 use network::gossip;
 use libp2p::Swarm;
 use network::transport::NodeBehaviour;
 use errors::NodeError;
 use futures::{StreamExt, FutureExt};
-use libp2p::Multiaddr;
+use libp2p::{gossipsub::MessageAcceptance, identity::Keypair, Multiaddr, PeerId};
+use mempool::Mempool;
 use metrics::register_metrics;
 use network::{
     dht::DHTManager,
+    identity::{decode_identity_hex, load_identity, load_or_create_identity, IDENTITY_ENV, IDENTITY_FILE},
+    peer_guard::BAN_LIST_FILE,
+    record_store::RecordStoreConfig,
+    state_sync::StateSync,
     storage::ProofStore,
     transport::{init_swarm, handle_network_event, NetworkEvent},
-    types::UpdateMsg,
+    types::{GossipMessage, UpdateMsg},
 };
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use structopt::StructOpt;
 use tokio::sync::mpsc;
@@ -45,6 +51,10 @@ struct Opt {
     #[structopt(short, long, parse(from_os_str))]
     data_dir: Option<PathBuf>,
 
+    /// Path to an identity key file to use instead of the one in the data directory
+    #[structopt(long, parse(from_os_str))]
+    identity: Option<PathBuf>,
+
     /// Bootstrap nodes to connect to
     #[structopt(short, long)]
     bootstrap: Vec<String>,
@@ -68,6 +78,38 @@ struct Opt {
     /// Metrics server address
     #[structopt(long, default_value = "127.0.0.1:9090")]
     metrics_addr: String,
+
+    /// Discover peers on the local network over mDNS, for devnets
+    #[structopt(long)]
+    mdns: bool,
+
+    /// Maximum number of DHT records to store
+    #[structopt(long, default_value = "65536")]
+    dht_max_records: usize,
+
+    /// How long a DHT record lives, in seconds
+    #[structopt(long, default_value = "129600")]
+    dht_record_ttl: u64,
+
+    /// How often this node republishes its DHT records, in seconds
+    #[structopt(long, default_value = "43200")]
+    dht_republish_interval: u64,
+
+    /// Subcommand to run instead of the node
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+/// Subcommands for the node daemon.
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Print the node's PeerId and the multiaddrs to share with operators
+    #[structopt(name = "identity")]
+    Identity {
+        /// Externally reachable addresses to print instead of the listen address
+        #[structopt(long)]
+        external: Vec<String>,
+    },
 }
 
 #[tokio::main]
@@ -100,6 +142,13 @@ async fn main() -> Result<()> {
     // Create data directory if it doesn't exist
     std::fs::create_dir_all(&data_dir)?;
 
+    // Load the node identity, so the PeerId stays the same across restarts
+    let local_key = load_node_identity(opt.identity.as_deref(), &data_dir)?;
+
+    if let Some(Command::Identity { external }) = &opt.cmd {
+        return print_identity(&local_key, &opt.listen, external);
+    }
+
     // Initialize proof store
     let mut proof_store_path = data_dir.clone();
     proof_store_path.push("proofs");
@@ -150,8 +199,26 @@ async fn main() -> Result<()> {
         })
         .collect();
 
-    // Initialize network swarm
-    let (mut swarm, dht_manager) = init_swarm(bootstrap_nodes.clone()).await?;
+    // Initialize network swarm, with DHT records persisted next to the proofs
+    let mut dht_path = data_dir.clone();
+    dht_path.push("dht");
+    let dht_config = RecordStoreConfig {
+        max_records: opt.dht_max_records,
+        record_ttl: std::time::Duration::from_secs(opt.dht_record_ttl),
+        publication_interval: std::time::Duration::from_secs(opt.dht_republish_interval),
+        ..RecordStoreConfig::default()
+    };
+    let (mut swarm, dht_manager, proof_exchange, state_sync) = init_swarm(
+        local_key,
+        bootstrap_nodes.clone(),
+        &dht_path,
+        dht_config,
+        config.network.gossip_config(),
+        &data_dir.join(BAN_LIST_FILE),
+        config.network.connection_config(),
+        opt.mdns || config.network.mdns,
+    )
+    .await?;
     
     // Get the local peer ID
     let local_peer_id = swarm.local_peer_id().to_string();
@@ -162,434 +229,115 @@ async fn main() -> Result<()> {
     swarm.listen_on(listen_addr.clone())?;
     info!("Listening on {}", listen_addr);
     
-    // Always try to sync state from bootstrap nodes, regardless of whether we have data or not
-    if !bootstrap_nodes.is_empty() {
-        let root = {
-            let smt_lock = smt.lock().unwrap();
-            smt_lock.root()
-        };
-        
-        // Check if we have an empty root (all zeros)
-        let is_empty_root = root.iter().all(|&b| b == 0);
-        
-        if is_empty_root {
-            info!("New node detected with empty state. Attempting to sync state from bootstrap nodes...");
-        } else {
-            info!("Node has existing state. Will still attempt to sync latest state from network...");
-        }
-        
-        // Try to connect to each bootstrap node and sync state
-        for bootstrap_node in &bootstrap_nodes {
-            info!("Attempting to sync state from bootstrap node: {}", bootstrap_node);
-            
-            // Extract the IP and port from the multiaddr
-            if let Some(ip_port) = extract_ip_port(bootstrap_node) {
-                let (ip, port) = ip_port;
-                
-                // Construct the RPC URL
-                let rpc_url = format!("http://{}:{}/rpc", ip, 8545); // Assuming RPC port is 8545
-                
-                info!("Connecting to RPC at {}", rpc_url);
-                
-                // Try to get the full state from the bootstrap node
-                match reqwest::Client::new()
-                    .post(&rpc_url)
-                    .json(&serde_json::json!({
-                        "jsonrpc": "2.0",
-                        "method": "get_full_state",
-                        "params": [],
-                        "id": 1
-                    }))
-                    .send()
-                    .await
-                {
-                    Ok(response) => {
-                        match response.json::<serde_json::Value>().await {
-                            Ok(json) => {
-                                if let Some(result) = json.get("result") {
-                                    // Try to directly apply the state to the SMT
-                                    match serde_json::from_value::<rpc::FullState>(result.clone()) {
-                                        Ok(full_state) => {
-                                            // Apply the state directly to the SMT
-                                            let mut smt_lock = smt.lock().unwrap();
-                                            
-                                            // If we already have state, compare the roots to see if we need to update
-                                            if !is_empty_root {
-                                                let current_root = smt_lock.root();
-                                                
-                                                // If our root is the same as the remote root, we're already in sync
-                                                if current_root == full_state.root {
-                                                    info!("Local state is already in sync with network (root: {:?})", current_root);
-                                                    break;
-                                                }
-                                                
-                                                let local_accounts = smt_lock.get_all_accounts().unwrap_or_default();
-                                                
-                                                // Calculate total balance and highest nonce for local state
-                                                let (local_total_balance, local_highest_nonce) = local_accounts.iter()
-                                                    .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                    });
-                                                
-                                                // Calculate total balance and highest nonce for remote state
-                                                let (remote_total_balance, remote_highest_nonce) = full_state.accounts.iter()
-                                                    .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                    });
-                                                
-                                                // Calculate active accounts for both states
-                                                let (_, _, local_active_accounts) = local_accounts.iter()
-                                                    .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                        let active = if account.bal > 0 { 1 } else { 0 };
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                    });
-                                                
-                                                let (_, _, remote_active_accounts) = full_state.accounts.iter()
-                                                    .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                        let active = if account.bal > 0 { 1 } else { 0 };
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                    });
-                                                
-                                                // Calculate a consensus score for each state
-                                                // This is a weighted combination of factors that indicate state freshness
-                                                let local_score = (local_active_accounts as u128 * 10) +
-                                                                 (local_highest_nonce as u128 * 100) +
-                                                                 (local_total_balance / 1000);
-                                                
-                                                let remote_score = (remote_active_accounts as u128 * 10) +
-                                                                  (remote_highest_nonce as u128 * 100) +
-                                                                  (remote_total_balance / 1000);
-                                                
-                                                // Log detailed state information for debugging
-                                                info!("State comparison:");
-                                                info!("Local: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                      local_accounts.len(), local_active_accounts, local_total_balance, local_highest_nonce, local_score);
-                                                info!("Remote: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                      full_state.accounts.len(), remote_active_accounts, remote_total_balance, remote_highest_nonce, remote_score);
-                                                
-                                                // If local state has a higher score, keep it
-                                                if local_score >= remote_score {
-                                                    info!("Local state has higher consensus score. Keeping local state.");
-                                                    break;
-                                                }
-                                                
-                                                info!("Network state appears more recent. Updating local state...");
-                                            }
-                                            
-                                            match smt_lock.set_full_state(full_state.accounts, full_state.root) {
-                                                Ok(_) => {
-                                                    info!("Successfully synced state from bootstrap node");
-                                                    // State is automatically persisted to RocksDB by set_full_state
-                                                    break; // Successfully synced, no need to try other nodes
-                                                }
-                                                Err(e) => {
-                                                    warn!("Failed to set state: {}", e);
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            warn!("Failed to parse state from bootstrap node: {}", e);
-                                        }
-                                    }
-                                } else {
-                                    warn!("Invalid response from bootstrap node: no result field");
-                                }
-                            }
-                            Err(e) => {
-                                warn!("Failed to parse response from bootstrap node: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to connect to bootstrap node RPC: {}", e);
-                    }
-                }
-            } else {
-                warn!("Failed to extract IP and port from bootstrap node address: {}", bootstrap_node);
-            }
-        }
+    // Register metrics if enabled
+    if opt.metrics {
+        register_metrics();
+        let metrics_addr = opt.metrics_addr.parse()?;
+        metrics::start_metrics_server(metrics_addr).await?;
+        info!("Metrics server listening on {}", opt.metrics_addr);
     }
+
+    // Create channels for broadcasting messages
+    let (gossip_tx, mut gossip_rx) = tokio::sync::mpsc::channel::<network::types::MintMsg>(100);
+    let (update_tx, mut update_rx) = tokio::sync::mpsc::channel::<network::types::UpdateMsg>(100);
+    let (system_tx, mut system_rx) = tokio::sync::mpsc::channel::<SystemMsg>(100);
     
-    // Perform full state synchronization on startup if bootstrap nodes are provided
-    if !bootstrap_nodes.is_empty() {
-        info!("Node has existing state. Will still attempt to sync latest state from network...");
-        
-        // Try to sync with each bootstrap node
-        for bootstrap_node in &bootstrap_nodes {
-            if let Some(ip_port) = extract_ip_port(bootstrap_node) {
-                info!("Attempting to sync state from bootstrap node: {}", bootstrap_node);
-                
-                // Construct the RPC URL
-                let (ip, _) = ip_port;
-                let rpc_url = format!("http://{}:{}/rpc", ip, 8545); // Assuming RPC port is 8545
-                
-                info!("Connecting to RPC at {}", rpc_url);
-                
-                // Try to get the full state from the bootstrap node
-                match reqwest::Client::new()
-                    .post(&rpc_url)
-                    .json(&serde_json::json!({
-                        "jsonrpc": "2.0",
-                        "method": "get_full_state",
-                        "params": [],
-                        "id": 1
-                    }))
-                    .send()
-                    .await
-                {
-                    Ok(response) => {
-                        match response.json::<serde_json::Value>().await {
-                            Ok(json) => {
-                                if let Some(result) = json.get("result") {
-                                    // Try to directly apply the state to the SMT
-                                    match serde_json::from_value::<rpc::FullState>(result.clone()) {
-                                        Ok(full_state) => {
-                                            // Apply the state directly to the SMT
-                                            let mut smt_lock = smt.lock().unwrap();
-                                            let current_root = smt_lock.root();
-                                            
-                                            // If our root is the same as the remote root, we're already in sync
-                                            if current_root == full_state.root {
-                                                info!("Local state is already in sync with network (root: {:?})", current_root);
-                                                break;
-                                            }
-                                            
-                                            // If remote state has more accounts or higher nonce, update local state
-                                            let local_accounts = smt_lock.get_all_accounts().unwrap_or_default();
-                                            
-                                            // Calculate metrics for comparison
-                                            let (local_total_balance, local_highest_nonce) = local_accounts.iter()
-                                                .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                    (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                });
-                                            
-                                            let (remote_total_balance, remote_highest_nonce) = full_state.accounts.iter()
-                                                .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                    (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                });
-                                            
-                                            // Calculate metrics for both states to implement a consensus-based approach
-                                            // This aligns with Volt's architecture of maintaining a single canonical state
-                                            let (_, _, local_active_accounts) = local_accounts.iter()
-                                                .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                    let active = if account.bal > 0 { 1 } else { 0 };
-                                                    (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                });
-                                            
-                                            let (_, _, remote_active_accounts) = full_state.accounts.iter()
-                                                .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                    let active = if account.bal > 0 { 1 } else { 0 };
-                                                    (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                });
-                                            
-                                            // Calculate a consensus score for each state
-                                            // This is a weighted combination of factors that indicate state freshness
-                                            let local_score = (local_active_accounts as u128 * 10) +
-                                                             (local_highest_nonce as u128 * 100) +
-                                                             (local_total_balance / 1000);
-                                            
-                                            let remote_score = (remote_active_accounts as u128 * 10) +
-                                                              (remote_highest_nonce as u128 * 100) +
-                                                              (remote_total_balance / 1000);
-                                            
-                                            // Log detailed state information for debugging
-                                            info!("State comparison:");
-                                            info!("Local: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                  local_accounts.len(), local_active_accounts, local_total_balance, local_highest_nonce, local_score);
-                                            info!("Remote: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                  full_state.accounts.len(), remote_active_accounts, remote_total_balance, remote_highest_nonce, remote_score);
-                                            
-                                            // If local state has a higher score, keep it
-                                            if local_score >= remote_score {
-                                                info!("Local state has higher consensus score. Keeping local state.");
-                                                break;
-                                            }
-                                            
-                                            info!("Network state appears more recent. Updating local state...");
-                                            match smt_lock.set_full_state(full_state.accounts, full_state.root) {
-                                                Ok(_) => {
-                                                    info!("Successfully synced state from bootstrap node");
-                                                    break; // Successfully synced, no need to try other nodes
-                                                }
-                                                Err(e) => {
-                                                    warn!("Failed to set state: {}", e);
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            warn!("Failed to parse state from bootstrap node: {}", e);
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                warn!("Failed to parse response from bootstrap node: {}", e);
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        warn!("Failed to connect to bootstrap node RPC: {}", e);
-                    }
+    
+    // Start polling the swarm, which state sync needs
+    let swarm_clone = Arc::new(Mutex::new(swarm));
+    
+    // Get a mutable reference to the swarm for the main loop
+    let swarm_mutex = Arc::clone(&swarm_clone);
+    
+    // Create a channel for network events
+    let (tx, mut rx) = mpsc::channel(100);
+    let tx_clone = tx.clone();
+    
+    // Create a channel for passing network events between tasks
+    let (event_tx, mut event_rx) = mpsc::channel(100);
+    
+    // Spawn a task to poll the swarm for events
+    let swarm_clone2 = swarm_clone.clone();
+    tokio::spawn(async move {
+        loop {
+            // Poll the swarm for events
+            let event_opt = {
+                let mut swarm = swarm_clone2.lock().unwrap();
+                match swarm.next().now_or_never() {
+                    Some(Some(event)) => Some(event),
+                    _ => None,
+                }
+            };
+            
+            if let Some(event) = event_opt {
+                // Send the event to the processing task
+                if let Err(e) = event_tx.send(event).await {
+                    error!("Failed to send event: {}", e);
+                    break;
                 }
             } else {
-                warn!("Failed to extract IP and port from bootstrap node address: {}", bootstrap_node);
+                // Sleep a bit before polling again
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
             }
         }
-    }
+    });
     
-    // Set up periodic state synchronization
-    let smt_for_sync = smt.clone();
-    let bootstrap_nodes_for_sync = bootstrap_nodes.clone();
+    // Spawn a task to process the events
+    // Create a channel for passing processed events
+    let (processed_tx, mut processed_rx) = mpsc::channel(100);
+    let processed_tx_clone = processed_tx.clone();
+    
+    // Spawn a task to process events without holding the lock across await points
+    let swarm_for_events = swarm_clone.clone();
+    let state_sync_for_events = state_sync.clone();
+    let proof_exchange_for_events = proof_exchange.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(60)); // Sync every 1 minute
+        let mut known_peers = HashSet::new();
         
-        loop {
-            interval.tick().await;
+        while let Some(event) = event_rx.recv().await {
+            // Process the event and get a network event if any
+            let mut network_event = None;
             
-            // Skip if no bootstrap nodes
-            if bootstrap_nodes_for_sync.is_empty() {
-                continue;
+            // Process the event in a block to ensure the MutexGuard is dropped
+            {
+                let mut swarm = swarm_for_events.lock().unwrap();
+                
+                // Use the synchronous version of handle_network_event
+                match network::transport::handle_network_event_sync(
+                    event,
+                    &dht_manager,
+                    &proof_exchange_for_events,
+                    &state_sync_for_events,
+                    &mut known_peers,
+                    &mut swarm,
+                ) {
+                    Ok(Some(evt)) => network_event = Some(evt),
+                    Err(e) => error!("Error handling network event: {}", e),
+                    _ => {}
+                }
             }
             
-            info!("Performing periodic state synchronization...");
-            
-            // Try to connect to each bootstrap node and sync state
-            for bootstrap_node in &bootstrap_nodes_for_sync {
-                // Extract the IP and port from the multiaddr
-                if let Some(ip_port) = extract_ip_port(bootstrap_node) {
-                    let (ip, port) = ip_port;
-                    
-                    // Construct the RPC URL
-                    let rpc_url = format!("http://{}:{}/rpc", ip, 8545); // Assuming RPC port is 8545
-                    
-                    // Try to get the full state from the bootstrap node
-                    match reqwest::Client::new()
-                        .post(&rpc_url)
-                        .json(&serde_json::json!({
-                            "jsonrpc": "2.0",
-                            "method": "get_full_state",
-                            "params": [],
-                            "id": 1
-                        }))
-                        .send()
-                        .await
-                    {
-                        Ok(response) => {
-                            match response.json::<serde_json::Value>().await {
-                                Ok(json) => {
-                                    if let Some(result) = json.get("result") {
-                                        // Try to directly apply the state to the SMT
-                                        match serde_json::from_value::<rpc::FullState>(result.clone()) {
-                                            Ok(full_state) => {
-                                                // Apply the state directly to the SMT
-                                                let mut smt_lock = smt_for_sync.lock().unwrap();
-                                                let current_root = smt_lock.root();
-                                                
-                                                // If our root is the same as the remote root, we're already in sync
-                                                if current_root == full_state.root {
-                                                    info!("Local state is already in sync with network (root: {:?})", current_root);
-                                                    break;
-                                                }
-                                                
-                                                // If our root is different, we need a more sophisticated approach to determine which state is more recent
-                                                // Compare state metadata like timestamps, block heights, or transaction counts
-                                                // For this implementation, we'll use a combination of account count, total balance, and highest nonce
-                                                
-                                                let local_accounts = smt_lock.get_all_accounts().unwrap_or_default();
-                                                
-                                                // Calculate total balance and highest nonce for local state
-                                                let (local_total_balance, local_highest_nonce) = local_accounts.iter()
-                                                    .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                    });
-                                                
-                                                // Calculate total balance and highest nonce for remote state
-                                                let (remote_total_balance, remote_highest_nonce) = full_state.accounts.iter()
-                                                    .fold((0u128, 0u64), |(total_balance, highest_nonce), account| {
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce))
-                                                    });
-                                                
-                                                // Calculate metrics for both states to implement a consensus-based approach
-                                                // This aligns with Volt's architecture of maintaining a single canonical state
-                                                let (_, _, local_active_accounts) = local_accounts.iter()
-                                                    .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                        let active = if account.bal > 0 { 1 } else { 0 };
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                    });
-                                                
-                                                let (_, _, remote_active_accounts) = full_state.accounts.iter()
-                                                    .fold((0u128, 0u64, 0usize), |(total_balance, highest_nonce, active_accounts), account| {
-                                                        let active = if account.bal > 0 { 1 } else { 0 };
-                                                        (total_balance + account.bal, std::cmp::max(highest_nonce, account.nonce), active_accounts + active)
-                                                    });
-                                                
-                                                // Calculate a consensus score for each state
-                                                // This is a weighted combination of factors that indicate state freshness
-                                                let local_score = (local_active_accounts as u128 * 10) +
-                                                                 (local_highest_nonce as u128 * 100) +
-                                                                 (local_total_balance / 1000);
-                                                
-                                                let remote_score = (remote_active_accounts as u128 * 10) +
-                                                                  (remote_highest_nonce as u128 * 100) +
-                                                                  (remote_total_balance / 1000);
-                                                
-                                                // Log detailed state information for debugging
-                                                info!("State comparison:");
-                                                info!("Local: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                      local_accounts.len(), local_active_accounts, local_total_balance, local_highest_nonce, local_score);
-                                                info!("Remote: {} accounts ({} active), {} total balance, highest nonce {}, score {}",
-                                                      full_state.accounts.len(), remote_active_accounts, remote_total_balance, remote_highest_nonce, remote_score);
-                                                
-                                                // If local state has a higher score, keep it
-                                                if local_score >= remote_score {
-                                                    info!("Local state has higher consensus score. Keeping local state.");
-                                                    break;
-                                                }
-                                                
-                                                info!("Network state appears more recent. Updating local state...");
-                                                match smt_lock.set_full_state(full_state.accounts, full_state.root) {
-                                                    Ok(_) => {
-                                                        info!("Successfully synced state from bootstrap node");
-                                                        break; // Successfully synced, no need to try other nodes
-                                                    }
-                                                    Err(e) => {
-                                                        warn!("Failed to set state: {}", e);
-                                                    }
-                                                }
-                                            }
-                                            Err(e) => {
-                                                warn!("Failed to parse state from bootstrap node: {}", e);
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    warn!("Failed to parse response from bootstrap node: {}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to connect to bootstrap node RPC: {}", e);
-                        }
-                    }
+            // If we got a network event, send it
+            if let Some(evt) = network_event {
+                if let Err(e) = processed_tx_clone.send(evt).await {
+                    error!("Failed to send processed event: {}", e);
                 }
             }
+            
+           
+        }
+    });
+    
+    // Spawn another task to forward the processed events to the main channel
+    tokio::spawn(async move {
+        while let Some(event) = processed_rx.recv().await {
+            if let Err(e) = tx_clone.send(event).await {
+                error!("Failed to send network event: {}", e);
+            }
         }
     });
 
 
-    // Register metrics if enabled
-    if opt.metrics {
-        register_metrics();
-        let metrics_addr = opt.metrics_addr.parse()?;
-        metrics::start_metrics_server(metrics_addr).await?;
-        info!("Metrics server listening on {}", opt.metrics_addr);
-    }
-
-    // Create channels for broadcasting messages
-    let (gossip_tx, mut gossip_rx) = tokio::sync::mpsc::channel::<network::types::MintMsg>(100);
-    let (update_tx, mut update_rx) = tokio::sync::mpsc::channel::<network::types::UpdateMsg>(100);
-    
     // Create a synchronization barrier flag
     let state_synced = Arc::new(std::sync::atomic::AtomicBool::new(false));
     
@@ -598,7 +346,8 @@ async fn main() -> Result<()> {
         info!("Cold-start safety: Blocking RPC and gossip until state is synchronized");
         
         // Try to synchronize state from bootstrap nodes
-        let sync_result = synchronize_state_from_network(&bootstrap_nodes, &smt).await;
+        let bootstrap_peers = bootstrap_peer_ids(&bootstrap_nodes);
+        let sync_result = synchronize_state_from_network(&bootstrap_peers, &swarm_clone, &state_sync, &smt).await;
         
         if sync_result {
             info!("Cold-start safety: State successfully synchronized from network");
@@ -628,19 +377,62 @@ async fn main() -> Result<()> {
         state_synced.store(true, std::sync::atomic::Ordering::SeqCst);
     }
     
+    // Hold transfers that arrive before their predecessors
+    let mempool = Arc::new(Mutex::new(Mempool::new(config.mempool.clone())));
+
+    // Set up periodic state synchronization from connected peers
+    if !bootstrap_nodes.is_empty() {
+        let smt_for_sync = smt.clone();
+        let proof_store_for_sync = proof_store.clone();
+        let mempool_for_sync = mempool.clone();
+        let swarm_for_sync = swarm_clone.clone();
+        let state_sync_for_sync = state_sync.clone();
+        let state_synced_for_sync = state_synced.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(60)); // Sync every 1 minute
+            
+            loop {
+                interval.tick().await;
+                
+                let peers: Vec<PeerId> = {
+                    let swarm = swarm_for_sync.lock().unwrap();
+                    swarm.connected_peers().copied().collect()
+                };
+                
+                // Skip if no peers are connected
+                if peers.is_empty() {
+                    continue;
+                }
+                
+                info!("Performing periodic state synchronization...");
+                
+                if synchronize_state_from_network(&peers, &swarm_for_sync, &state_sync_for_sync, &smt_for_sync).await {
+                    state_synced_for_sync.store(true, std::sync::atomic::Ordering::SeqCst);
+
+                    // The synced state may have caught up with pooled transfers
+                    apply_ready_pooled_updates(&smt_for_sync, &proof_store_for_sync, &mempool_for_sync).await;
+                }
+            }
+        });
+    }
+    
     // Start JSON-RPC server if enabled and state is synchronized
     let rpc_handle = if opt.rpc {
         let rpc_addr = opt.rpc_addr.parse()?;
         let smt_clone = smt.clone();
         let proof_store_clone = proof_store.clone();
+        let mempool_clone = mempool.clone();
+        let swarm_for_rpc = swarm_clone.clone();
+        let proof_exchange_for_rpc = proof_exchange.clone();
         
         // Create shared references to the gossip senders
         let gossip_tx = Arc::new(Mutex::new(gossip_tx));
         let update_tx = Arc::new(Mutex::new(update_tx));
+        let system_tx = Arc::new(Mutex::new(system_tx));
         
         // Only start the RPC server if state is synchronized
         if state_synced.load(std::sync::atomic::Ordering::SeqCst) {
-            rpc::start_rpc_server(rpc_addr, smt_clone, proof_store_clone, local_peer_id.clone(), gossip_tx, update_tx).await?;
+            rpc::start_rpc_server(rpc_addr, smt_clone, proof_store_clone, mempool_clone, swarm_for_rpc, proof_exchange_for_rpc, local_peer_id.clone(), gossip_tx, update_tx, system_tx).await?;
             info!("JSON-RPC server listening on {}", opt.rpc_addr);
             None
         } else {
@@ -653,7 +445,7 @@ async fn main() -> Result<()> {
                 }
                 
                 // Start the RPC server once state is synchronized
-                match rpc::start_rpc_server(rpc_addr, smt_clone, proof_store_clone, local_peer_id, gossip_tx, update_tx).await {
+                match rpc::start_rpc_server(rpc_addr, smt_clone, proof_store_clone, mempool_clone, swarm_for_rpc, proof_exchange_for_rpc, local_peer_id, gossip_tx, update_tx, system_tx).await {
                     Ok(_) => {
                         info!("JSON-RPC server listening on {}", opt.rpc_addr);
                     },
@@ -669,33 +461,19 @@ async fn main() -> Result<()> {
         None
     };
     
+    
     // Spawn a task to handle gossip messages
-    let swarm_clone = Arc::new(Mutex::new(swarm));
     let swarm_for_gossip = swarm_clone.clone();
     
     tokio::spawn(async move {
         while let Some(mint_msg) = gossip_rx.recv().await {
-            // Serialize the mint message
-            match bincode::serialize(&mint_msg) {
-                Ok(mint_msg_bytes) => {
-                    // Create a topic
-                    let topic = libp2p::gossipsub::IdentTopic::new(network::gossip::STATE_UPDATES_TOPIC);
-                    
-                    // Get a mutable reference to the swarm
-                    let mut swarm = swarm_for_gossip.lock().unwrap();
-                    
-                    // Publish the message
-                    match swarm.behaviour_mut().gossipsub.publish(topic, mint_msg_bytes) {
-                        Ok(_) => {
-                            info!("Successfully broadcast mint message");
-                        },
-                        Err(e) => {
-                            error!("Failed to broadcast mint message: {}", e);
-                        }
-                    }
+            let mut swarm = swarm_for_gossip.lock().unwrap();
+            match gossip::publish_message(&mut swarm.behaviour_mut().gossipsub, GossipMessage::Mint(mint_msg)) {
+                Ok(_) => {
+                    info!("Successfully broadcast mint message");
                 },
                 Err(e) => {
-                    error!("Failed to serialize mint message: {}", e);
+                    error!("Failed to broadcast mint message: {}", e);
                 }
             }
         }
@@ -703,140 +481,161 @@ async fn main() -> Result<()> {
     
     // Spawn a task to handle update messages
     let swarm_for_updates = swarm_clone.clone();
-    
+    let smt_for_updates = smt.clone();
+    let proof_store_for_updates = proof_store.clone();
+    let mempool_for_updates = mempool.clone();
+
     tokio::spawn(async move {
         while let Some(update_msg) = update_rx.recv().await {
-            // Serialize the update message
-            match bincode::serialize(&update_msg) {
-                Ok(update_msg_bytes) => {
-                    // Create a topic
-                    let topic = libp2p::gossipsub::IdentTopic::new(network::gossip::STATE_UPDATES_TOPIC);
-                    
-                    // Get a mutable reference to the swarm
-                    let mut swarm = swarm_for_updates.lock().unwrap();
-                    
-                    // Publish the message
-                    match swarm.behaviour_mut().gossipsub.publish(topic, update_msg_bytes) {
-                        Ok(_) => {
-                            info!("Successfully broadcast update message");
-                        },
-                        Err(e) => {
-                            error!("Failed to broadcast update message: {}", e);
-                        }
+            let (from, token_id, nonce) = (update_msg.from, update_msg.token_id, update_msg.nonce);
+            {
+                let mut swarm = swarm_for_updates.lock().unwrap();
+                match gossip::publish_message(&mut swarm.behaviour_mut().gossipsub, GossipMessage::Transfer(update_msg)) {
+                    Ok(_) => {
+                        info!("Successfully broadcast update message");
+                    },
+                    Err(e) => {
+                        error!("Failed to broadcast update message: {}", e);
                     }
-                },
-                Err(e) => {
-                    error!("Failed to serialize update message: {}", e);
                 }
             }
+
+            // The transfer was applied over RPC, so gossiped successors may be pooled
+            apply_pooled_updates(from, token_id, nonce + 1, &smt_for_updates, &proof_store_for_updates, &mempool_for_updates).await;
         }
     });
-    
-    // Get a mutable reference to the swarm for the main loop
-    let swarm_mutex = Arc::clone(&swarm_clone);
-    
-    // Create a channel for network events
-    let (tx, mut rx) = mpsc::channel(100);
-    let tx_clone = tx.clone();
-    
-    // Create a channel for passing network events between tasks
-    let (event_tx, mut event_rx) = mpsc::channel(100);
-    
-    // Spawn a task to poll the swarm for events
-    let swarm_clone2 = swarm_clone.clone();
-    tokio::spawn(async move {
-        loop {
-            // Poll the swarm for events
-            let event_opt = {
-                let mut swarm = swarm_clone2.lock().unwrap();
-                match swarm.next().now_or_never() {
-                    Some(Some(event)) => Some(event),
-                    _ => None,
-                }
-            };
-            
-            if let Some(event) = event_opt {
-                // Send the event to the processing task
-                if let Err(e) = event_tx.send(event).await {
-                    error!("Failed to send event: {}", e);
-                    break;
-                }
-            } else {
-                // Sleep a bit before polling again
-                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
-            }
-        }
-    });
-    
-    // Spawn a task to process the events
-    // Create a channel for passing processed events
-    let (processed_tx, mut processed_rx) = mpsc::channel(100);
-    let processed_tx_clone = processed_tx.clone();
-    
-    // Spawn a task to process events without holding the lock across await points
+
+    // Spawn a task to handle system messages
+    let swarm_for_system = swarm_clone.clone();
+
     tokio::spawn(async move {
-        let mut known_peers = HashSet::new();
-        
-        while let Some(event) = event_rx.recv().await {
-            // Process the event and get a network event if any
-            let mut network_event = None;
-            
-            // Process the event in a block to ensure the MutexGuard is dropped
-            {
-                let mut swarm = swarm_clone.lock().unwrap();
-                
-                // Use the synchronous version of handle_network_event
-                match network::transport::handle_network_event_sync(event, &dht_manager, &mut known_peers, &mut swarm) {
-                    Ok(Some(evt)) => network_event = Some(evt),
-                    Err(e) => error!("Error handling network event: {}", e),
-                    _ => {}
-                }
-            }
-            
-            // If we got a network event, send it
-            if let Some(evt) = network_event {
-                if let Err(e) = processed_tx_clone.send(evt).await {
-                    error!("Failed to send processed event: {}", e);
+        while let Some(message) = system_rx.recv().await {
+            let mut swarm = swarm_for_system.lock().unwrap();
+            match gossip::publish_message(&mut swarm.behaviour_mut().gossipsub, GossipMessage::from(message)) {
+                Ok(_) => {
+                    info!("Successfully broadcast system message");
+                },
+                Err(e) => {
+                    error!("Failed to broadcast system message: {}", e);
                 }
             }
-            
-           
         }
     });
     
-    // Spawn another task to forward the processed events to the main channel
-    tokio::spawn(async move {
-        while let Some(event) = processed_rx.recv().await {
-            if let Err(e) = tx_clone.send(event).await {
-                error!("Failed to send network event: {}", e);
-            }
-        }
-    });
-
-    // We've already created the channel and spawned the task to handle network events above
 
     // Main event loop
     info!("Node started");
     while let Some(event) = rx.recv().await {
         match event {
-            NetworkEvent::UpdateReceived(update) => {
+            NetworkEvent::UpdateReceived(update, message_id, source) => {
                 info!("Received update from network: from={:?}, to={:?}, amount={}",
                       update.from, update.to, update.amount);
                 
-                match handle_update(update, &smt, &proof_store, &swarm_mutex).await {
-                    Ok(_) => info!("Successfully processed update from network"),
+                let (from, token_id, nonce) = (update.from, update.token_id, update.nonce);
+                let result = handle_update(update.clone(), &smt, &proof_store).await;
+                match &result {
+                    Ok(_) => {
+                        info!("Successfully processed update from network");
+                        apply_pooled_updates(from, token_id, nonce + 1, &smt, &proof_store, &mempool).await;
+                    }
+                    Err(NodeError::FutureNonce { expected, actual }) => {
+                        match mempool.lock().unwrap().insert(update) {
+                            Ok(_) => debug!("Queued update with nonce {} until nonce {} applies", actual, expected),
+                            Err(e) => warn!("Dropping update with future nonce {}: {}", actual, e),
+                        }
+                    }
                     Err(e) => error!("Failed to process update from network: {}", e),
                 }
+
+                // Only accepted updates are forwarded, and peers lose score for rejected ones
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
+            }
+            NetworkEvent::SystemMessageReceived(message, message_id, source) => {
+                info!("Received system message from network: {}", message);
+
+                let result = handle_system_message(&message, &smt, &proof_store);
+                match &result {
+                    Ok(_) => info!("Successfully applied system message from network"),
+                    Err(e) => error!("Failed to apply system message from network: {}", e),
+                }
+
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
+            }
+            NetworkEvent::MintReceived(mint, message_id, source) => {
+                info!("Received mint from network: {}", mint);
+
+                let result = rpc::validate_mint(&mint).and_then(|_| rpc::apply_mint(&mint, &smt, &proof_store).map(|_| ()));
+                match &result {
+                    Ok(_) => info!("Successfully applied mint from network"),
+                    Err(e) => error!("Failed to apply mint from network: {}", e),
+                }
+
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
+            }
+            NetworkEvent::BurnReceived(burn, message_id, source) => {
+                // Burns are signed as their system message
+                let message = SystemMsg::from(burn);
+                info!("Received burn from network: {}", message);
+
+                let result = handle_system_message(&message, &smt, &proof_store);
+                match &result {
+                    Ok(_) => info!("Successfully applied burn from network"),
+                    Err(e) => error!("Failed to apply burn from network: {}", e),
+                }
+
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
+            }
+            NetworkEvent::IssueTokenReceived(issue, message_id, source) => {
+                info!("Received token issuance from network: token {} by {}", issue.token_id, hex::encode(issue.issuer));
+
+                let result = rpc::validate_issue_token(&issue, &smt)
+                    .and_then(|_| rpc::apply_system_msg(&SystemMsg::from(issue), &smt, &proof_store).map(|_| ()));
+                match &result {
+                    Ok(_) => info!("Successfully applied token issuance from network"),
+                    Err(e) => error!("Failed to apply token issuance from network: {}", e),
+                }
+
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
+            }
+            NetworkEvent::AttestationReceived(attestation, message_id, source) => {
+                // Attestations change no state; valid ones are forwarded
+                let result = rpc::validate_attestation(&attestation);
+                match &result {
+                    Ok(_) => {
+                        let matches = smt.lock().unwrap().root() == attestation.root;
+                        info!(
+                            "Received attestation of root {} by {}, matching ours: {}",
+                            hex::encode(attestation.root),
+                            hex::encode(attestation.attester),
+                            matches
+                        );
+                    }
+                    Err(e) => warn!("Invalid attestation from network: {}", e),
+                }
+
+                let acceptance = update_acceptance(&result);
+                let mut swarm = swarm_mutex.lock().unwrap();
+                let behaviour = swarm.behaviour_mut();
+                gossip::report_validation(&mut behaviour.gossipsub, &mut behaviour.guard, &message_id, &source, acceptance);
             }
             NetworkEvent::PeerDiscovered(peer_id) => {
                 info!("Discovered peer: {}", peer_id);
                 metrics::PEER_COUNT.inc();
-                
-                // Add the peer to our gossipsub mesh
-                let mut swarm = swarm_mutex.lock().unwrap();
-                swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
-                
-                info!("Added peer {} to gossipsub mesh", peer_id);
             }
             NetworkEvent::PeerDisconnected(peer_id) => {
                 info!("Disconnected from peer: {}", peer_id);
@@ -844,15 +643,49 @@ async fn main() -> Result<()> {
             }
             NetworkEvent::PeerIdentified(peer_id, addr) => {
                 info!("Identified peer {} at {}", peer_id, addr);
-                
-                // Add the peer to our gossipsub mesh
+
+                // Add the address to Kademlia for better connectivity
                 let mut swarm = swarm_mutex.lock().unwrap();
-                swarm.behaviour_mut().gossipsub.add_explicit_peer(&peer_id);
-                
-                // Also add the address to Kademlia for better connectivity
                 swarm.behaviour_mut().kademlia.add_address(&peer_id, addr.clone());
-                
-                info!("Added peer {} to gossipsub mesh", peer_id);
+            }
+            NetworkEvent::ProofRequestReceived(request, peer_id, channel) => {
+                debug!("Received proof request from {}: {}", peer_id, request);
+
+                let response = {
+                    let smt_lock = smt.lock().unwrap();
+                    network::answer_proof_request(&request, &proof_store, &smt_lock)
+                };
+
+                let mut swarm = swarm_mutex.lock().unwrap();
+                if swarm.behaviour_mut().proofs.send_response(channel, response).is_err() {
+                    warn!("Failed to answer proof request from {}", peer_id);
+                }
+            }
+            NetworkEvent::StateSyncRequestReceived(request, peer_id, channel) => {
+                debug!("Received state sync request from {}: {:?}", peer_id, request);
+
+                let response = {
+                    let smt_lock = smt.lock().unwrap();
+                    network::answer_state_sync_request(&request, &smt_lock)
+                };
+
+                let mut swarm = swarm_mutex.lock().unwrap();
+                if swarm.behaviour_mut().state_sync.send_response(channel, response).is_err() {
+                    warn!("Failed to answer state sync request from {}", peer_id);
+                }
+            }
+            NetworkEvent::ProofResponseReceived(response) => {
+                // A response that arrived after its caller gave up; keep the
+                // proof if it verifies, so the next request finds it locally
+                debug!("Received late proof response: {}", response);
+
+                if let Some(proof) = &response.proof {
+                    if !proof.verify(response.root, &response.address) {
+                        warn!("Discarding invalid proof for {}", hex::encode(response.address));
+                    } else if let Err(e) = proof_store.put_proof(&response.address, &response.root, proof) {
+                        warn!("Failed to store proof: {}", e);
+                    }
+                }
             }
             _ => {}
         }
@@ -861,16 +694,21 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Handles an update message.
+/// Handles an update message received over gossip.
+///
+/// The caller reports the result to gossipsub, which forwards the update to
+/// other peers only if it was accepted.
 pub async fn handle_update(
     update: UpdateMsg,
     smt: &Arc<Mutex<SMT>>,
     proof_store: &ProofStore,
-    swarm_mutex: &Arc<Mutex<Swarm<NodeBehaviour>>>,
 ) -> Result<(), NodeError> {
     debug!("Received update: {}", update);
     metrics::UPDATE_COUNTER.inc();
 
+    // Reject oversized memos before doing any cryptographic work
+    core::types::validate_memo(&update.memo)?;
+
     // First, verify the signature of the update message
     // This is a critical security check to ensure the transaction is authentic
     if let Err(e) = verify_signature(&update) {
@@ -932,45 +770,23 @@ pub async fn handle_update(
     {
         let mut smt_lock = smt.lock().unwrap();
         
-        // Get the sender's account
-        let sender_account = match smt_lock.get_account(&update.from) {
+        // Get the sender's account for the transfer's token, verifying the
+        // nonce first, since the balance may change before a future
+        // transaction applies
+        let sender_account = match mempool::check_nonce(&smt_lock, &update) {
             Ok(account) => {
                 // Verify the sender has sufficient balance
                 if account.bal < update.amount {
                     error!("Sender has insufficient balance: {} < {}", account.bal, update.amount);
                     return Err(NodeError::InsufficientBalance);
                 }
-                
-                // Verify the nonce with more flexibility to handle state transitions
-                if account.nonce > update.nonce {
-                    // If the account nonce is higher than the transaction nonce,
-                    // this might be a replay attack or a transaction that was already processed
-                    error!("Invalid nonce (possible replay attack): account nonce {} > transaction nonce {}",
-                           account.nonce, update.nonce);
-                    return Err(NodeError::InvalidNonce);
-                } else if account.nonce < update.nonce {
-                    // If the account nonce is lower than the transaction nonce,
-                    // this might be a future transaction that arrived early
-                    // In a distributed system, we might want to queue this for later processing
-                    // For now, we'll reject it but with a different error message
-                    warn!("Future nonce detected: account nonce {} < transaction nonce {}",
-                          account.nonce, update.nonce);
-                    warn!("This might indicate that nodes are out of sync");
-                    
-                    // If the difference is small (e.g., 1-2), we might still process it
-                    // This helps with network latency and slightly out-of-sync nodes
-                    if update.nonce - account.nonce <= 2 {
-                        info!("Nonce difference is small, proceeding with transaction");
-                        // We'll set the account nonce to match the transaction nonce
-                        // This is a compromise that helps with network latency
-                    } else {
-                        return Err(NodeError::InvalidNonce);
-                    }
+
+                // Check the token's compliance policy before changing any account
+                if let Err(e) = smt_lock.check_transfer_compliance(update.token_id, &update.from, &update.to) {
+                    error!("Transfer violates the token's compliance policy: {}", e);
+                    return Err(NodeError::Unauthorized(e.to_string()));
                 }
                 
-                // At this point, either the nonces match exactly or we've decided to
-                // process a transaction with a slightly future nonce
-                
                 // Update the account with the new balance and nonce
                 let mut updated_account = account.clone();
                 updated_account.bal -= update.amount;
@@ -985,14 +801,22 @@ pub async fn handle_update(
                 info!("Updated sender account: bal={}, nonce={}", updated_account.bal, updated_account.nonce);
                 updated_account
             },
+            Err(NodeError::FutureNonce { expected, actual }) => {
+                // A transaction that arrived before its predecessors, which
+                // the caller holds in the transaction pool until they apply
+                debug!("Future nonce: account nonce {} < transaction nonce {}", expected, actual);
+                return Err(NodeError::FutureNonce { expected, actual });
+            }
             Err(e) => {
-                error!("Failed to get sender account: {}", e);
-                return Err(NodeError::AccountNotFound("sender".to_string()));
+                // A stale nonce might be a replay attack or a transaction
+                // that was already processed
+                error!("Sender check failed for token {}: {}", update.token_id, e);
+                return Err(e);
             }
         };
             
-        // Get or create the recipient account
-        let recipient_account = match smt_lock.get_account(&update.to) {
+        // Get or create the recipient account for the token
+        let recipient_account = match smt_lock.get_account_with_token(&update.to, update.token_id) {
             Ok(account) => {
                 // Update the account with the new balance
                 let mut updated_account = account.clone();
@@ -1013,7 +837,7 @@ pub async fn handle_update(
                     update.to,
                     update.amount,
                     0,  // New accounts start with nonce 0
-                    0   // Assuming native token
+                    update.token_id
                 );
                 
                 // Update the SMT with the new account
@@ -1055,33 +879,6 @@ pub async fn handle_update(
         };
     }
     
-    // Broadcast the update message to all peers
-    // This is done outside the lock to avoid deadlocks
-    {
-        let mut swarm = swarm_mutex.lock().unwrap();
-        
-        // Serialize the update message
-        match bincode::serialize(&update) {
-            Ok(update_bytes) => {
-                // Create a topic
-                let topic = libp2p::gossipsub::IdentTopic::new(network::gossip::STATE_UPDATES_TOPIC);
-                
-                // Publish the message
-                match swarm.behaviour_mut().gossipsub.publish(topic, update_bytes) {
-                    Ok(_) => {
-                        info!("Successfully broadcast update message to all peers");
-                    },
-                    Err(e) => {
-                        error!("Failed to broadcast update message: {}", e);
-                    }
-                }
-            },
-            Err(e) => {
-                error!("Failed to serialize update message: {}", e);
-            }
-        }
-    }
-    
     // Store the proofs for future use
     if let Err(e) = proof_store.put_proof(&update.from, &root, &update.proof_from) {
         warn!("Failed to store sender proof: {}", e);
@@ -1091,6 +888,11 @@ pub async fn handle_update(
         warn!("Failed to store recipient proof: {}", e);
     }
     
+    // Record the transaction so both parties can look it up (and its memo) later
+    if let Err(e) = proof_store.put_tx_record(&update.to_tx_record()) {
+        warn!("Failed to store transaction record: {}", e);
+    }
+    
     // Log the successful transaction
     info!("Processed transfer from {:?} to {:?} of {} tokens",
           update.from, update.to, update.amount);
@@ -1098,54 +900,178 @@ pub async fn handle_update(
     Ok(())
 }
 
+/// Handles a system message received over gossip, with the same checks as
+/// the submitMessage RPC method except its claimed time, which was checked
+/// by the node it was submitted to.
+///
+/// Unlike transfers, system messages are not pooled: one whose nonce is
+/// ahead of its sender's account is ignored, and its effect reaches this
+/// node with the next periodic state sync.
+fn handle_system_message(message: &SystemMsg, smt: &Arc<Mutex<SMT>>, proof_store: &ProofStore) -> Result<(), NodeError> {
+    if !rpc::is_gossiped(message) {
+        return Err(NodeError::InvalidMessage("Message type is not sent over gossip".to_string()));
+    }
+    rpc::validate_system_msg(message)?;
+    rpc::apply_system_msg(message, smt, proof_store)?;
+    Ok(())
+}
+
 
-     // Extracts the IP address and port from a multiaddr.
-fn extract_ip_port(addr: &Multiaddr) -> Option<(String, u16)> {
-    use libp2p::multiaddr::Protocol;
-    
-    let mut iter = addr.iter();
-    let mut ip = None;
-    let mut port = None;
-    
-    while let Some(protocol) = iter.next() {
-        match protocol {
-            Protocol::Ip4(addr) => {
-                ip = Some(addr.to_string());
-            }
-            Protocol::Ip6(addr) => {
-                ip = Some(addr.to_string());
+/// Maps the result of handling a gossiped message to its gossipsub validation.
+///
+/// Messages with invalid signatures or proofs, or of the wrong kind, are
+/// rejected, which lowers the sender's peer score. Messages that only fail
+/// against the local state, such as a nonce or balance that may be out of
+/// date here, are ignored without penalty.
+fn update_acceptance(result: &Result<(), NodeError>) -> MessageAcceptance {
+    match result {
+        Ok(_) => MessageAcceptance::Accept,
+        Err(
+            NodeError::InvalidSignature(_)
+            | NodeError::InvalidProof(_)
+            | NodeError::InvalidMessage(_)
+            | NodeError::CoreError(_),
+        ) => {
+            MessageAcceptance::Reject
+        }
+        Err(_) => MessageAcceptance::Ignore,
+    }
+}
+
+/// Applies the sender's pooled transfers of a token in nonce order, starting
+/// at `nonce`, until one is missing or fails.
+async fn apply_pooled_updates(
+    from: Address,
+    token_id: u64,
+    mut nonce: u64,
+    smt: &Arc<Mutex<SMT>>,
+    proof_store: &ProofStore,
+    mempool: &Arc<Mutex<Mempool>>,
+) {
+    loop {
+        let update = match mempool.lock().unwrap().take_next(&from, token_id, nonce) {
+            Some(update) => update,
+            None => break,
+        };
+
+        match handle_update(update, smt, proof_store).await {
+            Ok(_) => {
+                info!("Applied pooled update with nonce {}", nonce);
+                nonce += 1;
             }
-            Protocol::Tcp(p) => {
-                port = Some(p);
+            Err(e) => {
+                warn!("Dropping pooled update with nonce {}: {}", nonce, e);
+                break;
             }
-            _ => {}
         }
     }
-    
-    match (ip, port) {
-        (Some(ip), Some(port)) => Some((ip, port)),
-        _ => None,
+}
+
+/// Applies the pooled transfers that follow on from each sender's current
+/// nonce, for when the state advanced without a gossiped transfer, such as
+/// after state sync.
+async fn apply_ready_pooled_updates(
+    smt: &Arc<Mutex<SMT>>,
+    proof_store: &ProofStore,
+    mempool: &Arc<Mutex<Mempool>>,
+) {
+    let senders = mempool.lock().unwrap().senders();
+    for (from, token_id) in senders {
+        let nonce = match smt.lock().unwrap().get_account_with_token(&from, token_id) {
+            Ok(account) => account.nonce,
+            Err(_) => continue,
+        };
+        apply_pooled_updates(from, token_id, nonce, smt, proof_store, mempool).await;
+    }
+}
+
+/// Loads the node's libp2p identity key.
+///
+/// The key comes from `identity_path` if given, then from the hex-encoded key
+/// in the `NODE_IDENTITY` environment variable, and otherwise from
+/// `identity.key` in the data directory, which is created on first start.
+fn load_node_identity(identity_path: Option<&Path>, data_dir: &Path) -> Result<Keypair> {
+    let local_key = match identity_path {
+        Some(path) => load_identity(path)?,
+        None => match std::env::var(IDENTITY_ENV) {
+            Ok(hex_key) => decode_identity_hex(&hex_key)?,
+            Err(_) => load_or_create_identity(data_dir.join(IDENTITY_FILE))?,
+        },
+    };
+    Ok(local_key)
+}
+
+/// Prints the node's PeerId and the multiaddrs other nodes can bootstrap from,
+/// which are the `external` addresses if given and the listen address otherwise.
+fn print_identity(local_key: &Keypair, listen: &str, external: &[String]) -> Result<()> {
+    use libp2p::multiaddr::Protocol;
+
+    let peer_id = PeerId::from(local_key.public());
+    let addrs = if external.is_empty() {
+        vec![listen.to_string()]
+    } else {
+        external.to_vec()
+    };
+
+    println!("PeerId: {}", peer_id);
+    for addr in addrs {
+        let mut addr: Multiaddr = addr.parse()?;
+        let unspecified = addr.iter().any(|protocol| match protocol {
+            Protocol::Ip4(ip) => ip.is_unspecified(),
+            Protocol::Ip6(ip) => ip.is_unspecified(),
+            _ => false,
+        });
+        if unspecified {
+            eprintln!("Note: {} is not reachable by other nodes; pass --external with a public address", addr);
+        }
+        if !addr.iter().any(|protocol| matches!(protocol, Protocol::P2p(_))) {
+            addr.push(Protocol::P2p(peer_id));
+        }
+        println!("{}", addr);
     }
+
+    Ok(())
+}
+
+/// Extracts the peer IDs of bootstrap nodes from their `/p2p/` multiaddrs.
+fn bootstrap_peer_ids(bootstrap_nodes: &[Multiaddr]) -> Vec<PeerId> {
+    use libp2p::multiaddr::Protocol;
+
+    bootstrap_nodes
+        .iter()
+        .filter_map(|addr| {
+            addr.iter().find_map(|protocol| match protocol {
+                Protocol::P2p(peer_id) => Some(peer_id),
+                _ => None,
+            })
+        })
+        .collect()
 }
+
 /// Synchronizes the node's state from the network.
 ///
-/// This function attempts to synchronize the node's state from the network by
-/// connecting to bootstrap nodes and requesting their state. It returns true if
-/// synchronization was successful, false otherwise.
+/// This function compares subtree hashes with each peer in turn over the libp2p
+/// state sync protocol and fetches only the leaves under the subtrees that
+/// differ, verifying them chunk by chunk against the peer's root. It returns
+/// true if synchronization was successful, false otherwise.
 ///
 /// # Arguments
 ///
-/// * `bootstrap_nodes` - A list of bootstrap nodes to connect to
+/// * `peers` - The peers to sync from, in order of preference
+/// * `swarm` - The swarm to send state sync requests through
+/// * `state_sync` - The state sync manager
 /// * `smt` - The Sparse Merkle Tree to synchronize
 ///
 /// # Returns
 ///
 /// `true` if synchronization was successful, `false` otherwise
 async fn synchronize_state_from_network(
-    bootstrap_nodes: &[Multiaddr],
+    peers: &[PeerId],
+    swarm: &Mutex<Swarm<NodeBehaviour>>,
+    state_sync: &StateSync,
     smt: &Arc<Mutex<SMT>>,
 ) -> bool {
-    if bootstrap_nodes.is_empty() {
+    if peers.is_empty() {
         return false;
     }
 
@@ -1164,93 +1090,63 @@ async fn synchronize_state_from_network(
         info!("Verifying existing state against network...");
     }
     
-    // Try to connect to each bootstrap node and sync state
-    for bootstrap_node in bootstrap_nodes {
-        info!("Attempting to sync state from bootstrap node: {}", bootstrap_node);
+    // Try each peer until one's state is synchronized
+    for peer in peers {
+        info!("Attempting to sync state from peer: {}", peer);
         
-        // Extract the IP and port from the multiaddr
-        if let Some(ip_port) = extract_ip_port(bootstrap_node) {
-            let (ip, _) = ip_port;
-            
-            // Construct the RPC URL
-            let rpc_url = format!("http://{}:{}/rpc", ip, 8545); // Assuming RPC port is 8545
+        let diff = match state_sync.fetch_diff(swarm, *peer, smt).await {
+            Ok(Some(diff)) => diff,
+            Ok(None) => {
+                info!("Local state is already in sync with network (root: {:?})", root);
+                return true;
+            }
+            Err(e) => {
+                warn!("Failed to fetch state from peer {}: {}", peer, e);
+                continue;
+            }
+        };
+        
+        let mut smt_lock = smt.lock().unwrap();
+        
+        // If we already have state, compare the differing parts to see which is more recent
+        if !is_empty_root {
+            let local_accounts: Vec<_> = diff
+                .subtrees
+                .iter()
+                .flat_map(|subtree| smt_lock.state_leaves(subtree))
+                .filter_map(|leaf| match leaf {
+                    StateLeaf::Account(account) => Some(account),
+                    StateLeaf::Data(..) => None,
+                })
+                .collect();
+            let remote_accounts: Vec<_> = diff
+                .leaves
+                .iter()
+                .filter_map(|leaf| match leaf {
+                    StateLeaf::Account(account) => Some(account.clone()),
+                    StateLeaf::Data(..) => None,
+                })
+                .collect();
             
-            info!("Connecting to RPC at {}", rpc_url);
+            // Calculate consensus scores for both states
+            let (local_score, remote_score) = calculate_consensus_scores(&local_accounts, &remote_accounts);
             
-            // Try to get the full state from the bootstrap node
-            match reqwest::Client::new()
-                .post(&rpc_url)
-                .json(&serde_json::json!({
-                    "jsonrpc": "2.0",
-                    "method": "get_full_state",
-                    "params": [],
-                    "id": 1
-                }))
-                .send()
-                .await
-            {
-                Ok(response) => {
-                    match response.json::<serde_json::Value>().await {
-                        Ok(json) => {
-                            if let Some(result) = json.get("result") {
-                                // Try to directly apply the state to the SMT
-                                match serde_json::from_value::<rpc::FullState>(result.clone()) {
-                                    Ok(full_state) => {
-                                        // Apply the state directly to the SMT
-                                        let mut smt_lock = smt.lock().unwrap();
-                                        
-                                        // If we already have state, compare the roots to see if we need to update
-                                        if !is_empty_root {
-                                            let current_root = smt_lock.root();
-                                            
-                                            // If our root is the same as the remote root, we're already in sync
-                                            if current_root == full_state.root {
-                                                info!("Local state is already in sync with network (root: {:?})", current_root);
-                                                return true;
-                                            }
-                                            
-                                            // Compare state metadata to determine which state is more recent
-                                            let local_accounts = smt_lock.get_all_accounts().unwrap_or_default();
-                                            
-                                            // Calculate consensus scores for both states
-                                            let (local_score, remote_score) = calculate_consensus_scores(
-                                                &local_accounts,
-                                                &full_state.accounts
-                                            );
-                                            
-                                            // If local state has a higher score, keep it
-                                            if local_score >= remote_score {
-                                                info!("Local state has higher consensus score. Keeping local state.");
-                                                return true;
-                                            }
-                                        }
-                                        
-                                        // Update the local state with the remote state
-                                        info!("Updating local state with network state...");
-                                        match smt_lock.set_full_state(full_state.accounts, full_state.root) {
-                                            Ok(_) => {
-                                                info!("Successfully synchronized state from network");
-                                                return true;
-                                            }
-                                            Err(e) => {
-                                                error!("Failed to set state: {}", e);
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        warn!("Failed to parse state from bootstrap node: {}", e);
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to parse response from bootstrap node: {}", e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to connect to bootstrap node RPC: {}", e);
-                }
+            // If local state has a higher score, keep it
+            if local_score >= remote_score {
+                info!("Local state has higher consensus score. Keeping local state.");
+                return true;
+            }
+        }
+        
+        // Replace the differing subtrees, which must rebuild the peer's root
+        info!("Updating {} differing subtrees with network state...", diff.subtrees.len());
+        match smt_lock.replace_subtrees(&diff.subtrees, diff.leaves, diff.root) {
+            Ok(_) => {
+                info!("Successfully synchronized state from network");
+                return true;
+            }
+            Err(e) => {
+                error!("Failed to set state from peer {}: {}", peer, e);
             }
         }
     }
@@ -1323,39 +1219,19 @@ fn calculate_consensus_scores(
 /// Verifies the signature in an update message.
 ///
 /// This function checks that the signature in the update message was created by the owner of the
-/// `from` address. In this system, addresses are derived from public keys, so we can extract
-/// the public key from the address and use it to verify the signature.
+/// `from` address. Ed25519 addresses are the public key itself; secp256k1 addresses embed the
+/// Ethereum address of the key, which is recovered from the signature.
 fn verify_signature(update: &UpdateMsg) -> Result<(), NodeError> {
-    use ed25519_dalek::{PublicKey, Signature, Verifier};
-    
-    // Extract the public key from the from address
-    // In our system, the address is derived directly from the public key
-    let mut public_key_bytes = [0u8; 32];
-    public_key_bytes.copy_from_slice(&update.from);
-    
-    // Create the public key from the bytes
-    let public_key = match PublicKey::from_bytes(&public_key_bytes) {
-        Ok(pk) => pk,
-        Err(e) => return Err(NodeError::InvalidProof(format!("Invalid public key: {}", e))),
-    };
-    
-    // Convert the core::types::Signature to ed25519_dalek::Signature
-    let signature_bytes = update.signature.0;
-    let signature = match Signature::from_bytes(&signature_bytes) {
-        Ok(sig) => sig,
-        Err(e) => return Err(NodeError::InvalidProof(format!("Invalid signature format: {}", e))),
-    };
-    
     // Create the transaction message for signature verification - matching how it's created in the CLI
-    let from_hex = hex::encode(&update.from);
-    let to_hex = hex::encode(&update.to);
-    
-    let transaction = serde_json::json!({
-        "from": from_hex,
-        "to": to_hex,
-        "amount": update.amount,
-        "nonce": update.nonce
-    });
+    let transaction = core::types::transfer_payload(
+        &update.from,
+        &update.to,
+        update.token_id,
+        update.amount,
+        update.nonce,
+        &update.memo,
+        update.ephemeral_key.as_ref(),
+    );
     
     // Serialize the transaction for signature verification
     let transaction_bytes = match serde_json::to_vec(&transaction) {
@@ -1363,13 +1239,14 @@ fn verify_signature(update: &UpdateMsg) -> Result<(), NodeError> {
         Err(e) => return Err(NodeError::InvalidProof(format!("Failed to serialize transaction: {}", e))),
     };
     
-    // Verify the signature
-    match public_key.verify(&transaction_bytes, &signature) {
+    // Verify the signature with the scheme that controls the sender's address
+    match core::crypto::verify_signature(update.scheme, &update.from, &transaction_bytes, &update.signature) {
         Ok(_) => Ok(()),
         Err(e) => {
             // For debugging
             debug!("Signature verification failed: {}", e);
             debug!("Transaction: {:?}", transaction);
+            debug!("Scheme: {}", update.scheme);
             debug!("From: {:?}", update.from);
             debug!("To: {:?}", update.to);
             debug!("Amount: {}", update.amount);
@@ -0,0 +1,264 @@
+//! Pool of transfers waiting for earlier nonces.
+//!
+//! Transfers can arrive out of order over gossip. A transfer whose nonce is
+//! ahead of the sender's account for its token is held here until the
+//! transfers before it have been applied, whether they arrive over gossip,
+//! are submitted over RPC or come with state sync, and is dropped if that
+//! does not happen within the TTL.
+
+use crate::errors::NodeError;
+use core::smt::SMT;
+use core::types::{AccountLeaf, Address};
+use network::types::UpdateMsg;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// The key of a pooled transfer: sender, token ID and nonce.
+pub type PoolKey = (Address, u64, u64);
+
+/// Limits of the transaction pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MempoolConfig {
+    /// Maximum number of transfers in the pool
+    #[serde(default = "default_max_size")]
+    pub max_size: usize,
+    /// Maximum number of transfers in the pool per sender
+    #[serde(default = "default_max_per_sender")]
+    pub max_per_sender: usize,
+    /// How long a transfer stays in the pool, in seconds
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+fn default_max_size() -> usize {
+    4096
+}
+
+fn default_max_per_sender() -> usize {
+    64
+}
+
+fn default_ttl_secs() -> u64 {
+    600
+}
+
+impl Default for MempoolConfig {
+    fn default() -> Self {
+        Self {
+            max_size: default_max_size(),
+            max_per_sender: default_max_per_sender(),
+            ttl_secs: default_ttl_secs(),
+        }
+    }
+}
+
+/// Errors when adding a transfer to the pool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MempoolError {
+    /// The pool holds `max_size` transfers
+    Full,
+    /// The sender has `max_per_sender` transfers in the pool
+    SenderFull,
+}
+
+impl std::fmt::Display for MempoolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MempoolError::Full => write!(f, "transaction pool is full"),
+            MempoolError::SenderFull => write!(f, "sender has too many pending transactions"),
+        }
+    }
+}
+
+impl std::error::Error for MempoolError {}
+
+/// A transfer held in the pool.
+#[derive(Debug, Clone)]
+pub struct PoolEntry {
+    /// The transfer
+    pub update: UpdateMsg,
+    /// When the transfer entered the pool
+    pub received: Instant,
+}
+
+/// Transfers with future nonces, indexed by `(address, token_id, nonce)`.
+#[derive(Debug)]
+pub struct Mempool {
+    config: MempoolConfig,
+    entries: BTreeMap<PoolKey, PoolEntry>,
+}
+
+impl Mempool {
+    /// Creates an empty pool.
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a transfer to the pool.
+    ///
+    /// A transfer with the same sender, token and nonce as a pooled one is
+    /// ignored, so the first one seen is kept.
+    pub fn insert(&mut self, update: UpdateMsg) -> Result<(), MempoolError> {
+        self.evict_expired();
+
+        let key = (update.from, update.token_id, update.nonce);
+        if self.entries.contains_key(&key) {
+            return Ok(());
+        }
+        if self.entries.len() >= self.config.max_size {
+            return Err(MempoolError::Full);
+        }
+        if self.sender_entries(&update.from).count() >= self.config.max_per_sender {
+            return Err(MempoolError::SenderFull);
+        }
+
+        self.entries.insert(key, PoolEntry { update, received: Instant::now() });
+        Ok(())
+    }
+
+    /// Removes and returns the sender's transfer of a token with the given
+    /// nonce, if it has not expired.
+    pub fn take_next(&mut self, from: &Address, token_id: u64, nonce: u64) -> Option<UpdateMsg> {
+        self.evict_expired();
+
+        self.entries.remove(&(*from, token_id, nonce)).map(|entry| entry.update)
+    }
+
+    /// Returns the senders and tokens with pooled transfers.
+    pub fn senders(&self) -> Vec<(Address, u64)> {
+        let mut senders: Vec<(Address, u64)> = self.entries.keys().map(|key| (key.0, key.1)).collect();
+        senders.dedup();
+        senders
+    }
+
+    /// Drops transfers older than the TTL and returns how many were dropped.
+    pub fn evict_expired(&mut self) -> usize {
+        let ttl = Duration::from_secs(self.config.ttl_secs);
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| entry.received.elapsed() < ttl);
+        before - self.entries.len()
+    }
+
+    /// Returns the pooled transfers in key order.
+    pub fn entries(&self) -> impl Iterator<Item = (&PoolKey, &PoolEntry)> {
+        self.entries.iter()
+    }
+
+    /// Drops all transfers and returns how many were dropped.
+    pub fn clear(&mut self) -> usize {
+        let count = self.entries.len();
+        self.entries.clear();
+        count
+    }
+
+    fn sender_entries<'a>(&'a self, from: &Address) -> impl Iterator<Item = (&'a PoolKey, &'a PoolEntry)> {
+        self.entries.range((*from, 0, 0)..=(*from, u64::MAX, u64::MAX))
+    }
+}
+
+/// Checks a transfer's nonce against the sender's account for the
+/// transfer's token, which is the nonce the pool is keyed by.
+///
+/// Returns the sender's account if the transfer is the next one,
+/// `FutureNonce` if it is ahead and belongs in the pool, and `InvalidNonce`
+/// if a transfer with its nonce has already applied.
+pub fn check_nonce(smt: &SMT, update: &UpdateMsg) -> Result<AccountLeaf, NodeError> {
+    let account = smt
+        .get_account_with_token(&update.from, update.token_id)
+        .map_err(|_| NodeError::AccountNotFound("sender".to_string()))?;
+
+    if account.nonce > update.nonce {
+        return Err(NodeError::InvalidNonce);
+    }
+    if account.nonce < update.nonce {
+        return Err(NodeError::FutureNonce { expected: account.nonce, actual: update.nonce });
+    }
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::{proofs::Proof, types::Signature};
+
+    fn update(from: u8, token_id: u64, nonce: u64) -> UpdateMsg {
+        let proof = Proof::new(Vec::new(), [0u8; 32], Vec::new(), 0);
+        UpdateMsg {
+            from: [from; 32],
+            to: [9u8; 32],
+            token_id,
+            amount: 1,
+            root: [0u8; 32],
+            post_root: [0u8; 32],
+            proof_from: proof.clone(),
+            proof_to: proof,
+            nonce,
+            memo: Vec::new(),
+            ephemeral_key: None,
+            scheme: Default::default(),
+            signature: Signature([0u8; 64]),
+        }
+    }
+
+    #[test]
+    fn test_take_next_by_nonce() {
+        let mut pool = Mempool::new(MempoolConfig::default());
+        pool.insert(update(1, 0, 3)).unwrap();
+        pool.insert(update(1, 7, 2)).unwrap();
+        pool.insert(update(2, 0, 2)).unwrap();
+
+        assert_eq!(pool.senders(), vec![([1u8; 32], 0), ([1u8; 32], 7), ([2u8; 32], 0)]);
+
+        // Nonces are counted per token
+        assert!(pool.take_next(&[1u8; 32], 0, 2).is_none());
+        assert_eq!(pool.take_next(&[1u8; 32], 7, 2).unwrap().token_id, 7);
+        assert_eq!(pool.take_next(&[1u8; 32], 0, 3).unwrap().nonce, 3);
+        assert_eq!(pool.entries().count(), 1);
+    }
+
+    #[test]
+    fn test_check_nonce_uses_the_token_account() {
+        let mut smt = SMT::new_zero();
+        smt.update(AccountLeaf::new([1u8; 32], 100, 0, 0)).unwrap();
+        smt.update(AccountLeaf::new([1u8; 32], 100, 5, 7)).unwrap();
+
+        // A non-native transfer is checked against the nonce for its token
+        assert_eq!(check_nonce(&smt, &update(1, 7, 5)).unwrap().token_id, 7);
+        assert!(matches!(
+            check_nonce(&smt, &update(1, 7, 6)),
+            Err(NodeError::FutureNonce { expected: 5, actual: 6 })
+        ));
+        assert!(matches!(check_nonce(&smt, &update(1, 7, 4)), Err(NodeError::InvalidNonce)));
+        assert!(matches!(
+            check_nonce(&smt, &update(1, 0, 5)),
+            Err(NodeError::FutureNonce { expected: 0, actual: 5 })
+        ));
+        assert!(matches!(check_nonce(&smt, &update(2, 7, 0)), Err(NodeError::AccountNotFound(_))));
+    }
+
+    #[test]
+    fn test_limits() {
+        let mut pool = Mempool::new(MempoolConfig { max_size: 3, max_per_sender: 2, ttl_secs: 60 });
+        pool.insert(update(1, 0, 1)).unwrap();
+        pool.insert(update(1, 0, 2)).unwrap();
+        pool.insert(update(1, 0, 2)).unwrap();
+        assert_eq!(pool.insert(update(1, 0, 3)), Err(MempoolError::SenderFull));
+
+        pool.insert(update(2, 0, 1)).unwrap();
+        assert_eq!(pool.insert(update(3, 0, 1)), Err(MempoolError::Full));
+        assert_eq!(pool.clear(), 3);
+    }
+
+    #[test]
+    fn test_expired_entries_are_evicted() {
+        let mut pool = Mempool::new(MempoolConfig { ttl_secs: 0, ..MempoolConfig::default() });
+        pool.insert(update(1, 0, 1)).unwrap();
+
+        assert!(pool.take_next(&[1u8; 32], 0, 1).is_none());
+        assert_eq!(pool.entries().count(), 0);
+    }
+}
@@ -1,9 +1,14 @@
 //! JSON-RPC server for the node daemon.
 
+use crate::errors::NodeError;
+use crate::mempool::Mempool;
 use anyhow::Result;
 use core::{proofs::Proof, smt::SMT, types::Address};
 use ed25519_dalek::Verifier;
+use libp2p::{PeerId, Swarm};
 use network::storage::ProofStore;
+use network::transport::NodeBehaviour;
+use network::ProofExchange;
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
 use std::net::SocketAddr;
@@ -57,30 +62,55 @@ struct JsonRpcError {
     data: Option<serde_json::Value>,
 }
 
+/// The treasury address, the only one allowed to mint native tokens.
+const TREASURY_ADDRESS: &str = "dcc80a50e84955049514913bd424ce6cbdff2bca048c612ab9eecbc7d703fa7e";
+
+/// How far the time claimed by a submitted message may lag behind this
+/// node's clock, in seconds.
+const MAX_CLOCK_SKEW_SECS: u64 = 300;
+
+/// How far the time claimed by a submitted message may be ahead of this
+/// node's clock, in seconds. It is kept small, so a sender cannot act much
+/// early on a period or deadline.
+const MAX_CLOCK_AHEAD_SECS: u64 = 5;
+
 /// State for the RPC server.
 struct RpcState {
     /// The Sparse Merkle Tree
     smt: Arc<Mutex<SMT>>,
     /// The proof store
     proof_store: ProofStore,
+    /// The pool of transfers waiting for earlier nonces
+    mempool: Arc<Mutex<Mempool>>,
+    /// The network swarm, for managing peers
+    swarm: Arc<Mutex<Swarm<NodeBehaviour>>>,
+    /// The proof exchange, for fetching proofs this node does not have
+    proof_exchange: ProofExchange,
     /// The local peer ID
     peer_id: String,
     /// Channel for broadcasting mint messages
     gossip_tx: Arc<Mutex<tokio::sync::mpsc::Sender<network::types::MintMsg>>>,
     /// Channel for broadcasting update messages
     update_tx: Arc<Mutex<tokio::sync::mpsc::Sender<network::types::UpdateMsg>>>,
+    /// Channel for broadcasting system messages
+    system_tx: Arc<Mutex<tokio::sync::mpsc::Sender<core::types::SystemMsg>>>,
 }
 
 /// Starts the JSON-RPC server.
+#[allow(clippy::too_many_arguments)]
 pub async fn start_rpc_server(
     addr: SocketAddr,
     smt: Arc<Mutex<SMT>>,
     proof_store: ProofStore,
+    mempool: Arc<Mutex<Mempool>>,
+    swarm: Arc<Mutex<Swarm<NodeBehaviour>>>,
+    proof_exchange: ProofExchange,
     peer_id: String,
     gossip_tx: Arc<Mutex<tokio::sync::mpsc::Sender<network::types::MintMsg>>>,
     update_tx: Arc<Mutex<tokio::sync::mpsc::Sender<network::types::UpdateMsg>>>,
+    system_tx: Arc<Mutex<tokio::sync::mpsc::Sender<core::types::SystemMsg>>>,
 ) -> Result<()> {
-    let state = Arc::new(RpcState { smt, proof_store, peer_id, gossip_tx, update_tx });
+    let state = Arc::new(RpcState { smt, proof_store, mempool, swarm, proof_exchange, peer_id, gossip_tx, update_tx, system_tx });
 
     let rpc_route = warp::path("rpc")
         .and(warp::post())
@@ -111,7 +141,7 @@ async fn handle_rpc(
 
     let result = match request.method.as_str() {
         "getRoot" => handle_get_root(&state),
-        "getProof" => handle_get_proof(&request.params, &state),
+        "getProof" => handle_get_proof(&request.params, &state).await,
         "getBalance" => handle_get_balance(&request.params, &state),
         "getBalanceWithToken" => handle_get_balance_with_token(&request.params, &state),
         "getAllBalances" => handle_get_all_balances(&request.params, &state),
@@ -132,6 +162,33 @@ async fn handle_rpc(
         "get_full_state" => handle_get_full_state(&state),
         "set_full_state" => handle_set_full_state(&request.params, &state),
         "get_tokens" => handle_get_tokens(&state),
+        "getTransactions" => handle_get_transactions(&request.params, &state),
+        "getTransaction" => handle_get_transaction(&request.params, &state),
+        "getStealthTransfers" => handle_get_stealth_transfers(&request.params, &state),
+        "verifyMessage" => handle_verify_message(&request.params, &state),
+        "verifyTypedData" => handle_verify_typed_data(&request.params, &state),
+        "submitMessage" => handle_submit_message(&request.params, &state),
+        "getConfidentialAccount" => handle_get_confidential_account(&request.params, &state),
+        "getConfidentialNotes" => handle_get_confidential_notes(&request.params, &state),
+        "resolveName" => handle_resolve_name(&request.params, &state),
+        "getNftCollection" => handle_get_nft_collection(&request.params, &state),
+        "getNft" => handle_get_nft(&request.params, &state),
+        "getNftsByOwner" => handle_get_nfts_by_owner(&request.params, &state),
+        "getCompliancePolicy" => handle_get_compliance_policy(&request.params, &state),
+        "getComplianceMembership" => handle_get_compliance_membership(&request.params, &state),
+        "getTokenCollateral" => handle_get_token_collateral(&request.params, &state),
+        "getAirdrop" => handle_get_airdrop(&request.params, &state),
+        "isAirdropClaimed" => handle_is_airdrop_claimed(&request.params, &state),
+        "getProposal" => handle_get_proposal(&request.params, &state),
+        "getVotingProof" => handle_get_voting_proof(&request.params, &state),
+        "getStandingOrders" => handle_get_standing_orders(&request.params, &state),
+        "getChannel" => handle_get_channel(&request.params, &state),
+        "getSessionKey" => handle_get_session_key(&request.params, &state),
+        "getMempool" => handle_get_mempool(&state),
+        "clearMempool" => handle_clear_mempool(&state),
+        "admin_banPeer" => handle_ban_peer(&request.params, &state),
+        "admin_unbanPeer" => handle_unban_peer(&request.params, &state),
+        "admin_listBans" => handle_list_bans(&state),
         _ => Err(JsonRpcError {
             code: -32601,
             message: "Method not found".to_string(),
@@ -169,7 +226,11 @@ fn handle_get_root(state: &RpcState) -> Result<serde_json::Value, JsonRpcError>
 }
 
 /// Handles the getProof method.
-fn handle_get_proof(
+///
+/// Without a root, or with the current one, the proof is generated from the
+/// SMT. Proofs at earlier roots come from the proof store, or are requested
+/// from connected peers and verified before they are returned.
+async fn handle_get_proof(
     params: &serde_json::Value,
     state: &RpcState,
 ) -> Result<serde_json::Value, JsonRpcError> {
@@ -182,7 +243,7 @@ fn handle_get_proof(
             data: None,
         })?;
 
-    if params.len() != 1 {
+    if params.is_empty() || params.len() > 2 {
         return Err(JsonRpcError {
             code: -32602,
             message: "Invalid params".to_string(),
@@ -190,31 +251,12 @@ fn handle_get_proof(
         });
     }
 
-    let address_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
-        code: -32602,
-        message: "Invalid address".to_string(),
-        data: None,
-    })?;
-
-    // Parse address
-    let address_bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
-        JsonRpcError {
-            code: -32602,
-            message: "Invalid address".to_string(),
-            data: Some(serde_json::to_value(e.to_string()).unwrap()),
-        }
-    })?;
-
-    if address_bytes.len() != 32 {
-        return Err(JsonRpcError {
-            code: -32602,
-            message: "Invalid address length".to_string(),
-            data: None,
-        });
-    }
+    let address = parse_address_param(&params[0])?;
 
-    let mut address = [0u8; 32];
-    address.copy_from_slice(&address_bytes);
+    let requested_root = match params.get(1) {
+        Some(root) => Some(parse_root_param(root)?),
+        None => None,
+    };
 
     // Get the current root
     let root = {
@@ -223,15 +265,20 @@ fn handle_get_proof(
     };
 
     // Get the proof
-    let proof = {
-        let smt = state.smt.lock().unwrap();
-        // The gen_proof method should work even for non-existent accounts
-        // It will generate a proof for an empty leaf
-        smt.gen_proof(&address).map_err(|e| JsonRpcError {
-            code: -32603,
-            message: "Failed to generate proof".to_string(),
-            data: Some(serde_json::to_value(e.to_string()).unwrap()),
-        })?
+    let proof = match requested_root {
+        Some(requested_root) if requested_root != root => {
+            fetch_proof(state, &address, &requested_root).await?
+        }
+        _ => {
+            let smt = state.smt.lock().unwrap();
+            // The gen_proof method should work even for non-existent accounts
+            // It will generate a proof for an empty leaf
+            smt.gen_proof(&address).map_err(|e| JsonRpcError {
+                code: -32603,
+                message: "Failed to generate proof".to_string(),
+                data: Some(serde_json::to_value(e.to_string()).unwrap()),
+            })?
+        }
     };
 
     // Serialize the proof using json! macro to ensure it's not null
@@ -244,6 +291,56 @@ fn handle_get_proof(
     Ok(serde_json::json!(proof_value))
 }
 
+/// Finds the proof of an account at an earlier root, in the proof store or
+/// else from connected peers, storing proofs fetched from peers. The proof
+/// exchange only returns proofs that verify against the root.
+async fn fetch_proof(state: &RpcState, address: &Address, root: &[u8; 32]) -> Result<Proof, JsonRpcError> {
+    if let Ok(proof) = state.proof_store.get_proof(address, root) {
+        return Ok(proof);
+    }
+
+    let peer = state.swarm.lock().unwrap().connected_peers().next().copied();
+    let peer = match peer {
+        Some(peer) => peer,
+        None => {
+            return Err(JsonRpcError {
+                code: -32603,
+                message: "Proof not found and no peers to request it from".to_string(),
+                data: None,
+            });
+        }
+    };
+
+    let proof = state
+        .proof_exchange
+        .request_proof(&state.swarm, peer, address, root)
+        .await
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Proof not found".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    if let Err(e) = state.proof_store.put_proof(address, root, &proof) {
+        warn!("Failed to store fetched proof: {}", e);
+    }
+
+    Ok(proof)
+}
+
+/// Parses a hex-encoded 32-byte state root parameter.
+fn parse_root_param(value: &serde_json::Value) -> Result<[u8; 32], JsonRpcError> {
+    value
+        .as_str()
+        .and_then(|root| hex::decode(root.trim_start_matches("0x")).ok())
+        .and_then(|root| root.try_into().ok())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid root".to_string(),
+            data: None,
+        })
+}
+
 /// Handles the getBalance method.
 fn handle_get_balance(
     params: &serde_json::Value,
@@ -444,6 +541,13 @@ fn handle_broadcast_update(
         }
     })?;
 
+    // Check the memo size
+    core::types::validate_memo(&update_msg.memo).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: "Invalid memo".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
     // Verify the signature
     let message_bytes = bincode::serialize(&network::types::UpdateMsg {
         from: update_msg.from,
@@ -455,6 +559,9 @@ fn handle_broadcast_update(
         proof_from: update_msg.proof_from.clone(),
         proof_to: update_msg.proof_to.clone(),
         nonce: update_msg.nonce,
+        memo: update_msg.memo.clone(),
+        ephemeral_key: update_msg.ephemeral_key,
+        scheme: update_msg.scheme,
         signature: core::types::Signature([0u8; 64]), // Empty signature for verification
     })
     .map_err(|e| JsonRpcError {
@@ -463,24 +570,8 @@ fn handle_broadcast_update(
         data: Some(serde_json::to_value(e.to_string()).unwrap()),
     })?;
 
-    // Verify the signature using ed25519-dalek
-    let public_key = ed25519_dalek::PublicKey::from_bytes(&update_msg.from[..32]).map_err(|e| {
-        JsonRpcError {
-            code: -32603,
-            message: "Invalid public key".to_string(),
-            data: Some(serde_json::to_value(e.to_string()).unwrap()),
-        }
-    })?;
-
-    let signature = ed25519_dalek::Signature::from_bytes(&update_msg.signature.0).map_err(|e| {
-        JsonRpcError {
-            code: -32603,
-            message: "Invalid signature".to_string(),
-            data: Some(serde_json::to_value(e.to_string()).unwrap()),
-        }
-    })?;
-
-    if let Err(e) = public_key.verify(&message_bytes, &signature) {
+    // Verify the signature with the scheme that controls the sender's address
+    if let Err(e) = core::crypto::verify_signature(update_msg.scheme, &update_msg.from, &message_bytes, &update_msg.signature) {
         return Err(JsonRpcError {
             code: -32603,
             message: "Invalid signature".to_string(),
@@ -545,6 +636,15 @@ fn handle_broadcast_update(
             });
         }
 
+        // Check the token's compliance policy
+        if let Err(e) = smt.check_transfer_compliance(update_msg.token_id, &update_msg.from, &update_msg.to) {
+            return Err(JsonRpcError {
+                code: -32603,
+                message: e.to_string(),
+                data: None,
+            });
+        }
+
         // Update the sender's account
         sender_account.bal -= update_msg.amount;
         sender_account.nonce += 1;
@@ -572,16 +672,14 @@ fn handle_broadcast_update(
         })?;
     }
 
-    // Generate a transaction hash
-    let tx_hash = {
-        let mut hasher = sha2::Sha256::new();
-        hasher.update(&bincode::serialize(&update_msg).unwrap());
-        let result = hasher.finalize();
-        hex::encode(result)
-    };
+    // Record the transaction
+    let record = update_msg.to_tx_record();
+    if let Err(e) = state.proof_store.put_tx_record(&record) {
+        warn!("Failed to store transaction record: {}", e);
+    }
 
     // Return the transaction hash
-    Ok(serde_json::json!(tx_hash))
+    Ok(serde_json::json!(hex::encode(record.tx_hash)))
 }
 
 /// Handles the get_proof_with_token method.
@@ -774,12 +872,13 @@ fn handle_issue_token(
 
     // Process the message
     match message {
-        core::types::SystemMsg::IssueToken { issuer, token_id: _, metadata, nonce, signature } => {
+        core::types::SystemMsg::IssueToken { issuer, token_id: _, metadata, collateral, nonce, signature } => {
             // Verify the signature
             let message_bytes = bincode::serialize(&core::types::SystemMsg::IssueToken {
                 issuer,
                 token_id: 0, // Will be assigned by the system
                 metadata: metadata.clone(),
+                collateral,
                 nonce,
                 signature: core::types::Signature([0u8; 64]), // Empty signature for verification
             })
@@ -798,7 +897,7 @@ fn handle_issue_token(
                 }
             })?;
 
-            let signature = ed25519_dalek::Signature::from_bytes(&signature.0).map_err(|e| {
+            let ed_signature = ed25519_dalek::Signature::from_bytes(&signature.0).map_err(|e| {
                 JsonRpcError {
                     code: -32603,
                     message: "Invalid signature".to_string(),
@@ -806,7 +905,7 @@ fn handle_issue_token(
                 }
             })?;
 
-            if let Err(e) = public_key.verify(&message_bytes, &signature) {
+            if let Err(e) = public_key.verify(&message_bytes, &ed_signature) {
                 return Err(JsonRpcError {
                     code: -32603,
                     message: "Invalid signature".to_string(),
@@ -814,16 +913,22 @@ fn handle_issue_token(
                 });
             }
 
-            // Register the token
+            // Register the token, locking its collateral
             let token_id = {
                 let mut smt = state.smt.lock().unwrap();
-                smt.register_token(&issuer, metadata).map_err(|e| JsonRpcError {
+                smt.issue_token(&issuer, metadata.clone(), collateral, nonce).map_err(|e| JsonRpcError {
                     code: -32603,
                     message: "Failed to register token".to_string(),
                     data: Some(serde_json::to_value(e.to_string()).unwrap()),
                 })?
             };
 
+            // Publish the issuance with the token ID it got, for peers to check
+            let issued = core::types::SystemMsg::IssueToken { issuer, token_id, metadata, collateral, nonce, signature };
+            if let Err(e) = state.system_tx.lock().unwrap().try_send(issued) {
+                error!("Failed to send token issuance to gossip channel: {}", e);
+            }
+
             // Return the token ID
             Ok(serde_json::json!(token_id))
         },
@@ -1287,11 +1392,11 @@ fn handle_mint(
         })?;
 
     // Check if we have the right number of parameters
-    // We need: [from_address, from_signature, to_address, amount]
-    if params.len() != 4 {
+    // We need: [from_address, from_signature, to_address, amount, nonce]
+    if params.len() != 5 {
         return Err(JsonRpcError {
             code: -32602,
-            message: "Invalid params. Expected [from_address, from_signature, to_address, amount]".to_string(),
+            message: "Invalid params. Expected [from_address, from_signature, to_address, amount, nonce]".to_string(),
             data: None,
         });
     }
@@ -1396,9 +1501,16 @@ fn handle_mint(
         });
     };
 
-    // Create the message that was signed
-    let message = format!("mint:{}:{}", to_hex, amount);
-    let message_bytes = message.as_bytes();
+    // Parse the treasury's nonce, which the signature covers
+    let nonce = params[4].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid nonce".to_string(),
+        data: None,
+    })?;
+
+    // Create the message that was signed, in the canonical form gossip
+    // peers check it in
+    let message_bytes = network::types::MintMsg::signing_message(&from, &to, 0, amount, nonce);
 
     // Verify the signature
     let public_key = ed25519_dalek::PublicKey::from_bytes(&from).map_err(|e| {
@@ -1417,7 +1529,7 @@ fn handle_mint(
         }
     })?;
 
-    if let Err(e) = public_key.verify(message_bytes, &ed_signature) {
+    if let Err(e) = public_key.verify(&message_bytes, &ed_signature) {
         return Err(JsonRpcError {
             code: -32603,
             message: "Invalid signature".to_string(),
@@ -1425,16 +1537,8 @@ fn handle_mint(
         });
     }
 
-    // Check if the from address is authorized to mint
-    // For now, we'll use a simple check - only the treasury address can mint
-    // This would be a configurable list of authorized minters
-    let wallet_address = hex::decode("dcc80a50e84955049514913bd424ce6cbdff2bca048c612ab9eecbc7d703fa7e").unwrap_or_default();
-    let mut treasury_address = [0u8; 32];
-    if wallet_address.len() == 32 {
-        treasury_address.copy_from_slice(&wallet_address);
-    }
-    
-    if from != treasury_address {
+    // Only the treasury can mint
+    if from != treasury_address() {
         return Err(JsonRpcError {
             code: -32603,
             message: "Unauthorized: Only the treasury can mint tokens".to_string(),
@@ -1443,7 +1547,7 @@ fn handle_mint(
     }
 
     // Mint tokens and prepare for broadcasting
-    let (root, proof_from, proof_to, nonce) = {
+    let (root, proof_from, proof_to) = {
         let mut smt = state.smt.lock().unwrap();
         
         // Get the treasury account (from address)
@@ -1455,8 +1559,15 @@ fn handle_mint(
             }
         };
         
-        // Get the current nonce for the treasury account
-        let nonce = treasury_account.nonce;
+        // The signed nonce must be the treasury's current one, so a
+        // signature cannot be used for a second mint
+        if nonce != treasury_account.nonce {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: format!("Invalid nonce: expected {}, got {}", treasury_account.nonce, nonce),
+                data: None,
+            });
+        }
         
         // Increment the nonce for the treasury account
         treasury_account.nonce += 1;
@@ -1499,7 +1610,7 @@ fn handle_mint(
             data: Some(serde_json::to_value(e.to_string()).unwrap()),
         })?;
         
-        (root, proof_from, proof_to, nonce)
+        (root, proof_from, proof_to)
     };
     
     // Create a MintMsg for broadcasting
@@ -1715,6 +1826,15 @@ fn handle_broadcast_mint(
             });
         }
 
+        // Check the token's compliance policy
+        if let Err(e) = smt.check_compliance(message.token_id, &message.to) {
+            return Err(JsonRpcError {
+                code: -32603,
+                message: e.to_string(),
+                data: None,
+            });
+        }
+
         // Update the sender's account (treasury)
         sender_account.nonce += 1;
         smt.update_account_with_token(sender_account, message.token_id).map_err(|e| JsonRpcError {
@@ -1769,10 +1889,10 @@ fn handle_send(
                     data: None,
                 })?;
         
-            if params.len() != 6 {
+            if !(6..=8).contains(&params.len()) {
                 return Err(JsonRpcError {
                     code: -32602,
-                    message: format!("Expected 6 parameters, got {}", params.len()),
+                    message: format!("Expected 6 to 8 parameters, got {}", params.len()),
                     data: None,
                 });
             }
@@ -1876,42 +1996,76 @@ fn handle_send(
             let mut signature = [0u8; 64];
             signature.copy_from_slice(&signature_bytes);
         
-            // Create the transaction message for signature verification
-            let transaction = serde_json::json!({
-                "from": from_hex,
-                "to": to_hex.trim_start_matches("0x"),
-                "token_id": token_id,
-                "amount": amount,
-                "nonce": nonce
-            });
+            // Parse the optional memo (hex-encoded bytes)
+            let memo = match params.get(6) {
+                Some(memo_param) if !memo_param.is_null() => {
+                    let memo_hex = memo_param.as_str().ok_or_else(|| JsonRpcError {
+                        code: -32602,
+                        message: "Invalid memo".to_string(),
+                        data: None,
+                    })?;
         
-            // Serialize the transaction for signature verification
-            let transaction_bytes = serde_json::to_vec(&transaction).map_err(|e| {
-                JsonRpcError {
-                    code: -32603,
-                    message: "Failed to serialize transaction".to_string(),
-                    data: Some(serde_json::to_value(e.to_string()).unwrap()),
+                    hex::decode(memo_hex.trim_start_matches("0x")).map_err(|e| JsonRpcError {
+                        code: -32602,
+                        message: "Invalid memo".to_string(),
+                        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+                    })?
                 }
+                _ => Vec::new(),
+            };
+        
+            core::types::validate_memo(&memo).map_err(|e| JsonRpcError {
+                code: -32602,
+                message: "Invalid memo".to_string(),
+                data: Some(serde_json::to_value(e.to_string()).unwrap()),
             })?;
         
-            // Verify the signature
-            let public_key = ed25519_dalek::PublicKey::from_bytes(&from).map_err(|e| {
-                JsonRpcError {
-                    code: -32603,
-                    message: "Invalid public key".to_string(),
-                    data: Some(serde_json::to_value(e.to_string()).unwrap()),
+            // Parse the optional stealth ephemeral key (32-byte hex)
+            let ephemeral_key = match params.get(7) {
+                Some(key_param) if !key_param.is_null() => {
+                    let key_bytes = key_param
+                        .as_str()
+                        .and_then(|key_hex| hex::decode(key_hex.trim_start_matches("0x")).ok())
+                        .ok_or_else(|| JsonRpcError {
+                            code: -32602,
+                            message: "Invalid ephemeral key".to_string(),
+                            data: None,
+                        })?;
+        
+                    let key: [u8; 32] = key_bytes.try_into().map_err(|_| JsonRpcError {
+                        code: -32602,
+                        message: "Invalid ephemeral key length".to_string(),
+                        data: None,
+                    })?;
+                    Some(key)
                 }
-            })?;
+                _ => None,
+            };
+        
+            // Create the transaction message for signature verification
+            let transaction = core::types::transfer_payload(
+                &from,
+                &to,
+                token_id,
+                amount,
+                nonce,
+                &memo,
+                ephemeral_key.as_ref(),
+            );
         
-            let ed_signature = ed25519_dalek::Signature::from_bytes(&signature).map_err(|e| {
+            // Serialize the transaction for signature verification
+            let transaction_bytes = serde_json::to_vec(&transaction).map_err(|e| {
                 JsonRpcError {
                     code: -32603,
-                    message: "Invalid signature".to_string(),
+                    message: "Failed to serialize transaction".to_string(),
                     data: Some(serde_json::to_value(e.to_string()).unwrap()),
                 }
             })?;
         
-            if let Err(e) = public_key.verify(&transaction_bytes, &ed_signature) {
+            // Verify the signature with the scheme that controls the sender's address
+            let scheme = core::types::SignatureScheme::of_address(&from);
+            let core_signature = core::types::Signature(signature);
+            if let Err(e) = core::crypto::verify_signature(scheme, &from, &transaction_bytes, &core_signature) {
                 return Err(JsonRpcError {
                     code: -32603,
                     message: "Invalid signature".to_string(),
@@ -1977,6 +2131,15 @@ fn handle_send(
                         data: None,
                     });
                 }
+
+                // Check the token's compliance policy
+                if let Err(e) = smt.check_transfer_compliance(token_id, &from, &to) {
+                    return Err(JsonRpcError {
+                        code: -32603,
+                        message: e.to_string(),
+                        data: None,
+                    });
+                }
         
                 // Update the sender's account
                 sender_account.bal -= amount;
@@ -2025,9 +2188,18 @@ fn handle_send(
                 proof_from,
                 proof_to,
                 nonce,
-                signature: core::types::Signature(signature),
+                memo,
+                ephemeral_key,
+                scheme,
+                signature: core_signature,
             };
 
+            // Record the transaction before handing the update to the broadcaster
+            let record = update_msg.to_tx_record();
+            if let Err(e) = state.proof_store.put_tx_record(&record) {
+                tracing::warn!("Failed to store transaction record: {}", e);
+            }
+
             // Broadcast the update to other nodes using the update_tx channel
             if let Err(e) = state.update_tx.lock().unwrap().try_send(update_msg) {
                 // Log the error but don't fail the transaction
@@ -2036,19 +2208,8 @@ fn handle_send(
                 tracing::info!("Successfully queued transaction update for broadcast");
             }
         
-            // Generate a transaction hash
-            let mut hasher = sha2::Sha256::new();
-            hasher.update(&from);
-            hasher.update(&to);
-            hasher.update(&token_id.to_be_bytes());
-            hasher.update(&amount.to_be_bytes());
-            hasher.update(&nonce.to_be_bytes());
-            hasher.update(&signature);
-            let tx_hash = hasher.finalize();
-            let tx_hash_hex = hex::encode(tx_hash);
-        
             // Return the transaction hash
-            Ok(serde_json::json!(tx_hash_hex))
+            Ok(serde_json::json!(hex::encode(record.tx_hash)))
         }
     
     
@@ -2385,11 +2546,26 @@ fn handle_get_tokens(state: &RpcState) -> Result<serde_json::Value, JsonRpcError
         
         // Convert token info to JSON
         for (token_id, token_info) in token_registry {
+            let non_fungible = smt.get_nft_collection(*token_id).map_err(|e| JsonRpcError {
+                code: -32603,
+                message: "Failed to get NFT collection".to_string(),
+                data: Some(serde_json::to_value(e.to_string()).unwrap()),
+            })?.is_some();
+
+            let escrow = smt.get_token_escrow(*token_id).map_err(|e| JsonRpcError {
+                code: -32603,
+                message: "Failed to get token escrow".to_string(),
+                data: Some(serde_json::to_value(e.to_string()).unwrap()),
+            })?;
+
             tokens.push(serde_json::json!({
                 "token_id": token_id,
                 "issuer": hex::encode(token_info.issuer),
                 "metadata": token_info.metadata,
                 "total_supply": token_info.total_supply,
+                "non_fungible": non_fungible,
+                "collateral": escrow.as_ref().map_or(0, |escrow| escrow.collateral),
+                "retired": escrow.is_some_and(|escrow| escrow.retired),
             }));
         }
         
@@ -2400,4 +2576,1941 @@ fn handle_get_tokens(state: &RpcState) -> Result<serde_json::Value, JsonRpcError
 
     // Return the tokens as a JSON array
     Ok(serde_json::json!(tokens))
-}
\ No newline at end of file
+}
+
+/// Converts a transaction record to its JSON-RPC representation.
+fn tx_record_to_json(record: &core::types::TxRecord) -> serde_json::Value {
+    serde_json::json!({
+        "tx_hash": hex::encode(record.tx_hash),
+        "from": hex::encode(record.from),
+        "to": hex::encode(record.to),
+        "token_id": record.token_id,
+        "amount": record.amount.to_string(),
+        "nonce": record.nonce,
+        "memo": hex::encode(&record.memo),
+        "memo_text": record.memo_text(),
+        "ephemeral_key": record.ephemeral_key.map(hex::encode),
+        "timestamp": record.timestamp
+    })
+}
+
+/// Handles the getTransactions method.
+///
+/// Returns every transfer sent or received by an address, oldest first.
+fn handle_get_transactions(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid address".to_string(),
+        data: None,
+    })?;
+
+    let address_bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid address".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if address_bytes.len() != 32 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid address length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&address_bytes);
+
+    let records = state.proof_store.get_tx_records(&address).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get transactions".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::Value::Array(records.iter().map(tx_record_to_json).collect()))
+}
+
+/// Handles the getTransaction method.
+fn handle_get_transaction(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let tx_hash_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid transaction hash".to_string(),
+        data: None,
+    })?;
+
+    let tx_hash_bytes = hex::decode(tx_hash_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid transaction hash".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if tx_hash_bytes.len() != 32 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid transaction hash length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut tx_hash = [0u8; 32];
+    tx_hash.copy_from_slice(&tx_hash_bytes);
+
+    match state.proof_store.get_tx_record(&tx_hash) {
+        Ok(Some(record)) => Ok(tx_record_to_json(&record)),
+        Ok(None) => Err(JsonRpcError {
+            code: -32603,
+            message: "Transaction not found".to_string(),
+            data: None,
+        }),
+        Err(e) => Err(JsonRpcError {
+            code: -32603,
+            message: "Failed to get transaction".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }),
+    }
+}
+
+/// Handles the getStealthTransfers method.
+///
+/// Returns the stealth payments processed at or after an optional Unix
+/// timestamp, oldest first, for wallets to scan for their own.
+fn handle_get_stealth_transfers(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    let since = match params.as_array().and_then(|params| params.first()) {
+        Some(since) if !since.is_null() => since.as_u64().ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid timestamp".to_string(),
+            data: None,
+        })?,
+        _ => 0,
+    };
+
+    let records = state.proof_store.get_stealth_tx_records(since).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get stealth transfers".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::Value::Array(records.iter().map(tx_record_to_json).collect()))
+}
+
+/// Handles a verifyMessage request.
+///
+/// Checks an off-chain signed message against the address that claims to have
+/// signed it. Returns `true` or `false`; malformed parameters are errors.
+fn handle_verify_message(
+    params: &serde_json::Value,
+    _state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 3 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid address".to_string(),
+        data: None,
+    })?;
+
+    let address_bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid address".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if address_bytes.len() != 32 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid address length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&address_bytes);
+
+    let message = params[1].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid message".to_string(),
+        data: None,
+    })?;
+
+    let signature_hex = params[2].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid signature".to_string(),
+        data: None,
+    })?;
+
+    let signature_bytes = hex::decode(signature_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid signature".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if signature_bytes.len() != 64 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid signature length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&signature_bytes);
+
+    let valid = core::crypto::verify_signed_message(
+        &address,
+        message.as_bytes(),
+        &core::types::Signature(signature),
+    )
+    .is_ok();
+
+    Ok(serde_json::json!(valid))
+}
+
+/// Handles a verifyTypedData request.
+///
+/// Checks a typed structured-data signature against the address that claims
+/// to have signed it. Returns `true` or `false`; malformed parameters,
+/// including typed data that does not match its declared types, are errors.
+fn handle_verify_typed_data(
+    params: &serde_json::Value,
+    _state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 3 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid address".to_string(),
+        data: None,
+    })?;
+
+    let address_bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid address".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if address_bytes.len() != 32 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid address length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut address = [0u8; 32];
+    address.copy_from_slice(&address_bytes);
+
+    let typed_data: core::typed_data::TypedData = serde_json::from_value(params[1].clone())
+        .map_err(|e| JsonRpcError {
+            code: -32602,
+            message: "Invalid typed data".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let signing_bytes = typed_data.signing_bytes().map_err(|e| JsonRpcError {
+        code: -32602,
+        message: "Invalid typed data".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let signature_hex = params[2].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid signature".to_string(),
+        data: None,
+    })?;
+
+    let signature_bytes = hex::decode(signature_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid signature".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    if signature_bytes.len() != 64 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid signature length".to_string(),
+            data: None,
+        });
+    }
+
+    let mut signature = [0u8; 64];
+    signature.copy_from_slice(&signature_bytes);
+
+    let valid = core::crypto::verify_signature(
+        core::types::SignatureScheme::of_address(&address),
+        &address,
+        &signing_bytes,
+        &core::types::Signature(signature),
+    )
+    .is_ok();
+
+    Ok(serde_json::json!(valid))
+}
+
+/// Parses a hex-encoded 32-byte address parameter.
+fn parse_address_param(value: &serde_json::Value) -> Result<Address, JsonRpcError> {
+    let address_hex = value.as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid address".to_string(),
+        data: None,
+    })?;
+
+    let address_bytes = hex::decode(address_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid address".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    address_bytes.try_into().map_err(|_| JsonRpcError {
+        code: -32602,
+        message: "Invalid address length".to_string(),
+        data: None,
+    })
+}
+
+/// Handles a submitMessage request.
+///
+/// Accepts a hex-encoded bincode `SystemMsg`, checks that it is signed by its
+/// signer and applies it to the state. Transfers, mints and token issuance
+/// have dedicated methods and are rejected here. Returns the new state root,
+/// for a new NFT collection its token ID, and for a new proposal its ID.
+///
+/// For a new proposal, the balance proofs of the token's holders against the
+/// proposal's snapshot are kept so voters can fetch them later.
+fn handle_submit_message(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let message_hex = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid message".to_string(),
+        data: None,
+    })?;
+
+    let message_bytes = hex::decode(message_hex.trim_start_matches("0x")).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid message".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    let message: core::types::SystemMsg = bincode::deserialize(&message_bytes).map_err(|e| {
+        JsonRpcError {
+            code: -32602,
+            message: "Invalid message".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        }
+    })?;
+
+    // The claimed time is only checked here, when the message enters the
+    // network; nodes receiving it over gossip apply it whatever their clock
+    let validated = validate_system_msg(&message)
+        .and_then(|_| message.claimed_time().map_or(Ok(()), check_claimed_time));
+
+    validated.map_err(|e| match e {
+        NodeError::InvalidSignature(_) => JsonRpcError {
+            code: -32602,
+            message: "Invalid signature".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        },
+        NodeError::ClockSkew { timestamp, node_time } => JsonRpcError {
+            code: -32602,
+            message: "Message timestamp is ahead of or too far behind the node's clock".to_string(),
+            data: Some(serde_json::json!({ "timestamp": timestamp, "node_time": node_time })),
+        },
+        e => JsonRpcError {
+            code: -32602,
+            message: e.to_string(),
+            data: None,
+        },
+    })?;
+
+    info!("Applying submitted message: {}", message);
+
+    let (root, issued_token_id) = apply_system_msg(&message, &state.smt, &state.proof_store).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to apply message".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    // Publish the message so that other nodes apply it too
+    if is_gossiped(&message) {
+        if let Err(e) = state.system_tx.lock().unwrap().try_send(message.clone()) {
+            error!("Failed to send system message to gossip channel: {}", e);
+        }
+    }
+
+    // Tell the issuer which token ID its new collection got
+    if let core::types::SystemMsg::IssueNftCollection { .. } = message {
+        return Ok(serde_json::json!({
+            "root": hex::encode(root),
+            "collection_id": issued_token_id,
+        }));
+    }
+
+    if let core::types::SystemMsg::CreateProposal { proposer, token_id, nonce, .. } = message {
+        let proposal_id = core::governance::proposal_id(&proposer, token_id, nonce);
+        return Ok(serde_json::json!({
+            "root": hex::encode(root),
+            "proposal_id": hex::encode(proposal_id),
+        }));
+    }
+
+    Ok(serde_json::json!({ "root": hex::encode(root) }))
+}
+
+/// Returns whether a system message is published over gossip once it is
+/// submitted, and applied when received from peers.
+///
+/// Each kind opts in once applying it gives the same state on every node;
+/// other kinds apply only on the node they are submitted to.
+pub fn is_gossiped(message: &core::types::SystemMsg) -> bool {
+    use core::types::SystemMsg;
+
+    matches!(
+        message,
+        // Burns, published on their own topic
+        SystemMsg::Burn { .. }
+            // Confidential transfers
+            | SystemMsg::Shield { .. }
+            | SystemMsg::Unshield { .. }
+            | SystemMsg::ConfidentialTransfer { .. }
+            // Names
+            | SystemMsg::RegisterName { .. }
+            | SystemMsg::TransferName { .. }
+            | SystemMsg::SetNameTarget { .. }
+            // NFT collections and items
+            | SystemMsg::IssueNftCollection { .. }
+            | SystemMsg::MintNft { .. }
+            | SystemMsg::TransferNft { .. }
+            // Compliance policies
+            | SystemMsg::SetCompliancePolicy { .. }
+            | SystemMsg::UpdateComplianceList { .. }
+            // Token retirement, which releases the issuance collateral
+            | SystemMsg::RetireToken { .. }
+            // Airdrops
+            | SystemMsg::CreateAirdrop { .. }
+            | SystemMsg::ClaimAirdrop { .. }
+            | SystemMsg::ReclaimAirdrop { .. }
+            // Governance
+            | SystemMsg::CreateProposal { .. }
+            | SystemMsg::Vote { .. }
+            // Standing orders
+            | SystemMsg::AuthorizeStandingOrder { .. }
+            | SystemMsg::CancelStandingOrder { .. }
+            | SystemMsg::PullPayment { .. }
+            // Payment channels
+            | SystemMsg::OpenChannel { .. }
+            | SystemMsg::CloseChannel { .. }
+            | SystemMsg::StartChannelTimeout { .. }
+            | SystemMsg::ReclaimChannel { .. }
+            // Session keys
+            | SystemMsg::AuthorizeSessionKey { .. }
+            | SystemMsg::RevokeSessionKey { .. }
+            | SystemMsg::SessionTransfer { .. }
+    )
+}
+
+/// Checks a system message before it is applied, whether it was submitted
+/// over RPC or received over gossip.
+///
+/// Transfers, mints and token issuance have their own methods and gossip
+/// topics, so they are not accepted as system messages. The claimed time is
+/// not checked here, since whether a gossiped message applies must not
+/// depend on the receiving node's clock.
+pub fn validate_system_msg(message: &core::types::SystemMsg) -> Result<(), NodeError> {
+    if matches!(
+        message,
+        core::types::SystemMsg::Transfer { .. }
+            | core::types::SystemMsg::Mint { .. }
+            | core::types::SystemMsg::IssueToken { .. }
+    ) {
+        return Err(NodeError::InvalidMessage(
+            "Message type must be submitted with its dedicated method".to_string(),
+        ));
+    }
+
+    core::crypto::verify_system_msg(message).map_err(|e| NodeError::InvalidSignature(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Checks that a claimed time is at most `MAX_CLOCK_AHEAD_SECS` ahead of
+/// this node's clock, and lags behind it by at most `MAX_CLOCK_SKEW_SECS`.
+fn check_claimed_time(timestamp: u64) -> Result<(), NodeError> {
+    let node_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    if timestamp > node_time.saturating_add(MAX_CLOCK_AHEAD_SECS)
+        || node_time.saturating_sub(timestamp) > MAX_CLOCK_SKEW_SECS
+    {
+        return Err(NodeError::ClockSkew { timestamp, node_time });
+    }
+    Ok(())
+}
+
+/// Returns the treasury address.
+fn treasury_address() -> Address {
+    let mut address = [0u8; 32];
+    if let Ok(bytes) = hex::decode(TREASURY_ADDRESS) {
+        address.copy_from_slice(&bytes);
+    }
+    address
+}
+
+/// Checks a treasury mint received over gossip.
+///
+/// The treasury signs the mint's sender, recipient, token, amount and nonce,
+/// so a signature cannot be replayed for another mint: the nonce it covers
+/// is used up when the mint applies.
+pub fn validate_mint(mint: &network::types::MintMsg) -> Result<(), NodeError> {
+    if mint.from != treasury_address() {
+        return Err(NodeError::InvalidMessage("Only the treasury can mint tokens".to_string()));
+    }
+    if mint.token_id != 0 {
+        return Err(NodeError::InvalidMessage("The treasury only mints the native token".to_string()));
+    }
+
+    core::crypto::verify_signature(
+        core::types::SignatureScheme::of_address(&mint.from),
+        &mint.from,
+        &mint.signing_bytes(),
+        &mint.signature,
+    )
+    .map_err(|e| NodeError::InvalidSignature(e.to_string()))
+}
+
+/// Applies a validated treasury mint, using the treasury's nonce and
+/// crediting the recipient. Returns the new state root.
+pub fn apply_mint(
+    mint: &network::types::MintMsg,
+    smt: &Mutex<SMT>,
+    proof_store: &ProofStore,
+) -> Result<[u8; 32], NodeError> {
+    let (root, proof_from, proof_to) = {
+        let mut smt = smt.lock().unwrap();
+
+        let mut treasury = smt
+            .get_account(&mint.from)
+            .unwrap_or_else(|_| core::types::AccountLeaf::new_empty(mint.from, 0));
+        if mint.nonce > treasury.nonce {
+            return Err(NodeError::FutureNonce { expected: treasury.nonce, actual: mint.nonce });
+        }
+        if mint.nonce < treasury.nonce {
+            return Err(NodeError::InvalidNonce);
+        }
+        smt.check_compliance(mint.token_id, &mint.to)
+            .map_err(|e| NodeError::Unauthorized(e.to_string()))?;
+
+        treasury.nonce += 1;
+        smt.update_account(treasury).map_err(|e| NodeError::UpdateFailed(e.to_string()))?;
+
+        let mut recipient = smt
+            .get_account(&mint.to)
+            .unwrap_or_else(|_| core::types::AccountLeaf::new_empty(mint.to, 0));
+        recipient.bal = recipient
+            .bal
+            .checked_add(mint.amount)
+            .ok_or_else(|| NodeError::UpdateFailed("Recipient balance overflows".to_string()))?;
+        smt.update_account(recipient).map_err(|e| NodeError::UpdateFailed(e.to_string()))?;
+
+        let proof_from = smt.gen_proof(&mint.from).map_err(|e| NodeError::UpdateFailed(e.to_string()))?;
+        let proof_to = smt.gen_proof(&mint.to).map_err(|e| NodeError::UpdateFailed(e.to_string()))?;
+        (smt.root(), proof_from, proof_to)
+    };
+
+    proof_store.put_proof(&mint.from, &root, &proof_from)?;
+    proof_store.put_proof(&mint.to, &root, &proof_to)?;
+
+    Ok(root)
+}
+
+/// Checks a token issuance received over gossip.
+///
+/// The issuer signs it with token ID 0, since the ID is assigned when it
+/// applies, and it must get the same ID here as on the node that published
+/// it.
+pub fn validate_issue_token(issue: &network::types::IssueTokenMsg, smt: &Mutex<SMT>) -> Result<(), NodeError> {
+    let signed = core::types::SystemMsg::IssueToken {
+        issuer: issue.issuer,
+        token_id: 0,
+        metadata: issue.metadata.clone(),
+        collateral: issue.collateral,
+        nonce: issue.nonce,
+        signature: issue.signature.clone(),
+    };
+    core::crypto::verify_system_msg(&signed).map_err(|e| NodeError::InvalidSignature(e.to_string()))?;
+
+    let next_token_id = smt.lock().unwrap().next_token_id();
+    if issue.token_id != next_token_id {
+        return Err(NodeError::StateMismatch(format!(
+            "Token {} was issued as token {} here",
+            issue.token_id, next_token_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks a state root attestation received over gossip: its signature, and
+/// its time against this node's clock.
+pub fn validate_attestation(attestation: &network::types::AttestationMsg) -> Result<(), NodeError> {
+    let bytes = attestation.signing_bytes()?;
+    core::crypto::verify_signature(
+        core::types::SignatureScheme::of_address(&attestation.attester),
+        &attestation.attester,
+        &bytes,
+        &attestation.signature,
+    )
+    .map_err(|e| NodeError::InvalidSignature(e.to_string()))?;
+
+    check_claimed_time(attestation.timestamp)
+}
+
+/// Applies a validated system message and stores what other RPC methods
+/// serve about it, such as voting proofs and confidential transfer notes.
+///
+/// Returns the new state root and the token ID the next issued token got
+/// before the message applied.
+pub fn apply_system_msg(
+    message: &core::types::SystemMsg,
+    smt: &Mutex<SMT>,
+    proof_store: &ProofStore,
+) -> Result<([u8; 32], core::types::TokenId), NodeError> {
+    let (root, issued_token_id, snapshot) = {
+        let mut smt = smt.lock().unwrap();
+        let next_token_id = smt.next_token_id();
+
+        // A proposal snapshots the state as it is before the proposal applies
+        let snapshot = match message {
+            core::types::SystemMsg::CreateProposal { .. } => {
+                Some(smt.state_leaves(&core::smt::Subtree::root()))
+            }
+            _ => None,
+        };
+
+        smt.apply(message.clone()).map_err(|e| NodeError::UpdateFailed(e.to_string()))?;
+        (smt.root(), next_token_id, snapshot)
+    };
+
+    // Voting proofs are generated from a copy of the snapshot, so the state
+    // stays unlocked while every holder's proof is built
+    if let (Some(leaves), core::types::SystemMsg::CreateProposal { proposer, token_id, nonce, .. }) =
+        (snapshot, message)
+    {
+        let proposal_id = core::governance::proposal_id(proposer, *token_id, *nonce);
+        let proofs = SMT::from_state_leaves(leaves).and_then(|snapshot| {
+            snapshot
+                .get_all_accounts()?
+                .iter()
+                .filter(|account| account.token_id == *token_id && account.bal > 0)
+                .map(|account| snapshot.gen_account_proof(&account.addr, *token_id))
+                .collect::<Result<Vec<_>, _>>()
+        });
+        match proofs {
+            Ok(proofs) => {
+                for proof in &proofs {
+                    if let Some(leaf) = &proof.leaf {
+                        if let Err(e) = proof_store.put_voting_proof(&proposal_id, &leaf.addr, proof) {
+                            warn!("Failed to store voting proof: {}", e);
+                        }
+                    }
+                }
+            }
+            Err(e) => warn!("Failed to generate voting proofs: {}", e),
+        }
+    }
+
+    // Keep what recipients need to find incoming confidential transfers
+    if let core::types::SystemMsg::ConfidentialTransfer {
+        from, to, token_id, amount_commitment, encrypted_opening, ..
+    } = message
+    {
+        let note = core::confidential::ConfidentialNote {
+            from: *from,
+            to: *to,
+            token_id: *token_id,
+            amount_commitment: *amount_commitment,
+            encrypted_opening: encrypted_opening.clone(),
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+
+        if let Err(e) = proof_store.put_confidential_note(&note) {
+            warn!("Failed to store confidential note: {}", e);
+        }
+    }
+
+    Ok((root, issued_token_id))
+}
+
+/// Handles a getConfidentialAccount request.
+///
+/// Returns the commitment and viewing key of an account's confidential
+/// balance for a token, or null if it has none.
+fn handle_get_confidential_account(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 2 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address = parse_address_param(&params[0])?;
+
+    let token_id = params[1].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid token ID".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let leaf = smt.get_confidential_account(&address, token_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get confidential account".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    match leaf {
+        Some(leaf) => Ok(serde_json::json!({
+            "address": hex::encode(leaf.addr),
+            "token_id": leaf.token_id,
+            "commitment": hex::encode(leaf.commitment),
+            "viewing_key": hex::encode(leaf.viewing_key),
+            "root": hex::encode(smt.root()),
+        })),
+        None => Ok(serde_json::Value::Null),
+    }
+}
+
+/// Handles a getConfidentialNotes request.
+///
+/// Returns the encrypted notes of the confidential transfers an address has received.
+fn handle_get_confidential_notes(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address = parse_address_param(&params[0])?;
+
+    let notes = state.proof_store.get_confidential_notes(&address).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get confidential notes".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let notes: Vec<serde_json::Value> = notes
+        .iter()
+        .map(|note| {
+            serde_json::json!({
+                "from": hex::encode(note.from),
+                "to": hex::encode(note.to),
+                "token_id": note.token_id,
+                "amount_commitment": hex::encode(note.amount_commitment),
+                "ephemeral_key": hex::encode(note.encrypted_opening.ephemeral_key),
+                "ciphertext": hex::encode(&note.encrypted_opening.ciphertext),
+                "timestamp": note.timestamp,
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!(notes))
+}
+
+/// Handles a resolveName request.
+///
+/// Returns the record of a name with a proof of it against the current root,
+/// or null if the name is not registered.
+fn handle_resolve_name(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let name = params[0].as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid name".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let record = smt.get_name(name).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get name".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let record = match record {
+        Some(record) => record,
+        None => return Ok(serde_json::Value::Null),
+    };
+
+    let proof = smt.gen_data_proof(&core::names::name_key(name)).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to generate name proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize name proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "name": record.name,
+        "owner": hex::encode(record.owner),
+        "target": hex::encode(record.target),
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Converts an NFT item to its JSON-RPC representation.
+fn nft_item_to_json(item: &core::nft::NftItem) -> serde_json::Value {
+    serde_json::json!({
+        "collection_id": item.collection_id,
+        "item_id": item.item_id,
+        "owner": hex::encode(item.owner),
+        "metadata_uri": item.metadata_uri,
+        "metadata_hash": item.metadata_hash.map(hex::encode),
+    })
+}
+
+/// Handles a getNftCollection request.
+///
+/// Returns the issuer, metadata and item count of a collection, or null if
+/// the token is not an NFT collection.
+fn handle_get_nft_collection(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let collection_id = params[0].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid collection ID".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let collection = smt.get_nft_collection(collection_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get NFT collection".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let collection = match collection {
+        Some(collection) => collection,
+        None => return Ok(serde_json::Value::Null),
+    };
+
+    let token_info = smt.get_token(collection_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get token".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "collection_id": collection.collection_id,
+        "issuer": hex::encode(collection.issuer),
+        "metadata": token_info.metadata,
+        "items": collection.items,
+    }))
+}
+
+/// Handles a getNft request.
+///
+/// Returns an item with a proof of its ownership against the current root,
+/// or null if the item has not been minted.
+fn handle_get_nft(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 2 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let collection_id = params[0].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid collection ID".to_string(),
+        data: None,
+    })?;
+
+    let item_id = params[1].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid item ID".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let item = smt.get_nft(collection_id, item_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get NFT".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let item = match item {
+        Some(item) => item,
+        None => return Ok(serde_json::Value::Null),
+    };
+
+    let proof = smt
+        .gen_data_proof(&core::nft::item_key(collection_id, item_id))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate ownership proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize ownership proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let mut result = nft_item_to_json(&item);
+    result["root"] = serde_json::json!(hex::encode(smt.root()));
+    result["proof"] = serde_json::json!(hex::encode(proof));
+    Ok(result)
+}
+
+/// Handles a getNftsByOwner request.
+///
+/// Returns the items owned by an address, ordered by collection and item ID.
+fn handle_get_nfts_by_owner(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let owner = parse_address_param(&params[0])?;
+
+    let smt = state.smt.lock().unwrap();
+    let items = smt.get_nfts_by_owner(&owner).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get NFTs".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!(items.iter().map(nft_item_to_json).collect::<Vec<_>>()))
+}
+
+/// Handles a getCompliancePolicy request.
+///
+/// Returns who may hold a token: "open", "allow-list" or "deny-list".
+fn handle_get_compliance_policy(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let token_id = params[0].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid token ID".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    smt.get_token(token_id).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: "Unknown token".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let policy = smt.get_compliance_policy(token_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get compliance policy".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "token_id": token_id,
+        "policy": policy.to_string(),
+    }))
+}
+
+/// Handles a getComplianceMembership request.
+///
+/// Returns whether an address is on a token's allow or deny list, with a
+/// proof of membership or non-membership against the current root.
+fn handle_get_compliance_membership(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 3 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let token_id = params[0].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid token ID".to_string(),
+        data: None,
+    })?;
+
+    let list: core::compliance::ComplianceList = params[1]
+        .as_str()
+        .and_then(|list| list.parse().ok())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid list (expected \"allow\" or \"deny\")".to_string(),
+            data: None,
+        })?;
+
+    let address = parse_address_param(&params[2])?;
+
+    let smt = state.smt.lock().unwrap();
+    let listed = smt.is_listed(token_id, list, &address).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get list membership".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = smt
+        .gen_data_proof(&core::compliance::list_key(token_id, list, &address))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate membership proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize membership proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "token_id": token_id,
+        "list": list.to_string(),
+        "address": hex::encode(address),
+        "listed": listed,
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Handles a getTokenCollateral request.
+///
+/// Returns the collateral locked for a token with a proof of its escrow
+/// against the current root. Tokens issued without collateral have no
+/// escrow until they are retired, which the proof also shows.
+fn handle_get_token_collateral(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let token_id = params[0].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid token ID".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let token_info = smt.get_token(token_id).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: "Unknown token".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let escrow = smt.get_token_escrow(token_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get token escrow".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = smt
+        .gen_data_proof(&core::collateral::escrow_key(token_id))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate escrow proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize escrow proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "token_id": token_id,
+        "issuer": hex::encode(token_info.issuer),
+        "collateral": escrow.as_ref().map_or(0, |escrow| escrow.collateral),
+        "retired": escrow.is_some_and(|escrow| escrow.retired),
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Parses a hex-encoded airdrop ID parameter.
+fn parse_airdrop_id_param(value: &serde_json::Value) -> Result<core::airdrop::AirdropId, JsonRpcError> {
+    value
+        .as_str()
+        .and_then(|id| hex::decode(id.trim_start_matches("0x")).ok())
+        .and_then(|id| id.try_into().ok())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid airdrop ID".to_string(),
+            data: None,
+        })
+}
+
+/// Handles a getAirdrop request.
+///
+/// Returns an airdrop's distribution root, escrowed total and claimed amount,
+/// along with a proof of the airdrop leaf against the current state root.
+fn handle_get_airdrop(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let airdrop_id = parse_airdrop_id_param(&params[0])?;
+
+    let smt = state.smt.lock().unwrap();
+    let airdrop = smt
+        .get_airdrop(&airdrop_id)
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to get airdrop".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Unknown airdrop".to_string(),
+            data: None,
+        })?;
+
+    let proof = smt
+        .gen_data_proof(&core::airdrop::airdrop_key(&airdrop_id))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate airdrop proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize airdrop proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "airdrop_id": hex::encode(airdrop.airdrop_id),
+        "issuer": hex::encode(airdrop.issuer),
+        "token_id": airdrop.token_id,
+        "merkle_root": hex::encode(airdrop.merkle_root),
+        "entries": airdrop.entries,
+        "total": airdrop.total,
+        "claimed": airdrop.claimed,
+        "expires_at": airdrop.expires_at,
+        "reclaimed": airdrop.reclaimed,
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Handles an isAirdropClaimed request.
+///
+/// Returns whether an airdrop entry has been claimed, along with a proof of
+/// the claimed-bitmap leaf covering it against the current state root.
+fn handle_is_airdrop_claimed(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 2 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let airdrop_id = parse_airdrop_id_param(&params[0])?;
+
+    let index = params[1].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid entry index".to_string(),
+        data: None,
+    })?;
+
+    let smt = state.smt.lock().unwrap();
+    let claimed = smt.is_airdrop_claimed(&airdrop_id, index).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get claim status".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = smt
+        .gen_data_proof(&core::airdrop::claims_key(&airdrop_id, index))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate claims proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize claims proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "airdrop_id": hex::encode(airdrop_id),
+        "index": index,
+        "claimed": claimed,
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Parses a hex-encoded proposal ID parameter.
+fn parse_proposal_id_param(value: &serde_json::Value) -> Result<core::governance::ProposalId, JsonRpcError> {
+    value
+        .as_str()
+        .and_then(|id| hex::decode(id.trim_start_matches("0x")).ok())
+        .and_then(|id| id.try_into().ok())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid proposal ID".to_string(),
+            data: None,
+        })
+}
+
+/// Handles a getProposal request.
+///
+/// Returns a governance proposal and its tally, along with a proof of the
+/// proposal leaf against the current state root.
+fn handle_get_proposal(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let proposal_id = parse_proposal_id_param(&params[0])?;
+
+    let smt = state.smt.lock().unwrap();
+    let proposal = smt
+        .get_proposal(&proposal_id)
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to get proposal".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Unknown proposal".to_string(),
+            data: None,
+        })?;
+
+    let proof = smt
+        .gen_data_proof(&core::governance::proposal_key(&proposal_id))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate proposal proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize proposal proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "proposal_id": hex::encode(proposal.proposal_id),
+        "token_id": proposal.token_id,
+        "proposer": hex::encode(proposal.proposer),
+        "action": proposal.action.to_string(),
+        "snapshot_root": hex::encode(proposal.snapshot_root),
+        "snapshot_supply": proposal.snapshot_supply,
+        "quorum": proposal.quorum,
+        "deadline": proposal.deadline,
+        "votes_for": proposal.votes_for,
+        "votes_against": proposal.votes_against,
+        "status": proposal.status.to_string(),
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Handles a getVotingProof request.
+///
+/// Returns the proof of an address's balance against a proposal's snapshot,
+/// kept when this node applied the proposal, whether it was submitted here or
+/// received over gossip, or null if the address held none of the token at the
+/// snapshot.
+fn handle_get_voting_proof(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 2 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let proposal_id = parse_proposal_id_param(&params[0])?;
+    let address = parse_address_param(&params[1])?;
+
+    let proof = state
+        .proof_store
+        .get_voting_proof(&proposal_id, &address)
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to get voting proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = match proof {
+        Some(proof) => proof,
+        None => return Ok(serde_json::Value::Null),
+    };
+
+    let weight = proof.balance();
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize voting proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    Ok(serde_json::json!({
+        "proposal_id": hex::encode(proposal_id),
+        "address": hex::encode(address),
+        "weight": weight,
+        "proof": hex::encode(proof),
+    }))
+}
+
+/// Handles a getStandingOrders request.
+///
+/// Returns the active standing orders an address pays or is paid by.
+fn handle_get_standing_orders(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let address = parse_address_param(&params[0])?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let smt = state.smt.lock().unwrap();
+    let orders = smt.get_standing_orders(&address, now).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get standing orders".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let orders: Vec<serde_json::Value> = orders
+        .iter()
+        .map(|order| {
+            serde_json::json!({
+                "order_id": hex::encode(order.order_id),
+                "payer": hex::encode(order.payer),
+                "payee": hex::encode(order.payee),
+                "token_id": order.token_id,
+                "amount": order.amount,
+                "period_secs": order.period_secs,
+                "start": order.start,
+                "expiry": order.expiry,
+                "last_period": order.last_period,
+                "current_period": order.period_at(now),
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!(orders))
+}
+
+/// Parses a hex-encoded payment channel ID parameter.
+fn parse_channel_id_param(value: &serde_json::Value) -> Result<core::channels::ChannelId, JsonRpcError> {
+    value
+        .as_str()
+        .and_then(|id| hex::decode(id.trim_start_matches("0x")).ok())
+        .and_then(|id| id.try_into().ok())
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid channel ID".to_string(),
+            data: None,
+        })
+}
+
+/// Handles a getChannel request.
+///
+/// Returns a payment channel, or only `open: false` once it has been closed
+/// or reclaimed, along with a proof of the channel leaf, or its absence,
+/// against the current state root.
+fn handle_get_channel(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 1 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let channel_id = parse_channel_id_param(&params[0])?;
+
+    let smt = state.smt.lock().unwrap();
+    let channel = smt.get_channel(&channel_id).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get channel".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = smt
+        .gen_data_proof(&core::channels::channel_key(&channel_id))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate channel proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize channel proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let mut response = serde_json::json!({
+        "channel_id": hex::encode(channel_id),
+        "open": channel.is_some(),
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    });
+    if let Some(channel) = channel {
+        response["payer"] = serde_json::json!(hex::encode(channel.payer));
+        response["payee"] = serde_json::json!(hex::encode(channel.payee));
+        response["token_id"] = serde_json::json!(channel.token_id);
+        response["deposit"] = serde_json::json!(channel.deposit);
+        response["timeout_secs"] = serde_json::json!(channel.timeout_secs);
+        response["closes_at"] = serde_json::json!(channel.closes_at);
+    }
+
+    Ok(response)
+}
+
+/// Handles a getSessionKey request.
+///
+/// Returns an owner's delegation to a session key and what the key may still
+/// spend of each token in the current window, or only `authorized: false` if
+/// there is none, along with a proof of the delegation leaf, or its absence,
+/// against the current state root.
+fn handle_get_session_key(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    // Parse parameters
+    let params = params
+        .as_array()
+        .ok_or_else(|| JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        })?;
+
+    if params.len() != 2 {
+        return Err(JsonRpcError {
+            code: -32602,
+            message: "Invalid params".to_string(),
+            data: None,
+        });
+    }
+
+    let owner = parse_address_param(&params[0])?;
+    let session_key = parse_address_param(&params[1])?;
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let smt = state.smt.lock().unwrap();
+    let delegation = smt.get_session_key(&owner, &session_key).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to get session key".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let proof = smt
+        .gen_data_proof(&core::session_keys::session_key_key(&owner, &session_key))
+        .map_err(|e| JsonRpcError {
+            code: -32603,
+            message: "Failed to generate session key proof".to_string(),
+            data: Some(serde_json::to_value(e.to_string()).unwrap()),
+        })?;
+
+    let proof = bincode::serialize(&proof).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: "Failed to serialize session key proof".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })?;
+
+    let mut response = serde_json::json!({
+        "owner": hex::encode(owner),
+        "session_key": hex::encode(session_key),
+        "authorized": delegation.is_some(),
+        "root": hex::encode(smt.root()),
+        "proof": hex::encode(proof),
+    });
+    if let Some(delegation) = delegation {
+        let limits: Vec<serde_json::Value> = delegation
+            .limits
+            .iter()
+            .map(|limit| {
+                serde_json::json!({
+                    "token_id": limit.token_id,
+                    "amount": limit.amount,
+                    "remaining": delegation.remaining(limit.token_id, now),
+                })
+            })
+            .collect();
+        response["limits"] = serde_json::json!(limits);
+        response["window_secs"] = serde_json::json!(delegation.window_secs);
+        response["start"] = serde_json::json!(delegation.start);
+        response["expiry"] = serde_json::json!(delegation.expiry);
+        response["current_window"] = serde_json::json!(delegation.window_at(now));
+    }
+
+    Ok(response)
+}
+
+/// Handles the getMempool method.
+fn handle_get_mempool(state: &RpcState) -> Result<serde_json::Value, JsonRpcError> {
+    let mut mempool = state.mempool.lock().unwrap();
+    mempool.evict_expired();
+
+    let entries: Vec<serde_json::Value> = mempool
+        .entries()
+        .map(|((from, token_id, nonce), entry)| {
+            serde_json::json!({
+                "from": hex::encode(from),
+                "to": hex::encode(entry.update.to),
+                "token_id": token_id,
+                "nonce": nonce,
+                "amount": entry.update.amount,
+                "age_secs": entry.received.elapsed().as_secs(),
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!({
+        "size": entries.len(),
+        "entries": entries,
+    }))
+}
+
+/// Handles the clearMempool method.
+fn handle_clear_mempool(state: &RpcState) -> Result<serde_json::Value, JsonRpcError> {
+    let removed = state.mempool.lock().unwrap().clear();
+    info!("Cleared {} transfers from the transaction pool", removed);
+    Ok(serde_json::json!({ "removed": removed }))
+}
+
+/// Parses a peer ID parameter.
+fn parse_peer_id(param: &serde_json::Value) -> Result<PeerId, JsonRpcError> {
+    let peer_id = param.as_str().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid peer ID".to_string(),
+        data: None,
+    })?;
+
+    peer_id.parse::<PeerId>().map_err(|e| JsonRpcError {
+        code: -32602,
+        message: "Invalid peer ID".to_string(),
+        data: Some(serde_json::to_value(e.to_string()).unwrap()),
+    })
+}
+
+/// Handles the admin_banPeer method.
+fn handle_ban_peer(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    let params = match params.as_array() {
+        Some(params) if params.len() == 2 || params.len() == 3 => params,
+        _ => {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: "Invalid params".to_string(),
+                data: None,
+            })
+        }
+    };
+
+    let peer_id = parse_peer_id(&params[0])?;
+    let duration_secs = params[1].as_u64().ok_or_else(|| JsonRpcError {
+        code: -32602,
+        message: "Invalid ban duration".to_string(),
+        data: None,
+    })?;
+    let reason = params.get(2).and_then(|reason| reason.as_str()).unwrap_or("banned by operator");
+
+    let mut swarm = state.swarm.lock().unwrap();
+    swarm
+        .behaviour_mut()
+        .ban_peer(peer_id, std::time::Duration::from_secs(duration_secs), reason);
+
+    Ok(serde_json::json!(true))
+}
+
+/// Handles the admin_unbanPeer method.
+fn handle_unban_peer(
+    params: &serde_json::Value,
+    state: &RpcState,
+) -> Result<serde_json::Value, JsonRpcError> {
+    let params = match params.as_array() {
+        Some(params) if params.len() == 1 => params,
+        _ => {
+            return Err(JsonRpcError {
+                code: -32602,
+                message: "Invalid params".to_string(),
+                data: None,
+            })
+        }
+    };
+
+    let peer_id = parse_peer_id(&params[0])?;
+    let mut swarm = state.swarm.lock().unwrap();
+    let unbanned = swarm.behaviour_mut().guard.unban(&peer_id);
+
+    Ok(serde_json::json!(unbanned))
+}
+
+/// Handles the admin_listBans method.
+fn handle_list_bans(state: &RpcState) -> Result<serde_json::Value, JsonRpcError> {
+    let bans = state.swarm.lock().unwrap().behaviour_mut().guard.bans();
+
+    let bans: Vec<serde_json::Value> = bans
+        .into_iter()
+        .map(|(peer_id, ban)| {
+            serde_json::json!({
+                "peer_id": peer_id.to_string(),
+                "until": ban.until,
+                "reason": ban.reason,
+            })
+        })
+        .collect();
+
+    Ok(serde_json::json!(bans))
+}
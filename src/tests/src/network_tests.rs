@@ -7,6 +7,10 @@ use core::{
 };
 use network::{
     dht::DHTManager,
+    gossip::GossipConfig,
+    identity::{load_or_create_identity, IDENTITY_FILE},
+    peer_guard::{ConnectionConfig, BAN_LIST_FILE},
+    record_store::RecordStoreConfig,
     storage::ProofStore,
     transport::{init_swarm, handle_network_event},
     types::UpdateMsg,
@@ -63,8 +67,21 @@ fn test_swarm_init() {
     let rt = Runtime::new().unwrap();
     
     // Initialize the swarm
+    let dir = tempdir().unwrap();
     rt.block_on(async {
-        let (swarm, dht_manager) = init_swarm(vec![]).await.unwrap();
+        let local_key = load_or_create_identity(dir.path().join(IDENTITY_FILE)).unwrap();
+        let (swarm, dht_manager, _proof_exchange, _state_sync) = init_swarm(
+            local_key,
+            vec![],
+            &dir.path().join("dht"),
+            RecordStoreConfig::default(),
+            GossipConfig::default(),
+            &dir.path().join(BAN_LIST_FILE),
+            ConnectionConfig::default(),
+            false,
+        )
+            .await
+            .unwrap();
         
         // Check that the swarm and DHT manager were created successfully
         // Check that the swarm was created successfully
@@ -81,8 +98,21 @@ fn test_network_event_handling() {
     let rt = Runtime::new().unwrap();
     
     // Initialize the swarm
+    let dir = tempdir().unwrap();
     rt.block_on(async {
-        let (mut swarm, dht_manager) = init_swarm(vec![]).await.unwrap();
+        let local_key = load_or_create_identity(dir.path().join(IDENTITY_FILE)).unwrap();
+        let (mut swarm, dht_manager, proof_exchange, state_sync) = init_swarm(
+            local_key,
+            vec![],
+            &dir.path().join("dht"),
+            RecordStoreConfig::default(),
+            GossipConfig::default(),
+            &dir.path().join(BAN_LIST_FILE),
+            ConnectionConfig::default(),
+            false,
+        )
+            .await
+            .unwrap();
         let mut known_peers = HashSet::new();
         
         // Listen on a local address
@@ -93,7 +123,7 @@ fn test_network_event_handling() {
         while !listening {
             match swarm.select_next_some().await {
                 event => {
-                    match handle_network_event(event, &dht_manager, &mut known_peers, &mut swarm).await {
+                    match handle_network_event(event, &dht_manager, &proof_exchange, &state_sync, &mut known_peers, &mut swarm).await {
                         Ok(Some(_)) => {
                             // Event was handled successfully
                         }
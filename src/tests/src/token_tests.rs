@@ -68,6 +68,9 @@ fn test_token_issuance_and_operations() {
         token_id,
         amount: 200,
         nonce: 0, // The recipient's nonce is still 0 since they haven't done any transactions yet
+        memo: Vec::new(),
+        ephemeral_key: None,
+        scheme: core::types::SignatureScheme::Ed25519,
         signature: core::types::Signature([0u8; 64]), // In a real scenario, this would be a valid signature
     };
     